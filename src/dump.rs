@@ -0,0 +1,466 @@
+use std::io::{Cursor, Seek, SeekFrom};
+
+use crate::readers::{read_unsigned_leb128, read_x};
+use crate::wasm_components::instruction::Instruction;
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{DataSegment, ElementSegment, ParseError, TypeEntry};
+use crate::wasm_components::types::{
+    ExternalKind, GlobalType, MemoryType, RecGroup, SubType, TableType, TagType, ValueType,
+};
+
+/*
+ * Annotated Hex Dump
+ *
+ * wasmファイルのバイト列を先頭から読み直し、各バイト範囲にその意味を付ける
+ * (wasm-objdump -x -s や wat2wasm -v の出力に近いもの)
+ *
+ * セクションの区切りはパース時に記録した位置を使い、セクションの中身は
+ * 各エントリのパーサでバイト列を読み直して、読む前後の位置から範囲を求める
+ * (パディングされたLEB128を含むオブジェクトファイルでも正しい範囲になる)
+ */
+
+/// ダンプの1行(バイト範囲とその意味)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpLine {
+    offset: u64, // ファイル先頭からの位置
+    size: u64,   // 0の場合は見出し
+    depth: u32,  // 表示の字下げ(ブロックの入れ子など)
+    label: String,
+}
+
+impl DumpLine {
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn get_depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn get_label(&self) -> &String {
+        &self.label
+    }
+
+    /// セクションの見出しかどうか(バイト範囲を持たない行)
+    pub fn is_heading(&self) -> bool {
+        self.size == 0
+    }
+}
+
+/// モジュールのバイト列(パースしたファイルの内容)のダンプを作る
+pub fn dump_module(module: &WasmModule, bytes: &[u8]) -> Result<Vec<DumpLine>, ParseError> {
+    let mut dumper = Dumper::new(bytes);
+
+    dumper.read_bytes(4, |_| String::from("magic"))?;
+    dumper.read_bytes(4, |data| {
+        format!(
+            "version {}",
+            u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+        )
+    })?;
+
+    for (idx, section) in module.get_sections().iter().enumerate() {
+        let section_name = module
+            .get_section_name_by_index(idx as u32)
+            .unwrap_or_default();
+        dumper.seek(section.get_offset())?;
+        dumper.heading(format!(
+            "section \"{}\" ({})",
+            section_name,
+            section.get_id()
+        ));
+        dumper.dump_section(module, section.get_id())?;
+    }
+
+    Ok(dumper.lines)
+}
+
+struct Dumper<'a> {
+    cursor: Cursor<&'a [u8]>,
+    lines: Vec<DumpLine>,
+    depth: u32,
+}
+
+impl<'a> Dumper<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            cursor: Cursor::new(bytes),
+            lines: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    fn pos(&self) -> u64 {
+        self.cursor.position()
+    }
+
+    fn seek(&mut self, offset: u64) -> Result<(), ParseError> {
+        match self.cursor.seek(SeekFrom::Start(offset)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+        }
+    }
+
+    fn heading(&mut self, label: String) {
+        self.lines.push(DumpLine {
+            offset: self.pos(),
+            size: 0,
+            depth: 0,
+            label,
+        });
+    }
+
+    // startから現在の位置までの範囲に意味を付ける
+    fn push(&mut self, start: u64, label: String) {
+        self.lines.push(DumpLine {
+            offset: start,
+            size: self.pos() - start,
+            depth: self.depth,
+            label,
+        });
+    }
+
+    // パーサで1エントリを読み、読んだ範囲に意味を付ける
+    fn parse<T, P, L>(&mut self, parser: P, label: L) -> Result<T, ParseError>
+    where
+        P: FnOnce(&mut Cursor<&'a [u8]>) -> Result<T, ParseError>,
+        L: FnOnce(&T) -> String,
+    {
+        let start = self.pos();
+        let value = parser(&mut self.cursor)?;
+        self.push(start, label(&value));
+        Ok(value)
+    }
+
+    fn read_bytes<L>(&mut self, size: u64, label: L) -> Result<Vec<u8>, ParseError>
+    where
+        L: FnOnce(&[u8]) -> String,
+    {
+        self.parse(
+            |reader| match read_x(reader, size as usize) {
+                Ok(data) => Ok(data),
+                Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+            },
+            |data| label(data),
+        )
+    }
+
+    fn read_leb<L>(&mut self, label: L) -> Result<u64, ParseError>
+    where
+        L: FnOnce(u64) -> String,
+    {
+        self.parse(
+            |reader| {
+                let mut value: u64 = 0;
+                match read_unsigned_leb128(reader, &mut value) {
+                    Ok(_rs) => Ok(value),
+                    Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+                }
+            },
+            |value| label(*value),
+        )
+    }
+
+    // 長さ付きの文字列を読む
+    fn read_name(&mut self, what: &str) -> Result<String, ParseError> {
+        let start = self.pos();
+        let mut name_len: u64 = 0;
+        if let Err(err) = read_unsigned_leb128(&mut self.cursor, &mut name_len) {
+            return Err(ParseError::ReaderError(format!("{:?}", err)));
+        }
+        let name = match read_x(&mut self.cursor, name_len as usize) {
+            Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        self.push(start, format!("{} {:?}", what, name));
+
+        Ok(name)
+    }
+
+    fn read_external_kind(&mut self) -> Result<ExternalKind, ParseError> {
+        self.parse(ExternalKind::parse, |kind| format!("kind: {}", kind))
+    }
+
+    // 命令列を1命令ずつ読む
+    //
+    // endがexpr_endまでの場合はその位置まで、Noneの場合は対応するendまで読む
+    fn dump_instructions(&mut self, expr_end: Option<u64>) -> Result<(), ParseError> {
+        let base_depth = self.depth;
+        let expr_start = self.pos();
+        let mut block_depth: u32 = 0;
+        loop {
+            if let Some(end) = expr_end {
+                if self.pos() >= end {
+                    break;
+                }
+            }
+
+            let offset = (self.pos() - expr_start) as u32;
+            let start = self.pos();
+            let instr = Instruction::parse(&mut self.cursor, offset)?;
+
+            // ブロックを閉じる命令はブロックの外側の深さで表示する
+            if instr.is_block_end() && block_depth > 0 {
+                block_depth -= 1;
+            }
+            self.depth = base_depth + block_depth;
+            self.push(start, instr.to_string());
+
+            if instr.is_block_start() {
+                block_depth += 1;
+            } else if instr.is_block_end() && block_depth == 0 && expr_end.is_none() {
+                break;
+            }
+        }
+        self.depth = base_depth;
+
+        Ok(())
+    }
+
+    fn dump_section(&mut self, module: &WasmModule, section_id: u8) -> Result<(), ParseError> {
+        self.read_bytes(1, |_| String::from("section id"))?;
+        let payload_len = self.read_leb(|v| format!("section size ({} bytes)", v))?;
+        let payload_end = self.pos() + payload_len;
+
+        self.depth = 1;
+        match section_id {
+            0 => {
+                self.read_name("section name")?;
+            }
+            1 => self.dump_type_section()?,
+            2 => self.dump_import_section()?,
+            3 => self.dump_function_section(module)?,
+            4 => self.dump_entries("num tables", |dumper, idx| {
+                // 0x40 0x00で始まるテーブルは、テーブル型の後に初期化式が続く
+                let has_init = dumper.cursor.get_ref().get(dumper.pos() as usize) == Some(&0x40);
+                if has_init {
+                    dumper.read_bytes(2, |_| String::from("table with init expr"))?;
+                }
+                dumper.parse(TableType::parse, |x| format!("table[{}] {}", idx, x))?;
+                if has_init {
+                    dumper.depth += 1;
+                    dumper.dump_instructions(None)?;
+                    dumper.depth -= 1;
+                }
+                Ok(())
+            })?,
+            5 => self.dump_entries("num memories", |dumper, idx| {
+                dumper.parse(MemoryType::parse, |x| format!("memory[{}] {}", idx, x))?;
+                Ok(())
+            })?,
+            6 => self.dump_entries("num globals", |dumper, idx| {
+                dumper.parse(GlobalType::parse, |x| format!("global[{}] {}", idx, x))?;
+                dumper.depth += 1;
+                dumper.dump_instructions(None)?;
+                dumper.depth -= 1;
+                Ok(())
+            })?,
+            7 => self.dump_entries("num exports", |dumper, _| {
+                dumper.read_name("export name")?;
+                dumper.read_external_kind()?;
+                dumper.read_leb(|v| format!("index {}", v))?;
+                Ok(())
+            })?,
+            8 => {
+                self.read_leb(|v| format!("start function {}", v))?;
+            }
+            9 => self.dump_entries("num elem segments", |dumper, idx| {
+                dumper.parse(ElementSegment::parse, |x| {
+                    format!(
+                        "elem segment[{}] ({}, {} elements)",
+                        idx,
+                        get_segment_mode(x.is_active(), x.is_passive()),
+                        x.get_num_elements()
+                    )
+                })?;
+                Ok(())
+            })?,
+            10 => self.dump_code_section(module)?,
+            11 => self.dump_data_section()?,
+            12 => {
+                self.read_leb(|v| format!("data count {}", v))?;
+            }
+            13 => self.dump_entries("num tags", |dumper, idx| {
+                dumper.parse(TagType::parse, |x| format!("tag[{}] {}", idx, x))?;
+                Ok(())
+            })?,
+            _ => (),
+        }
+        self.depth = 1;
+
+        // カスタムセクション・未知のセクションのペイロードは中身を解釈しない
+        let rest = match payload_end.checked_sub(self.pos()) {
+            Some(rest) => rest,
+            None => {
+                return Err(ParseError::FormatError(format!(
+                    "section {} overruns its size (end={:#x}, read={:#x})",
+                    section_id,
+                    payload_end,
+                    self.pos()
+                )))
+            }
+        };
+        if rest > 0 {
+            self.read_bytes(rest, |_| format!("payload ({} bytes)", rest))?;
+        }
+        self.depth = 0;
+
+        Ok(())
+    }
+
+    // 要素数に続いてエントリが並ぶ形式のペイロードを読む
+    fn dump_entries<F>(&mut self, count_label: &str, mut dump_entry: F) -> Result<(), ParseError>
+    where
+        F: FnMut(&mut Self, u64) -> Result<(), ParseError>,
+    {
+        let count = self.read_leb(|v| format!("{} {}", count_label, v))?;
+        for idx in 0..count {
+            dump_entry(self, idx)?;
+        }
+
+        Ok(())
+    }
+
+    fn dump_type_section(&mut self) -> Result<(), ParseError> {
+        // 型インデックスはrec groupを展開した通し番号
+        let mut type_idx: u32 = 0;
+        self.dump_entries("num types", |dumper, _| {
+            let rec_group = dumper.parse(RecGroup::parse, |x| {
+                if x.is_explicit() {
+                    format!("rec group ({} types)", x.get_num_sub_types())
+                } else {
+                    let sub_types = x.get_sub_types();
+                    format!("type[{}] {}", type_idx, describe_sub_type(sub_types[0]))
+                }
+            })?;
+            type_idx += rec_group.get_num_sub_types();
+            Ok(())
+        })
+    }
+
+    fn dump_import_section(&mut self) -> Result<(), ParseError> {
+        self.dump_entries("num imports", |dumper, idx| {
+            dumper.heading_entry(format!("import[{}]", idx));
+            dumper.read_name("module name")?;
+            dumper.read_name("field name")?;
+            let kind = dumper.read_external_kind()?;
+            dumper.parse(
+                |reader| TypeEntry::parse(reader, &kind),
+                |x| match x {
+                    TypeEntry::FuncIndex { type_ } => format!("type index {}", type_),
+                    TypeEntry::TblType { type_ } => format!("{}", type_),
+                    TypeEntry::MemType { type_ } => format!("{}", type_),
+                    TypeEntry::GblType { type_ } => format!("{}", type_),
+                    TypeEntry::TagType { type_ } => format!("{}", type_),
+                },
+            )?;
+            dumper.depth -= 1;
+            Ok(())
+        })
+    }
+
+    fn dump_function_section(&mut self, module: &WasmModule) -> Result<(), ParseError> {
+        let num_imported_functions = module.get_num_imported_functions() as u64;
+        self.dump_entries("num functions", |dumper, idx| {
+            dumper
+                .read_leb(|v| format!("func[{}] type index {}", num_imported_functions + idx, v))?;
+            Ok(())
+        })
+    }
+
+    fn dump_code_section(&mut self, module: &WasmModule) -> Result<(), ParseError> {
+        let num_imported_functions = module.get_num_imported_functions();
+        let name_section = module.get_name_section();
+        self.dump_entries("num functions", |dumper, idx| {
+            let func_idx = num_imported_functions + idx as u32;
+            let func_name = name_section.and_then(|x| x.get_function_name(func_idx));
+            dumper.heading_entry(match func_name {
+                Some(name) => format!("func[{}] <{}>", func_idx, name),
+                None => format!("func[{}]", func_idx),
+            });
+
+            let body_size = dumper.read_leb(|v| format!("body size ({} bytes)", v))?;
+            let body_end = dumper.pos() + body_size;
+            dumper.dump_entries("num local decls", |dumper, _| {
+                let start = dumper.pos();
+                let mut count: u64 = 0;
+                if let Err(err) = read_unsigned_leb128(&mut dumper.cursor, &mut count) {
+                    return Err(ParseError::ReaderError(format!("{:?}", err)));
+                }
+                let value_type = ValueType::parse(&mut dumper.cursor)?;
+                dumper.push(start, format!("local {} x {}", count, value_type));
+                Ok(())
+            })?;
+            dumper.dump_instructions(Some(body_end))?;
+
+            dumper.depth -= 1;
+            Ok(())
+        })
+    }
+
+    fn dump_data_section(&mut self) -> Result<(), ParseError> {
+        self.dump_entries("num data segments", |dumper, idx| {
+            // 末尾のデータ本体を除いた部分(モード・オフセット式・長さ)とデータ本体を分けて表示する
+            let start = dumper.pos();
+            let segment = DataSegment::parse(&mut dumper.cursor)?;
+            let end = dumper.pos();
+            let data_start = end - segment.get_data_size() as u64;
+
+            dumper.seek(data_start)?;
+            let mode = match segment.get_offset() {
+                Some(offset) => format!(
+                    "memory {} offset {}",
+                    segment.get_memory_index().unwrap_or(0),
+                    offset
+                ),
+                None => String::from(get_segment_mode(
+                    !segment.is_passive(),
+                    segment.is_passive(),
+                )),
+            };
+            dumper.push(start, format!("data segment[{}] ({})", idx, mode));
+
+            dumper.seek(end)?;
+            dumper.push(
+                data_start,
+                format!("data ({} bytes)", segment.get_data_size()),
+            );
+            Ok(())
+        })
+    }
+
+    // エントリの見出し(バイト範囲なし)を付け、以降の行を字下げする
+    fn heading_entry(&mut self, label: String) {
+        self.lines.push(DumpLine {
+            offset: self.pos(),
+            size: 0,
+            depth: self.depth,
+            label,
+        });
+        self.depth += 1;
+    }
+}
+
+// MVPの関数型はこれまで通り"(params) -> result"の形で表示する
+fn describe_sub_type(sub_type: &SubType) -> String {
+    match sub_type.get_func_type() {
+        Some(func_type) if sub_type.is_final() && sub_type.get_super_types().is_empty() => {
+            func_type.to_string()
+        }
+        _ => sub_type.to_string(),
+    }
+}
+
+fn get_segment_mode(is_active: bool, is_passive: bool) -> &'static str {
+    if is_active {
+        "active"
+    } else if is_passive {
+        "passive"
+    } else {
+        "declarative"
+    }
+}
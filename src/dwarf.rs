@@ -0,0 +1,421 @@
+mod abbrev;
+mod form;
+mod info;
+mod line;
+mod ranges;
+mod reader;
+
+pub use self::info::*;
+pub use self::line::*;
+
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{CustomSectionPayload, ParseError, SectionCommonInterface};
+
+/*
+ * DWARF
+ *
+ * .debug_* カスタムセクション(CustomSectionPayload::General)を読み、
+ * コンパイル単位・関数・行番号表を解決する
+ *
+ * WebAssemblyのDWARFでは、コードのアドレスは
+ * コードセクションのペイロード先頭(セクションIDとサイズの直後)からのオフセットで表す
+ */
+
+// DWARFの各セクションのデータ(存在しないセクションは空)
+struct DwarfSections<'a> {
+    debug_info: &'a [u8],
+    debug_abbrev: &'a [u8],
+    debug_str: &'a [u8],
+    debug_line: &'a [u8],
+    debug_line_str: &'a [u8],
+    debug_str_offsets: &'a [u8],
+    debug_addr: &'a [u8],
+    debug_ranges: &'a [u8],
+    debug_rnglists: &'a [u8],
+}
+
+fn get_section_data<'a>(module: &'a WasmModule, name: &str) -> &'a [u8] {
+    module
+        .get_custom_sections()
+        .into_iter()
+        .filter(|x| x.get_name().map(|x| x.as_str()) == Some(name))
+        .find_map(|x| match x.get_payload() {
+            CustomSectionPayload::General { payload } => Some(payload.as_slice()),
+            _ => None,
+        })
+        .unwrap_or(&[])
+}
+
+/// モジュールのデバッグ情報
+#[derive(Debug)]
+pub struct DebugInfo {
+    units: Vec<CompilationUnit>,
+}
+
+/// アドレスに対応するソース上のフレーム
+///
+/// インライン展開された関数では、展開先の関数とは別のフレームになる
+#[derive(Debug)]
+pub struct SourceFrame {
+    name: Option<String>,
+    linkage_name: Option<String>,
+    file: Option<String>,
+    line: Option<u64>,
+    column: Option<u64>,
+    inlined: bool,
+}
+
+impl DebugInfo {
+    /// モジュールのDWARFを読む(.debug_infoがない場合はNone)
+    pub fn parse(module: &WasmModule) -> Result<Option<Self>, ParseError> {
+        let sections = DwarfSections {
+            debug_info: get_section_data(module, ".debug_info"),
+            debug_abbrev: get_section_data(module, ".debug_abbrev"),
+            debug_str: get_section_data(module, ".debug_str"),
+            debug_line: get_section_data(module, ".debug_line"),
+            debug_line_str: get_section_data(module, ".debug_line_str"),
+            debug_str_offsets: get_section_data(module, ".debug_str_offsets"),
+            debug_addr: get_section_data(module, ".debug_addr"),
+            debug_ranges: get_section_data(module, ".debug_ranges"),
+            debug_rnglists: get_section_data(module, ".debug_rnglists"),
+        };
+
+        if sections.debug_info.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            units: parse_units(&sections)?,
+        }))
+    }
+
+    pub fn get_units(&self) -> Vec<&CompilationUnit> {
+        self.units.iter().collect()
+    }
+
+    /// アドレスを含む関数を返す
+    pub fn find_function(&self, address: u64) -> Option<&DwarfFunction> {
+        self.units
+            .iter()
+            .flat_map(|x| x.get_functions())
+            .find(|x| x.contains(address))
+    }
+
+    /// アドレスに対応する(ファイルのパス, 行番号表の行)を返す
+    pub fn find_location(&self, address: u64) -> Option<(String, &LineRow)> {
+        self.units.iter().find_map(|unit| {
+            let line_table = unit.get_line_table()?;
+            let row = line_table.find_row(address)?;
+            let path = line_table.get_file_path(row.get_file_index())?;
+            Some((path, row))
+        })
+    }
+
+    /// アドレスに対応するフレームを、内側(インライン展開された関数)から順に返す
+    ///
+    /// 内側のフレームの位置は行番号表から、外側のフレームの位置は展開元(DW_AT_call_*)から求める
+    pub fn find_frames(&self, address: u64) -> Vec<SourceFrame> {
+        let location = self.find_location(address);
+        let mut file = location.as_ref().map(|x| x.0.clone());
+        let mut line = location.as_ref().map(|x| x.1.get_line());
+        let mut column = location.as_ref().map(|x| x.1.get_column());
+
+        let function = match self.find_function(address) {
+            Some(function) => function,
+            None if location.is_some() => {
+                return vec![SourceFrame {
+                    name: None,
+                    linkage_name: None,
+                    file,
+                    line,
+                    column,
+                    inlined: false,
+                }]
+            }
+            None => return Vec::new(),
+        };
+
+        let mut frames: Vec<SourceFrame> = Vec::new();
+        for inlined in function.find_inlined_chain(address).into_iter().rev() {
+            frames.push(SourceFrame {
+                name: inlined.get_name().cloned(),
+                linkage_name: inlined.get_linkage_name().cloned(),
+                file,
+                line,
+                column,
+                inlined: true,
+            });
+            file = inlined.get_call_file().cloned();
+            line = inlined.get_call_line();
+            column = inlined.get_call_column();
+        }
+        frames.push(SourceFrame {
+            name: function.get_name().cloned(),
+            linkage_name: function.get_linkage_name().cloned(),
+            file,
+            line,
+            column,
+            inlined: false,
+        });
+
+        frames
+    }
+}
+
+impl SourceFrame {
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_linkage_name(&self) -> Option<&String> {
+        self.linkage_name.as_ref()
+    }
+
+    pub fn get_file(&self) -> Option<&String> {
+        self.file.as_ref()
+    }
+
+    pub fn get_line(&self) -> Option<u64> {
+        self.line
+    }
+
+    /// 列番号を返す(0は不明)
+    pub fn get_column(&self) -> Option<u64> {
+        self.column
+    }
+
+    /// インライン展開された関数のフレームかどうかを返す
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::abbrev::parse_abbrev_table;
+    use super::form::*;
+    use super::line::LineTable;
+    use super::reader::DwarfReader;
+    use super::*;
+
+    fn cstr(s: &str) -> Vec<u8> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        bytes
+    }
+
+    fn u32le(value: u32) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+
+    // unit_length(32-bit DWARF)を先頭に付ける
+    fn unit(body: &[u8]) -> Vec<u8> {
+        [u32le(body.len() as u32), body.to_vec()].concat()
+    }
+
+    fn sections<'a>(
+        debug_info: &'a [u8],
+        debug_abbrev: &'a [u8],
+        debug_line: &'a [u8],
+    ) -> DwarfSections<'a> {
+        DwarfSections {
+            debug_info,
+            debug_abbrev,
+            debug_str: &[],
+            debug_line,
+            debug_line_str: &[],
+            debug_str_offsets: &[],
+            debug_addr: &[],
+            debug_ranges: &[],
+            debug_rnglists: &[],
+        }
+    }
+
+    // DWARF4の行番号表
+    // ディレクトリ1 "src"、ファイル1 "a.c"(ディレクトリ1)
+    // 行: 0x10 line 10, 0x14 line 11, 0x18 end_sequence
+    fn line_program() -> Vec<u8> {
+        let header = [
+            vec![1, 1, 1, 0xfb, 14, 13], // min_inst_length, max_ops, default_is_stmt, line_base=-5, line_range, opcode_base
+            vec![0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1], // standard_opcode_lengths
+            cstr("src"),
+            vec![0],
+            cstr("a.c"),
+            vec![1, 0, 0], // ディレクトリ番号, mtime, length
+            vec![0],
+        ]
+        .concat();
+        let program = [
+            vec![0x00, 5, 0x02], // DW_LNE_set_address
+            u32le(0x10),
+            vec![0x03, 9],       // DW_LNS_advance_line 9
+            vec![0x01],          // DW_LNS_copy
+            vec![75],            // special opcode: address += 4, line += 1
+            vec![0x02, 4],       // DW_LNS_advance_pc 4
+            vec![0x00, 1, 0x01], // DW_LNE_end_sequence
+        ]
+        .concat();
+        unit(&[vec![4, 0], u32le(header.len() as u32), header, program].concat())
+    }
+
+    fn abbrevs() -> Vec<u8> {
+        [
+            // 1: compile_unit (name, comp_dir, stmt_list, low_pc, high_pc)
+            vec![1, 0x11, 1],
+            vec![
+                0x03, 0x08, 0x1b, 0x08, 0x10, 0x17, 0x11, 0x01, 0x12, 0x06, 0, 0,
+            ],
+            // 2: subprogram (name, low_pc, high_pc, decl_file, decl_line)
+            vec![2, 0x2e, 1],
+            vec![
+                0x03, 0x08, 0x11, 0x01, 0x12, 0x06, 0x3a, 0x0b, 0x3b, 0x0b, 0, 0,
+            ],
+            // 3: inlined_subroutine (abstract_origin, low_pc, high_pc, call_file, call_line, call_column)
+            vec![3, 0x1d, 0],
+            vec![
+                0x31, 0x13, 0x11, 0x01, 0x12, 0x06, 0x58, 0x0b, 0x59, 0x0b, 0x57, 0x0b, 0, 0,
+            ],
+            // 4: 抽象実体のsubprogram (name)
+            vec![4, 0x2e, 0],
+            vec![0x03, 0x08, 0, 0],
+            // 5: variable (name: DW_FORM_indirect)
+            vec![5, 0x34, 0],
+            vec![0x03, 0x16, 0, 0],
+            vec![0],
+        ]
+        .concat()
+    }
+
+    // DWARF4のコンパイル単位
+    // main(0x10..0x30)の中で、inl(0x14..0x18)がa.cの7行目5列目から展開されている
+    fn debug_info() -> Vec<u8> {
+        let header = [vec![4, 0], u32le(0), vec![4]].concat();
+        let cu_die = [
+            vec![1],
+            cstr("a.c"),
+            cstr("/work"),
+            u32le(0),
+            u32le(0),
+            u32le(0x100),
+        ]
+        .concat();
+        // unit_lengthを含むユニット先頭からのオフセット
+        let inl_offset = 4 + header.len() + cu_die.len();
+        let inl_die = [vec![4], cstr("inl")].concat();
+        let main_die = [
+            vec![2],
+            cstr("main"),
+            u32le(0x10),
+            u32le(0x20),
+            vec![1, 3],
+            vec![3],
+            u32le(inl_offset as u32),
+            u32le(0x14),
+            u32le(4),
+            vec![1, 7, 5],
+            vec![0], // mainの子の終わり
+        ]
+        .concat();
+        let var_die = [vec![5, 0x08], cstr("x")].concat();
+
+        unit(&[header, cu_die, inl_die, main_die, var_die, vec![0]].concat())
+    }
+
+    #[test]
+    fn parse_abbrev_table_reads_attrs_and_implicit_const() {
+        // 1: base_type (name: string, byte_size: implicit_const 4)
+        let data = [vec![1, 0x24, 0, 0x03, 0x08, 0x0b, 0x21, 4, 0, 0], vec![0]].concat();
+        let table = parse_abbrev_table(&data, 0).unwrap();
+
+        let abbrev = table.get(&1).unwrap();
+        assert_eq!(abbrev.tag, 0x24);
+        assert!(!abbrev.has_children);
+        assert_eq!(abbrev.attrs.len(), 2);
+        assert_eq!(abbrev.attrs[0].form, DW_FORM_STRING);
+        assert_eq!(abbrev.attrs[1].implicit_const, Some(4));
+    }
+
+    #[test]
+    fn read_attr_value_resolves_indirect_form() {
+        let encoding = Encoding {
+            address_size: 4,
+            is_dwarf64: false,
+            version: 4,
+        };
+
+        let data = [DW_FORM_DATA1 as u8, 0x2a];
+        let mut reader = DwarfReader::new(&data, 0);
+        assert_eq!(
+            read_attr_value(&mut reader, DW_FORM_INDIRECT, None, &encoding).unwrap(),
+            AttrValue::Udata(0x2a)
+        );
+
+        // DW_FORM_indirectがDW_FORM_indirectを指す場合はエラー
+        let data = [DW_FORM_INDIRECT as u8, DW_FORM_DATA1 as u8, 0x2a];
+        let mut reader = DwarfReader::new(&data, 0);
+        assert!(read_attr_value(&mut reader, DW_FORM_INDIRECT, None, &encoding).is_err());
+    }
+
+    #[test]
+    fn line_table_runs_line_program() {
+        let debug_line = line_program();
+        let sections = sections(&[], &[], &debug_line);
+        let comp_dir = String::from("/work");
+        let table = LineTable::parse(&sections, 0, Some(&comp_dir)).unwrap();
+
+        let rows: Vec<(u64, u64, bool)> = table
+            .get_rows()
+            .into_iter()
+            .map(|x| (x.get_address(), x.get_line(), x.is_end_sequence()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![(0x10, 10, false), (0x14, 11, false), (0x18, 11, true)]
+        );
+        assert_eq!(table.find_row(0x16).unwrap().get_line(), 11);
+        assert!(table.find_row(0x18).is_none());
+        assert_eq!(table.get_file_path(1), Some(String::from("/work/src/a.c")));
+    }
+
+    #[test]
+    fn parse_units_reads_functions_and_inlined_frames() {
+        let (debug_info, debug_abbrev, debug_line) = (debug_info(), abbrevs(), line_program());
+        let sections = sections(&debug_info, &debug_abbrev, &debug_line);
+        let debug_info = DebugInfo {
+            units: parse_units(&sections).unwrap(),
+        };
+
+        let unit = debug_info.get_units()[0];
+        assert_eq!(unit.get_name(), Some(&String::from("a.c")));
+        assert_eq!(unit.get_comp_dir(), Some(&String::from("/work")));
+
+        let function = debug_info.find_function(0x16).unwrap();
+        assert_eq!(function.get_name(), Some(&String::from("main")));
+        assert_eq!(
+            (function.get_low_pc(), function.get_high_pc()),
+            (0x10, 0x30)
+        );
+        assert_eq!(function.get_decl_line(), Some(3));
+        assert!(debug_info.find_function(0x30).is_none());
+
+        let frames = debug_info.find_frames(0x16);
+        let frames: Vec<(String, u64, u64, bool)> = frames
+            .iter()
+            .map(|x| {
+                (
+                    x.get_name().cloned().unwrap_or_default(),
+                    x.get_line().unwrap_or(0),
+                    x.get_column().unwrap_or(0),
+                    x.is_inlined(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (String::from("inl"), 11, 0, true),
+                (String::from("main"), 7, 5, false),
+            ]
+        );
+    }
+}
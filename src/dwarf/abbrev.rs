@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use super::form::DW_FORM_IMPLICIT_CONST;
+use super::reader::DwarfReader;
+
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * .debug_abbrev
+ *
+ * DIEの構造(タグ・属性と値のエンコーディング)の定義
+ */
+
+#[derive(Debug)]
+pub(super) struct Abbrev {
+    pub tag: u64,
+    pub has_children: bool,
+    pub attrs: Vec<AbbrevAttr>,
+}
+
+#[derive(Debug)]
+pub(super) struct AbbrevAttr {
+    pub name: u64,
+    pub form: u64,
+    pub implicit_const: Option<i64>,
+}
+
+/// offsetから始まる略語表を読み、コード -> 定義 の対応を返す
+pub(super) fn parse_abbrev_table(
+    debug_abbrev: &[u8],
+    offset: u64,
+) -> Result<HashMap<u64, Abbrev>, ParseError> {
+    let mut reader = DwarfReader::new(debug_abbrev, offset as usize);
+    let mut table: HashMap<u64, Abbrev> = HashMap::new();
+
+    loop {
+        let code = reader.read_uleb128()?;
+        if code == 0 {
+            break;
+        }
+
+        let tag = reader.read_uleb128()?;
+        let has_children = reader.read_u8()? != 0;
+
+        let mut attrs: Vec<AbbrevAttr> = Vec::new();
+        loop {
+            let name = reader.read_uleb128()?;
+            let form = reader.read_uleb128()?;
+            if name == 0 && form == 0 {
+                break;
+            }
+
+            let implicit_const = if form == DW_FORM_IMPLICIT_CONST {
+                Some(reader.read_sleb128()?)
+            } else {
+                None
+            };
+            attrs.push(AbbrevAttr {
+                name,
+                form,
+                implicit_const,
+            });
+        }
+
+        table.insert(
+            code,
+            Abbrev {
+                tag,
+                has_children,
+                attrs,
+            },
+        );
+    }
+
+    Ok(table)
+}
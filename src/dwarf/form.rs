@@ -0,0 +1,200 @@
+use super::reader::DwarfReader;
+
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * DWARFの属性値のエンコーディング(DW_FORM_*)
+ */
+
+pub(super) const DW_FORM_ADDR: u64 = 0x01;
+pub(super) const DW_FORM_BLOCK2: u64 = 0x03;
+pub(super) const DW_FORM_BLOCK4: u64 = 0x04;
+pub(super) const DW_FORM_DATA2: u64 = 0x05;
+pub(super) const DW_FORM_DATA4: u64 = 0x06;
+pub(super) const DW_FORM_DATA8: u64 = 0x07;
+pub(super) const DW_FORM_STRING: u64 = 0x08;
+pub(super) const DW_FORM_BLOCK: u64 = 0x09;
+pub(super) const DW_FORM_BLOCK1: u64 = 0x0a;
+pub(super) const DW_FORM_DATA1: u64 = 0x0b;
+pub(super) const DW_FORM_FLAG: u64 = 0x0c;
+pub(super) const DW_FORM_SDATA: u64 = 0x0d;
+pub(super) const DW_FORM_STRP: u64 = 0x0e;
+pub(super) const DW_FORM_UDATA: u64 = 0x0f;
+pub(super) const DW_FORM_REF_ADDR: u64 = 0x10;
+pub(super) const DW_FORM_REF1: u64 = 0x11;
+pub(super) const DW_FORM_REF2: u64 = 0x12;
+pub(super) const DW_FORM_REF4: u64 = 0x13;
+pub(super) const DW_FORM_REF8: u64 = 0x14;
+pub(super) const DW_FORM_REF_UDATA: u64 = 0x15;
+pub(super) const DW_FORM_INDIRECT: u64 = 0x16;
+pub(super) const DW_FORM_SEC_OFFSET: u64 = 0x17;
+pub(super) const DW_FORM_EXPRLOC: u64 = 0x18;
+pub(super) const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+pub(super) const DW_FORM_STRX: u64 = 0x1a;
+pub(super) const DW_FORM_ADDRX: u64 = 0x1b;
+pub(super) const DW_FORM_REF_SUP4: u64 = 0x1c;
+pub(super) const DW_FORM_STRP_SUP: u64 = 0x1d;
+pub(super) const DW_FORM_DATA16: u64 = 0x1e;
+pub(super) const DW_FORM_LINE_STRP: u64 = 0x1f;
+pub(super) const DW_FORM_REF_SIG8: u64 = 0x20;
+pub(super) const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+pub(super) const DW_FORM_LOCLISTX: u64 = 0x22;
+pub(super) const DW_FORM_RNGLISTX: u64 = 0x23;
+pub(super) const DW_FORM_REF_SUP8: u64 = 0x24;
+pub(super) const DW_FORM_STRX1: u64 = 0x25;
+pub(super) const DW_FORM_STRX2: u64 = 0x26;
+pub(super) const DW_FORM_STRX3: u64 = 0x27;
+pub(super) const DW_FORM_STRX4: u64 = 0x28;
+pub(super) const DW_FORM_ADDRX1: u64 = 0x29;
+pub(super) const DW_FORM_ADDRX2: u64 = 0x2a;
+pub(super) const DW_FORM_ADDRX3: u64 = 0x2b;
+pub(super) const DW_FORM_ADDRX4: u64 = 0x2c;
+
+/// 属性値
+///
+/// 文字列やアドレスのインデックスは、ユニットのstr_offsets_base/addr_baseが
+/// 分かるまで解決できないため、そのままの形で持つ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum AttrValue {
+    Addr(u64),
+    AddrIndex(u64),
+    Udata(u64),
+    Sdata(i64),
+    Flag(bool),
+    String(String),
+    StrOffset(u64),     // .debug_str
+    LineStrOffset(u64), // .debug_line_str
+    StrIndex(u64),      // .debug_str_offsets
+    SecOffset(u64),
+    RangeListIndex(u64), // .debug_rnglists
+    UnitRef(u64),        // ユニット先頭からのオフセット
+    Block,               // 式やブロックは読み飛ばす
+    Other,
+}
+
+/// 値を読むために必要なユニットの情報
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Encoding {
+    pub address_size: u8,
+    pub is_dwarf64: bool,
+    pub version: u16,
+}
+
+pub(super) fn read_attr_value(
+    reader: &mut DwarfReader,
+    form: u64,
+    implicit_const: Option<i64>,
+    encoding: &Encoding,
+) -> Result<AttrValue, ParseError> {
+    let value = match form {
+        DW_FORM_ADDR => AttrValue::Addr(reader.read_sized(encoding.address_size)?),
+        DW_FORM_ADDRX => AttrValue::AddrIndex(reader.read_uleb128()?),
+        DW_FORM_ADDRX1 => AttrValue::AddrIndex(reader.read_sized(1)?),
+        DW_FORM_ADDRX2 => AttrValue::AddrIndex(reader.read_sized(2)?),
+        DW_FORM_ADDRX3 => AttrValue::AddrIndex(reader.read_sized(3)?),
+        DW_FORM_ADDRX4 => AttrValue::AddrIndex(reader.read_sized(4)?),
+
+        DW_FORM_DATA1 => AttrValue::Udata(reader.read_sized(1)?),
+        DW_FORM_DATA2 => AttrValue::Udata(reader.read_sized(2)?),
+        DW_FORM_DATA4 => AttrValue::Udata(reader.read_sized(4)?),
+        DW_FORM_DATA8 => AttrValue::Udata(reader.read_sized(8)?),
+        DW_FORM_DATA16 => {
+            reader.skip(16)?;
+            AttrValue::Other
+        }
+        DW_FORM_UDATA => AttrValue::Udata(reader.read_uleb128()?),
+        DW_FORM_SDATA => AttrValue::Sdata(reader.read_sleb128()?),
+        DW_FORM_IMPLICIT_CONST => AttrValue::Sdata(implicit_const.unwrap_or(0)),
+
+        DW_FORM_FLAG => AttrValue::Flag(reader.read_u8()? != 0),
+        DW_FORM_FLAG_PRESENT => AttrValue::Flag(true),
+
+        DW_FORM_STRING => AttrValue::String(reader.read_cstr()?),
+        DW_FORM_STRP => AttrValue::StrOffset(reader.read_offset(encoding.is_dwarf64)?),
+        DW_FORM_LINE_STRP => AttrValue::LineStrOffset(reader.read_offset(encoding.is_dwarf64)?),
+        DW_FORM_STRX => AttrValue::StrIndex(reader.read_uleb128()?),
+        DW_FORM_STRX1 => AttrValue::StrIndex(reader.read_sized(1)?),
+        DW_FORM_STRX2 => AttrValue::StrIndex(reader.read_sized(2)?),
+        DW_FORM_STRX3 => AttrValue::StrIndex(reader.read_sized(3)?),
+        DW_FORM_STRX4 => AttrValue::StrIndex(reader.read_sized(4)?),
+        DW_FORM_STRP_SUP => {
+            reader.read_offset(encoding.is_dwarf64)?;
+            AttrValue::Other
+        }
+
+        DW_FORM_REF1 => AttrValue::UnitRef(reader.read_sized(1)?),
+        DW_FORM_REF2 => AttrValue::UnitRef(reader.read_sized(2)?),
+        DW_FORM_REF4 => AttrValue::UnitRef(reader.read_sized(4)?),
+        DW_FORM_REF8 => AttrValue::UnitRef(reader.read_sized(8)?),
+        DW_FORM_REF_UDATA => AttrValue::UnitRef(reader.read_uleb128()?),
+        DW_FORM_REF_ADDR => {
+            // DWARF2ではアドレスサイズ、それ以降はオフセットサイズ
+            if encoding.version <= 2 {
+                reader.read_sized(encoding.address_size)?;
+            } else {
+                reader.read_offset(encoding.is_dwarf64)?;
+            }
+            AttrValue::Other
+        }
+        DW_FORM_REF_SIG8 => {
+            reader.skip(8)?;
+            AttrValue::Other
+        }
+        DW_FORM_REF_SUP4 => {
+            reader.skip(4)?;
+            AttrValue::Other
+        }
+        DW_FORM_REF_SUP8 => {
+            reader.skip(8)?;
+            AttrValue::Other
+        }
+
+        DW_FORM_SEC_OFFSET => AttrValue::SecOffset(reader.read_offset(encoding.is_dwarf64)?),
+        DW_FORM_RNGLISTX => AttrValue::RangeListIndex(reader.read_uleb128()?),
+        DW_FORM_LOCLISTX => {
+            reader.read_uleb128()?;
+            AttrValue::Other
+        }
+
+        DW_FORM_BLOCK1 => {
+            let size = reader.read_sized(1)?;
+            reader.skip(size as usize)?;
+            AttrValue::Block
+        }
+        DW_FORM_BLOCK2 => {
+            let size = reader.read_sized(2)?;
+            reader.skip(size as usize)?;
+            AttrValue::Block
+        }
+        DW_FORM_BLOCK4 => {
+            let size = reader.read_sized(4)?;
+            reader.skip(size as usize)?;
+            AttrValue::Block
+        }
+        DW_FORM_BLOCK | DW_FORM_EXPRLOC => {
+            let size = reader.read_uleb128()?;
+            reader.skip(size as usize)?;
+            AttrValue::Block
+        }
+
+        DW_FORM_INDIRECT => {
+            // 実際のformがさらにDW_FORM_indirectの場合は、再帰が止まらないためエラーにする
+            let actual_form = reader.read_uleb128()?;
+            if actual_form == DW_FORM_INDIRECT {
+                return Err(ParseError::FormatError(String::from(
+                    "DWARF: DW_FORM_indirect refers to DW_FORM_indirect",
+                )));
+            }
+            return read_attr_value(reader, actual_form, implicit_const, encoding);
+        }
+
+        _ => {
+            return Err(ParseError::FormatError(format!(
+                "DWARF: unknown form: {:#x}",
+                form
+            )))
+        }
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,667 @@
+use std::collections::HashMap;
+
+use super::abbrev::parse_abbrev_table;
+use super::form::{read_attr_value, AttrValue, Encoding};
+use super::line::LineTable;
+use super::ranges::{read_ranges, read_rnglist};
+use super::reader::{read_str_at, DwarfReader};
+use super::DwarfSections;
+
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * .debug_info
+ *
+ * コンパイル単位(CU)と、その中の関数(DW_TAG_subprogram)、
+ * 関数内でインライン展開された関数(DW_TAG_inlined_subroutine)を読む
+ */
+
+const DW_TAG_COMPILE_UNIT: u64 = 0x11;
+const DW_TAG_INLINED_SUBROUTINE: u64 = 0x1d;
+const DW_TAG_SUBPROGRAM: u64 = 0x2e;
+const DW_TAG_PARTIAL_UNIT: u64 = 0x3c;
+const DW_TAG_SKELETON_UNIT: u64 = 0x4a;
+
+const DW_AT_NAME: u64 = 0x03;
+const DW_AT_STMT_LIST: u64 = 0x10;
+const DW_AT_LOW_PC: u64 = 0x11;
+const DW_AT_HIGH_PC: u64 = 0x12;
+const DW_AT_LANGUAGE: u64 = 0x13;
+const DW_AT_COMP_DIR: u64 = 0x1b;
+const DW_AT_PRODUCER: u64 = 0x25;
+const DW_AT_ABSTRACT_ORIGIN: u64 = 0x31;
+const DW_AT_DECL_FILE: u64 = 0x3a;
+const DW_AT_DECL_LINE: u64 = 0x3b;
+const DW_AT_SPECIFICATION: u64 = 0x47;
+const DW_AT_RANGES: u64 = 0x55;
+const DW_AT_CALL_COLUMN: u64 = 0x57;
+const DW_AT_CALL_FILE: u64 = 0x58;
+const DW_AT_CALL_LINE: u64 = 0x59;
+const DW_AT_LINKAGE_NAME: u64 = 0x6e;
+const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+const DW_AT_ADDR_BASE: u64 = 0x73;
+const DW_AT_RNGLISTS_BASE: u64 = 0x74;
+const DW_AT_MIPS_LINKAGE_NAME: u64 = 0x2007;
+
+const DW_UT_COMPILE: u8 = 1;
+const DW_UT_TYPE: u8 = 2;
+const DW_UT_PARTIAL: u8 = 3;
+const DW_UT_SKELETON: u8 = 4;
+const DW_UT_SPLIT_COMPILE: u8 = 5;
+const DW_UT_SPLIT_TYPE: u8 = 6;
+
+/// コンパイル単位
+#[derive(Debug)]
+pub struct CompilationUnit {
+    offset: u64, // .debug_info内のオフセット
+    version: u16,
+    address_size: u8,
+    name: Option<String>,
+    comp_dir: Option<String>,
+    producer: Option<String>,
+    language: Option<u64>,
+    functions: Vec<DwarfFunction>,
+    line_table: Option<LineTable>,
+}
+
+/// 関数(DW_TAG_subprogram)
+///
+/// アドレスはコードセクションのペイロード先頭からのオフセット
+#[derive(Debug)]
+pub struct DwarfFunction {
+    name: Option<String>,
+    linkage_name: Option<String>,
+    low_pc: u64,
+    high_pc: u64,
+    decl_file: Option<String>,
+    decl_line: Option<u64>,
+    inlined: Vec<InlinedFunction>,
+}
+
+/// インライン展開された関数(DW_TAG_inlined_subroutine)
+///
+/// call_*は展開元(呼び出し側)のソース上の位置
+#[derive(Debug)]
+pub struct InlinedFunction {
+    name: Option<String>,
+    linkage_name: Option<String>,
+    ranges: Vec<(u64, u64)>,
+    call_file: Option<String>,
+    call_line: Option<u64>,
+    call_column: Option<u64>,
+    depth: usize, // 関数のDIEからの木の深さ
+}
+
+// 属性値を読み終えたDIE
+struct Die {
+    offset: u64, // ユニット先頭からのオフセット
+    depth: usize,
+    tag: u64,
+    attrs: Vec<(u64, AttrValue)>,
+}
+
+impl Die {
+    fn get_attr(&self, name: u64) -> Option<&AttrValue> {
+        self.attrs.iter().find(|x| x.0 == name).map(|x| &x.1)
+    }
+}
+
+// 文字列・アドレスのインデックスを解決するためのユニットの情報
+struct UnitContext<'a> {
+    sections: &'a DwarfSections<'a>,
+    encoding: Encoding,
+    str_offsets_base: u64,
+    addr_base: u64,
+    rnglists_base: u64,
+    base_address: u64, // ユニットのDW_AT_low_pc
+}
+
+impl<'a> UnitContext<'a> {
+    fn resolve_string(&self, value: &AttrValue) -> Result<Option<String>, ParseError> {
+        let s = match value {
+            AttrValue::String(s) => s.clone(),
+            AttrValue::StrOffset(offset) => read_str_at(self.sections.debug_str, *offset)?,
+            AttrValue::LineStrOffset(offset) => read_str_at(self.sections.debug_line_str, *offset)?,
+            AttrValue::StrIndex(index) => {
+                let offset_size = if self.encoding.is_dwarf64 { 8 } else { 4 };
+                let pos = self
+                    .str_offsets_base
+                    .wrapping_add(index.wrapping_mul(offset_size));
+                let offset = DwarfReader::new(self.sections.debug_str_offsets, pos as usize)
+                    .read_offset(self.encoding.is_dwarf64)?;
+                read_str_at(self.sections.debug_str, offset)?
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(s))
+    }
+
+    fn resolve_address(&self, value: &AttrValue) -> Result<Option<u64>, ParseError> {
+        match value {
+            AttrValue::Addr(addr) => Ok(Some(*addr)),
+            AttrValue::AddrIndex(index) => {
+                let pos = self
+                    .addr_base
+                    .wrapping_add(index.wrapping_mul(self.encoding.address_size as u64));
+                let addr = DwarfReader::new(self.sections.debug_addr, pos as usize)
+                    .read_sized(self.encoding.address_size)?;
+                Ok(Some(addr))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn resolve_constant(&self, value: &AttrValue) -> Option<u64> {
+        match value {
+            AttrValue::Udata(v) => Some(*v),
+            AttrValue::Sdata(v) => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    /// DW_AT_rangesの値から範囲のリストを読む
+    fn resolve_ranges(&self, value: &AttrValue) -> Result<Vec<(u64, u64)>, ParseError> {
+        let resolve_addrx = |index: u64| -> Result<u64, ParseError> {
+            Ok(self
+                .resolve_address(&AttrValue::AddrIndex(index))?
+                .unwrap_or(0))
+        };
+
+        match value {
+            AttrValue::SecOffset(offset) | AttrValue::Udata(offset) => {
+                if self.encoding.version >= 5 {
+                    read_rnglist(
+                        self.sections.debug_rnglists,
+                        *offset,
+                        &self.encoding,
+                        self.base_address,
+                        &resolve_addrx,
+                    )
+                } else {
+                    read_ranges(
+                        self.sections.debug_ranges,
+                        *offset,
+                        &self.encoding,
+                        self.base_address,
+                    )
+                }
+            }
+            AttrValue::RangeListIndex(index) => {
+                // オフセット表はrnglists_baseから始まり、値はrnglists_baseからの相対位置
+                let offset_size = if self.encoding.is_dwarf64 { 8 } else { 4 };
+                let pos = self
+                    .rnglists_base
+                    .wrapping_add(index.wrapping_mul(offset_size));
+                let offset = DwarfReader::new(self.sections.debug_rnglists, pos as usize)
+                    .read_offset(self.encoding.is_dwarf64)?;
+                read_rnglist(
+                    self.sections.debug_rnglists,
+                    self.rnglists_base.wrapping_add(offset),
+                    &self.encoding,
+                    self.base_address,
+                    &resolve_addrx,
+                )
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// DW_AT_low_pc/DW_AT_high_pcの範囲を返す
+    ///
+    /// low_pcがない、またはリンク時に削除された(tombstone)場合はNone
+    fn resolve_pc_range(&self, die: &Die) -> Result<Option<(u64, u64)>, ParseError> {
+        let tombstone = match self.encoding.address_size {
+            8 => u64::MAX,
+            _ => u32::MAX as u64,
+        };
+
+        let low_pc = match die.get_attr(DW_AT_LOW_PC) {
+            Some(value) => self.resolve_address(value)?,
+            None => None,
+        };
+        let low_pc = match low_pc {
+            Some(low_pc) if low_pc != tombstone => low_pc,
+            _ => return Ok(None),
+        };
+
+        // high_pcは定数の場合low_pcからの長さ、アドレスの場合は終端アドレス
+        let high_pc = match die.get_attr(DW_AT_HIGH_PC) {
+            Some(value) => match self.resolve_address(value)? {
+                Some(addr) => addr,
+                None => low_pc.wrapping_add(self.resolve_constant(value).unwrap_or(0)),
+            },
+            None => low_pc,
+        };
+
+        Ok(Some((low_pc, high_pc)))
+    }
+}
+
+/// .debug_infoのすべてのコンパイル単位を読む
+pub(super) fn parse_units(sections: &DwarfSections) -> Result<Vec<CompilationUnit>, ParseError> {
+    let mut units: Vec<CompilationUnit> = Vec::new();
+    let mut reader = DwarfReader::new(sections.debug_info, 0);
+
+    while !reader.is_empty() {
+        let unit_offset = reader.get_position();
+        let (unit_length, is_dwarf64) = reader.read_unit_length()?;
+        let unit_end = reader.get_position().saturating_add(unit_length as usize);
+
+        let version = reader.read_u16()?;
+        let unit_type: u8;
+        let address_size: u8;
+        let abbrev_offset: u64;
+        if version >= 5 {
+            unit_type = reader.read_u8()?;
+            address_size = reader.read_u8()?;
+            abbrev_offset = reader.read_offset(is_dwarf64)?;
+            match unit_type {
+                DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => reader.skip(8)?, // dwo_id
+                DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+                    reader.skip(8)?; // type_signature
+                    reader.read_offset(is_dwarf64)?; // type_offset
+                }
+                _ => (),
+            }
+        } else if version >= 2 {
+            unit_type = DW_UT_COMPILE;
+            abbrev_offset = reader.read_offset(is_dwarf64)?;
+            address_size = reader.read_u8()?;
+        } else {
+            return Err(ParseError::FormatError(format!(
+                "DWARF: unsupported unit version: {}",
+                version
+            )));
+        }
+
+        if matches!(
+            unit_type,
+            DW_UT_COMPILE | DW_UT_PARTIAL | DW_UT_SKELETON | DW_UT_SPLIT_COMPILE
+        ) {
+            let encoding = Encoding {
+                address_size,
+                is_dwarf64,
+                version,
+            };
+            let dies = read_dies(
+                sections,
+                &mut reader,
+                unit_offset,
+                unit_end,
+                abbrev_offset,
+                &encoding,
+            )?;
+            if let Some(unit) =
+                CompilationUnit::from_dies(sections, unit_offset as u64, encoding, dies)?
+            {
+                units.push(unit);
+            }
+        }
+
+        reader.set_position(unit_end);
+    }
+
+    Ok(units)
+}
+
+// ユニット内のすべてのDIEを順に読む (木構造は各DIEの深さで表す)
+fn read_dies(
+    sections: &DwarfSections,
+    reader: &mut DwarfReader,
+    unit_offset: usize,
+    unit_end: usize,
+    abbrev_offset: u64,
+    encoding: &Encoding,
+) -> Result<Vec<Die>, ParseError> {
+    let abbrevs = parse_abbrev_table(sections.debug_abbrev, abbrev_offset)?;
+
+    let mut dies: Vec<Die> = Vec::new();
+    let mut depth: usize = 0;
+    while reader.get_position() < unit_end {
+        let die_offset = (reader.get_position() - unit_offset) as u64;
+        let code = reader.read_uleb128()?;
+        if code == 0 {
+            // 子の列の終わり
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+
+        let abbrev = match abbrevs.get(&code) {
+            Some(abbrev) => abbrev,
+            None => {
+                return Err(ParseError::FormatError(format!(
+                    "DWARF: abbrev code {} not found",
+                    code
+                )))
+            }
+        };
+
+        let mut attrs: Vec<(u64, AttrValue)> = Vec::new();
+        for attr in abbrev.attrs.iter() {
+            let value = read_attr_value(reader, attr.form, attr.implicit_const, encoding)?;
+            attrs.push((attr.name, value));
+        }
+
+        dies.push(Die {
+            offset: die_offset,
+            depth,
+            tag: abbrev.tag,
+            attrs,
+        });
+        if abbrev.has_children {
+            depth += 1;
+        }
+    }
+
+    Ok(dies)
+}
+
+impl CompilationUnit {
+    fn from_dies(
+        sections: &DwarfSections,
+        offset: u64,
+        encoding: Encoding,
+        dies: Vec<Die>,
+    ) -> Result<Option<Self>, ParseError> {
+        let unit_die = match dies.first() {
+            Some(die)
+                if matches!(
+                    die.tag,
+                    DW_TAG_COMPILE_UNIT | DW_TAG_PARTIAL_UNIT | DW_TAG_SKELETON_UNIT
+                ) =>
+            {
+                die
+            }
+            _ => return Ok(None),
+        };
+
+        // DWARF5のヘッダの大きさ(.debug_rnglistsは12バイト、それ以外は8バイト)をベースの既定値とする
+        let base_of = |name: u64, default: u64| match unit_die.get_attr(name) {
+            Some(AttrValue::SecOffset(v)) | Some(AttrValue::Udata(v)) => *v,
+            _ => default,
+        };
+        let mut ctx = UnitContext {
+            sections,
+            encoding,
+            str_offsets_base: base_of(DW_AT_STR_OFFSETS_BASE, 8),
+            addr_base: base_of(DW_AT_ADDR_BASE, 8),
+            rnglists_base: base_of(DW_AT_RNGLISTS_BASE, 12),
+            base_address: 0,
+        };
+        if let Some(value) = unit_die.get_attr(DW_AT_LOW_PC) {
+            ctx.base_address = ctx.resolve_address(value)?.unwrap_or(0);
+        }
+
+        let mut name: Option<String> = None;
+        let mut comp_dir: Option<String> = None;
+        let mut producer: Option<String> = None;
+        let mut language: Option<u64> = None;
+        let mut stmt_list: Option<u64> = None;
+        for (attr_name, value) in unit_die.attrs.iter() {
+            match *attr_name {
+                DW_AT_NAME => name = ctx.resolve_string(value)?,
+                DW_AT_COMP_DIR => comp_dir = ctx.resolve_string(value)?,
+                DW_AT_PRODUCER => producer = ctx.resolve_string(value)?,
+                DW_AT_LANGUAGE => language = ctx.resolve_constant(value),
+                DW_AT_STMT_LIST => {
+                    stmt_list = match value {
+                        AttrValue::SecOffset(v) | AttrValue::Udata(v) => Some(*v),
+                        _ => None,
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let line_table = match stmt_list {
+            Some(stmt_list) if !sections.debug_line.is_empty() => {
+                Some(LineTable::parse(sections, stmt_list, comp_dir.as_ref())?)
+            }
+            _ => None,
+        };
+
+        // DW_AT_specification/DW_AT_abstract_originの参照先から名前を引けるようにする
+        let mut names: HashMap<u64, (Option<String>, Option<String>)> = HashMap::new();
+        let mut refs: HashMap<u64, u64> = HashMap::new();
+        for die in dies.iter() {
+            names.insert(die.offset, read_names(&ctx, die)?);
+            for attr in [DW_AT_SPECIFICATION, DW_AT_ABSTRACT_ORIGIN].iter() {
+                if let Some(AttrValue::UnitRef(target)) = die.get_attr(*attr) {
+                    refs.insert(die.offset, *target);
+                }
+            }
+        }
+        // 参照をたどり、欠けている名前を補う (抽象実体 -> 宣言 のように連鎖する)
+        let find_names = |die: &Die| {
+            let (mut name, mut linkage_name) = names.get(&die.offset).cloned().unwrap_or_default();
+            let mut current = die.offset;
+            for _ in 0..8 {
+                current = match refs.get(&current) {
+                    Some(target) => *target,
+                    None => break,
+                };
+                if let Some((n, l)) = names.get(&current) {
+                    name = name.or_else(|| n.clone());
+                    linkage_name = linkage_name.or_else(|| l.clone());
+                }
+            }
+            (name, linkage_name)
+        };
+
+        let file_path = |value: Option<&AttrValue>| match (value, line_table.as_ref()) {
+            (Some(value), Some(line_table)) => ctx
+                .resolve_constant(value)
+                .and_then(|x| line_table.get_file_path(x)),
+            _ => None,
+        };
+
+        let mut functions: Vec<DwarfFunction> = Vec::new();
+        for (idx, die) in dies.iter().enumerate() {
+            if die.tag != DW_TAG_SUBPROGRAM {
+                continue;
+            }
+            // 宣言のみ、またはリンク時に削除された関数は対象外
+            let (low_pc, high_pc) = match ctx.resolve_pc_range(die)? {
+                Some(range) => range,
+                None => continue,
+            };
+
+            let (func_name, linkage_name) = find_names(die);
+
+            let decl_line = die
+                .get_attr(DW_AT_DECL_LINE)
+                .and_then(|x| ctx.resolve_constant(x));
+
+            // 関数のDIEの子孫にあるインライン展開
+            let mut inlined: Vec<InlinedFunction> = Vec::new();
+            for child in dies[idx + 1..]
+                .iter()
+                .take_while(|x| x.depth > die.depth)
+                .filter(|x| x.tag == DW_TAG_INLINED_SUBROUTINE)
+            {
+                let ranges = match child.get_attr(DW_AT_RANGES) {
+                    Some(value) => ctx.resolve_ranges(value)?,
+                    None => ctx.resolve_pc_range(child)?.into_iter().collect(),
+                };
+                if ranges.is_empty() {
+                    continue;
+                }
+
+                let (name, linkage_name) = find_names(child);
+                inlined.push(InlinedFunction {
+                    name,
+                    linkage_name,
+                    ranges,
+                    call_file: file_path(child.get_attr(DW_AT_CALL_FILE)),
+                    call_line: child
+                        .get_attr(DW_AT_CALL_LINE)
+                        .and_then(|x| ctx.resolve_constant(x)),
+                    call_column: child
+                        .get_attr(DW_AT_CALL_COLUMN)
+                        .and_then(|x| ctx.resolve_constant(x)),
+                    depth: child.depth - die.depth,
+                });
+            }
+
+            functions.push(DwarfFunction {
+                name: func_name,
+                linkage_name,
+                low_pc,
+                high_pc,
+                decl_file: file_path(die.get_attr(DW_AT_DECL_FILE)),
+                decl_line,
+                inlined,
+            });
+        }
+
+        Ok(Some(Self {
+            offset,
+            version: encoding.version,
+            address_size: encoding.address_size,
+            name,
+            comp_dir,
+            producer,
+            language,
+            functions,
+            line_table,
+        }))
+    }
+
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn get_version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn get_address_size(&self) -> u8 {
+        self.address_size
+    }
+
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_comp_dir(&self) -> Option<&String> {
+        self.comp_dir.as_ref()
+    }
+
+    pub fn get_producer(&self) -> Option<&String> {
+        self.producer.as_ref()
+    }
+
+    /// DW_LANG_*の値を返す
+    pub fn get_language(&self) -> Option<u64> {
+        self.language
+    }
+
+    pub fn get_functions(&self) -> Vec<&DwarfFunction> {
+        self.functions.iter().collect()
+    }
+
+    pub fn get_line_table(&self) -> Option<&LineTable> {
+        self.line_table.as_ref()
+    }
+}
+
+// DIEの(名前, リンケージ名)を返す
+fn read_names(
+    ctx: &UnitContext,
+    die: &Die,
+) -> Result<(Option<String>, Option<String>), ParseError> {
+    let name = match die.get_attr(DW_AT_NAME) {
+        Some(value) => ctx.resolve_string(value)?,
+        None => None,
+    };
+    let linkage_name = match die
+        .get_attr(DW_AT_LINKAGE_NAME)
+        .or_else(|| die.get_attr(DW_AT_MIPS_LINKAGE_NAME))
+    {
+        Some(value) => ctx.resolve_string(value)?,
+        None => None,
+    };
+
+    Ok((name, linkage_name))
+}
+
+impl DwarfFunction {
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_linkage_name(&self) -> Option<&String> {
+        self.linkage_name.as_ref()
+    }
+
+    pub fn get_low_pc(&self) -> u64 {
+        self.low_pc
+    }
+
+    pub fn get_high_pc(&self) -> u64 {
+        self.high_pc
+    }
+
+    pub fn get_decl_file(&self) -> Option<&String> {
+        self.decl_file.as_ref()
+    }
+
+    pub fn get_decl_line(&self) -> Option<u64> {
+        self.decl_line
+    }
+
+    pub fn get_inlined_functions(&self) -> Vec<&InlinedFunction> {
+        self.inlined.iter().collect()
+    }
+
+    /// アドレスを含むインライン展開を、外側から順に返す
+    pub fn find_inlined_chain(&self, address: u64) -> Vec<&InlinedFunction> {
+        let mut chain: Vec<&InlinedFunction> = self
+            .inlined
+            .iter()
+            .filter(|x| x.contains(address))
+            .collect();
+        chain.sort_by_key(|x| x.depth);
+
+        chain
+    }
+
+    /// アドレスが関数の範囲内かどうかを返す
+    pub fn contains(&self, address: u64) -> bool {
+        self.low_pc <= address && address < self.high_pc
+    }
+}
+
+impl InlinedFunction {
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_linkage_name(&self) -> Option<&String> {
+        self.linkage_name.as_ref()
+    }
+
+    pub fn get_ranges(&self) -> &[(u64, u64)] {
+        &self.ranges
+    }
+
+    pub fn get_call_file(&self) -> Option<&String> {
+        self.call_file.as_ref()
+    }
+
+    pub fn get_call_line(&self) -> Option<u64> {
+        self.call_line
+    }
+
+    pub fn get_call_column(&self) -> Option<u64> {
+        self.call_column
+    }
+
+    /// アドレスが展開された範囲内かどうかを返す
+    pub fn contains(&self, address: u64) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| *start <= address && address < *end)
+    }
+}
@@ -0,0 +1,402 @@
+use super::form::{read_attr_value, AttrValue, Encoding};
+use super::reader::{read_str_at, DwarfReader};
+use super::DwarfSections;
+
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * .debug_line
+ *
+ * 行番号プログラムを実行し、アドレス -> (ファイル, 行, 列) の表を作る
+ * DWARF 2-5 に対応する
+ */
+
+const DW_LNS_COPY: u8 = 1;
+const DW_LNS_ADVANCE_PC: u8 = 2;
+const DW_LNS_ADVANCE_LINE: u8 = 3;
+const DW_LNS_SET_FILE: u8 = 4;
+const DW_LNS_SET_COLUMN: u8 = 5;
+const DW_LNS_NEGATE_STMT: u8 = 6;
+const DW_LNS_SET_BASIC_BLOCK: u8 = 7;
+const DW_LNS_CONST_ADD_PC: u8 = 8;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 9;
+const DW_LNS_SET_PROLOGUE_END: u8 = 10;
+const DW_LNS_SET_EPILOGUE_BEGIN: u8 = 11;
+const DW_LNS_SET_ISA: u8 = 12;
+
+const DW_LNE_END_SEQUENCE: u8 = 1;
+const DW_LNE_SET_ADDRESS: u8 = 2;
+const DW_LNE_DEFINE_FILE: u8 = 3;
+
+const DW_LNCT_PATH: u64 = 1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 2;
+
+/// 行番号表
+#[derive(Debug)]
+pub struct LineTable {
+    version: u16,
+    directories: Vec<String>,
+    files: Vec<LineFile>,
+    rows: Vec<LineRow>,
+}
+
+#[derive(Debug)]
+pub struct LineFile {
+    name: String,
+    directory_index: u64,
+}
+
+/// 行番号表の1行
+///
+/// addressはコードセクションのペイロード先頭からのオフセット
+#[derive(Debug, Clone, Copy)]
+pub struct LineRow {
+    address: u64,
+    file_index: u64,
+    line: u64,
+    column: u64,
+    is_stmt: bool,
+    end_sequence: bool,
+}
+
+impl LineTable {
+    pub(super) fn parse(
+        sections: &DwarfSections,
+        offset: u64,
+        comp_dir: Option<&String>,
+    ) -> Result<Self, ParseError> {
+        let mut reader = DwarfReader::new(sections.debug_line, offset as usize);
+
+        let (unit_length, is_dwarf64) = reader.read_unit_length()?;
+        let unit_end = reader.get_position().saturating_add(unit_length as usize);
+
+        let version = reader.read_u16()?;
+        if !(2..=5).contains(&version) {
+            return Err(ParseError::FormatError(format!(
+                "DWARF: unsupported line table version: {}",
+                version
+            )));
+        }
+
+        let mut address_size: u8 = 4;
+        if version >= 5 {
+            address_size = reader.read_u8()?;
+            let _segment_selector_size = reader.read_u8()?;
+        }
+
+        let header_length = reader.read_offset(is_dwarf64)?;
+        let program_start = reader.get_position().saturating_add(header_length as usize);
+
+        let min_inst_length = reader.read_u8()?;
+        if version >= 4 {
+            let _max_ops_per_inst = reader.read_u8()?;
+        }
+        let default_is_stmt = reader.read_u8()? != 0;
+        let line_base = reader.read_u8()? as i8;
+        let line_range = reader.read_u8()?;
+        let opcode_base = reader.read_u8()?;
+        if line_range == 0 {
+            return Err(ParseError::FormatError(String::from(
+                "DWARF: line_range must not be 0",
+            )));
+        }
+
+        let mut standard_opcode_lengths: Vec<u8> = Vec::new();
+        for _ in 1..opcode_base {
+            standard_opcode_lengths.push(reader.read_u8()?);
+        }
+
+        let encoding = Encoding {
+            address_size,
+            is_dwarf64,
+            version,
+        };
+
+        let mut directories: Vec<String> = Vec::new();
+        let mut files: Vec<LineFile> = Vec::new();
+        if version >= 5 {
+            for entry in read_entries(&mut reader, sections, &encoding)?.into_iter() {
+                directories.push(entry.0);
+            }
+            for entry in read_entries(&mut reader, sections, &encoding)?.into_iter() {
+                files.push(LineFile {
+                    name: entry.0,
+                    directory_index: entry.1,
+                });
+            }
+        } else {
+            // DWARF4以前はディレクトリ0がコンパイル時のディレクトリを表す
+            directories.push(comp_dir.cloned().unwrap_or_default());
+            loop {
+                let dir = reader.read_cstr()?;
+                if dir.is_empty() {
+                    break;
+                }
+                directories.push(dir);
+            }
+
+            // ファイル番号は1から始まるため、0番目は空にしておく
+            files.push(LineFile {
+                name: String::new(),
+                directory_index: 0,
+            });
+            loop {
+                let name = reader.read_cstr()?;
+                if name.is_empty() {
+                    break;
+                }
+                files.push(read_v4_file_entry(&mut reader, name)?);
+            }
+        }
+
+        let mut table = Self {
+            version,
+            directories,
+            files,
+            rows: Vec::new(),
+        };
+
+        reader.set_position(program_start);
+        let mut state = LineRow::new(default_is_stmt);
+        while reader.get_position() < unit_end {
+            let opcode = reader.read_u8()?;
+
+            if opcode >= opcode_base {
+                // special opcode
+                let adjusted = opcode - opcode_base;
+                state.address = state
+                    .address
+                    .wrapping_add((adjusted / line_range) as u64 * min_inst_length as u64);
+                state.line = state
+                    .line
+                    .wrapping_add((line_base as i64 + (adjusted % line_range) as i64) as u64);
+                table.rows.push(state);
+                continue;
+            }
+
+            match opcode {
+                0 => {
+                    let length = reader.read_uleb128()?;
+                    if length == 0 {
+                        return Err(ParseError::FormatError(String::from(
+                            "DWARF: empty extended opcode",
+                        )));
+                    }
+                    let sub_opcode_start = reader.get_position();
+                    let sub_opcode = reader.read_u8()?;
+                    match sub_opcode {
+                        DW_LNE_END_SEQUENCE => {
+                            state.end_sequence = true;
+                            table.rows.push(state);
+                            state = LineRow::new(default_is_stmt);
+                        }
+                        DW_LNE_SET_ADDRESS => {
+                            state.address = reader.read_sized((length - 1) as u8)?;
+                        }
+                        DW_LNE_DEFINE_FILE => {
+                            let name = reader.read_cstr()?;
+                            let file = read_v4_file_entry(&mut reader, name)?;
+                            table.files.push(file);
+                        }
+                        _ => (/* DW_LNE_set_discriminatorなどは読み飛ばす */),
+                    }
+                    reader.set_position(sub_opcode_start + length as usize);
+                }
+                DW_LNS_COPY => {
+                    table.rows.push(state);
+                }
+                DW_LNS_ADVANCE_PC => {
+                    let advance = reader.read_uleb128()?.wrapping_mul(min_inst_length as u64);
+                    state.address = state.address.wrapping_add(advance);
+                }
+                DW_LNS_ADVANCE_LINE => {
+                    state.line = state.line.wrapping_add(reader.read_sleb128()? as u64);
+                }
+                DW_LNS_SET_FILE => {
+                    state.file_index = reader.read_uleb128()?;
+                }
+                DW_LNS_SET_COLUMN => {
+                    state.column = reader.read_uleb128()?;
+                }
+                DW_LNS_NEGATE_STMT => {
+                    state.is_stmt = !state.is_stmt;
+                }
+                DW_LNS_SET_BASIC_BLOCK | DW_LNS_SET_PROLOGUE_END | DW_LNS_SET_EPILOGUE_BEGIN => {}
+                DW_LNS_CONST_ADD_PC => {
+                    let adjusted = 255 - opcode_base;
+                    state.address = state
+                        .address
+                        .wrapping_add((adjusted / line_range) as u64 * min_inst_length as u64);
+                }
+                DW_LNS_FIXED_ADVANCE_PC => {
+                    state.address = state.address.wrapping_add(reader.read_u16()? as u64);
+                }
+                DW_LNS_SET_ISA => {
+                    reader.read_uleb128()?;
+                }
+                _ => {
+                    // 未知の標準オペコードは、定義された数のULEB128引数を読み飛ばす
+                    for _ in 0..standard_opcode_lengths[opcode as usize - 1] {
+                        reader.read_uleb128()?;
+                    }
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    pub fn get_version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn get_rows(&self) -> Vec<&LineRow> {
+        self.rows.iter().collect()
+    }
+
+    pub fn get_files(&self) -> Vec<&LineFile> {
+        self.files.iter().collect()
+    }
+
+    /// ファイル番号に対応するファイルのパスを返す
+    ///
+    /// ディレクトリが相対パスの場合は、コンパイル時のディレクトリからのパスにする
+    pub fn get_file_path(&self, file_index: u64) -> Option<String> {
+        let file = self.files.get(file_index as usize)?;
+        if file.name.starts_with('/') {
+            return Some(file.name.clone());
+        }
+
+        let dir = self
+            .directories
+            .get(file.directory_index as usize)
+            .cloned()
+            .unwrap_or_default();
+        let dir = match self.directories.first() {
+            Some(comp_dir) if file.directory_index != 0 && !dir.starts_with('/') => {
+                join_path(comp_dir, &dir)
+            }
+            _ => dir,
+        };
+
+        Some(join_path(&dir, &file.name))
+    }
+
+    /// アドレスを含む行を返す
+    pub fn find_row(&self, address: u64) -> Option<&LineRow> {
+        self.rows
+            .windows(2)
+            .rev()
+            .find(|x| !x[0].end_sequence && x[0].address <= address && address < x[1].address)
+            .map(|x| &x[0])
+    }
+}
+
+impl LineFile {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_directory_index(&self) -> u64 {
+        self.directory_index
+    }
+}
+
+impl LineRow {
+    fn new(default_is_stmt: bool) -> Self {
+        Self {
+            address: 0,
+            file_index: 1,
+            line: 1,
+            column: 0,
+            is_stmt: default_is_stmt,
+            end_sequence: false,
+        }
+    }
+
+    pub fn get_address(&self) -> u64 {
+        self.address
+    }
+
+    pub fn get_file_index(&self) -> u64 {
+        self.file_index
+    }
+
+    pub fn get_line(&self) -> u64 {
+        self.line
+    }
+
+    pub fn get_column(&self) -> u64 {
+        self.column
+    }
+
+    pub fn is_stmt(&self) -> bool {
+        self.is_stmt
+    }
+
+    /// シーケンスの終わり(直前の行の終端アドレス)を示す行かどうかを返す
+    pub fn is_end_sequence(&self) -> bool {
+        self.end_sequence
+    }
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        String::from(name)
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+// DWARF4以前のファイルエントリ(名前以降)を読む
+fn read_v4_file_entry(reader: &mut DwarfReader, name: String) -> Result<LineFile, ParseError> {
+    let directory_index = reader.read_uleb128()?;
+    let _mtime = reader.read_uleb128()?;
+    let _length = reader.read_uleb128()?;
+
+    Ok(LineFile {
+        name,
+        directory_index,
+    })
+}
+
+// DWARF5のディレクトリ・ファイルエントリの表を読み、(パス, ディレクトリ番号)のリストを返す
+fn read_entries(
+    reader: &mut DwarfReader,
+    sections: &DwarfSections,
+    encoding: &Encoding,
+) -> Result<Vec<(String, u64)>, ParseError> {
+    let format_count = reader.read_u8()?;
+    let mut formats: Vec<(u64, u64)> = Vec::new();
+    for _ in 0..format_count {
+        let content_type = reader.read_uleb128()?;
+        let form = reader.read_uleb128()?;
+        formats.push((content_type, form));
+    }
+
+    let count = reader.read_uleb128()?;
+    let mut entries: Vec<(String, u64)> = Vec::new();
+    for _ in 0..count {
+        let mut path = String::new();
+        let mut directory_index: u64 = 0;
+        for (content_type, form) in formats.iter() {
+            let value = read_attr_value(reader, *form, None, encoding)?;
+            match (*content_type, value) {
+                (DW_LNCT_PATH, AttrValue::String(s)) => path = s,
+                (DW_LNCT_PATH, AttrValue::LineStrOffset(offset)) => {
+                    path = read_str_at(sections.debug_line_str, offset)?
+                }
+                (DW_LNCT_PATH, AttrValue::StrOffset(offset)) => {
+                    path = read_str_at(sections.debug_str, offset)?
+                }
+                (DW_LNCT_DIRECTORY_INDEX, AttrValue::Udata(index)) => directory_index = index,
+                _ => (/* タイムスタンプ、サイズ、MD5などは使わない */),
+            }
+        }
+        entries.push((path, directory_index));
+    }
+
+    Ok(entries)
+}
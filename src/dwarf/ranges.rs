@@ -0,0 +1,120 @@
+use super::form::Encoding;
+use super::reader::DwarfReader;
+
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * .debug_ranges (DWARF2-4) / .debug_rnglists (DWARF5)
+ *
+ * DW_AT_rangesで表される不連続なアドレス範囲のリスト
+ */
+
+const DW_RLE_END_OF_LIST: u8 = 0x00;
+const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+const DW_RLE_STARTX_ENDX: u8 = 0x02;
+const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+const DW_RLE_START_END: u8 = 0x06;
+const DW_RLE_START_LENGTH: u8 = 0x07;
+
+/// .debug_rangesのoffsetから範囲 [start, end) のリストを読む
+///
+/// baseはユニットの基準アドレス(DW_AT_low_pc)
+pub(super) fn read_ranges(
+    debug_ranges: &[u8],
+    offset: u64,
+    encoding: &Encoding,
+    base: u64,
+) -> Result<Vec<(u64, u64)>, ParseError> {
+    let mut reader = DwarfReader::new(debug_ranges, offset as usize);
+    let max_address = match encoding.address_size {
+        8 => u64::MAX,
+        _ => u32::MAX as u64,
+    };
+
+    let mut base = base;
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    loop {
+        let start = reader.read_sized(encoding.address_size)?;
+        let end = reader.read_sized(encoding.address_size)?;
+        if start == 0 && end == 0 {
+            break;
+        }
+
+        if start == max_address {
+            // 基準アドレスの変更
+            base = end;
+        } else if start < end {
+            ranges.push((base.wrapping_add(start), base.wrapping_add(end)));
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// .debug_rnglistsのoffsetから範囲 [start, end) のリストを読む
+///
+/// resolve_addrxは.debug_addrのインデックスをアドレスに解決する
+pub(super) fn read_rnglist(
+    debug_rnglists: &[u8],
+    offset: u64,
+    encoding: &Encoding,
+    base: u64,
+    resolve_addrx: &dyn Fn(u64) -> Result<u64, ParseError>,
+) -> Result<Vec<(u64, u64)>, ParseError> {
+    let mut reader = DwarfReader::new(debug_rnglists, offset as usize);
+
+    let mut base = base;
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    loop {
+        let (start, end) = match reader.read_u8()? {
+            DW_RLE_END_OF_LIST => break,
+            DW_RLE_BASE_ADDRESSX => {
+                base = resolve_addrx(reader.read_uleb128()?)?;
+                continue;
+            }
+            DW_RLE_BASE_ADDRESS => {
+                base = reader.read_sized(encoding.address_size)?;
+                continue;
+            }
+            DW_RLE_STARTX_ENDX => {
+                let start = resolve_addrx(reader.read_uleb128()?)?;
+                let end = resolve_addrx(reader.read_uleb128()?)?;
+                (start, end)
+            }
+            DW_RLE_STARTX_LENGTH => {
+                let start = resolve_addrx(reader.read_uleb128()?)?;
+                let length = reader.read_uleb128()?;
+                (start, start.wrapping_add(length))
+            }
+            DW_RLE_OFFSET_PAIR => {
+                let start = reader.read_uleb128()?;
+                let end = reader.read_uleb128()?;
+                (base.wrapping_add(start), base.wrapping_add(end))
+            }
+            DW_RLE_START_END => {
+                let start = reader.read_sized(encoding.address_size)?;
+                let end = reader.read_sized(encoding.address_size)?;
+                (start, end)
+            }
+            DW_RLE_START_LENGTH => {
+                let start = reader.read_sized(encoding.address_size)?;
+                let length = reader.read_uleb128()?;
+                (start, start.wrapping_add(length))
+            }
+            kind => {
+                return Err(ParseError::FormatError(format!(
+                    "DWARF: unknown range list entry: {:#x}",
+                    kind
+                )))
+            }
+        };
+
+        if start < end {
+            ranges.push((start, end));
+        }
+    }
+
+    Ok(ranges)
+}
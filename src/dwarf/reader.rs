@@ -0,0 +1,146 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::wasm_components::sections::ParseError;
+
+// DWARFのセクションを読むためのReader
+//
+// DWARFはオフセットで別の位置を参照することが多いため、
+// std::io::Readではなくスライスと位置の組で扱う
+#[derive(Debug, Clone)]
+pub(super) struct DwarfReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+fn eof_error() -> ParseError {
+    ParseError::FormatError(String::from("DWARF: unexpected end of data"))
+}
+
+impl<'a> DwarfReader<'a> {
+    pub fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn read_bytes(&mut self, size: usize) -> Result<&'a [u8], ParseError> {
+        let end = self.pos.checked_add(size).ok_or_else(eof_error)?;
+        if end > self.data.len() {
+            return Err(eof_error());
+        }
+
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, size: usize) -> Result<(), ParseError> {
+        self.read_bytes(size).map(|_| ())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, ParseError> {
+        Ok(LittleEndian::read_u16(self.read_bytes(2)?))
+    }
+
+    pub fn read_u24(&mut self) -> Result<u32, ParseError> {
+        Ok(LittleEndian::read_u24(self.read_bytes(3)?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, ParseError> {
+        Ok(LittleEndian::read_u32(self.read_bytes(4)?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, ParseError> {
+        Ok(LittleEndian::read_u64(self.read_bytes(8)?))
+    }
+
+    /// サイズを指定して符号なし整数を読む (1, 2, 3, 4, 8 bytes)
+    pub fn read_sized(&mut self, size: u8) -> Result<u64, ParseError> {
+        match size {
+            1 => self.read_u8().map(|x| x as u64),
+            2 => self.read_u16().map(|x| x as u64),
+            3 => self.read_u24().map(|x| x as u64),
+            4 => self.read_u32().map(|x| x as u64),
+            8 => self.read_u64(),
+            _ => Err(ParseError::FormatError(format!(
+                "DWARF: unsupported value size: {}",
+                size
+            ))),
+        }
+    }
+
+    /// 32-bit DWARFでは4バイト、64-bit DWARFでは8バイトのオフセットを読む
+    pub fn read_offset(&mut self, is_dwarf64: bool) -> Result<u64, ParseError> {
+        if is_dwarf64 {
+            self.read_u64()
+        } else {
+            self.read_u32().map(|x| x as u64)
+        }
+    }
+
+    pub fn read_uleb128(&mut self) -> Result<u64, ParseError> {
+        let mut rest = &self.data[self.pos.min(self.data.len())..];
+        let before = rest.len();
+        match leb128::read::unsigned(&mut rest) {
+            Ok(v) => {
+                self.pos += before - rest.len();
+                Ok(v)
+            }
+            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+        }
+    }
+
+    pub fn read_sleb128(&mut self) -> Result<i64, ParseError> {
+        let mut rest = &self.data[self.pos.min(self.data.len())..];
+        let before = rest.len();
+        match leb128::read::signed(&mut rest) {
+            Ok(v) => {
+                self.pos += before - rest.len();
+                Ok(v)
+            }
+            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+        }
+    }
+
+    /// NUL終端の文字列を読む
+    pub fn read_cstr(&mut self) -> Result<String, ParseError> {
+        let rest = &self.data[self.pos.min(self.data.len())..];
+        let len = match rest.iter().position(|x| *x == 0) {
+            Some(len) => len,
+            None => return Err(eof_error()),
+        };
+
+        let s = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Ok(s)
+    }
+
+    /// unit_lengthを読み、(長さ, 64-bit DWARFかどうか)を返す
+    pub fn read_unit_length(&mut self) -> Result<(u64, bool), ParseError> {
+        let length = self.read_u32()?;
+        if length == 0xffff_ffff {
+            Ok((self.read_u64()?, true))
+        } else {
+            Ok((length as u64, false))
+        }
+    }
+}
+
+/// offsetの位置にあるNUL終端の文字列を返す (.debug_str, .debug_line_str)
+pub(super) fn read_str_at(data: &[u8], offset: u64) -> Result<String, ParseError> {
+    DwarfReader::new(data, offset as usize).read_cstr()
+}
@@ -0,0 +1,758 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::wasm_components::code::Expr;
+use crate::wasm_components::instruction::{
+    BlockSignature, Immediate, Instruction, PREFIX_GC, PREFIX_MISC, PREFIX_SIMD, PREFIX_THREADS,
+};
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{
+    get_section_name, CustomSectionPayload, ParseError, TargetFeaturesSectionPayload, TypeEntry,
+};
+use crate::wasm_components::types::{
+    CompositeType, FuncType, HeapType, IndexType, LangTypes, ResizableLimits, StorageType,
+};
+
+/*
+ * Features
+ *
+ * モジュールが実際に利用しているポストMVPの提案(proposal)を検出する
+ *
+ * 型・セクションの種類・デコードした命令を走査し、
+ * MVPの範囲外の構文が見つかった提案を集める
+ */
+
+/// ポストMVPの提案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Proposal {
+    SignExtension,
+    SaturatingFloatToInt,
+    MultiValue,
+    BulkMemory,
+    ReferenceTypes,
+    Simd,
+    TailCall,
+    ExtendedConst,
+    MultiMemory,
+    Memory64,
+    Threads,
+    ExceptionHandling,
+    FunctionReferences,
+    GC,
+}
+
+impl Proposal {
+    /// すべての提案
+    pub const ALL: [Proposal; 14] = [
+        Proposal::SignExtension,
+        Proposal::SaturatingFloatToInt,
+        Proposal::MultiValue,
+        Proposal::BulkMemory,
+        Proposal::ReferenceTypes,
+        Proposal::Simd,
+        Proposal::TailCall,
+        Proposal::ExtendedConst,
+        Proposal::MultiMemory,
+        Proposal::Memory64,
+        Proposal::Threads,
+        Proposal::ExceptionHandling,
+        Proposal::FunctionReferences,
+        Proposal::GC,
+    ];
+
+    /// 名前(get_name()の値)から提案を返す
+    pub fn from_name(name: &str) -> Option<Proposal> {
+        Proposal::ALL.iter().copied().find(|x| x.get_name() == name)
+    }
+
+    /// 提案の名前を返す
+    ///
+    /// target_featuresセクションと比較できるよう、LLVMのfeature名に合わせている
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Proposal::SignExtension => "sign-ext",
+            Proposal::SaturatingFloatToInt => "nontrapping-fptoint",
+            Proposal::MultiValue => "multivalue",
+            Proposal::BulkMemory => "bulk-memory",
+            Proposal::ReferenceTypes => "reference-types",
+            Proposal::Simd => "simd128",
+            Proposal::TailCall => "tail-call",
+            Proposal::ExtendedConst => "extended-const",
+            Proposal::MultiMemory => "multimemory",
+            Proposal::Memory64 => "memory64",
+            Proposal::Threads => "atomics",
+            Proposal::ExceptionHandling => "exception-handling",
+            Proposal::FunctionReferences => "function-references",
+            Proposal::GC => "gc",
+        }
+    }
+}
+
+impl fmt::Display for Proposal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+/// 提案の集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    proposals: BTreeSet<Proposal>,
+}
+
+impl FeatureSet {
+    pub fn new() -> Self {
+        Self {
+            proposals: BTreeSet::new(),
+        }
+    }
+
+    /// 提案を追加する
+    pub fn insert(&mut self, proposal: Proposal) {
+        self.proposals.insert(proposal);
+    }
+
+    /// 提案が含まれているかを返す
+    pub fn contains(&self, proposal: Proposal) -> bool {
+        self.proposals.contains(&proposal)
+    }
+
+    /// 含まれている提案のリストを返す
+    pub fn get_proposals(&self) -> Vec<Proposal> {
+        self.proposals.iter().copied().collect()
+    }
+
+    /// MVPの範囲内かどうか(提案を1つも含まないか)を返す
+    pub fn is_mvp(&self) -> bool {
+        self.proposals.is_empty()
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_mvp() {
+            return write!(f, "mvp");
+        }
+
+        let names: Vec<&str> = self.proposals.iter().map(|x| x.get_name()).collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// 有効にする提案の設定
+///
+/// Parserに渡すと、無効にした提案を利用しているモジュールをエラーにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmFeatures {
+    /// MVPの機能(無効にするとカスタムセクション以外のセクションをすべてエラーにする)
+    pub mvp: bool,
+    pub sign_extension: bool,
+    pub saturating_float_to_int: bool,
+    pub multi_value: bool,
+    pub bulk_memory: bool,
+    pub reference_types: bool,
+    pub simd: bool,
+    pub tail_call: bool,
+    pub extended_const: bool,
+    pub multi_memory: bool,
+    pub memory64: bool,
+    pub threads: bool,
+    pub exceptions: bool,
+    pub function_references: bool,
+    pub gc: bool,
+}
+
+impl WasmFeatures {
+    /// すべての提案を有効にする
+    pub fn all() -> Self {
+        Self::with_all(true)
+    }
+
+    /// MVPのみ(すべての提案を無効にする)
+    pub fn mvp() -> Self {
+        Self::with_all(false)
+    }
+
+    // MVPの機能はどちらの場合も有効にする
+    fn with_all(enabled: bool) -> Self {
+        Self {
+            mvp: true,
+            sign_extension: enabled,
+            saturating_float_to_int: enabled,
+            multi_value: enabled,
+            bulk_memory: enabled,
+            reference_types: enabled,
+            simd: enabled,
+            tail_call: enabled,
+            extended_const: enabled,
+            multi_memory: enabled,
+            memory64: enabled,
+            threads: enabled,
+            exceptions: enabled,
+            function_references: enabled,
+            gc: enabled,
+        }
+    }
+
+    /// 提案が有効かどうかを返す
+    pub fn is_enabled(&self, proposal: Proposal) -> bool {
+        match proposal {
+            Proposal::SignExtension => self.sign_extension,
+            Proposal::SaturatingFloatToInt => self.saturating_float_to_int,
+            Proposal::MultiValue => self.multi_value,
+            Proposal::BulkMemory => self.bulk_memory,
+            Proposal::ReferenceTypes => self.reference_types,
+            Proposal::Simd => self.simd,
+            Proposal::TailCall => self.tail_call,
+            Proposal::ExtendedConst => self.extended_const,
+            Proposal::MultiMemory => self.multi_memory,
+            Proposal::Memory64 => self.memory64,
+            Proposal::Threads => self.threads,
+            Proposal::ExceptionHandling => self.exceptions,
+            Proposal::FunctionReferences => self.function_references,
+            Proposal::GC => self.gc,
+        }
+    }
+}
+
+impl Default for WasmFeatures {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// 提案を利用している箇所
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureLocation {
+    /// セクション自体 (e.g. DataCountSection)
+    Section { section_id: u8 },
+    /// セクション内のidx番目のエントリ
+    Entry { section_id: u8, index: u32 },
+    /// 関数内の命令
+    ///
+    /// offsetは関数本体の命令列(FunctionBody::get_code())の先頭からの位置
+    Instruction { func_index: u32, offset: u32 },
+}
+
+impl fmt::Display for FeatureLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureLocation::Section { section_id } => {
+                write!(f, "{} section", get_section_name(*section_id))
+            }
+            FeatureLocation::Entry { section_id, index } => {
+                write!(f, "{}[{}]", get_section_name(*section_id), index)
+            }
+            FeatureLocation::Instruction { func_index, offset } => {
+                write!(f, "func[{}] +{:#x}", func_index, offset)
+            }
+        }
+    }
+}
+
+/// 提案の利用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureUse {
+    proposal: Proposal,
+    location: FeatureLocation,
+}
+
+impl FeatureUse {
+    /// 利用している提案を返す
+    pub fn get_proposal(&self) -> Proposal {
+        self.proposal
+    }
+
+    /// 利用している箇所を返す
+    pub fn get_location(&self) -> &FeatureLocation {
+        &self.location
+    }
+}
+
+impl fmt::Display for FeatureUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.proposal, self.location)
+    }
+}
+
+/// target_featuresカスタムセクションの内容を返す(セクションがない場合はNone)
+pub fn get_target_features(module: &WasmModule) -> Option<&TargetFeaturesSectionPayload> {
+    module
+        .get_custom_sections()
+        .into_iter()
+        .find_map(|x| match x.get_payload() {
+            CustomSectionPayload::TargetFeatures { payload } => Some(payload),
+            _ => None,
+        })
+}
+
+// 現在の位置を覚えておき、見つかった提案をその位置で記録する
+struct UseCollector {
+    uses: Vec<FeatureUse>,
+    location: FeatureLocation,
+}
+
+impl UseCollector {
+    fn new(section_id: u8) -> Self {
+        Self {
+            uses: Vec::new(),
+            location: FeatureLocation::Section { section_id },
+        }
+    }
+
+    fn at_entry(&mut self, section_id: u8, index: usize) {
+        self.location = FeatureLocation::Entry {
+            section_id,
+            index: index as u32,
+        };
+    }
+
+    fn insert(&mut self, proposal: Proposal) {
+        self.uses.push(FeatureUse {
+            proposal,
+            location: self.location,
+        });
+    }
+}
+
+// 利用箇所を調べるセクションの順番(バイナリ上の並び順)
+const SECTION_ORDER: [u8; 13] = [1, 2, 3, 4, 5, 13, 6, 7, 8, 9, 12, 10, 11];
+
+/// モジュールが利用している提案を検出する
+pub fn detect_features(module: &WasmModule) -> Result<FeatureSet, ParseError> {
+    let mut features = FeatureSet::new();
+    for feature_use in detect_feature_uses(module)?.into_iter() {
+        features.insert(feature_use.get_proposal());
+    }
+
+    Ok(features)
+}
+
+/// モジュール内で提案を利用している箇所をすべて返す
+///
+/// バイナリ上の出現順に並ぶ
+pub fn detect_feature_uses(module: &WasmModule) -> Result<Vec<FeatureUse>, ParseError> {
+    let mut uses: Vec<FeatureUse> = Vec::new();
+    for section_id in SECTION_ORDER.iter() {
+        uses.extend(detect_section_feature_uses(module, *section_id)?);
+    }
+
+    Ok(uses)
+}
+
+/// 提案ごとに最初の利用箇所を返す
+///
+/// 提案の順(Proposalの定義順)に並ぶ
+pub fn detect_first_feature_uses(module: &WasmModule) -> Result<Vec<FeatureUse>, ParseError> {
+    let mut first_uses: Vec<FeatureUse> = Vec::new();
+    for feature_use in detect_feature_uses(module)?.into_iter() {
+        if first_uses
+            .iter()
+            .all(|x| x.get_proposal() != feature_use.get_proposal())
+        {
+            first_uses.push(feature_use);
+        }
+    }
+    first_uses.sort_by_key(|x| x.get_proposal());
+
+    Ok(first_uses)
+}
+
+/// 指定したセクション内で提案を利用している箇所を返す
+///
+/// テーブル・メモリの数など、前のセクションの内容に依存するものがあるため、
+/// バイナリ上でそれより前にあるセクションはmoduleに読み込まれている必要がある
+pub fn detect_section_feature_uses(
+    module: &WasmModule,
+    section_id: u8,
+) -> Result<Vec<FeatureUse>, ParseError> {
+    let mut c = UseCollector::new(section_id);
+
+    match section_id {
+        1 => detect_type_section(&mut c, module),
+        2 => detect_import_section(&mut c, module),
+        4 => detect_table_section(&mut c, module)?,
+        5 => detect_memory_section(&mut c, module),
+        6 => detect_global_section(&mut c, module)?,
+        9 => detect_element_section(&mut c, module)?,
+        10 => detect_code_section(&mut c, module)?,
+        11 => detect_data_section(&mut c, module)?,
+        12 if module.get_data_count_section().is_some() => c.insert(Proposal::BulkMemory),
+        13 if module.get_tag_section().is_some() => c.insert(Proposal::ExceptionHandling),
+        _ => (),
+    }
+
+    Ok(c.uses)
+}
+
+fn detect_type_section(c: &mut UseCollector, module: &WasmModule) {
+    let type_section = match module.get_type_section() {
+        Some(sec) => sec,
+        None => return,
+    };
+
+    let mut type_idx = 0;
+    for rec_group in type_section.get_rec_groups().into_iter() {
+        for sub_type in rec_group.get_sub_types().into_iter() {
+            c.at_entry(1, type_idx);
+            if rec_group.is_explicit()
+                || !sub_type.is_final()
+                || !sub_type.get_super_types().is_empty()
+            {
+                c.insert(Proposal::GC);
+            }
+            match sub_type.get_composite_type() {
+                CompositeType::Func(func_type) => detect_func_type(c, func_type),
+                CompositeType::Struct(struct_type) => {
+                    c.insert(Proposal::GC);
+                    for field in struct_type.get_fields().into_iter() {
+                        detect_storage_type(c, field.get_storage_type());
+                    }
+                }
+                CompositeType::Array(array_type) => {
+                    c.insert(Proposal::GC);
+                    detect_storage_type(c, array_type.get_field().get_storage_type());
+                }
+            }
+            type_idx += 1;
+        }
+    }
+}
+
+fn detect_import_section(c: &mut UseCollector, module: &WasmModule) {
+    let import_section = match module.get_import_section() {
+        Some(sec) => sec,
+        None => return,
+    };
+
+    let mut num_tables = 0;
+    let mut num_memories = 0;
+    for (cnt, entry) in import_section.get_import_entries().into_iter().enumerate() {
+        c.at_entry(2, cnt);
+        match entry.get_type() {
+            TypeEntry::FuncIndex { .. } => (),
+            TypeEntry::TblType { type_ } => {
+                num_tables += 1;
+                if num_tables > 1 {
+                    c.insert(Proposal::ReferenceTypes);
+                }
+                detect_elem_type(c, type_.get_elem_type());
+                detect_limits(c, type_.get_limits());
+            }
+            TypeEntry::MemType { type_ } => {
+                num_memories += 1;
+                if num_memories > 1 {
+                    c.insert(Proposal::MultiMemory);
+                }
+                detect_limits(c, type_.get_limits());
+            }
+            TypeEntry::GblType { type_ } => detect_value_type(c, type_.get_type()),
+            TypeEntry::TagType { .. } => c.insert(Proposal::ExceptionHandling),
+        }
+    }
+}
+
+fn detect_table_section(c: &mut UseCollector, module: &WasmModule) -> Result<(), ParseError> {
+    let table_section = match module.get_table_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    // MVPではテーブルは(インポートを含めて)1つまで
+    let mut num_tables = count_imports(module, |entry| matches!(entry, TypeEntry::TblType { .. }));
+    for (cnt, table) in table_section.get_table_list().into_iter().enumerate() {
+        c.at_entry(4, cnt);
+        num_tables += 1;
+        if num_tables > 1 {
+            c.insert(Proposal::ReferenceTypes);
+        }
+        detect_elem_type(c, table.get_elem_type());
+        detect_limits(c, table.get_limits());
+        // 初期化式を持つテーブルはtyped function references提案で追加された
+        if let Some(init_expr) = table_section.get_table_init_expr(cnt) {
+            c.insert(Proposal::FunctionReferences);
+            detect_const_expr(c, init_expr)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn detect_memory_section(c: &mut UseCollector, module: &WasmModule) {
+    let memory_section = match module.get_memory_section() {
+        Some(sec) => sec,
+        None => return,
+    };
+
+    // MVPではメモリは(インポートを含めて)1つまで
+    let mut num_memories =
+        count_imports(module, |entry| matches!(entry, TypeEntry::MemType { .. }));
+    for (cnt, memory) in memory_section.get_memories().into_iter().enumerate() {
+        c.at_entry(5, cnt);
+        num_memories += 1;
+        if num_memories > 1 {
+            c.insert(Proposal::MultiMemory);
+        }
+        detect_limits(c, memory.get_limits());
+    }
+}
+
+fn detect_global_section(c: &mut UseCollector, module: &WasmModule) -> Result<(), ParseError> {
+    let global_section = match module.get_global_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (cnt, global_var) in global_section
+        .get_global_variable_list()
+        .into_iter()
+        .enumerate()
+    {
+        c.at_entry(6, cnt);
+        detect_value_type(c, global_var.get_global_type().get_type());
+        detect_const_expr(c, global_var.get_init_expr())?;
+    }
+
+    Ok(())
+}
+
+fn detect_element_section(c: &mut UseCollector, module: &WasmModule) -> Result<(), ParseError> {
+    let element_section = match module.get_element_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (cnt, elem_segment) in element_section.get_element_list().into_iter().enumerate() {
+        c.at_entry(9, cnt);
+        // MVPの形式はテーブル0へのactiveセグメントで、要素は関数インデックスのみ
+        if !elem_segment.is_active()
+            || elem_segment.get_table_index() != Some(0)
+            || !elem_segment.get_element_exprs().is_empty()
+        {
+            c.insert(Proposal::BulkMemory);
+        }
+        detect_elem_type(c, &elem_segment.get_elem_type());
+        if let Some(expr) = elem_segment.get_offset_expr() {
+            detect_const_expr(c, expr)?;
+        }
+        for expr in elem_segment.get_element_exprs().into_iter() {
+            detect_const_expr(c, expr)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn detect_code_section(c: &mut UseCollector, module: &WasmModule) -> Result<(), ParseError> {
+    let code_section = match module.get_code_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    let base_func_idx = module.get_num_imported_functions();
+    for (cnt, func_body) in code_section.get_func_body_list().into_iter().enumerate() {
+        let func_index = base_func_idx + cnt as u32;
+
+        // ローカル変数の宣言は命令列より前にあるため、関数の先頭の位置として記録する
+        c.location = FeatureLocation::Instruction {
+            func_index,
+            offset: 0,
+        };
+        for local in func_body.get_locals().into_iter() {
+            detect_value_type(c, local);
+        }
+
+        for instr in func_body.get_instructions()?.iter() {
+            c.location = FeatureLocation::Instruction {
+                func_index,
+                offset: instr.get_offset(),
+            };
+            for proposal in instruction_proposals(instr).into_iter() {
+                c.insert(proposal);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn detect_data_section(c: &mut UseCollector, module: &WasmModule) -> Result<(), ParseError> {
+    let data_section = match module.get_data_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (cnt, data_segment) in data_section.get_data_segment_list().into_iter().enumerate() {
+        c.at_entry(11, cnt);
+        if data_segment.is_passive() {
+            c.insert(Proposal::BulkMemory);
+        }
+        if let Some(expr) = data_segment.get_offset_expr() {
+            detect_const_expr(c, expr)?;
+        }
+    }
+
+    Ok(())
+}
+
+// 条件に一致するインポートの数を数える
+fn count_imports<F: Fn(&TypeEntry) -> bool>(module: &WasmModule, pred: F) -> u32 {
+    match module.get_import_section() {
+        Some(sec) => sec
+            .get_import_entries()
+            .into_iter()
+            .filter(|entry| pred(entry.get_type()))
+            .count() as u32,
+        None => 0,
+    }
+}
+
+/// 命令が必要とする提案を返す(MVPの命令の場合は空)
+pub fn instruction_proposals(instr: &Instruction) -> Vec<Proposal> {
+    let mut proposals: Vec<Proposal> = Vec::new();
+
+    let opcode = instr.get_opcode();
+    match instr.get_sub_opcode() {
+        Some(sub) if opcode == PREFIX_MISC => match sub {
+            0..=7 => proposals.push(Proposal::SaturatingFloatToInt),
+            15..=17 => proposals.push(Proposal::ReferenceTypes),
+            _ => proposals.push(Proposal::BulkMemory),
+        },
+        Some(_) if opcode == PREFIX_GC => proposals.push(Proposal::GC),
+        Some(_) if opcode == PREFIX_SIMD => proposals.push(Proposal::Simd),
+        Some(_) if opcode == PREFIX_THREADS => proposals.push(Proposal::Threads),
+        Some(_) => (),
+        None => match opcode {
+            0x06..=0x0A | 0x18 | 0x19 | 0x1F => proposals.push(Proposal::ExceptionHandling),
+            0x12 | 0x13 => proposals.push(Proposal::TailCall),
+            0x14 | 0xD3 | 0xD4 | 0xD6 => proposals.push(Proposal::FunctionReferences),
+            0x15 => {
+                proposals.push(Proposal::TailCall);
+                proposals.push(Proposal::FunctionReferences);
+            }
+            0x1C | 0x25 | 0x26 | 0xD0..=0xD2 => proposals.push(Proposal::ReferenceTypes),
+            0xC0..=0xC4 => proposals.push(Proposal::SignExtension),
+            0xD5 => proposals.push(Proposal::GC),
+            _ => (),
+        },
+    }
+
+    // 即値から分かるもの
+    match instr.get_immediate() {
+        Immediate::Block(BlockSignature::TypeIndex(_))
+        | Immediate::TryTable {
+            signature: BlockSignature::TypeIndex(_),
+            ..
+        } => proposals.push(Proposal::MultiValue),
+        Immediate::Block(BlockSignature::Value(ty)) => {
+            proposals.extend(value_type_proposal(ty));
+        }
+        Immediate::MemArg(memarg) | Immediate::MemArgLane { memarg, .. }
+            if memarg.get_memory_index() != 0 =>
+        {
+            proposals.push(Proposal::MultiMemory)
+        }
+        Immediate::Memory(idx) if *idx != 0 => proposals.push(Proposal::MultiMemory),
+        Immediate::MemoryInit { memory_index, .. } if *memory_index != 0 => {
+            proposals.push(Proposal::MultiMemory)
+        }
+        Immediate::MemoryCopy {
+            dst_index,
+            src_index,
+        } if *dst_index != 0 || *src_index != 0 => proposals.push(Proposal::MultiMemory),
+        Immediate::CallIndirect { table_index, .. } if *table_index != 0 => {
+            proposals.push(Proposal::ReferenceTypes)
+        }
+        Immediate::HeapType(heap_type) => {
+            proposals.extend(value_type_proposal(&LangTypes::from_heap_type(
+                true, *heap_type,
+            )));
+        }
+        Immediate::SelectTypes(types) => {
+            for ty in types.iter() {
+                proposals.extend(value_type_proposal(ty));
+            }
+        }
+        _ => (),
+    }
+
+    proposals.sort();
+    proposals.dedup();
+    proposals
+}
+
+// 値型として使われる型が必要とする提案を返す
+//
+// funcrefはMVPではテーブルの要素型としてのみ使え、値型として使うにはreference types提案が必要
+fn value_type_proposal(ty: &LangTypes) -> Option<Proposal> {
+    match ty {
+        LangTypes::V128 => Some(Proposal::Simd),
+        LangTypes::ANYFUNC => Some(Proposal::ReferenceTypes),
+        LangTypes::REF {
+            nullable,
+            heap_type,
+        } => match heap_type {
+            HeapType::Func | HeapType::Extern if *nullable => Some(Proposal::ReferenceTypes),
+            HeapType::Func | HeapType::Extern | HeapType::Concrete(_) => {
+                Some(Proposal::FunctionReferences)
+            }
+            HeapType::Exn | HeapType::NoExn => Some(Proposal::ExceptionHandling),
+            _ => Some(Proposal::GC),
+        },
+        _ => None,
+    }
+}
+
+fn detect_value_type(c: &mut UseCollector, ty: &LangTypes) {
+    if let Some(proposal) = value_type_proposal(ty) {
+        c.insert(proposal);
+    }
+}
+
+// テーブルの要素型(funcrefはMVP)
+fn detect_elem_type(c: &mut UseCollector, ty: &LangTypes) {
+    if *ty != LangTypes::ANYFUNC {
+        detect_value_type(c, ty);
+    }
+}
+
+fn detect_storage_type(c: &mut UseCollector, storage_type: &StorageType) {
+    if let Some(ty) = storage_type.get_value_type() {
+        detect_value_type(c, ty);
+    }
+}
+
+fn detect_func_type(c: &mut UseCollector, func_type: &FuncType) {
+    for param in func_type.get_param_types().into_iter() {
+        detect_value_type(c, param.get_value());
+    }
+    for rty in func_type.get_return_types().into_iter() {
+        detect_value_type(c, rty.get_value());
+    }
+    if func_type.get_num_return() > 1 {
+        c.insert(Proposal::MultiValue);
+    }
+}
+
+fn detect_limits(c: &mut UseCollector, limits: &ResizableLimits) {
+    if limits.get_index_type() == IndexType::I64 {
+        c.insert(Proposal::Memory64);
+    }
+    if limits.is_shared() {
+        c.insert(Proposal::Threads);
+    }
+}
+
+// 定数式(extended-const提案でi32/i64のadd, sub, mulが使えるようになった)
+fn detect_const_expr(c: &mut UseCollector, expr: &Expr) -> Result<(), ParseError> {
+    for instr in expr.get_instructions()?.iter() {
+        if matches!(instr.get_opcode(), 0x6A..=0x6C | 0x7C..=0x7E) {
+            c.insert(Proposal::ExtendedConst);
+        }
+        for proposal in instruction_proposals(instr).into_iter() {
+            c.insert(proposal);
+        }
+    }
+
+    Ok(())
+}
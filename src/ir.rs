@@ -0,0 +1,401 @@
+mod arena;
+mod builder;
+mod encoder;
+mod gc;
+mod items;
+mod remap;
+#[cfg(test)]
+pub(crate) mod test_utils;
+
+pub use self::arena::*;
+pub use self::gc::*;
+pub use self::items::*;
+
+use crate::wasm_components::types::LangTypes;
+
+/*
+ * Module IR
+ *
+ * 変更可能なモジュールの中間表現
+ *
+ * 関数・グローバル変数などはインデックスではなくIDで管理する(Arena)
+ * 要素を削除・挿入してもIDは変わらず、エンコード時にインデックスを振り直して
+ * 命令列・エクスポート・elemセグメント・startセクション・nameセクションなどの参照を書き換える
+ *
+ * 各インデックス空間では、インポートされたもの(importsに並ぶ順)が
+ * 定義されたもの(Arenaに並ぶ順)より前に並ぶ
+ *
+ * name以外のカスタムセクションはバイト列のまま保持する
+ * (CustomSectionRegistryに登録したデコーダで読んだものは、デコードしたペイロードを保持し、
+ *  エンコード時にCustomPayload::encodeで書き出す)
+ * linkingやreloc.*などインデックスを含むカスタムセクションは書き換えないため、
+ * インデックスが変わると内容が合わなくなることに注意
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct ModuleIr {
+    pub version: u32,
+    pub types: Arena<IrType>,
+    pub functions: Arena<IrFunction>,
+    pub tables: Arena<IrTable>,
+    pub memories: Arena<IrMemory>,
+    pub globals: Arena<IrGlobal>,
+    pub tags: Arena<IrTag>,
+    pub elements: Arena<IrElement>,
+    pub data: Arena<IrData>,
+    pub imports: Vec<IrImport>,
+    pub exports: Vec<IrExport>,
+    pub start: Option<FuncId>,
+    pub data_count: bool, // datacountセクションを出力するか
+    pub names: IrNames,
+    pub custom_sections: Vec<IrCustomSection>,
+}
+
+impl ModuleIr {
+    pub fn new() -> Self {
+        Self {
+            version: 1,
+            ..Default::default()
+        }
+    }
+
+    /// インポートされているかを返す
+    pub fn is_imported(&self, target: IrExternal) -> bool {
+        self.imports.iter().any(|x| x.target == target)
+    }
+
+    /// 定義された関数を末尾に追加する
+    pub fn add_function(&mut self, type_id: TypeId, body: IrFunctionBody) -> FuncId {
+        self.functions.push(IrFunction {
+            type_id,
+            body: Some(body),
+        })
+    }
+
+    /// 関数をインポートとして末尾に追加する
+    pub fn add_imported_function(&mut self, module: &str, field: &str, type_id: TypeId) -> FuncId {
+        let id = self.functions.push(IrFunction {
+            type_id,
+            body: None,
+        });
+        self.add_import(module, field, IrExternal::Function(id));
+        id
+    }
+
+    /// グローバル変数をインポートとして末尾に追加する
+    pub fn add_imported_global(
+        &mut self,
+        module: &str,
+        field: &str,
+        content_type: LangTypes,
+        mutable: bool,
+    ) -> GlobalId {
+        let id = self.globals.push(IrGlobal {
+            content_type,
+            mutable,
+            init: None,
+        });
+        self.add_import(module, field, IrExternal::Global(id));
+        id
+    }
+
+    fn add_import(&mut self, module: &str, field: &str, target: IrExternal) {
+        self.imports.push(IrImport {
+            module: String::from(module),
+            field: String::from(field),
+            target,
+        });
+    }
+
+    /// 関数を削除する
+    ///
+    /// インポート・エクスポート・startセクション・nameセクションからも取り除く
+    /// 命令列やelemセグメントから参照されている場合は、エンコード時にエラーになる
+    pub fn remove_function(&mut self, id: FuncId) -> Option<IrFunction> {
+        let function = self.functions.remove(id)?;
+        self.remove_external(IrExternal::Function(id));
+        if self.start == Some(id) {
+            self.start = None;
+        }
+        self.names.functions.remove(&id);
+        self.names.locals.remove(&id);
+        self.names.labels.remove(&id);
+        Some(function)
+    }
+
+    /// グローバル変数を削除する
+    pub fn remove_global(&mut self, id: GlobalId) -> Option<IrGlobal> {
+        let global = self.globals.remove(id)?;
+        self.remove_external(IrExternal::Global(id));
+        self.names.globals.remove(&id);
+        Some(global)
+    }
+
+    /// 型を削除する
+    pub fn remove_type(&mut self, id: TypeId) -> Option<IrType> {
+        let ty = self.types.remove(id)?;
+        self.names.types.remove(&id);
+        self.names.fields.remove(&id);
+        Some(ty)
+    }
+
+    /// dataセグメントを削除する
+    pub fn remove_data(&mut self, id: DataId) -> Option<IrData> {
+        let data = self.data.remove(id)?;
+        self.names.data.remove(&id);
+        Some(data)
+    }
+
+    /// elemセグメントを削除する
+    pub fn remove_element(&mut self, id: ElemId) -> Option<IrElement> {
+        let element = self.elements.remove(id)?;
+        self.names.elems.remove(&id);
+        Some(element)
+    }
+
+    // インポート・エクスポートから取り除く
+    fn remove_external(&mut self, target: IrExternal) {
+        self.imports.retain(|x| x.target != target);
+        self.exports.retain(|x| x.target != target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use crate::wasm_components::instruction::Immediate;
+    use crate::wasm_components::module::WasmModule;
+
+    // 型: 0 () -> (), 1 (i32) -> (i32)
+    // 関数: 0 インポート, 1 空, 2 local.get 0, 3 関数2・グローバル変数1・call_indirectを呼ぶ
+    // グローバル変数: 0 インポート, 1 定義
+    fn sample_module() -> Vec<u8> {
+        module(&[
+            section(1, &vec(&[func_type(&[], &[]), func_type(&[0x7f], &[0x7f])])),
+            section(
+                2,
+                &vec(&[
+                    [name("env"), name("f"), vec![0x00, 0x00]].concat(),
+                    [name("env"), name("g"), vec![0x03, 0x7f, 0x00]].concat(),
+                ]),
+            ),
+            section(3, &vec(&[vec![0x00], vec![0x01], vec![0x00]])),
+            section(4, &vec(&[vec![0x70, 0x00, 0x02]])),
+            section(5, &vec(&[vec![0x00, 0x01]])),
+            section(6, &vec(&[vec![0x7f, 0x01, 0x41, 0x05, 0x0b]])),
+            section(
+                7,
+                &vec(&[
+                    [name("c"), vec![0x00, 0x03]].concat(),
+                    [name("h"), vec![0x03, 0x01]].concat(),
+                ]),
+            ),
+            section(9, &vec(&[vec![0x00, 0x41, 0x00, 0x0b, 0x02, 0x02, 0x03]])),
+            section(
+                10,
+                &vec(&[
+                    func_body(&[], &[]),
+                    func_body(&[(1, 0x7f)], &[0x20, 0x00]),
+                    func_body(
+                        &[],
+                        &[
+                            0x41, 0x01, 0x10, 0x02, 0x1a, // i32.const 1, call 2, drop
+                            0x23, 0x01, 0x1a, // global.get 1, drop
+                            0x41, 0x00, 0x11, 0x00, 0x00, // i32.const 0, call_indirect 0 0
+                        ],
+                    ),
+                ]),
+            ),
+            section(11, &vec(&[vec![0x00, 0x41, 0x00, 0x0b, 0x02, b'h', b'i']])),
+            custom_section(
+                "name",
+                &[
+                    vec![0x01],
+                    sized(&name_map(&[(0, "f"), (1, "a"), (2, "b"), (3, "c")])),
+                    vec![0x02],
+                    sized(&vec(&[[uleb(2), name_map(&[(0, "x"), (1, "y")])].concat()])),
+                    vec![0x07],
+                    sized(&name_map(&[(0, "g"), (1, "h")])),
+                ]
+                .concat(),
+            ),
+        ])
+    }
+
+    fn build(bytes: &[u8]) -> ModuleIr {
+        ModuleIr::from_module(&parse(bytes)).unwrap()
+    }
+
+    fn get_export_index(module: &WasmModule, export_name: &str) -> u32 {
+        let entry = module
+            .get_export_section()
+            .unwrap()
+            .get_export_entry_list()
+            .into_iter()
+            .find(|x| x.get_entry_name() == export_name)
+            .unwrap();
+        entry.get_index()
+    }
+
+    fn get_elements(module: &WasmModule) -> Vec<u32> {
+        module
+            .get_element_section()
+            .unwrap()
+            .get_element(0)
+            .unwrap()
+            .get_elements()
+    }
+
+    #[test]
+    fn encode_without_changes_is_identical() {
+        let bytes = sample_module();
+        assert_eq!(build(&bytes).encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn remove_function_remaps_references() {
+        let mut ir = build(&sample_module());
+        assert!(ir.remove_function(Id::new(1)).is_some());
+        let module = parse(&ir.encode().unwrap());
+
+        assert_eq!(
+            module.get_function_section().unwrap().get_indice_list(),
+            vec![1, 0]
+        );
+        assert_eq!(
+            get_references(&module, 1),
+            vec![
+                Immediate::Function(1),
+                Immediate::Global(1),
+                Immediate::CallIndirect {
+                    type_index: 0,
+                    table_index: 0
+                },
+            ]
+        );
+        assert_eq!(get_elements(&module), vec![1, 2]);
+        assert_eq!(get_export_index(&module, "c"), 2);
+    }
+
+    #[test]
+    fn insert_function_remaps_references() {
+        let mut ir = build(&sample_module());
+        // インポートされた関数0の直後(定義された関数の先頭)に挿入する
+        let id = ir.functions.insert(
+            1,
+            IrFunction {
+                type_id: Id::new(0),
+                body: Some(IrFunctionBody {
+                    locals: Vec::new(),
+                    code: vec![0x10, 0x03], // call 3
+                }),
+            },
+        );
+        assert_eq!(id, Id::new(4));
+        let module = parse(&ir.encode().unwrap());
+
+        assert_eq!(
+            module.get_function_section().unwrap().get_indice_list(),
+            vec![0, 0, 1, 0]
+        );
+        assert_eq!(get_references(&module, 0), vec![Immediate::Function(4)]);
+        assert_eq!(get_references(&module, 3)[0], Immediate::Function(3));
+        assert_eq!(get_elements(&module), vec![3, 4]);
+        assert_eq!(get_export_index(&module, "c"), 4);
+    }
+
+    #[test]
+    fn add_imported_function_remaps_references() {
+        let mut ir = build(&sample_module());
+        let id = ir.add_imported_function("env", "f2", Id::new(0));
+        assert_eq!(id, Id::new(4));
+        let module = parse(&ir.encode().unwrap());
+
+        // インポートされた関数は定義された関数より前に並ぶ
+        assert_eq!(get_references(&module, 2)[0], Immediate::Function(3));
+        assert_eq!(get_elements(&module), vec![3, 4]);
+        let names = module.get_name_section().unwrap();
+        assert_eq!(names.get_function_name(1), None);
+        assert_eq!(names.get_function_name(4), Some(&"c".to_string()));
+    }
+
+    #[test]
+    fn remove_global_remaps_references() {
+        let mut ir = build(&sample_module());
+        assert!(ir.remove_global(Id::new(0)).is_some());
+        assert_eq!(ir.imports.len(), 1);
+        let module = parse(&ir.encode().unwrap());
+
+        assert_eq!(
+            module
+                .get_import_section()
+                .unwrap()
+                .get_num_import_entries(),
+            1
+        );
+        assert_eq!(get_references(&module, 2)[1], Immediate::Global(0));
+        assert_eq!(get_export_index(&module, "h"), 0);
+        let names = module.get_name_section().unwrap();
+        assert_eq!(names.get_global_name(0), Some(&"h".to_string()));
+        assert_eq!(names.get_global_name(1), None);
+    }
+
+    #[test]
+    fn insert_and_remove_type_remaps_references() {
+        let bytes = sample_module();
+        let mut ir = build(&bytes);
+        let id = ir.types.insert(
+            0,
+            IrType {
+                rec_group: None,
+                is_final: true,
+                super_types: Vec::new(),
+                composite: IrCompositeType::Func {
+                    params: vec![LangTypes::I64],
+                    results: Vec::new(),
+                },
+            },
+        );
+        let module = parse(&ir.encode().unwrap());
+
+        assert_eq!(
+            module.get_function_section().unwrap().get_indice_list(),
+            vec![1, 2, 1]
+        );
+        assert_eq!(
+            get_references(&module, 2)[2],
+            Immediate::CallIndirect {
+                type_index: 1,
+                table_index: 0
+            }
+        );
+
+        // 削除すると元のバイト列に戻る
+        assert!(ir.remove_type(id).is_some());
+        assert_eq!(ir.encode().unwrap(), bytes);
+    }
+
+    #[test]
+    fn remove_function_remaps_names() {
+        let mut ir = build(&sample_module());
+        assert!(ir.remove_function(Id::new(1)).is_some());
+        let module = parse(&ir.encode().unwrap());
+        let names = module.get_name_section().unwrap();
+
+        assert_eq!(names.get_function_name(0), Some(&"f".to_string()));
+        assert_eq!(names.get_function_name(1), Some(&"b".to_string()));
+        assert_eq!(names.get_function_name(2), Some(&"c".to_string()));
+        assert_eq!(names.get_function_name(3), None);
+        assert_eq!(names.get_local_name(1, 1), Some(&"y".to_string()));
+        assert_eq!(names.get_local_name(2, 0), None);
+    }
+
+    #[test]
+    fn referenced_removed_function_is_error() {
+        let mut ir = build(&sample_module());
+        assert!(ir.remove_function(Id::new(2)).is_some());
+        assert!(ir.encode().is_err());
+    }
+}
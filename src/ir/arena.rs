@@ -0,0 +1,165 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// アリーナ内の要素を指すID
+///
+/// 要素を削除・挿入しても、他の要素のIDは変わらない
+pub struct Id<T> {
+    index: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            _marker: PhantomData,
+        }
+    }
+
+    /// IDの番号を返す
+    ///
+    /// WasmModuleから変換した要素では、変換元のインデックスと同じ値になる
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+}
+
+// PhantomDataのため、deriveではTにも同じトレイトを要求してしまう
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Id({})", self.index)
+    }
+}
+
+impl<T> fmt::Display for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.index)
+    }
+}
+
+/// IDで要素を管理する配列
+///
+/// 削除した要素のIDは再利用しない。要素の並び(インデックス空間での順序)はIDとは別に持つ
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    slots: Vec<Option<T>>,
+    order: Vec<u32>, // 並び順のID
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// 末尾に追加し、そのIDを返す
+    pub fn push(&mut self, item: T) -> Id<T> {
+        let position = self.order.len();
+        self.insert(position, item)
+    }
+
+    /// 並びのposition番目に挿入し、そのIDを返す
+    pub fn insert(&mut self, position: usize, item: T) -> Id<T> {
+        let index = self.slots.len() as u32;
+        self.slots.push(Some(item));
+        self.order.insert(position.min(self.order.len()), index);
+        Id::new(index)
+    }
+
+    /// 削除して返す(存在しない場合はNone)
+    pub fn remove(&mut self, id: Id<T>) -> Option<T> {
+        let item = self.slots.get_mut(id.index as usize)?.take()?;
+        self.order.retain(|x| *x != id.index);
+        Some(item)
+    }
+
+    pub fn get(&self, id: Id<T>) -> Option<&T> {
+        self.slots.get(id.index as usize)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+        self.slots.get_mut(id.index as usize)?.as_mut()
+    }
+
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// 要素の数を返す
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// これまでに割り当てたIDの数を返す(削除したものを含む)
+    pub fn get_capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// 並び順のIDのリストを返す
+    pub fn get_ids(&self) -> Vec<Id<T>> {
+        self.order.iter().map(|x| Id::new(*x)).collect()
+    }
+
+    /// 並び順に(ID, 要素)を返す
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.order.iter().filter_map(|x| {
+            self.slots[*x as usize]
+                .as_ref()
+                .map(|item| (Id::new(*x), item))
+        })
+    }
+
+    /// 並びの中での位置を返す
+    pub fn position(&self, id: Id<T>) -> Option<usize> {
+        self.order.iter().position(|x| *x == id.index)
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+
+use super::arena::Id;
+use super::items::*;
+use super::ModuleIr;
+
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{
+    CustomSectionPayload, IndirectNameMapSubsection, NameMapSubsection, ParseError,
+    SectionCommonInterface, TypeEntry,
+};
+use crate::wasm_components::types::{
+    CompositeType, ExternalKind, FieldType, IndexType, LangTypes, ResizableLimits, StorageType,
+};
+
+/*
+ * WasmModule -> ModuleIr
+ *
+ * 各インデックス空間のIDの番号は変換元のインデックスと同じにする
+ * (インポートされたものを先に追加する)
+ * そのため命令列・初期化式のバイト列はそのままコピーできる
+ */
+
+impl ModuleIr {
+    /// WasmModuleからIRを作る
+    pub fn from_module(module: &WasmModule) -> Result<Self, ParseError> {
+        let mut ir = ModuleIr::new();
+        ir.version = module.get_magic_and_version().get_version();
+
+        // Type
+        if let Some(sec) = module.get_type_section() {
+            for (group_cnt, rec_group) in sec.get_rec_groups().into_iter().enumerate() {
+                let group = if rec_group.is_explicit() {
+                    Some(group_cnt as u32)
+                } else {
+                    None
+                };
+                for sub_type in rec_group.get_sub_types().into_iter() {
+                    ir.types.push(IrType {
+                        rec_group: group,
+                        is_final: sub_type.is_final(),
+                        super_types: sub_type
+                            .get_super_types()
+                            .iter()
+                            .map(|x| Id::new(*x))
+                            .collect(),
+                        composite: convert_composite_type(sub_type.get_composite_type()),
+                    });
+                }
+            }
+        }
+
+        // Import
+        // インポートされたものは、定義されたものより先にArenaに追加する
+        if let Some(sec) = module.get_import_section() {
+            for entry in sec.get_import_entries().into_iter() {
+                let target = match entry.get_type() {
+                    TypeEntry::FuncIndex { type_ } => {
+                        IrExternal::Function(ir.functions.push(IrFunction {
+                            type_id: Id::new(*type_),
+                            body: None,
+                        }))
+                    }
+                    TypeEntry::TblType { type_ } => IrExternal::Table(ir.tables.push(IrTable {
+                        elem_type: *type_.get_elem_type(),
+                        limits: convert_limits(type_.get_limits(), type_.get_index_type()),
+                        init: None,
+                    })),
+                    TypeEntry::MemType { type_ } => {
+                        IrExternal::Memory(ir.memories.push(IrMemory {
+                            limits: convert_limits(type_.get_limits(), type_.get_index_type()),
+                        }))
+                    }
+                    TypeEntry::GblType { type_ } => IrExternal::Global(ir.globals.push(IrGlobal {
+                        content_type: *type_.get_type(),
+                        mutable: type_.get_mutability(),
+                        init: None,
+                    })),
+                    TypeEntry::TagType { type_ } => IrExternal::Tag(ir.tags.push(IrTag {
+                        type_id: Id::new(type_.get_type_index()),
+                    })),
+                };
+                ir.imports.push(IrImport {
+                    module: entry.get_import_module_name().clone(),
+                    field: entry.get_import_entry_name().clone(),
+                    target,
+                });
+            }
+        }
+
+        // Function, Code
+        let type_indices = match module.get_function_section() {
+            Some(sec) => sec.get_indice_list(),
+            None => Vec::new(),
+        };
+        for (cnt, type_index) in type_indices.into_iter().enumerate() {
+            let body = match module.get_code_section().and_then(|x| x.get_func_body(cnt)) {
+                Some(body) => body,
+                None => {
+                    return Err(ParseError::ValidationError(format!(
+                        "function body is missing: defined function {}",
+                        cnt
+                    )))
+                }
+            };
+            ir.functions.push(IrFunction {
+                type_id: Id::new(type_index),
+                body: Some(IrFunctionBody {
+                    locals: body
+                        .get_local_entries()
+                        .into_iter()
+                        .map(|x| (x.get_count(), *x.get_value_type()))
+                        .collect(),
+                    code: body.get_code().clone(),
+                }),
+            });
+        }
+
+        // Table
+        if let Some(sec) = module.get_table_section() {
+            for (cnt, table_type) in sec.get_table_list().into_iter().enumerate() {
+                ir.tables.push(IrTable {
+                    elem_type: *table_type.get_elem_type(),
+                    limits: convert_limits(table_type.get_limits(), table_type.get_index_type()),
+                    init: sec.get_table_init_expr(cnt).map(|x| x.get_instrs().clone()),
+                });
+            }
+        }
+
+        // Memory
+        if let Some(sec) = module.get_memory_section() {
+            for memory_type in sec.get_memories().into_iter() {
+                ir.memories.push(IrMemory {
+                    limits: convert_limits(memory_type.get_limits(), memory_type.get_index_type()),
+                });
+            }
+        }
+
+        // Tag
+        if let Some(sec) = module.get_tag_section() {
+            for tag_type in sec.get_tag_list().into_iter() {
+                ir.tags.push(IrTag {
+                    type_id: Id::new(tag_type.get_type_index()),
+                });
+            }
+        }
+
+        // Global
+        if let Some(sec) = module.get_global_section() {
+            for global_var in sec.get_global_variable_list().into_iter() {
+                let global_type = global_var.get_global_type();
+                ir.globals.push(IrGlobal {
+                    content_type: *global_type.get_type(),
+                    mutable: global_type.get_mutability(),
+                    init: Some(global_var.get_init_expr().get_instrs().clone()),
+                });
+            }
+        }
+
+        // Export
+        if let Some(sec) = module.get_export_section() {
+            for entry in sec.get_export_entry_list().into_iter() {
+                let index = entry.get_index();
+                let target = match entry.get_kind() {
+                    ExternalKind::Function => IrExternal::Function(Id::new(index)),
+                    ExternalKind::Table => IrExternal::Table(Id::new(index)),
+                    ExternalKind::Memory => IrExternal::Memory(Id::new(index)),
+                    ExternalKind::Global => IrExternal::Global(Id::new(index)),
+                    ExternalKind::Tag => IrExternal::Tag(Id::new(index)),
+                };
+                ir.exports.push(IrExport {
+                    name: entry.get_entry_name().clone(),
+                    target,
+                });
+            }
+        }
+
+        // Start
+        ir.start = module
+            .get_start_section()
+            .map(|x| Id::new(x.get_start_func_index()));
+
+        // Element
+        if let Some(sec) = module.get_element_section() {
+            for segment in sec.get_element_list().into_iter() {
+                let mode = if segment.is_declarative() {
+                    IrElementMode::Declarative
+                } else if segment.is_passive() {
+                    IrElementMode::Passive
+                } else {
+                    IrElementMode::Active {
+                        table: Id::new(segment.get_table_index().unwrap_or(0)),
+                        offset: match segment.get_offset_expr() {
+                            Some(expr) => expr.get_instrs().clone(),
+                            None => Vec::new(),
+                        },
+                    }
+                };
+
+                let exprs = segment.get_element_exprs();
+                let items = if exprs.is_empty() && segment.get_elem_type() == LangTypes::ANYFUNC {
+                    IrElementItems::Functions(
+                        segment.get_elements().into_iter().map(Id::new).collect(),
+                    )
+                } else {
+                    IrElementItems::Exprs(
+                        exprs.into_iter().map(|x| x.get_instrs().clone()).collect(),
+                    )
+                };
+
+                ir.elements.push(IrElement {
+                    mode,
+                    elem_type: segment.get_elem_type(),
+                    items,
+                });
+            }
+        }
+
+        // Data
+        if let Some(sec) = module.get_data_section() {
+            for segment in sec.get_data_segment_list().into_iter() {
+                let mode = if segment.is_passive() {
+                    IrDataMode::Passive
+                } else {
+                    IrDataMode::Active {
+                        memory: Id::new(segment.get_memory_index().unwrap_or(0)),
+                        offset: match segment.get_offset_expr() {
+                            Some(expr) => expr.get_instrs().clone(),
+                            None => Vec::new(),
+                        },
+                    }
+                };
+                ir.data.push(IrData {
+                    mode,
+                    bytes: segment.get_data(),
+                });
+            }
+        }
+
+        ir.data_count = module.get_data_count_section().is_some();
+
+        // Name
+        if let Some(payload) = module.get_name_section() {
+            let names = &mut ir.names;
+            names.module = payload.get_module_name().map(|x| x.get_name().clone());
+            names.functions = convert_name_map(payload.get_function_names());
+            names.locals = convert_indirect_name_map(payload.get_local_names());
+            names.labels = convert_indirect_name_map(payload.get_label_names());
+            names.types = convert_name_map(payload.get_type_names());
+            names.tables = convert_name_map(payload.get_table_names());
+            names.memories = convert_name_map(payload.get_memory_names());
+            names.globals = convert_name_map(payload.get_global_names());
+            names.elems = convert_name_map(payload.get_elem_names());
+            names.data = convert_name_map(payload.get_data_names());
+            names.fields = convert_indirect_name_map(payload.get_field_names());
+            names.tags = convert_name_map(payload.get_tag_names());
+        }
+
+        // Custom
+        // section_idsを先頭から見て、直前にある(カスタムセクション以外の)セクションのIDを記録する
+        let mut custom_sections = module.get_custom_sections().into_iter();
+        let mut after: u8 = 0;
+        for section in module.get_sections().iter() {
+            let id = section.get_id();
+            if id != 0 {
+                after = id;
+                continue;
+            }
+            if let Some(custom_section) = custom_sections.next() {
+                let name = match custom_section.get_name() {
+                    Some(name) => name.clone(),
+                    None => String::new(),
+                };
+                // nameセクションはエンコード時にnamesから作り直すため、位置だけを記録する
+                let payload = match custom_section.get_payload() {
+                    _ if name == "name" => IrCustomPayload::Bytes(Vec::new()),
+                    CustomSectionPayload::Registered { payload, .. } => {
+                        IrCustomPayload::Registered(payload.clone())
+                    }
+                    _ => IrCustomPayload::Bytes(custom_section.get_raw_payload().to_vec()),
+                };
+                ir.custom_sections.push(IrCustomSection {
+                    name,
+                    after,
+                    payload,
+                });
+            }
+        }
+
+        Ok(ir)
+    }
+}
+
+fn convert_limits(limits: &ResizableLimits, index_type: IndexType) -> IrLimits {
+    IrLimits {
+        initial: limits.get_initial_length(),
+        maximum: limits.get_maximum_length(),
+        shared: limits.is_shared(),
+        index64: index_type == IndexType::I64,
+    }
+}
+
+fn convert_field_type(field_type: &FieldType) -> IrFieldType {
+    let storage = match field_type.get_storage_type() {
+        StorageType::Val(vt) => IrStorageType::Val(*vt.get_value()),
+        StorageType::I8 => IrStorageType::I8,
+        StorageType::I16 => IrStorageType::I16,
+    };
+
+    IrFieldType {
+        storage,
+        mutable: field_type.get_mutability(),
+    }
+}
+
+fn convert_composite_type(composite_type: &CompositeType) -> IrCompositeType {
+    match composite_type {
+        CompositeType::Func(func_type) => IrCompositeType::Func {
+            params: func_type
+                .get_param_types()
+                .into_iter()
+                .map(|x| *x.get_value())
+                .collect(),
+            results: func_type
+                .get_return_types()
+                .into_iter()
+                .map(|x| *x.get_value())
+                .collect(),
+        },
+        CompositeType::Struct(struct_type) => IrCompositeType::Struct(
+            struct_type
+                .get_fields()
+                .into_iter()
+                .map(convert_field_type)
+                .collect(),
+        ),
+        CompositeType::Array(array_type) => {
+            IrCompositeType::Array(convert_field_type(array_type.get_field()))
+        }
+    }
+}
+
+fn convert_name_map<T>(subsection: Option<&NameMapSubsection>) -> BTreeMap<Id<T>, String> {
+    let mut names: BTreeMap<Id<T>, String> = BTreeMap::new();
+    if let Some(subsection) = subsection {
+        for naming in subsection.get_name_map().get_name_list().into_iter() {
+            names.insert(Id::new(naming.get_indice()), naming.get_name_str().clone());
+        }
+    }
+    names
+}
+
+fn convert_indirect_name_map<T>(
+    subsection: Option<&IndirectNameMapSubsection>,
+) -> BTreeMap<Id<T>, BTreeMap<u32, String>> {
+    let mut names: BTreeMap<Id<T>, BTreeMap<u32, String>> = BTreeMap::new();
+    if let Some(subsection) = subsection {
+        for entry in subsection.get_entries().into_iter() {
+            let inner: BTreeMap<u32, String> = entry
+                .get_name_map()
+                .get_name_list()
+                .into_iter()
+                .map(|x| (x.get_indice(), x.get_name_str().clone()))
+                .collect();
+            names.insert(Id::new(entry.get_indice()), inner);
+        }
+    }
+    names
+}
@@ -0,0 +1,692 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Cursor;
+
+use super::arena::{Arena, Id};
+use super::items::*;
+use super::remap::{remap_instructions, IndexMap, IndexMaps, RemapWriter};
+use super::ModuleIr;
+
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::ParseError;
+use crate::wasm_components::types::LangTypes;
+
+/*
+ * ModuleIr -> バイナリ
+ *
+ * 各インデックス空間のインデックスを振り直し、IDの参照をすべてインデックスに置き換える
+ * セクションは仕様の順(type, import, function, table, memory, tag, global, export,
+ * start, element, datacount, code, data)で出力し、空のセクションは出力しない
+ * カスタムセクションは、afterのセクションの直後(afterのセクションを出力しない場合もその位置)に出力する
+ */
+
+// セクションの出力順
+const SECTION_ORDER: [u8; 13] = [1, 2, 3, 4, 5, 13, 6, 7, 8, 9, 12, 10, 11];
+
+impl ModuleIr {
+    /// バイナリにエンコードする
+    ///
+    /// 削除された要素を参照している場合はValidationError
+    pub fn encode(&self) -> Result<Vec<u8>, ParseError> {
+        let maps = self.get_index_maps();
+
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(b"\0asm");
+        out.extend_from_slice(&self.version.to_le_bytes());
+
+        let mut has_name_section = false;
+        for after in std::iter::once(0).chain(SECTION_ORDER.iter().copied()) {
+            if after != 0 {
+                if let Some(payload) = self.encode_section(after, &maps)? {
+                    write_section(&mut out, after, &payload);
+                }
+            }
+
+            for custom_section in self.custom_sections.iter().filter(|x| x.after == after) {
+                let payload = if custom_section.name == "name" {
+                    has_name_section = true;
+                    let payload = self.encode_name_section(&maps);
+                    if payload.is_empty() {
+                        continue;
+                    }
+                    payload
+                } else {
+                    custom_section.payload.encode()
+                };
+                write_custom_section(&mut out, &custom_section.name, &payload);
+            }
+        }
+
+        // nameセクションがなかったモジュールに名前を付けた場合は末尾に追加する
+        if !has_name_section {
+            let payload = self.encode_name_section(&maps);
+            if !payload.is_empty() {
+                write_custom_section(&mut out, "name", &payload);
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// エンコードしてWasmModuleに変換する
+    pub fn to_module(&self) -> Result<WasmModule, ParseError> {
+        let bytes = self.encode()?;
+        WasmModule::parse(&mut Cursor::new(bytes))
+    }
+
+    // 各インデックス空間で、IDの番号からインデックスへの対応を作る
+    fn get_index_maps(&self) -> IndexMaps {
+        let imported = |kind: fn(&IrExternal) -> Option<u32>| -> Vec<u32> {
+            self.imports
+                .iter()
+                .filter_map(|x| kind(&x.target))
+                .collect()
+        };
+
+        IndexMaps {
+            types: IndexMap::new(
+                "type",
+                self.types.get_capacity(),
+                &get_order(&self.types, &[]),
+            ),
+            functions: IndexMap::new(
+                "function",
+                self.functions.get_capacity(),
+                &get_order(
+                    &self.functions,
+                    &imported(|x| match x {
+                        IrExternal::Function(id) => Some(id.get_index()),
+                        _ => None,
+                    }),
+                ),
+            ),
+            tables: IndexMap::new(
+                "table",
+                self.tables.get_capacity(),
+                &get_order(
+                    &self.tables,
+                    &imported(|x| match x {
+                        IrExternal::Table(id) => Some(id.get_index()),
+                        _ => None,
+                    }),
+                ),
+            ),
+            memories: IndexMap::new(
+                "memory",
+                self.memories.get_capacity(),
+                &get_order(
+                    &self.memories,
+                    &imported(|x| match x {
+                        IrExternal::Memory(id) => Some(id.get_index()),
+                        _ => None,
+                    }),
+                ),
+            ),
+            globals: IndexMap::new(
+                "global",
+                self.globals.get_capacity(),
+                &get_order(
+                    &self.globals,
+                    &imported(|x| match x {
+                        IrExternal::Global(id) => Some(id.get_index()),
+                        _ => None,
+                    }),
+                ),
+            ),
+            tags: IndexMap::new(
+                "tag",
+                self.tags.get_capacity(),
+                &get_order(
+                    &self.tags,
+                    &imported(|x| match x {
+                        IrExternal::Tag(id) => Some(id.get_index()),
+                        _ => None,
+                    }),
+                ),
+            ),
+            elements: IndexMap::new(
+                "elem segment",
+                self.elements.get_capacity(),
+                &get_order(&self.elements, &[]),
+            ),
+            data: IndexMap::new(
+                "data segment",
+                self.data.get_capacity(),
+                &get_order(&self.data, &[]),
+            ),
+        }
+    }
+
+    // 定義された要素を並び順に返す(インポートされたものを除く)
+    fn get_defined<'a, T>(
+        &'a self,
+        arena: &'a Arena<T>,
+        is_target: fn(&IrExternal, u32) -> bool,
+    ) -> Vec<(Id<T>, &'a T)> {
+        arena
+            .iter()
+            .filter(|(id, _)| {
+                !self
+                    .imports
+                    .iter()
+                    .any(|x| is_target(&x.target, id.get_index()))
+            })
+            .collect()
+    }
+
+    // セクションのペイロードを作る(出力しない場合はNone)
+    fn encode_section(&self, id: u8, maps: &IndexMaps) -> Result<Option<Vec<u8>>, ParseError> {
+        let mut w = RemapWriter::new(maps);
+        match id {
+            // Type
+            1 => {
+                if self.types.is_empty() {
+                    return Ok(None);
+                }
+                let types: Vec<&IrType> = self.types.iter().map(|(_, x)| x).collect();
+
+                // 同じrec_groupの連続した型を1つのrec groupにまとめる
+                let mut groups: Vec<&[&IrType]> = Vec::new();
+                let mut begin = 0;
+                while begin < types.len() {
+                    let mut end = begin + 1;
+                    if let Some(group) = types[begin].rec_group {
+                        while end < types.len() && types[end].rec_group == Some(group) {
+                            end += 1;
+                        }
+                    }
+                    groups.push(&types[begin..end]);
+                    begin = end;
+                }
+
+                w.u32(groups.len() as u32);
+                for group in groups.into_iter() {
+                    if group[0].rec_group.is_some() {
+                        w.byte(0x4e);
+                        w.u32(group.len() as u32);
+                    }
+                    for ty in group.iter() {
+                        write_sub_type(&mut w, ty)?;
+                    }
+                }
+            }
+            // Import
+            2 => {
+                if self.imports.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(self.imports.len() as u32);
+                for import in self.imports.iter() {
+                    w.name(&import.module);
+                    w.name(&import.field);
+                    match import.target {
+                        IrExternal::Function(func_id) => {
+                            let function = get_item(&self.functions, func_id, "function")?;
+                            w.byte(0x00);
+                            w.type_index(function.type_id.get_index())?;
+                        }
+                        IrExternal::Table(table_id) => {
+                            let table = get_item(&self.tables, table_id, "table")?;
+                            w.byte(0x01);
+                            write_table_type(&mut w, table)?;
+                        }
+                        IrExternal::Memory(memory_id) => {
+                            let memory = get_item(&self.memories, memory_id, "memory")?;
+                            w.byte(0x02);
+                            write_limits(&mut w, &memory.limits);
+                        }
+                        IrExternal::Global(global_id) => {
+                            let global = get_item(&self.globals, global_id, "global")?;
+                            w.byte(0x03);
+                            w.lang_type(&global.content_type)?;
+                            w.byte(global.mutable as u8);
+                        }
+                        IrExternal::Tag(tag_id) => {
+                            let tag = get_item(&self.tags, tag_id, "tag")?;
+                            w.byte(0x04);
+                            w.byte(0x00);
+                            w.type_index(tag.type_id.get_index())?;
+                        }
+                    }
+                }
+            }
+            // Function
+            3 => {
+                let functions = self.get_defined(&self.functions, is_function);
+                if functions.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(functions.len() as u32);
+                for (_, function) in functions.into_iter() {
+                    w.type_index(function.type_id.get_index())?;
+                }
+            }
+            // Table
+            4 => {
+                let tables = self.get_defined(&self.tables, is_table);
+                if tables.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(tables.len() as u32);
+                for (_, table) in tables.into_iter() {
+                    match &table.init {
+                        Some(init) => {
+                            w.byte(0x40);
+                            w.byte(0x00);
+                            write_table_type(&mut w, table)?;
+                            w.bytes(&remap_instructions(init, maps)?);
+                        }
+                        None => write_table_type(&mut w, table)?,
+                    }
+                }
+            }
+            // Memory
+            5 => {
+                let memories = self.get_defined(&self.memories, is_memory);
+                if memories.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(memories.len() as u32);
+                for (_, memory) in memories.into_iter() {
+                    write_limits(&mut w, &memory.limits);
+                }
+            }
+            // Tag
+            13 => {
+                let tags = self.get_defined(&self.tags, is_tag);
+                if tags.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(tags.len() as u32);
+                for (_, tag) in tags.into_iter() {
+                    w.byte(0x00);
+                    w.type_index(tag.type_id.get_index())?;
+                }
+            }
+            // Global
+            6 => {
+                let globals = self.get_defined(&self.globals, is_global);
+                if globals.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(globals.len() as u32);
+                for (global_id, global) in globals.into_iter() {
+                    let init = match &global.init {
+                        Some(init) => init,
+                        None => {
+                            return Err(ParseError::ValidationError(format!(
+                                "global {} has no init expr",
+                                global_id
+                            )))
+                        }
+                    };
+                    w.lang_type(&global.content_type)?;
+                    w.byte(global.mutable as u8);
+                    w.bytes(&remap_instructions(init, maps)?);
+                }
+            }
+            // Export
+            7 => {
+                if self.exports.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(self.exports.len() as u32);
+                for export in self.exports.iter() {
+                    w.name(&export.name);
+                    let (kind, map, id) = match export.target {
+                        IrExternal::Function(id) => (0x00, &maps.functions, id.get_index()),
+                        IrExternal::Table(id) => (0x01, &maps.tables, id.get_index()),
+                        IrExternal::Memory(id) => (0x02, &maps.memories, id.get_index()),
+                        IrExternal::Global(id) => (0x03, &maps.globals, id.get_index()),
+                        IrExternal::Tag(id) => (0x04, &maps.tags, id.get_index()),
+                    };
+                    w.byte(kind);
+                    w.index(map, id)?;
+                }
+            }
+            // Start
+            8 => match self.start {
+                Some(func_id) => w.index(&maps.functions, func_id.get_index())?,
+                None => return Ok(None),
+            },
+            // Element
+            9 => {
+                if self.elements.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(self.elements.len() as u32);
+                for (_, element) in self.elements.iter() {
+                    write_element(&mut w, element, maps)?;
+                }
+            }
+            // DataCount
+            12 => {
+                if !self.data_count {
+                    return Ok(None);
+                }
+                w.u32(self.data.len() as u32);
+            }
+            // Code
+            10 => {
+                let functions = self.get_defined(&self.functions, is_function);
+                if functions.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(functions.len() as u32);
+                for (func_id, function) in functions.into_iter() {
+                    let body = match &function.body {
+                        Some(body) => body,
+                        None => {
+                            return Err(ParseError::ValidationError(format!(
+                                "function {} is neither imported nor has a body",
+                                func_id
+                            )))
+                        }
+                    };
+
+                    let mut body_w = RemapWriter::new(maps);
+                    body_w.u32(body.locals.len() as u32);
+                    for (count, local_type) in body.locals.iter() {
+                        body_w.u32(*count);
+                        body_w.lang_type(local_type)?;
+                    }
+                    body_w.bytes(&remap_instructions(&body.code, maps)?);
+                    body_w.byte(0x0b); // end
+                    w.vec(&body_w.into_bytes());
+                }
+            }
+            // Data
+            11 => {
+                if self.data.is_empty() {
+                    return Ok(None);
+                }
+                w.u32(self.data.len() as u32);
+                for (_, data) in self.data.iter() {
+                    match &data.mode {
+                        IrDataMode::Active { memory, offset } => {
+                            let memory_index = maps.memories.get(memory.get_index())?;
+                            if memory_index == 0 {
+                                w.byte(0x00);
+                            } else {
+                                w.byte(0x02);
+                                w.u32(memory_index);
+                            }
+                            w.bytes(&remap_instructions(offset, maps)?);
+                        }
+                        IrDataMode::Passive => w.byte(0x01),
+                    }
+                    w.vec(&data.bytes);
+                }
+            }
+            _ => return Ok(None),
+        }
+
+        Ok(Some(w.into_bytes()))
+    }
+
+    // nameセクションのペイロードを作る(名前がない場合は空)
+    //
+    // 削除された要素の名前は取り除き、振り直したインデックスの順に並べる
+    fn encode_name_section(&self, maps: &IndexMaps) -> Vec<u8> {
+        let names = &self.names;
+        let mut w = RemapWriter::new(maps);
+
+        if let Some(module_name) = &names.module {
+            let mut sub_w = RemapWriter::new(maps);
+            sub_w.name(module_name);
+            w.byte(0);
+            w.vec(&sub_w.into_bytes());
+        }
+        write_name_map(&mut w, 1, &names.functions, &maps.functions);
+        write_indirect_name_map(&mut w, 2, &names.locals, &maps.functions);
+        write_indirect_name_map(&mut w, 3, &names.labels, &maps.functions);
+        write_name_map(&mut w, 4, &names.types, &maps.types);
+        write_name_map(&mut w, 5, &names.tables, &maps.tables);
+        write_name_map(&mut w, 6, &names.memories, &maps.memories);
+        write_name_map(&mut w, 7, &names.globals, &maps.globals);
+        write_name_map(&mut w, 8, &names.elems, &maps.elements);
+        write_name_map(&mut w, 9, &names.data, &maps.data);
+        write_indirect_name_map(&mut w, 10, &names.fields, &maps.types);
+        write_name_map(&mut w, 11, &names.tags, &maps.tags);
+
+        w.into_bytes()
+    }
+}
+
+fn is_function(target: &IrExternal, id: u32) -> bool {
+    matches!(target, IrExternal::Function(x) if x.get_index() == id)
+}
+
+fn is_table(target: &IrExternal, id: u32) -> bool {
+    matches!(target, IrExternal::Table(x) if x.get_index() == id)
+}
+
+fn is_memory(target: &IrExternal, id: u32) -> bool {
+    matches!(target, IrExternal::Memory(x) if x.get_index() == id)
+}
+
+fn is_global(target: &IrExternal, id: u32) -> bool {
+    matches!(target, IrExternal::Global(x) if x.get_index() == id)
+}
+
+fn is_tag(target: &IrExternal, id: u32) -> bool {
+    matches!(target, IrExternal::Tag(x) if x.get_index() == id)
+}
+
+// インデックス空間での並び順(IDの番号のリスト)を返す
+//
+// インポートされたもの(importsの順)の後に、定義されたもの(Arenaの順)が並ぶ
+fn get_order<T>(arena: &Arena<T>, imported: &[u32]) -> Vec<u32> {
+    let mut order: Vec<u32> = Vec::new();
+    let mut seen: HashSet<u32> = HashSet::new();
+    for id in imported.iter() {
+        if arena.contains(Id::new(*id)) && seen.insert(*id) {
+            order.push(*id);
+        }
+    }
+    for id in arena.get_ids().into_iter() {
+        if !seen.contains(&id.get_index()) {
+            order.push(id.get_index());
+        }
+    }
+    order
+}
+
+fn get_item<'a, T>(arena: &'a Arena<T>, id: Id<T>, kind: &str) -> Result<&'a T, ParseError> {
+    match arena.get(id) {
+        Some(item) => Ok(item),
+        None => Err(ParseError::ValidationError(format!(
+            "imported {} {} does not exist",
+            kind, id
+        ))),
+    }
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+    out.push(id);
+    let _ = leb128::write::unsigned(out, payload.len() as u64);
+    out.extend_from_slice(payload);
+}
+
+fn write_custom_section(out: &mut Vec<u8>, name: &str, payload: &[u8]) {
+    let mut content: Vec<u8> = Vec::new();
+    let _ = leb128::write::unsigned(&mut content, name.len() as u64);
+    content.extend_from_slice(name.as_bytes());
+    content.extend_from_slice(payload);
+    write_section(out, 0, &content);
+}
+
+fn write_limits(w: &mut RemapWriter, limits: &IrLimits) {
+    let flags = (limits.maximum.is_some() as u8)
+        | ((limits.shared as u8) << 1)
+        | ((limits.index64 as u8) << 2);
+    w.byte(flags);
+    w.u64(limits.initial);
+    if let Some(maximum) = limits.maximum {
+        w.u64(maximum);
+    }
+}
+
+fn write_table_type(w: &mut RemapWriter, table: &IrTable) -> Result<(), ParseError> {
+    w.lang_type(&table.elem_type)?;
+    write_limits(w, &table.limits);
+    Ok(())
+}
+
+fn write_field_type(w: &mut RemapWriter, field_type: &IrFieldType) -> Result<(), ParseError> {
+    match &field_type.storage {
+        IrStorageType::Val(ty) => w.lang_type(ty)?,
+        IrStorageType::I8 => w.byte(0x78),
+        IrStorageType::I16 => w.byte(0x77),
+    }
+    w.byte(field_type.mutable as u8);
+    Ok(())
+}
+
+fn write_sub_type(w: &mut RemapWriter, ty: &IrType) -> Result<(), ParseError> {
+    // finalで親の型がない場合は、subを省略した形で書く
+    if !ty.is_final || !ty.super_types.is_empty() {
+        w.byte(if ty.is_final { 0x4f } else { 0x50 });
+        w.u32(ty.super_types.len() as u32);
+        for super_type in ty.super_types.iter() {
+            w.type_index(super_type.get_index())?;
+        }
+    }
+
+    match &ty.composite {
+        IrCompositeType::Func { params, results } => {
+            w.byte(0x60);
+            w.u32(params.len() as u32);
+            for param in params.iter() {
+                w.lang_type(param)?;
+            }
+            w.u32(results.len() as u32);
+            for result in results.iter() {
+                w.lang_type(result)?;
+            }
+        }
+        IrCompositeType::Struct(fields) => {
+            w.byte(0x5f);
+            w.u32(fields.len() as u32);
+            for field in fields.iter() {
+                write_field_type(w, field)?;
+            }
+        }
+        IrCompositeType::Array(field) => {
+            w.byte(0x5e);
+            write_field_type(w, field)?;
+        }
+    }
+    Ok(())
+}
+
+// elemセグメントを、モードと要素の表し方に応じたフラグで書く
+fn write_element(
+    w: &mut RemapWriter,
+    element: &IrElement,
+    maps: &IndexMaps,
+) -> Result<(), ParseError> {
+    let is_exprs = matches!(element.items, IrElementItems::Exprs(_));
+    let expr_flag: u8 = if is_exprs { 0x04 } else { 0x00 };
+
+    match &element.mode {
+        IrElementMode::Active { table, offset } => {
+            let table_index = maps.tables.get(table.get_index())?;
+            if table_index == 0 && element.elem_type == LangTypes::ANYFUNC {
+                w.byte(expr_flag);
+                w.bytes(&remap_instructions(offset, maps)?);
+            } else {
+                w.byte(0x02 | expr_flag);
+                w.u32(table_index);
+                w.bytes(&remap_instructions(offset, maps)?);
+                write_elem_kind(w, element)?;
+            }
+        }
+        IrElementMode::Passive => {
+            w.byte(0x01 | expr_flag);
+            write_elem_kind(w, element)?;
+        }
+        IrElementMode::Declarative => {
+            w.byte(0x03 | expr_flag);
+            write_elem_kind(w, element)?;
+        }
+    }
+
+    match &element.items {
+        IrElementItems::Functions(functions) => {
+            w.u32(functions.len() as u32);
+            for func_id in functions.iter() {
+                w.index(&maps.functions, func_id.get_index())?;
+            }
+        }
+        IrElementItems::Exprs(exprs) => {
+            w.u32(exprs.len() as u32);
+            for expr in exprs.iter() {
+                w.bytes(&remap_instructions(expr, maps)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+// 関数インデックスの場合はelemkind(0x00)、初期化式の場合は参照型を書く
+fn write_elem_kind(w: &mut RemapWriter, element: &IrElement) -> Result<(), ParseError> {
+    match element.items {
+        IrElementItems::Functions(_) => {
+            w.byte(0x00);
+            Ok(())
+        }
+        IrElementItems::Exprs(_) => w.lang_type(&element.elem_type),
+    }
+}
+
+fn write_name_map<T>(
+    w: &mut RemapWriter,
+    name_type: u8,
+    names: &BTreeMap<Id<T>, String>,
+    map: &IndexMap,
+) {
+    let entries: BTreeMap<u32, &String> = names
+        .iter()
+        .filter_map(|(id, name)| map.get(id.get_index()).ok().map(|idx| (idx, name)))
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    let mut sub_w = w.new_sub_writer();
+    sub_w.u32(entries.len() as u32);
+    for (idx, name) in entries.into_iter() {
+        sub_w.u32(idx);
+        sub_w.name(name);
+    }
+    w.byte(name_type);
+    w.vec(&sub_w.into_bytes());
+}
+
+fn write_indirect_name_map<T>(
+    w: &mut RemapWriter,
+    name_type: u8,
+    names: &BTreeMap<Id<T>, BTreeMap<u32, String>>,
+    map: &IndexMap,
+) {
+    let entries: BTreeMap<u32, &BTreeMap<u32, String>> = names
+        .iter()
+        .filter_map(|(id, inner)| map.get(id.get_index()).ok().map(|idx| (idx, inner)))
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    let mut sub_w = w.new_sub_writer();
+    sub_w.u32(entries.len() as u32);
+    for (idx, inner) in entries.into_iter() {
+        sub_w.u32(idx);
+        sub_w.u32(inner.len() as u32);
+        for (inner_idx, name) in inner.iter() {
+            sub_w.u32(*inner_idx);
+            sub_w.name(name);
+        }
+    }
+    w.byte(name_type);
+    w.vec(&sub_w.into_bytes());
+}
@@ -0,0 +1,596 @@
+use std::collections::HashSet;
+
+use super::arena::Id;
+use super::items::*;
+use super::ModuleIr;
+
+use crate::wasm_components::instruction::{decode_instructions, BlockSignature, Immediate};
+use crate::wasm_components::sections::{is_debug_section_name, ParseError};
+use crate::wasm_components::types::{HeapType, LangTypes};
+
+/*
+ * Dead code elimination (tree shaking)
+ *
+ * エクスポート・startセクション・elemセグメント・指定したルートから到達可能な要素を求め、
+ * 到達できない関数・グローバル変数・型・passiveなdataセグメント・インポートを削除する
+ *
+ * テーブル・メモリ・タグ・elemセグメント・activeなdataセグメントは
+ * インスタンス化時に効果があるため削除しない(これらが参照するものは到達可能とする)
+ *
+ * 要素を削除するとコードセクションが書き換わり、DWARF・ソースマップのコードオフセットや
+ * build_idが元のバイナリのものと合わなくなるため、それらのカスタムセクションも削除する
+ * (nameセクションはエンコード時に作り直すため残す)
+ */
+
+/// 削除した要素の数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub functions: usize, // インポートされた関数を含む
+    pub globals: usize,   // インポートされたグローバル変数を含む
+    pub types: usize,
+    pub data: usize,
+    pub imports: usize,
+    pub custom_sections: Vec<String>, // 削除したデバッグ情報・build_idのカスタムセクションの名前
+}
+
+impl GcReport {
+    /// 要素を1つ以上削除したかを返す
+    pub fn is_changed(&self) -> bool {
+        self.functions + self.globals + self.types + self.data + self.imports > 0
+    }
+}
+
+impl ModuleIr {
+    /// 到達できない要素を削除する
+    ///
+    /// rootsはエクスポート・start・elemセグメント以外に、追加で到達可能とする関数
+    /// リロケーション情報を持つオブジェクトファイルはインデックスを書き換えられないためエラー
+    pub fn gc(&mut self, roots: &[FuncId]) -> Result<GcReport, ParseError> {
+        if let Some(custom_section) = self
+            .custom_sections
+            .iter()
+            .find(|x| x.name == "linking" || x.name.starts_with("reloc."))
+        {
+            return Err(ParseError::ValidationError(format!(
+                "relocatable object file is not supported: found {} section",
+                custom_section.name
+            )));
+        }
+
+        let mut marker = Marker::new(self);
+        marker.mark_roots(roots)?;
+        marker.run()?;
+
+        let dead_functions: Vec<FuncId> = self
+            .functions
+            .get_ids()
+            .into_iter()
+            .filter(|x| !marker.functions.contains(x))
+            .collect();
+        let dead_globals: Vec<GlobalId> = self
+            .globals
+            .get_ids()
+            .into_iter()
+            .filter(|x| !marker.globals.contains(x))
+            .collect();
+        let dead_types: Vec<TypeId> = self
+            .types
+            .get_ids()
+            .into_iter()
+            .filter(|x| !marker.types.contains(x))
+            .collect();
+        let dead_data: Vec<DataId> = self
+            .data
+            .iter()
+            .filter(|(id, data)| data.mode == IrDataMode::Passive && !marker.data.contains(id))
+            .map(|(id, _)| id)
+            .collect();
+
+        let num_imports = self.imports.len();
+        let report = GcReport {
+            functions: dead_functions.len(),
+            globals: dead_globals.len(),
+            types: dead_types.len(),
+            data: dead_data.len(),
+            imports: 0,
+            custom_sections: Vec::new(),
+        };
+
+        for id in dead_functions.into_iter() {
+            self.remove_function(id);
+        }
+        for id in dead_globals.into_iter() {
+            self.remove_global(id);
+        }
+        for id in dead_types.into_iter() {
+            self.remove_type(id);
+        }
+        for id in dead_data.into_iter() {
+            self.remove_data(id);
+        }
+
+        let mut report = GcReport {
+            imports: num_imports - self.imports.len(),
+            ..report
+        };
+
+        // コードのオフセット・モジュールの同一性に依存するカスタムセクションを削除する
+        if report.is_changed() {
+            report.custom_sections = self
+                .custom_sections
+                .iter()
+                .filter(|x| is_offset_dependent_section(&x.name))
+                .map(|x| x.name.clone())
+                .collect();
+            self.custom_sections
+                .retain(|x| !is_offset_dependent_section(&x.name));
+        }
+
+        Ok(report)
+    }
+}
+
+// 要素を削除すると内容が合わなくなるカスタムセクションかどうか
+fn is_offset_dependent_section(name: &str) -> bool {
+    name != "name" && (is_debug_section_name(name) || name == "build_id")
+}
+
+// 到達可能な要素の集合を求める
+struct Marker<'a> {
+    ir: &'a ModuleIr,
+    functions: HashSet<FuncId>,
+    globals: HashSet<GlobalId>,
+    types: HashSet<TypeId>,
+    data: HashSet<DataId>,
+    function_queue: Vec<FuncId>,
+    global_queue: Vec<GlobalId>,
+    type_queue: Vec<TypeId>,
+}
+
+impl<'a> Marker<'a> {
+    fn new(ir: &'a ModuleIr) -> Self {
+        Self {
+            ir,
+            functions: HashSet::new(),
+            globals: HashSet::new(),
+            types: HashSet::new(),
+            data: HashSet::new(),
+            function_queue: Vec::new(),
+            global_queue: Vec::new(),
+            type_queue: Vec::new(),
+        }
+    }
+
+    fn mark_function(&mut self, id: FuncId) {
+        if self.ir.functions.contains(id) && self.functions.insert(id) {
+            self.function_queue.push(id);
+        }
+    }
+
+    fn mark_global(&mut self, id: GlobalId) {
+        if self.ir.globals.contains(id) && self.globals.insert(id) {
+            self.global_queue.push(id);
+        }
+    }
+
+    fn mark_type(&mut self, id: TypeId) {
+        if self.ir.types.contains(id) && self.types.insert(id) {
+            self.type_queue.push(id);
+        }
+    }
+
+    fn mark_heap_type(&mut self, heap_type: &HeapType) {
+        if let HeapType::Concrete(id) = heap_type {
+            self.mark_type(Id::new(*id));
+        }
+    }
+
+    fn mark_lang_type(&mut self, lang_type: &LangTypes) {
+        if let LangTypes::REF { heap_type, .. } = lang_type {
+            self.mark_heap_type(heap_type);
+        }
+    }
+
+    // 削除しない要素と、エクスポート・start・指定したルートを到達可能とする
+    fn mark_roots(&mut self, roots: &[FuncId]) -> Result<(), ParseError> {
+        let ir = self.ir;
+
+        for export in ir.exports.iter() {
+            match export.target {
+                IrExternal::Function(id) => self.mark_function(id),
+                IrExternal::Global(id) => self.mark_global(id),
+                _ => (),
+            }
+        }
+        if let Some(id) = ir.start {
+            self.mark_function(id);
+        }
+        for id in roots.iter() {
+            self.mark_function(*id);
+        }
+
+        for (_, table) in ir.tables.iter() {
+            self.mark_lang_type(&table.elem_type);
+            if let Some(init) = &table.init {
+                self.mark_instructions(init)?;
+            }
+        }
+        for (_, tag) in ir.tags.iter() {
+            self.mark_type(tag.type_id);
+        }
+
+        for (_, element) in ir.elements.iter() {
+            self.mark_lang_type(&element.elem_type);
+            if let IrElementMode::Active { offset, .. } = &element.mode {
+                self.mark_instructions(offset)?;
+            }
+            match &element.items {
+                IrElementItems::Functions(functions) => {
+                    for id in functions.iter() {
+                        self.mark_function(*id);
+                    }
+                }
+                IrElementItems::Exprs(exprs) => {
+                    for expr in exprs.iter() {
+                        self.mark_instructions(expr)?;
+                    }
+                }
+            }
+        }
+
+        for (_, data) in ir.data.iter() {
+            if let IrDataMode::Active { offset, .. } = &data.mode {
+                self.mark_instructions(offset)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 到達可能になった要素から参照されるものを、新たに到達するものがなくなるまでたどる
+    fn run(&mut self) -> Result<(), ParseError> {
+        let ir = self.ir;
+
+        loop {
+            if let Some(id) = self.function_queue.pop() {
+                let function = ir.functions.get(id).unwrap();
+                self.mark_type(function.type_id);
+                if let Some(body) = &function.body {
+                    for (_, local_type) in body.locals.iter() {
+                        self.mark_lang_type(local_type);
+                    }
+                    self.mark_instructions(&body.code)?;
+                }
+            } else if let Some(id) = self.global_queue.pop() {
+                let global = ir.globals.get(id).unwrap();
+                self.mark_lang_type(&global.content_type);
+                if let Some(init) = &global.init {
+                    self.mark_instructions(init)?;
+                }
+            } else if let Some(id) = self.type_queue.pop() {
+                self.mark_type_contents(id);
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mark_type_contents(&mut self, id: TypeId) {
+        let ir = self.ir;
+        let ty = ir.types.get(id).unwrap();
+
+        // rec groupの型は一部だけを削除すると型の同一性が変わるため、グループ全体を残す
+        if let Some(group) = ty.rec_group {
+            for (other_id, other) in ir.types.iter() {
+                if other.rec_group == Some(group) {
+                    self.mark_type(other_id);
+                }
+            }
+        }
+
+        for super_type in ty.super_types.iter() {
+            self.mark_type(*super_type);
+        }
+
+        match &ty.composite {
+            IrCompositeType::Func { params, results } => {
+                for lang_type in params.iter().chain(results.iter()) {
+                    self.mark_lang_type(lang_type);
+                }
+            }
+            IrCompositeType::Struct(fields) => {
+                for field in fields.iter() {
+                    self.mark_field_type(field);
+                }
+            }
+            IrCompositeType::Array(field) => self.mark_field_type(field),
+        }
+    }
+
+    fn mark_field_type(&mut self, field_type: &IrFieldType) {
+        if let IrStorageType::Val(lang_type) = &field_type.storage {
+            self.mark_lang_type(lang_type);
+        }
+    }
+
+    fn mark_block_signature(&mut self, signature: &BlockSignature) {
+        match signature {
+            BlockSignature::Value(lang_type) => self.mark_lang_type(lang_type),
+            BlockSignature::TypeIndex(id) => self.mark_type(Id::new(*id)),
+            BlockSignature::Empty => (),
+        }
+    }
+
+    // 命令列から参照される関数・グローバル変数・型・dataセグメントをたどる
+    fn mark_instructions(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        for instr in decode_instructions(bytes)?.iter() {
+            match instr.get_immediate() {
+                Immediate::Block(signature) => self.mark_block_signature(signature),
+                Immediate::Function(id) => self.mark_function(Id::new(*id)),
+                Immediate::CallIndirect { type_index, .. } => self.mark_type(Id::new(*type_index)),
+                Immediate::Global(id) => self.mark_global(Id::new(*id)),
+                Immediate::MemoryInit { data_index, .. } => {
+                    self.data.insert(Id::new(*data_index));
+                }
+                Immediate::Data(id) => {
+                    self.data.insert(Id::new(*id));
+                }
+                Immediate::TryTable { signature, .. } => self.mark_block_signature(signature),
+                Immediate::SelectTypes(types) => {
+                    for lang_type in types.iter() {
+                        self.mark_lang_type(lang_type);
+                    }
+                }
+                Immediate::Type(id)
+                | Immediate::Field { type_index: id, .. }
+                | Immediate::ArrayFixed { type_index: id, .. }
+                | Immediate::ArrayElem { type_index: id, .. } => self.mark_type(Id::new(*id)),
+                Immediate::ArrayData {
+                    type_index,
+                    data_index,
+                } => {
+                    self.mark_type(Id::new(*type_index));
+                    self.data.insert(Id::new(*data_index));
+                }
+                Immediate::ArrayCopy {
+                    dst_index,
+                    src_index,
+                } => {
+                    self.mark_type(Id::new(*dst_index));
+                    self.mark_type(Id::new(*src_index));
+                }
+                Immediate::HeapType(heap_type) => self.mark_heap_type(heap_type),
+                Immediate::RefType(lang_type) => self.mark_lang_type(lang_type),
+                Immediate::BrOnCast { from, to, .. } => {
+                    self.mark_lang_type(from);
+                    self.mark_lang_type(to);
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    // 型: 0 () -> (), 1 (i32) -> () 未使用, rec group {2 (i64) -> (), 3 (f32) -> ()}
+    // 関数: 0 インポート 未使用, 1 インポート startから呼ばれる,
+    //       2 エクスポート, 3 start, 4 elemセグメント, 5 未使用
+    // グローバル変数: 0 インポート activeなdataのオフセット, 1 未使用
+    // data: 0 active, 1 passive 未使用, 2 passive 関数2のdata.dropから参照
+    fn sample_module(custom_sections: &[Vec<u8>]) -> Vec<u8> {
+        let mut sections = vec![
+            section(
+                1,
+                &vec(&[
+                    func_type(&[], &[]),
+                    func_type(&[0x7f], &[]),
+                    [
+                        vec![0x4e],
+                        vec(&[func_type(&[0x7e], &[]), func_type(&[0x7d], &[])]),
+                    ]
+                    .concat(),
+                ]),
+            ),
+            section(
+                2,
+                &vec(&[
+                    [name("env"), name("a"), vec![0x00, 0x00]].concat(),
+                    [name("env"), name("b"), vec![0x00, 0x00]].concat(),
+                    [name("env"), name("g"), vec![0x03, 0x7f, 0x00]].concat(),
+                ]),
+            ),
+            section(3, &vec(&[vec![0x00], vec![0x00], vec![0x03], vec![0x00]])),
+            section(4, &vec(&[vec![0x70, 0x00, 0x01]])),
+            section(5, &vec(&[vec![0x00, 0x01]])),
+            section(6, &vec(&[vec![0x7f, 0x00, 0x41, 0x00, 0x0b]])),
+            section(7, &vec(&[[name("main"), vec![0x00, 0x02]].concat()])),
+            section(8, &uleb(3)),
+            section(9, &vec(&[vec![0x00, 0x41, 0x00, 0x0b, 0x01, 0x04]])),
+            section(12, &uleb(3)),
+            section(
+                10,
+                &vec(&[
+                    func_body(&[], &[0xfc, 0x09, 0x02]), // data.drop 2
+                    func_body(&[], &[0x10, 0x01]),       // call 1
+                    func_body(&[], &[]),
+                    func_body(&[], &[0x10, 0x00]), // call 0
+                ]),
+            ),
+            section(
+                11,
+                &vec(&[
+                    vec![0x00, 0x23, 0x00, 0x0b, 0x01, b'a'], // global.get 0
+                    vec![0x01, 0x01, b'b'],
+                    vec![0x01, 0x01, b'c'],
+                ]),
+            ),
+        ];
+        sections.extend_from_slice(custom_sections);
+        module(&sections)
+    }
+
+    fn build(bytes: &[u8]) -> ModuleIr {
+        ModuleIr::from_module(&parse(bytes)).unwrap()
+    }
+
+    #[test]
+    fn gc_removes_unreachable_items() {
+        let mut ir = build(&sample_module(&[]));
+        let report = ir.gc(&[]).unwrap();
+
+        assert_eq!(
+            report,
+            GcReport {
+                functions: 2,
+                globals: 1,
+                types: 1,
+                data: 1,
+                imports: 1,
+                custom_sections: Vec::new(),
+            }
+        );
+        for id in [1, 2, 3, 4] {
+            assert!(ir.functions.contains(Id::new(id)));
+        }
+        assert!(ir.globals.contains(Id::new(0)));
+        assert!(ir.data.contains(Id::new(0)));
+        assert!(ir.data.contains(Id::new(2)));
+        assert_eq!(
+            ir.imports
+                .iter()
+                .map(|x| x.field.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["b", "g"]
+        );
+
+        // 残った要素のインデックスが振り直される
+        let module = parse(&ir.encode().unwrap());
+        assert_eq!(
+            module.get_function_section().unwrap().get_indice_list(),
+            vec![0, 0, 2]
+        );
+        assert_eq!(
+            module.get_start_section().unwrap().get_start_func_index(),
+            2
+        );
+        assert_eq!(
+            module
+                .get_element_section()
+                .unwrap()
+                .get_element(0)
+                .unwrap()
+                .get_elements(),
+            vec![3]
+        );
+        assert_eq!(get_references(&module, 1), vec![Immediate::Function(0)]);
+    }
+
+    #[test]
+    fn gc_keeps_given_roots() {
+        let mut ir = build(&sample_module(&[]));
+        let report = ir.gc(&[Id::new(5)]).unwrap();
+
+        // 関数5から呼ばれるインポートされた関数0も残る
+        assert_eq!(report.functions, 0);
+        assert_eq!(report.imports, 0);
+        assert!(ir.functions.contains(Id::new(0)));
+        assert!(ir.functions.contains(Id::new(5)));
+    }
+
+    #[test]
+    fn gc_keeps_whole_rec_group() {
+        let mut ir = build(&sample_module(&[]));
+        ir.gc(&[]).unwrap();
+
+        // 型2は参照されていないが、型3と同じrec groupに属する
+        assert!(ir.types.contains(Id::new(0)));
+        assert!(!ir.types.contains(Id::new(1)));
+        assert!(ir.types.contains(Id::new(2)));
+        assert!(ir.types.contains(Id::new(3)));
+    }
+
+    #[test]
+    fn gc_drops_debug_sections_when_code_changes() {
+        let custom_sections = [
+            custom_section(".debug_line", &[0x00]),
+            custom_section("sourceMappingURL", &name("a.map")),
+            custom_section("build_id", &sized(&[0x01, 0x02])),
+            custom_section("producers", &[0x00]),
+        ];
+
+        let mut ir = build(&sample_module(&custom_sections));
+        let report = ir.gc(&[]).unwrap();
+        assert_eq!(
+            report.custom_sections,
+            vec![".debug_line", "sourceMappingURL", "build_id"]
+        );
+        assert_eq!(
+            ir.custom_sections
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["producers"]
+        );
+
+        // 何も削除しない場合はコードが変わらないため残す
+        let mut ir = build(&module(&[
+            section(1, &vec(&[func_type(&[], &[])])),
+            section(3, &vec(&[vec![0x00]])),
+            section(7, &vec(&[[name("main"), vec![0x00, 0x00]].concat()])),
+            section(10, &vec(&[func_body(&[], &[])])),
+            custom_sections[0].clone(),
+        ]));
+        let report = ir.gc(&[]).unwrap();
+        assert!(!report.is_changed());
+        assert_eq!(ir.custom_sections.len(), 1);
+    }
+
+    #[test]
+    fn gc_keeps_table_init_references() {
+        // 関数0は未使用、関数1は(ref 0)のテーブルの初期化式から参照される
+        let bytes = module(&[
+            section(1, &vec(&[func_type(&[], &[])])),
+            section(3, &vec(&[vec![0x00], vec![0x00]])),
+            // 0x40 0x00 (ref 0) (limits 1) ref.func 1 end
+            section(
+                4,
+                &vec(&[vec![0x40, 0x00, 0x64, 0x00, 0x00, 0x01, 0xd2, 0x01, 0x0b]]),
+            ),
+            section(10, &vec(&[func_body(&[], &[]), func_body(&[], &[])])),
+        ]);
+        let mut ir = build(&bytes);
+        let report = ir.gc(&[]).unwrap();
+
+        assert_eq!(report.functions, 1);
+        assert!(ir.functions.contains(Id::new(1)));
+
+        // 初期化式の関数インデックスが振り直される
+        let module = parse(&ir.encode().unwrap());
+        let table_section = module.get_table_section().unwrap();
+        assert_eq!(
+            table_section.get_table_init_expr(0).unwrap().get_instrs(),
+            &vec![0xd2, 0x00, 0x0b]
+        );
+    }
+
+    #[test]
+    fn gc_rejects_relocatable_object() {
+        // linkingはバージョン2のみ、reloc.CODEは対象のセクション番号と0個のエントリ
+        for custom in [
+            custom_section("linking", &[0x02]),
+            custom_section("reloc.CODE", &[0x0a, 0x00]),
+        ] {
+            let mut ir = build(&sample_module(&[custom]));
+            assert!(matches!(ir.gc(&[]), Err(ParseError::ValidationError(_))));
+            assert_eq!(ir.functions.len(), 6);
+        }
+    }
+}
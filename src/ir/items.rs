@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use super::arena::Id;
+
+use crate::wasm_components::sections::CustomPayload;
+use crate::wasm_components::types::LangTypes;
+
+/*
+ * IRの要素
+ *
+ * 他の要素への参照はIDで持つ
+ * 命令列(関数本体・初期化式)と型(LangTypesのHeapType::Concrete)の中のインデックスも、
+ * インデックスではなくIDの番号で表し、エンコード時にインデックスへ置き換える
+ */
+
+pub type TypeId = Id<IrType>;
+pub type FuncId = Id<IrFunction>;
+pub type TableId = Id<IrTable>;
+pub type MemoryId = Id<IrMemory>;
+pub type GlobalId = Id<IrGlobal>;
+pub type TagId = Id<IrTag>;
+pub type ElemId = Id<IrElement>;
+pub type DataId = Id<IrData>;
+
+/// 型 (rec groupは展開し、同じグループの型にrec_groupで同じ番号を付ける)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrType {
+    pub rec_group: Option<u32>, // 0x4eで明示的にグループ化されている場合のグループ番号
+    pub is_final: bool,
+    pub super_types: Vec<TypeId>,
+    pub composite: IrCompositeType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrCompositeType {
+    Func {
+        params: Vec<LangTypes>,
+        results: Vec<LangTypes>,
+    },
+    Struct(Vec<IrFieldType>),
+    Array(IrFieldType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IrFieldType {
+    pub storage: IrStorageType,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IrStorageType {
+    Val(LangTypes),
+    I8,
+    I16,
+}
+
+/// 関数 (インポートされた関数はbodyを持たない)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrFunction {
+    pub type_id: TypeId,
+    pub body: Option<IrFunctionBody>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrFunctionBody {
+    pub locals: Vec<(u32, LangTypes)>, // (個数, 型)
+    pub code: Vec<u8>,                 // 末尾のendを除く命令列
+}
+
+/// テーブル・メモリのサイズの制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrLimits {
+    pub initial: u64,
+    pub maximum: Option<u64>,
+    pub shared: bool,
+    pub index64: bool,
+}
+
+/// テーブル (initは初期化式を持つ定義されたテーブルの場合のみ)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrTable {
+    pub elem_type: LangTypes,
+    pub limits: IrLimits,
+    pub init: Option<Vec<u8>>, // 末尾のendを含む初期化式
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrMemory {
+    pub limits: IrLimits,
+}
+
+/// グローバル変数 (インポートされたグローバル変数はinitを持たない)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrGlobal {
+    pub content_type: LangTypes,
+    pub mutable: bool,
+    pub init: Option<Vec<u8>>, // 末尾のendを含む初期化式
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrTag {
+    pub type_id: TypeId,
+}
+
+/// インポート・エクスポートの対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrExternal {
+    Function(FuncId),
+    Table(TableId),
+    Memory(MemoryId),
+    Global(GlobalId),
+    Tag(TagId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrImport {
+    pub module: String,
+    pub field: String,
+    pub target: IrExternal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrExport {
+    pub name: String,
+    pub target: IrExternal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrElementMode {
+    Active { table: TableId, offset: Vec<u8> },
+    Passive,
+    Declarative,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrElementItems {
+    Functions(Vec<FuncId>),
+    Exprs(Vec<Vec<u8>>), // 末尾のendを含む初期化式
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrElement {
+    pub mode: IrElementMode,
+    pub elem_type: LangTypes,
+    pub items: IrElementItems,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrDataMode {
+    Active { memory: MemoryId, offset: Vec<u8> },
+    Passive,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrData {
+    pub mode: IrDataMode,
+    pub bytes: Vec<u8>,
+}
+
+/// nameセクションの内容
+///
+/// 削除した要素の名前はエンコード時に取り除く
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IrNames {
+    pub module: Option<String>,
+    pub functions: BTreeMap<FuncId, String>,
+    pub locals: BTreeMap<FuncId, BTreeMap<u32, String>>,
+    pub labels: BTreeMap<FuncId, BTreeMap<u32, String>>,
+    pub types: BTreeMap<TypeId, String>,
+    pub tables: BTreeMap<TableId, String>,
+    pub memories: BTreeMap<MemoryId, String>,
+    pub globals: BTreeMap<GlobalId, String>,
+    pub elems: BTreeMap<ElemId, String>,
+    pub data: BTreeMap<DataId, String>,
+    pub fields: BTreeMap<TypeId, BTreeMap<u32, String>>,
+    pub tags: BTreeMap<TagId, String>,
+}
+
+/// カスタムセクション
+///
+/// afterは直前にある(カスタムセクション以外の)セクションのID。先頭にある場合は0
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrCustomSection {
+    pub name: String,
+    pub after: u8,
+    pub payload: IrCustomPayload,
+}
+
+/// カスタムセクションのペイロード
+///
+/// RegisteredはCustomSectionRegistryに登録したデコーダで読んだもので、
+/// 変更した内容をエンコード時にCustomPayload::encodeで書き出す
+#[derive(Debug, Clone)]
+pub enum IrCustomPayload {
+    Bytes(Vec<u8>),
+    Registered(Box<dyn CustomPayload>),
+}
+
+impl IrCustomPayload {
+    /// ペイロードのバイト列(セクション名の後ろの部分)を返す
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            IrCustomPayload::Bytes(bytes) => bytes.clone(),
+            IrCustomPayload::Registered(payload) => payload.encode(),
+        }
+    }
+}
+
+impl PartialEq for IrCustomPayload {
+    fn eq(&self, other: &Self) -> bool {
+        self.encode() == other.encode()
+    }
+}
@@ -0,0 +1,398 @@
+use crate::wasm_components::instruction::{
+    decode_instructions, BlockSignature, CatchClause, Immediate, MemArg,
+};
+use crate::wasm_components::sections::ParseError;
+use crate::wasm_components::types::{HeapType, LangTypes};
+
+/*
+ * インデックスの書き換え
+ *
+ * IRの命令列・型はIDの番号でほかの要素を参照している
+ * エンコード時に、IDの番号をインデックス空間でのインデックスに置き換える
+ *
+ * 即値が変わらない命令は元のバイト列をそのままコピーする
+ * (オブジェクトファイルのパディングされたLEB128などを保つため)
+ */
+
+/// ID -> インデックス の対応
+#[derive(Debug)]
+pub struct IndexMap {
+    kind: &'static str,
+    map: Vec<Option<u32>>, // IDの番号の位置に、振り直したインデックス(削除した場合はNone)
+}
+
+/// すべてのインデックス空間の対応
+#[derive(Debug)]
+pub struct IndexMaps {
+    pub types: IndexMap,
+    pub functions: IndexMap,
+    pub tables: IndexMap,
+    pub memories: IndexMap,
+    pub globals: IndexMap,
+    pub tags: IndexMap,
+    pub elements: IndexMap,
+    pub data: IndexMap,
+}
+
+impl IndexMap {
+    /// 並び順のIDの番号のリストから作る
+    pub fn new(kind: &'static str, capacity: usize, order: &[u32]) -> Self {
+        let mut map: Vec<Option<u32>> = vec![None; capacity];
+        for (idx, id) in order.iter().enumerate() {
+            map[*id as usize] = Some(idx as u32);
+        }
+
+        Self { kind, map }
+    }
+
+    /// IDの番号に対応するインデックスを返す
+    ///
+    /// 削除された要素を参照している場合はエラー
+    pub fn get(&self, id: u32) -> Result<u32, ParseError> {
+        match self.map.get(id as usize).copied().flatten() {
+            Some(idx) => Ok(idx),
+            None => Err(ParseError::ValidationError(format!(
+                "{} {} is referenced, but does not exist",
+                self.kind, id
+            ))),
+        }
+    }
+}
+
+/// インデックスを書き換えながらバイト列を書き出す
+pub struct RemapWriter<'a> {
+    maps: &'a IndexMaps,
+    buf: Vec<u8>,
+    changed: bool, // IDの番号と異なるインデックスを書いたか
+}
+
+impl<'a> RemapWriter<'a> {
+    pub fn new(maps: &'a IndexMaps) -> Self {
+        Self {
+            maps,
+            buf: Vec::new(),
+            changed: false,
+        }
+    }
+
+    /// 同じ対応を使う別のWriterを作る(長さ付きの部分を書くため)
+    pub fn new_sub_writer(&self) -> RemapWriter<'a> {
+        RemapWriter::new(self.maps)
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn byte(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn bytes(&mut self, v: &[u8]) {
+        self.buf.extend_from_slice(v);
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.u64(v as u64);
+    }
+
+    pub fn u64(&mut self, v: u64) {
+        // Vecへの書き込みは失敗しない
+        let _ = leb128::write::unsigned(&mut self.buf, v);
+    }
+
+    pub fn i64(&mut self, v: i64) {
+        let _ = leb128::write::signed(&mut self.buf, v);
+    }
+
+    /// 長さ付きの文字列を書く
+    pub fn name(&mut self, v: &str) {
+        self.u32(v.len() as u32);
+        self.bytes(v.as_bytes());
+    }
+
+    /// 長さ付きのバイト列を書く
+    pub fn vec(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.bytes(v);
+    }
+
+    /// IDの番号をインデックスに置き換えて書く
+    pub fn index(&mut self, map: &IndexMap, id: u32) -> Result<(), ParseError> {
+        let idx = map.get(id)?;
+        if idx != id {
+            self.changed = true;
+        }
+        self.u32(idx);
+        Ok(())
+    }
+
+    pub fn type_index(&mut self, id: u32) -> Result<(), ParseError> {
+        self.index(&self.maps.types, id)
+    }
+
+    pub fn heap_type(&mut self, heap_type: &HeapType) -> Result<(), ParseError> {
+        let v: u8 = match heap_type {
+            HeapType::Func => 0x70,
+            HeapType::Extern => 0x6f,
+            HeapType::Any => 0x6e,
+            HeapType::Eq => 0x6d,
+            HeapType::I31 => 0x6c,
+            HeapType::Struct => 0x6b,
+            HeapType::Array => 0x6a,
+            HeapType::Exn => 0x69,
+            HeapType::NoExn => 0x74,
+            HeapType::NoFunc => 0x73,
+            HeapType::NoExtern => 0x72,
+            HeapType::None => 0x71,
+            HeapType::Concrete(id) => {
+                // 型インデックスはs33で表す
+                let idx = self.maps.types.get(*id)?;
+                if idx != *id {
+                    self.changed = true;
+                }
+                self.i64(idx as i64);
+                return Ok(());
+            }
+        };
+        self.byte(v);
+        Ok(())
+    }
+
+    pub fn lang_type(&mut self, lang_type: &LangTypes) -> Result<(), ParseError> {
+        let v: u8 = match lang_type {
+            LangTypes::I32 => 0x7f,
+            LangTypes::I64 => 0x7e,
+            LangTypes::F32 => 0x7d,
+            LangTypes::F64 => 0x7c,
+            LangTypes::V128 => 0x7b,
+            LangTypes::ANYFUNC => 0x70,
+            LangTypes::FUNC => 0x60,
+            LangTypes::PSEUDO => 0x40,
+            LangTypes::REF {
+                nullable,
+                heap_type,
+            } => {
+                // null許容の抽象ヒープ型は省略形(1byte)で書く
+                if *nullable && !matches!(heap_type, HeapType::Concrete(_)) {
+                    return self.heap_type(heap_type);
+                }
+                self.byte(if *nullable { 0x63 } else { 0x64 });
+                return self.heap_type(heap_type);
+            }
+        };
+        self.byte(v);
+        Ok(())
+    }
+
+    fn block_signature(&mut self, signature: &BlockSignature) -> Result<(), ParseError> {
+        match signature {
+            BlockSignature::Empty => {
+                self.byte(0x40);
+                Ok(())
+            }
+            BlockSignature::Value(ty) => self.lang_type(ty),
+            BlockSignature::TypeIndex(id) => {
+                let idx = self.maps.types.get(*id)?;
+                if idx != *id {
+                    self.changed = true;
+                }
+                self.i64(idx as i64);
+                Ok(())
+            }
+        }
+    }
+
+    fn mem_arg(&mut self, memarg: &MemArg) -> Result<(), ParseError> {
+        let memory_index = self.maps.memories.get(memarg.get_memory_index())?;
+        if memory_index != memarg.get_memory_index() {
+            self.changed = true;
+        }
+
+        // メモリインデックスが0以外の場合は、alignのbit6を立ててインデックスを続ける
+        if memory_index == 0 {
+            self.u32(memarg.get_align());
+        } else {
+            self.u32(memarg.get_align() | 0x40);
+            self.u32(memory_index);
+        }
+        self.u64(memarg.get_offset());
+        Ok(())
+    }
+
+    /// 即値を書き換えて書く
+    ///
+    /// インデックスを含まない即値の場合はfalseを返し、何も書かない
+    fn immediate(&mut self, immediate: &Immediate) -> Result<bool, ParseError> {
+        let maps = self.maps;
+        match immediate {
+            Immediate::Block(signature) => self.block_signature(signature)?,
+            Immediate::Function(id) => self.index(&maps.functions, *id)?,
+            Immediate::CallIndirect {
+                type_index,
+                table_index,
+            } => {
+                self.index(&maps.types, *type_index)?;
+                self.index(&maps.tables, *table_index)?;
+            }
+            Immediate::Global(id) => self.index(&maps.globals, *id)?,
+            Immediate::MemArg(memarg) => self.mem_arg(memarg)?,
+            Immediate::Memory(id) => self.index(&maps.memories, *id)?,
+            Immediate::MemoryInit {
+                data_index,
+                memory_index,
+            } => {
+                self.index(&maps.data, *data_index)?;
+                self.index(&maps.memories, *memory_index)?;
+            }
+            Immediate::MemoryCopy {
+                dst_index,
+                src_index,
+            } => {
+                self.index(&maps.memories, *dst_index)?;
+                self.index(&maps.memories, *src_index)?;
+            }
+            Immediate::Data(id) => self.index(&maps.data, *id)?,
+            Immediate::Table(id) => self.index(&maps.tables, *id)?,
+            Immediate::TableInit {
+                elem_index,
+                table_index,
+            } => {
+                self.index(&maps.elements, *elem_index)?;
+                self.index(&maps.tables, *table_index)?;
+            }
+            Immediate::TableCopy {
+                dst_index,
+                src_index,
+            } => {
+                self.index(&maps.tables, *dst_index)?;
+                self.index(&maps.tables, *src_index)?;
+            }
+            Immediate::Elem(id) => self.index(&maps.elements, *id)?,
+            Immediate::Tag(id) => self.index(&maps.tags, *id)?,
+            Immediate::TryTable { signature, catches } => {
+                self.block_signature(signature)?;
+                self.u32(catches.len() as u32);
+                for catch in catches.iter() {
+                    match catch {
+                        CatchClause::Catch { tag_index, label } => {
+                            self.byte(0x00);
+                            self.index(&maps.tags, *tag_index)?;
+                            self.u32(*label);
+                        }
+                        CatchClause::CatchRef { tag_index, label } => {
+                            self.byte(0x01);
+                            self.index(&maps.tags, *tag_index)?;
+                            self.u32(*label);
+                        }
+                        CatchClause::CatchAll { label } => {
+                            self.byte(0x02);
+                            self.u32(*label);
+                        }
+                        CatchClause::CatchAllRef { label } => {
+                            self.byte(0x03);
+                            self.u32(*label);
+                        }
+                    }
+                }
+            }
+            Immediate::SelectTypes(types) => {
+                self.u32(types.len() as u32);
+                for ty in types.iter() {
+                    self.lang_type(ty)?;
+                }
+            }
+            Immediate::Type(id) => self.index(&maps.types, *id)?,
+            Immediate::Field {
+                type_index,
+                field_index,
+            } => {
+                self.index(&maps.types, *type_index)?;
+                self.u32(*field_index);
+            }
+            Immediate::ArrayFixed { type_index, length } => {
+                self.index(&maps.types, *type_index)?;
+                self.u32(*length);
+            }
+            Immediate::ArrayData {
+                type_index,
+                data_index,
+            } => {
+                self.index(&maps.types, *type_index)?;
+                self.index(&maps.data, *data_index)?;
+            }
+            Immediate::ArrayElem {
+                type_index,
+                elem_index,
+            } => {
+                self.index(&maps.types, *type_index)?;
+                self.index(&maps.elements, *elem_index)?;
+            }
+            Immediate::ArrayCopy {
+                dst_index,
+                src_index,
+            } => {
+                self.index(&maps.types, *dst_index)?;
+                self.index(&maps.types, *src_index)?;
+            }
+            Immediate::HeapType(heap_type) => self.heap_type(heap_type)?,
+            // null許容かどうかはオペコードで区別されるため、ヒープ型のみを書く
+            Immediate::RefType(ty) => self.heap_type(&get_ref_parts(ty).1)?,
+            Immediate::BrOnCast { label, from, to } => {
+                let (from_nullable, from_heap_type) = get_ref_parts(from);
+                let (to_nullable, to_heap_type) = get_ref_parts(to);
+                self.byte((from_nullable as u8) | ((to_nullable as u8) << 1));
+                self.u32(*label);
+                self.heap_type(&from_heap_type)?;
+                self.heap_type(&to_heap_type)?;
+            }
+            Immediate::MemArgLane { memarg, lane } => {
+                self.mem_arg(memarg)?;
+                self.byte(*lane);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+// 参照型の(null許容か, ヒープ型)を返す (funcrefは(ref null func))
+fn get_ref_parts(lang_type: &LangTypes) -> (bool, HeapType) {
+    match lang_type {
+        LangTypes::REF {
+            nullable,
+            heap_type,
+        } => (*nullable, *heap_type),
+        _ => (true, HeapType::Func),
+    }
+}
+
+/// 命令列のインデックスを書き換える
+///
+/// インデックスが変わらない命令は元のバイト列をそのまま使う
+pub fn remap_instructions(bytes: &[u8], maps: &IndexMaps) -> Result<Vec<u8>, ParseError> {
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    for instr in decode_instructions(bytes)?.iter() {
+        let begin = instr.get_offset() as usize;
+        let end = begin + instr.get_size() as usize;
+
+        let mut writer = RemapWriter::new(maps);
+        if !writer.immediate(instr.get_immediate())? || !writer.changed {
+            out.extend_from_slice(&bytes[begin..end]);
+            continue;
+        }
+
+        // オペコード(とプレフィックス付き命令のサブオペコード)は元のバイト列を使う
+        let mut header_len: usize = 1;
+        if instr.get_sub_opcode().is_some() {
+            while bytes[begin + header_len] & 0x80 != 0 {
+                header_len += 1;
+            }
+            header_len += 1;
+        }
+        out.extend_from_slice(&bytes[begin..begin + header_len]);
+        out.extend_from_slice(&writer.into_bytes());
+    }
+
+    Ok(out)
+}
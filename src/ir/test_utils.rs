@@ -0,0 +1,113 @@
+use std::io::Cursor;
+
+use crate::wasm_components::instruction::{decode_instructions, Immediate};
+use crate::wasm_components::module::WasmModule;
+
+/*
+ * テスト用のバイナリ組み立て
+ */
+
+pub fn uleb(value: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    leb128::write::unsigned(&mut bytes, value).unwrap();
+    bytes
+}
+
+pub fn name(s: &str) -> Vec<u8> {
+    let mut bytes = uleb(s.len() as u64);
+    bytes.extend_from_slice(s.as_bytes());
+    bytes
+}
+
+// 要素数を先頭に付ける
+pub fn vec(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = uleb(items.len() as u64);
+    for item in items.iter() {
+        bytes.extend_from_slice(item);
+    }
+    bytes
+}
+
+// サイズを先頭に付ける
+pub fn sized(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = uleb(payload.len() as u64);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+pub fn section(id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![id];
+    bytes.extend(sized(payload));
+    bytes
+}
+
+pub fn custom_section(section_name: &str, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = name(section_name);
+    bytes.extend_from_slice(payload);
+    section(0, &bytes)
+}
+
+pub fn module(sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = b"\0asm".to_vec();
+    bytes.extend_from_slice(&[1, 0, 0, 0]);
+    for sec in sections.iter() {
+        bytes.extend_from_slice(sec);
+    }
+    bytes
+}
+
+pub fn func_type(params: &[u8], results: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x60];
+    bytes.extend(sized(params));
+    bytes.extend(sized(results));
+    bytes
+}
+
+// localsは(個数, 型)の組、codeは最後のendを含まない命令列
+pub fn func_body(locals: &[(u32, u8)], code: &[u8]) -> Vec<u8> {
+    let mut bytes = vec(&locals
+        .iter()
+        .map(|(count, ty)| {
+            let mut entry = uleb(*count as u64);
+            entry.push(*ty);
+            entry
+        })
+        .collect::<Vec<Vec<u8>>>());
+    bytes.extend_from_slice(code);
+    bytes.push(0x0b);
+    sized(&bytes)
+}
+
+pub fn name_map(names: &[(u32, &str)]) -> Vec<u8> {
+    vec(&names
+        .iter()
+        .map(|(index, s)| {
+            let mut entry = uleb(*index as u64);
+            entry.extend(name(s));
+            entry
+        })
+        .collect::<Vec<Vec<u8>>>())
+}
+
+pub fn parse(bytes: &[u8]) -> WasmModule {
+    WasmModule::parse(&mut Cursor::new(bytes.to_vec())).unwrap()
+}
+
+// 定義された関数のidx番目の命令列から、関数・グローバル変数・型を参照する即値を取り出す
+pub fn get_references(module: &WasmModule, idx: usize) -> Vec<Immediate> {
+    let body = module
+        .get_code_section()
+        .and_then(|x| x.get_func_body(idx))
+        .unwrap();
+    decode_instructions(body.get_code())
+        .unwrap()
+        .into_iter()
+        .map(|x| x.get_immediate().clone())
+        .filter(|x| {
+            matches!(
+                x,
+                Immediate::Function(_) | Immediate::Global(_) | Immediate::CallIndirect { .. }
+            )
+        })
+        .collect()
+}
@@ -1,3 +1,11 @@
+pub mod dump;
+pub mod dwarf;
+pub mod features;
+pub mod ir;
 pub mod parser;
 pub mod readers;
+#[cfg(feature = "serde")]
+pub mod schema;
+pub mod source_map;
+pub mod validator;
 pub mod wasm_components;
@@ -6,13 +6,18 @@
 
 mod printer;
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use wasmdump::dwarf::DebugInfo;
+use wasmdump::features::WasmFeatures;
+use wasmdump::ir::{FuncId, ModuleIr};
 use wasmdump::parser::Parser as WasmParser;
+use wasmdump::source_map::SourceMap;
+use wasmdump::validator;
 use wasmdump::wasm_components::module::WasmModule;
-use wasmdump::wasm_components::sections::ParseError;
+use wasmdump::wasm_components::sections::{ParseError, UrlSectionPayload};
 
 use clap::Parser;
 #[derive(Parser)]
@@ -29,13 +34,75 @@ struct CmdArgs {
 
     // Positional arg
     path: String,
+
+    // サブコマンドごとの追加の引数
+    //   addr2line: コードセクション内のオフセット(10進数または0x付きの16進数)
+    //   id: 比較するwasmファイルのパス
+    #[clap(value_parser)]
+    rest: Vec<String>,
+
+    // addr2line: ソースマップのパス(省略時はsourceMappingURLセクションのパス)
+    #[clap(long, value_parser)]
+    source_map: Option<String>,
+
+    // disasm: 表示する関数(nameセクションの名前、またはインポートされた関数を含む絶対インデックス)
+    #[clap(
+        long,
+        value_parser,
+        help = "Function to disassemble: a name or an absolute function index (imports first)"
+    )]
+    func: Option<String>,
+
+    // print: 表示するセクション(カンマ区切り)
+    #[clap(long, value_parser, value_delimiter = ',')]
+    section: Vec<String>,
+
+    // print: 表示するカスタムセクションの名前(glob、複数指定可)
+    #[clap(long, value_parser)]
+    custom_section: Vec<String>,
+
+    // print: セクションの内容の代わりに一覧を表示する
+    #[clap(long, action)]
+    summary: bool,
+
+    // print: 表示するインポートの名前(glob)
+    #[clap(long, value_parser)]
+    import_filter: Option<String>,
+
+    // print: 表示するエクスポートの名前(glob)
+    #[clap(long, value_parser)]
+    export_filter: Option<String>,
+
+    // print: 出力形式
+    #[clap(long, arg_enum, value_parser, default_value = "text")]
+    format: Format,
+
+    // gc: 出力するwasmファイルのパス
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+
+    // gc: 追加で到達可能とする関数(nameセクションの名前、または関数インデックス、複数指定可)
+    #[clap(long, value_parser)]
+    root: Vec<String>,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
 }
 
 #[derive(clap::ArgEnum, Clone, Debug)]
 enum Action {
     Print,
     Dump,
-    DumpTmp,
+    Disasm,
+    Features,
+    Reloc,
+    Lines,
+    Addr2line,
+    Id,
+    Gc,
 }
 
 fn main() {
@@ -43,47 +110,355 @@ fn main() {
 
     let path = Path::new(args.path.as_str());
 
+    // セクションのハッシュはidでのみ使う
+    let wasm_module = parse_module(path, matches!(args.action, Action::Id));
+
+    if let Err(err) = validator::validate(&wasm_module) {
+        eprintln!(" > Warn: {}", err);
+    }
+
+    if args.format == Format::Json && !matches!(args.action, Action::Print) {
+        eprintln!(" > Warn: --format json is supported only by print");
+    }
+
+    match args.action {
+        Action::Print if args.format == Format::Json => subcommand_print_json(&wasm_module),
+        Action::Print => subcommand_print(&wasm_module, &args),
+        Action::Dump => subcommand_dump(&wasm_module, path),
+        Action::Disasm => subcommand_disasm(&wasm_module, &args),
+        Action::Features => subcommand_features(&wasm_module),
+        Action::Reloc => subcommand_reloc(&wasm_module),
+        Action::Lines => subcommand_lines(&wasm_module),
+        Action::Addr2line => subcommand_addr2line(&wasm_module, path, &args),
+        Action::Id => subcommand_id(&wasm_module, &args),
+        Action::Gc => subcommand_gc(&wasm_module, &args),
+        // _ => panic!("unknown subcommand: {:?}", act),
+    }
+}
+
+fn parse_module(path: &Path, record_digests: bool) -> WasmModule {
     let file = File::open(path).unwrap();
 
     let mut reader = BufReader::new(file);
 
-    let mut parser = WasmParser::new(&mut reader);
+    let mut parser = WasmParser::new(&mut reader, WasmFeatures::default());
+    parser.set_record_section_digests(record_digests);
 
-    let wasm_module = match parser.parse_all() {
+    match parser.parse_all() {
         Ok(module) => module,
         Err(err) => match err {
             ParseError::ReaderError(msg) => panic!(" > Error: {}", msg),
             ParseError::FormatError(msg) => panic!(" > Error: {}", msg),
             ParseError::UnexpectedError(msg) => panic!(" > Error: {}", msg),
+            ParseError::ValidationError(msg) => panic!(" > Error: {}", msg),
+            ParseError::FeatureError(msg) => panic!(" > Error: {}", msg),
         },
+    }
+}
+
+// printの--sectionで指定できるセクション名
+const PRINT_SECTION_NAMES: [&str; 14] = [
+    "type",
+    "import",
+    "function",
+    "table",
+    "memory",
+    "tag",
+    "global",
+    "export",
+    "start",
+    "elem",
+    "code",
+    "data",
+    "datacount",
+    "custom",
+];
+
+fn subcommand_print(wasm_module: &WasmModule, args: &CmdArgs) {
+    for section in args.section.iter() {
+        if !PRINT_SECTION_NAMES.contains(&section.as_str()) {
+            panic!(
+                " > Error: unknown section: {} (expected one of: {})",
+                section,
+                PRINT_SECTION_NAMES.join(", ")
+            );
+        }
+    }
+
+    let selection = printer::SectionSelection::new(&args.section, &args.custom_section);
+    if args.summary {
+        printer::print_summary(wasm_module, &selection);
+        return;
+    }
+
+    if selection.is_selected("type") {
+        printer::print_type_section(wasm_module);
+    }
+    if selection.is_selected("import") {
+        printer::print_import_section(wasm_module, args.import_filter.as_deref());
+    }
+    if selection.is_selected("function") {
+        printer::print_function_section(wasm_module);
+    }
+    if selection.is_selected("table") {
+        printer::print_table_section(wasm_module);
+    }
+    if selection.is_selected("memory") {
+        printer::print_memory_section(wasm_module);
+    }
+    if selection.is_selected("tag") {
+        printer::print_tag_section(wasm_module);
+    }
+    if selection.is_selected("global") {
+        printer::print_global_section(wasm_module);
+    }
+    if selection.is_selected("export") {
+        printer::print_export_section(wasm_module, args.export_filter.as_deref());
+    }
+    if selection.is_selected("start") {
+        printer::print_start_section(wasm_module);
+    }
+    if selection.is_selected("elem") {
+        printer::print_element_section(wasm_module);
+    }
+    // コードセクションは大きいため、明示的に指定した場合のみ表示する
+    if args.section.iter().any(|x| x == "code") {
+        printer::print_disasm(wasm_module, None);
+    }
+    if selection.is_selected("data") {
+        printer::print_data_section(wasm_module);
+    }
+    if selection.is_selected("datacount") {
+        printer::print_data_count_section(wasm_module);
+    }
+    printer::print_custom_sections(wasm_module, &selection);
+    if selection.is_all() {
+        printer::print_features(wasm_module);
+    }
+
+    if false {
+        printer::print_all_section_for_debug(wasm_module);
+    }
+}
+
+#[cfg(feature = "serde")]
+fn subcommand_print_json(wasm_module: &WasmModule) {
+    printer::print_json(wasm_module);
+}
+
+#[cfg(not(feature = "serde"))]
+fn subcommand_print_json(_wasm_module: &WasmModule) {
+    panic!(" > Error: --format json requires the \"serde\" feature");
+}
+
+fn subcommand_features(wasm_module: &WasmModule) {
+    printer::print_feature_report(wasm_module);
+}
+
+fn subcommand_reloc(wasm_module: &WasmModule) {
+    printer::print_relocations(wasm_module);
+}
+
+fn subcommand_lines(wasm_module: &WasmModule) {
+    printer::print_lines(wasm_module);
+}
+
+fn subcommand_addr2line(wasm_module: &WasmModule, path: &Path, args: &CmdArgs) {
+    let mut parsed_offsets: Vec<u32> = Vec::new();
+    for offset in args.rest.iter() {
+        let parsed = match offset.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => offset.parse::<u32>(),
+        };
+        match parsed {
+            Ok(v) => parsed_offsets.push(v),
+            Err(_) => panic!(" > Error: invalid offset: {}", offset),
+        }
+    }
+
+    let debug_info = load_debug_info(wasm_module, path);
+    let source_map = load_source_map(wasm_module, path, args.source_map.as_ref());
+
+    printer::print_addr2line(
+        wasm_module,
+        debug_info.as_ref(),
+        source_map.as_ref(),
+        &parsed_offsets,
+    );
+}
+
+// カスタムセクションのURLをwasmファイルからの相対パスとして解決する (ローカルのファイルのみ)
+fn resolve_local_url(path: &Path, payload: Option<&UrlSectionPayload>) -> Option<PathBuf> {
+    let payload = payload.filter(|x| x.is_local_path())?;
+    let url = payload.get_url();
+    let url = url.strip_prefix("file://").unwrap_or(url);
+
+    Some(path.parent().unwrap_or_else(|| Path::new("")).join(url))
+}
+
+// モジュールのDWARFを読む (なければexternal_debug_infoが指すファイルから読む)
+fn load_debug_info(wasm_module: &WasmModule, path: &Path) -> Option<DebugInfo> {
+    match DebugInfo::parse(wasm_module) {
+        Ok(Some(debug_info)) => return Some(debug_info),
+        Ok(None) => (),
+        Err(err) => {
+            eprintln!(" > Warn: DWARF: {}", err);
+            return None;
+        }
+    }
+
+    let debug_path = resolve_local_url(path, wasm_module.get_external_debug_info())?;
+    let debug_module = File::open(&debug_path)
+        .map_err(|err| ParseError::ReaderError(format!("{:?}", err)))
+        .and_then(|file| {
+            WasmParser::new(&mut BufReader::new(file), WasmFeatures::all()).parse_all()
+        })
+        .and_then(|module| DebugInfo::parse(&module));
+    match debug_module {
+        Ok(debug_info) => debug_info,
+        Err(err) => {
+            eprintln!(" > Warn: {}: {}", debug_path.display(), err);
+            None
+        }
+    }
+}
+
+// ソースマップを読む (パスの指定がなければsourceMappingURLが指すファイルから読む)
+fn load_source_map(
+    wasm_module: &WasmModule,
+    path: &Path,
+    source_map_path: Option<&String>,
+) -> Option<SourceMap> {
+    let source_map_path = match source_map_path {
+        Some(source_map_path) => PathBuf::from(source_map_path),
+        None => resolve_local_url(path, wasm_module.get_source_mapping_url())?,
     };
 
-    match args.action {
-        Action::Print => subcommand_print(&wasm_module),
-        Action::Dump => panic!("not implemented !"),
-        Action::DumpTmp => subcommand_dump(&wasm_module),
-        // _ => panic!("unknown subcommand: {:?}", act),
+    let source_map = fs::read_to_string(&source_map_path)
+        .map_err(|err| ParseError::ReaderError(format!("{:?}", err)))
+        .and_then(|text| SourceMap::parse(&text));
+    match source_map {
+        Ok(source_map) => Some(source_map),
+        Err(err) => {
+            eprintln!(" > Warn: {}: {}", source_map_path.display(), err);
+            None
+        }
     }
 }
 
-fn subcommand_print(wasm_module: &WasmModule) {
-    printer::print_type_section(&wasm_module);
-    printer::print_import_section(&wasm_module);
-    printer::print_function_section(&wasm_module);
-    printer::print_table_section(&wasm_module);
-    printer::print_memory_section(&wasm_module);
-    printer::print_global_section(&wasm_module);
-    printer::print_export_section(&wasm_module);
-    printer::print_start_section(&wasm_module);
-    printer::print_element_section(&wasm_module);
-    printer::print_data_section(&wasm_module);
-    printer::print_custom_sections(&wasm_module);
+fn subcommand_id(wasm_module: &WasmModule, args: &CmdArgs) {
+    printer::print_module_id(wasm_module);
 
-    if false {
-        printer::print_all_section_for_debug(&wasm_module);
+    for other_path in args.rest.iter() {
+        let other_module = parse_module(Path::new(other_path), true);
+        printer::print_module_id_comparison(wasm_module, &other_module, other_path);
     }
 }
 
-fn subcommand_dump(_wasm_module: &WasmModule) {
-    println!("to be implemented")
+fn subcommand_dump(wasm_module: &WasmModule, path: &Path) {
+    let bytes = fs::read(path).unwrap();
+    printer::print_dump(wasm_module, &bytes);
+}
+
+// 名前を優先し、一致しなければ関数インデックスとして扱う
+fn resolve_function(wasm_module: &WasmModule, func: &str) -> u32 {
+    match wasm_module.find_function_by_name(func) {
+        Some(idx) => idx,
+        None => match func.parse::<u32>() {
+            Ok(idx) => idx,
+            Err(_) => panic!(" > Error: function not found: {}", func),
+        },
+    }
+}
+
+// disasmの対象の関数を求める (インポートされた関数は本体がないので対象にできない)
+fn resolve_disasm_function(wasm_module: &WasmModule, func: &str) -> u32 {
+    let idx = resolve_function(wasm_module, func);
+    if idx < wasm_module.get_num_imported_functions() {
+        panic!(" > Error: func[{}] is an imported function", idx);
+    }
+    if idx as usize >= wasm_module.functions().len() {
+        panic!(" > Error: func[{}] not found", idx);
+    }
+    idx
+}
+
+fn subcommand_disasm(wasm_module: &WasmModule, args: &CmdArgs) {
+    let func_idx = args
+        .func
+        .as_ref()
+        .map(|func| resolve_disasm_function(wasm_module, func));
+
+    printer::print_disasm(wasm_module, func_idx);
+}
+
+fn subcommand_gc(wasm_module: &WasmModule, args: &CmdArgs) {
+    let output = match &args.output {
+        Some(output) => output,
+        None => panic!(" > Error: gc requires an output path (-o <out>)"),
+    };
+
+    // IRのIDは変換元のインデックスと同じ
+    let roots: Vec<FuncId> = args
+        .root
+        .iter()
+        .map(|func| FuncId::new(resolve_function(wasm_module, func)))
+        .collect();
+
+    let mut ir = match ModuleIr::from_module(wasm_module) {
+        Ok(ir) => ir,
+        Err(err) => panic!(" > Error: {}", err),
+    };
+    let report = match ir.gc(&roots) {
+        Ok(report) => report,
+        Err(err) => panic!(" > Error: {}", err),
+    };
+    let bytes = match ir.encode() {
+        Ok(bytes) => bytes,
+        Err(err) => panic!(" > Error: {}", err),
+    };
+    fs::write(output, &bytes).unwrap();
+
+    if !report.custom_sections.is_empty() {
+        eprintln!(
+            " > Warn: code was rewritten, dropped custom sections: {}",
+            report.custom_sections.join(", ")
+        );
+    }
+    println!(
+        "removed: functions={}, globals={}, types={}, data={}, imports={}",
+        report.functions, report.globals, report.types, report.data, report.imports
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    // (import "env" "f" (func)) と定義された関数1つ
+    fn parse_module_with_import() -> WasmModule {
+        let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+        bytes.extend_from_slice(&[0x01, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        bytes.extend_from_slice(&[
+            0x02, 0x09, 0x01, 0x03, b'e', b'n', b'v', 0x01, b'f', 0x00, 0x00,
+        ]);
+        bytes.extend_from_slice(&[0x03, 0x02, 0x01, 0x00]);
+        bytes.extend_from_slice(&[0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        WasmModule::parse(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn disasm_function_index_includes_imports() {
+        let wasm_module = parse_module_with_import();
+        assert_eq!(resolve_disasm_function(&wasm_module, "1"), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "func[2] not found")]
+    fn disasm_function_index_out_of_range() {
+        let wasm_module = parse_module_with_import();
+        resolve_disasm_function(&wasm_module, "2");
+    }
 }
@@ -1,7 +1,10 @@
-use std::io::{Read, Seek};
+use std::io::{self, Read, Seek, SeekFrom};
 
-use crate::readers::peep_8;
-use crate::wasm_components::module::WasmModule;
+use sha2::{Digest, Sha256};
+
+use crate::features::{detect_section_feature_uses, FeatureLocation, WasmFeatures};
+use crate::readers::{peep_8, read_unsigned_leb128};
+use crate::wasm_components::module::{SectionInfo, WasmModule};
 use crate::wasm_components::sections::MagicAndVersion;
 use crate::wasm_components::sections::*;
 
@@ -9,12 +12,34 @@ use crate::wasm_components::sections::*;
 pub struct Parser<'a, R: Read> {
     reader: &'a mut R,
     // offset: u64,     // offsetはreaderから取得する
+    features: WasmFeatures, // 無効な提案を利用している場合はエラーにする
+    registry: Option<&'a CustomSectionRegistry>, // 独自のカスタムセクションのデコーダ
+    default_registry: CustomSectionRegistry, // registryを設定しない場合に使う組み込みのデコーダ
+    record_digests: bool,   // セクションのハッシュを求めるか (content_hashで使う)
 }
 
 // 構造体のメンバに参照を使う場合ライフタイム注釈が必要(その参照と構造体自身の生存期間の関係を明示するため)
 impl<'a, R: Read + Seek> Parser<'a, R> {
-    pub fn new(reader: &'a mut R) -> Parser<R> {
-        Parser { reader: reader }
+    pub fn new(reader: &'a mut R, features: WasmFeatures) -> Parser<'a, R> {
+        Parser {
+            reader,
+            features,
+            registry: None,
+            default_registry: CustomSectionRegistry::new(),
+            record_digests: false,
+        }
+    }
+
+    /// セクション全体のバイト列のハッシュを記録するかを設定する
+    ///
+    /// WasmModule::content_hashを使う場合に有効にする(デフォルトでは記録しない)
+    pub fn set_record_section_digests(&mut self, record: bool) {
+        self.record_digests = record;
+    }
+
+    /// カスタムセクションのパースに使うデコーダの登録先を設定する
+    pub fn set_custom_section_registry(&mut self, registry: &'a CustomSectionRegistry) {
+        self.registry = Some(registry);
     }
 
     // readerを使って、バイナリを順に読んでいき、読み込んだ値をデータ構造に落とし込む
@@ -24,15 +49,22 @@ impl<'a, R: Read + Seek> Parser<'a, R> {
         let mut module = WasmModule::empty(&magic_and_version);
 
         // Read body
-        loop {
-            let section_id = match peep_8(self.reader) {
-                Ok(section_id) => section_id[0],
-                Err(_) => {
-                    // println!(" > Info : reach EOF");
-                    break;
-                }
+        // peep_8が失敗した場合はEOFに到達したとみなす
+        while let Ok(section_id) = peep_8(self.reader) {
+            let section_id = section_id[0];
+            let section_offset = match self.reader.stream_position() {
+                Ok(pos) => pos,
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
             };
 
+            let (payload_offset, payload_len) = self.read_payload_offset(section_offset)?;
+            let section_size = payload_offset - section_offset + payload_len;
+            let mut section =
+                SectionInfo::new(section_id, section_offset, payload_offset, section_size);
+            if self.record_digests {
+                section = section.with_digest(self.digest_section(section_offset, section_size)?);
+            }
+            module.push_section(section);
             match section_id {
                 1 => {
                     let type_section = TypeSection::parse(self.reader)?;
@@ -78,18 +110,210 @@ impl<'a, R: Read + Seek> Parser<'a, R> {
                     let data_section = DataSection::parse(self.reader)?;
                     module.data_section = Some(data_section);
                 }
+                12 => {
+                    let data_count_section = DataCountSection::parse(self.reader)?;
+                    module.data_count_section = Some(data_count_section);
+                }
+                13 => {
+                    let tag_section = TagSection::parse(self.reader)?;
+                    module.tag_section = Some(tag_section);
+                }
                 0 => {
-                    let custom_section = CustomSection::parse(self.reader)?;
+                    let custom_section = CustomSection::parse_with_registry(
+                        self.reader,
+                        self.registry.unwrap_or(&self.default_registry),
+                    )?;
                     module.custom_sections.push(custom_section);
                 }
                 _ => {
-                    println!(
+                    eprintln!(
                         " > Info: section_id={}, unknow or not implemented",
                         section_id
                     );
+                    // 未知のセクションは読み飛ばす
+                    let common = SectionCommon::parse(self.reader)?;
+                    if let Err(err) = self
+                        .reader
+                        .seek(SeekFrom::Current(common.payload_len as i64))
+                    {
+                        return Err(ParseError::ReaderError(format!("{:?}", err)));
+                    }
                 }
             }
+
+            self.check_features(&module, section_id, section_offset)?;
         }
         Ok(module)
     }
+
+    // セクションID・大きさを読み飛ばした位置(ペイロードの位置)とペイロードの大きさを求め、
+    // section_offsetに戻る
+    fn read_payload_offset(&mut self, section_offset: u64) -> Result<(u64, u64), ParseError> {
+        if let Err(err) = self.reader.seek(SeekFrom::Start(section_offset + 1)) {
+            return Err(ParseError::ReaderError(format!("{:?}", err)));
+        }
+        let mut payload_len: u64 = 0;
+        if let Err(err) = read_unsigned_leb128(self.reader, &mut payload_len) {
+            return Err(ParseError::ReaderError(format!("{:?}", err)));
+        }
+
+        let payload_offset = match self.reader.stream_position() {
+            Ok(pos) => pos,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        if let Err(err) = self.reader.seek(SeekFrom::Start(section_offset)) {
+            return Err(ParseError::ReaderError(format!("{:?}", err)));
+        }
+
+        Ok((payload_offset, payload_len))
+    }
+
+    // section_offsetから始まるセクション全体のバイト列のハッシュを求め、section_offsetに戻る
+    //
+    // セクションの大きさはファイルに書かれた値なので、バッファに読み込まずに少しずつハッシュに渡す
+    fn digest_section(
+        &mut self,
+        section_offset: u64,
+        section_size: u64,
+    ) -> Result<[u8; 32], ParseError> {
+        let mut hasher = Sha256::new();
+        match io::copy(&mut self.reader.take(section_size), &mut hasher) {
+            Ok(size) if size == section_size => (),
+            Ok(size) => {
+                return Err(ParseError::FormatError(format!(
+                    "section is truncated: section_size={}, read_size={}",
+                    section_size, size
+                )))
+            }
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        let digest = hasher.finalize().into();
+        if let Err(err) = self.reader.seek(SeekFrom::Start(section_offset)) {
+            return Err(ParseError::ReaderError(format!("{:?}", err)));
+        }
+
+        Ok(digest)
+    }
+
+    // 読み込んだセクションが、無効にした提案を利用していないかを検査する
+    fn check_features(
+        &self,
+        module: &WasmModule,
+        section_id: u8,
+        section_offset: u64,
+    ) -> Result<(), ParseError> {
+        // MVPの機能を無効にした場合は、カスタムセクション以外を受け付けない
+        if !self.features.mvp && section_id != 0 {
+            return Err(ParseError::FeatureError(format!(
+                "mvp is disabled, but used at {} (offset={:#x})",
+                FeatureLocation::Section { section_id },
+                section_offset
+            )));
+        }
+
+        let feature_uses = detect_section_feature_uses(module, section_id)?;
+        let feature_use = match feature_uses
+            .iter()
+            .find(|x| !self.features.is_enabled(x.get_proposal()))
+        {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+
+        // 命令・エントリの場合はその位置、それ以外はセクションの位置を示す
+        // (読み込み時に記録した実際の位置を使う。パディングされたLEB128に対応するため)
+        let payload_offset = module
+            .get_sections()
+            .last()
+            .map(|x| x.get_payload_offset())
+            .unwrap_or(section_offset);
+        let offset = match feature_use.get_location() {
+            FeatureLocation::Instruction { func_index, offset } => {
+                let body_idx = func_index - module.get_num_imported_functions();
+                let code_offset = module
+                    .get_code_section()
+                    .and_then(|sec| sec.get_code_offset(body_idx as usize))
+                    .unwrap_or(0);
+                payload_offset + code_offset as u64 + *offset as u64
+            }
+            FeatureLocation::Entry { section_id, index } => {
+                match get_entry_offset(module, *section_id, *index as usize) {
+                    Some(entry_offset) => payload_offset + entry_offset as u64,
+                    None => section_offset,
+                }
+            }
+            FeatureLocation::Section { .. } => section_offset,
+        };
+
+        Err(ParseError::FeatureError(format!(
+            "{} is disabled, but used at {} (offset={:#x})",
+            feature_use.get_proposal(),
+            feature_use.get_location(),
+            offset
+        )))
+    }
+}
+
+// セクション内のidx番目のエントリの位置(ペイロードの先頭から)を返す
+fn get_entry_offset(module: &WasmModule, section_id: u8, idx: usize) -> Option<u32> {
+    match section_id {
+        1 => module.get_type_section()?.get_entry_offset(idx),
+        2 => module.get_import_section()?.get_entry_offset(idx),
+        4 => module.get_table_section()?.get_entry_offset(idx),
+        5 => module.get_memory_section()?.get_entry_offset(idx),
+        6 => module.get_global_section()?.get_entry_offset(idx),
+        9 => module.get_element_section()?.get_entry_offset(idx),
+        11 => module.get_data_section()?.get_entry_offset(idx),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::ir::test_utils::*;
+
+    fn parse_with_features(bytes: &[u8], features: WasmFeatures) -> Result<WasmModule, ParseError> {
+        WasmModule::parse_with_features(&mut Cursor::new(bytes.to_vec()), features)
+    }
+
+    #[test]
+    fn feature_error_reports_entry_offset() {
+        // 2番目のメモリだけがmemory64 (flags=0x04)
+        let memories = vec(&[vec![0x00, 0x01], vec![0x04, 0x01]]);
+        let bytes = module(&[section(5, &memories)]);
+        let features = WasmFeatures {
+            memory64: false,
+            ..WasmFeatures::all()
+        };
+
+        // header(8) + id(1) + size(1) + count(1) + memory[0](2)
+        match parse_with_features(&bytes, features) {
+            Err(ParseError::FeatureError(msg)) => {
+                assert!(msg.contains("memory[1]"), "{}", msg);
+                assert!(msg.contains("offset=0xd"), "{}", msg);
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn mvp_disabled_rejects_non_custom_sections() {
+        let features = WasmFeatures {
+            mvp: false,
+            ..WasmFeatures::mvp()
+        };
+
+        let bytes = module(&[custom_section("note", &[0x01])]);
+        assert!(parse_with_features(&bytes, features).is_ok());
+
+        let bytes = module(&[section(1, &vec(&[func_type(&[], &[])]))]);
+        assert!(matches!(
+            parse_with_features(&bytes, features),
+            Err(ParseError::FeatureError(_))
+        ));
+    }
 }
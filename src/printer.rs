@@ -1,8 +1,165 @@
+use wasmdump::dump;
+use wasmdump::dwarf::DebugInfo;
+use wasmdump::features::{self, Proposal};
+#[cfg(feature = "serde")]
+use wasmdump::schema::ModuleSchema;
+use wasmdump::source_map::SourceMap;
+use wasmdump::wasm_components::base::Sizeof;
+use wasmdump::wasm_components::code::Expr;
+use wasmdump::wasm_components::entity::FunctionEntity;
+use wasmdump::wasm_components::instruction::Immediate;
+use wasmdump::wasm_components::module::{ContentHashOptions, WasmModule};
 use wasmdump::wasm_components::sections::{
-    CustomSectionPayload, SectionCommonInterface, TypeEntry,
+    CustomSectionPayload, DylinkSectionPayload, DylinkSubsection, LinkingSectionPayload,
+    LinkingSubsectionPayload, NameMapSubsection, RelocSectionPayload, SectionCommonInterface,
+    SymbolKind, TargetFeaturePrefix, TypeEntry, NAME_TYPE_FIELD,
 };
 use wasmdump::wasm_components::types::ExternalKind;
-use wasmdump::wasm_components::{base::Sizeof, module::WasmModule};
+
+/// printで表示するセクションの選択 (--section, --custom-section)
+///
+/// どちらも指定しない場合はすべてのセクションを選択する
+pub struct SectionSelection<'a> {
+    sections: &'a [String],        // セクション名(get_section_name)
+    custom_sections: &'a [String], // カスタムセクションの名前(glob)
+}
+
+impl<'a> SectionSelection<'a> {
+    pub fn new(sections: &'a [String], custom_sections: &'a [String]) -> Self {
+        Self {
+            sections,
+            custom_sections,
+        }
+    }
+
+    /// すべてのセクションを選択しているかどうか
+    pub fn is_all(&self) -> bool {
+        self.sections.is_empty() && self.custom_sections.is_empty()
+    }
+
+    /// セクション名(get_section_name)のセクションを選択しているかどうか
+    pub fn is_selected(&self, section_name: &str) -> bool {
+        self.is_all() || self.sections.iter().any(|x| x == section_name)
+    }
+
+    /// カスタムセクションを選択しているかどうか
+    ///
+    /// --section customの場合はすべてのカスタムセクションを選択する
+    pub fn is_custom_selected(&self, name: &str) -> bool {
+        self.is_selected("custom") || self.custom_sections.iter().any(|x| glob_match(x, name))
+    }
+}
+
+// globのパターン(*は任意の文字列、?は任意の1文字)に一致するかどうか
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // 最後に現れた*の位置と、その*に対応させたtextの位置を覚えておき、
+    // 一致しなくなったら*に対応させる文字を1つ増やしてやり直す
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|x| *x == '*')
+}
+
+/// セクションの一覧(位置・大きさ・要素数)を表示する
+pub fn print_summary(wasm_module: &WasmModule, selection: &SectionSelection) {
+    let sections = wasm_module.get_sections();
+
+    let file_size = match sections.last() {
+        Some(section) => section.get_offset() + section.get_size(),
+        None => wasm_module.get_magic_and_version().sizeof() as u64,
+    };
+    println!(
+        "[Summary ({} bytes)] version={}, {} sections",
+        file_size,
+        wasm_module.get_magic_and_version().get_version(),
+        sections.len()
+    );
+
+    for (idx, section) in sections.iter().enumerate() {
+        let section_id = section.get_id();
+        let section_name = wasm_module
+            .get_section_name_by_index(idx as u32)
+            .unwrap_or_default();
+        let (selected, label) = if section_id == 0 {
+            (
+                selection.is_custom_selected(&section_name),
+                format!("custom \"{}\"", section_name),
+            )
+        } else {
+            (selection.is_selected(&section_name), section_name)
+        };
+        if !selected {
+            continue;
+        }
+
+        let line = format!(
+            "  {:>2}: {:<24} offset={:#010x} size={:<8}",
+            idx,
+            label,
+            section.get_offset(),
+            section.get_size()
+        );
+        match get_section_item_count(wasm_module, section_id) {
+            Some(count) => println!("{} count={}", line, count),
+            None => println!("{}", line.trim_end()),
+        }
+    }
+}
+
+// セクションの要素の数を返す (要素を持たないセクションはNone)
+fn get_section_item_count(wasm_module: &WasmModule, section_id: u8) -> Option<u32> {
+    match section_id {
+        1 => wasm_module.get_type_section().map(|x| x.get_num_types()),
+        2 => wasm_module
+            .get_import_section()
+            .map(|x| x.get_num_import_entries()),
+        3 => wasm_module
+            .get_function_section()
+            .map(|x| x.get_num_functions()),
+        4 => wasm_module.get_table_section().map(|x| x.get_num_tables()),
+        5 => wasm_module
+            .get_memory_section()
+            .map(|x| x.get_num_memories()),
+        6 => wasm_module
+            .get_global_section()
+            .map(|x| x.get_num_globals()),
+        7 => wasm_module
+            .get_export_section()
+            .map(|x| x.get_num_export_entries()),
+        9 => wasm_module
+            .get_element_section()
+            .map(|x| x.get_num_elements()),
+        10 => wasm_module
+            .get_code_section()
+            .map(|x| x.get_num_func_bodies()),
+        11 => wasm_module
+            .get_data_section()
+            .map(|x| x.get_num_data_segments()),
+        12 => wasm_module
+            .get_data_count_section()
+            .map(|x| x.get_data_count()),
+        13 => wasm_module.get_tag_section().map(|x| x.get_num_tags()),
+        _ => None,
+    }
+}
 
 pub fn print_type_section(wasm_module: &WasmModule) {
     let type_section = wasm_module.get_type_section();
@@ -15,92 +172,130 @@ pub fn print_type_section(wasm_module: &WasmModule) {
     let type_section = type_section.unwrap();
     println!("[Type Section ({} bytes)]", type_section.sizeof());
 
-    for (cnt, func_type) in type_section.get_type_list().into_iter().enumerate() {
-        println!("  {}: {}", cnt, func_type);
-    }
-}
-
-pub fn print_import_section(wasm_module: &WasmModule) {
-    let import_section = wasm_module.get_import_section();
-    let type_section = wasm_module.get_type_section().unwrap();
+    // 型インデックスはrec groupを展開した通し番号
+    let type_names = wasm_module
+        .get_name_section()
+        .and_then(|x| x.get_type_names());
+    let mut type_idx = 0;
+    for rec_group in type_section.get_rec_groups().into_iter() {
+        let indent = if rec_group.is_explicit() {
+            println!("  rec:");
+            "    "
+        } else {
+            "  "
+        };
 
-    if import_section.is_none() {
-        println!("[Import Section (0 bytes)] None");
-        return;
+        for sub_type in rec_group.get_sub_types().into_iter() {
+            let name_str = format_name(lookup_name(type_names, type_idx));
+            // MVPの関数型はこれまで通り"(params) -> result"の形で表示する
+            match sub_type.get_func_type() {
+                Some(func_type) if sub_type.is_final() && sub_type.get_super_types().is_empty() => {
+                    println!("{}{}{}: {}", indent, type_idx, name_str, func_type)
+                }
+                _ => println!("{}{}{}: {}", indent, type_idx, name_str, sub_type),
+            }
+            type_idx += 1;
+        }
     }
+}
 
-    let import_section = import_section.unwrap();
+/// インポートを表示する
+///
+/// patternを指定した場合は、名前("module.field"またはfield)がglobに一致するものだけを表示する
+pub fn print_import_section(wasm_module: &WasmModule, pattern: Option<&str>) {
+    let import_section = match wasm_module.get_import_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Import Section (0 bytes)] None");
+            return;
+        }
+    };
     println!("[Import Section ({} bytes)]", import_section.sizeof());
 
     let import_entries = import_section.get_import_entries();
     for (cnt, import_entry) in import_entries.into_iter().enumerate() {
+        let field_name = import_entry.get_import_entry_name();
+        let full_name = format!("{}.{}", import_entry.get_import_module_name(), field_name);
+        if let Some(pattern) = pattern {
+            if !glob_match(pattern, &full_name) && !glob_match(pattern, field_name) {
+                continue;
+            }
+        }
+
         match import_entry.get_type() {
             TypeEntry::FuncIndex { type_ } => {
-                let type_idx = *type_;
-                let func_type = type_section.get_type(type_idx as usize).unwrap();
-                println!("  {}: (Function) {}", cnt, func_type);
+                println!(
+                    "  {}: (Function) {}: {}",
+                    cnt,
+                    full_name,
+                    describe_type_index(wasm_module, *type_)
+                );
             }
             // 処理をまとめたい
             TypeEntry::TblType { type_ } => {
-                println!("  {}: (Table) {}", cnt, type_)
+                println!("  {}: (Table) {}: {}", cnt, full_name, type_)
             }
             TypeEntry::MemType { type_ } => {
-                println!("  {}: (Memory) {}", cnt, type_)
+                println!("  {}: (Memory) {}: {}", cnt, full_name, type_)
             }
             TypeEntry::GblType { type_ } => {
-                println!("  {}: (Global) {}", cnt, type_)
+                println!("  {}: (Global) {}: {}", cnt, full_name, type_)
+            }
+            TypeEntry::TagType { type_ } => {
+                println!("  {}: (Tag) {}: {}", cnt, full_name, type_)
             }
         };
     }
 }
 
 pub fn print_function_section(wasm_module: &WasmModule) {
-    let func_section = wasm_module.get_function_section();
-    let type_section = wasm_module.get_type_section().unwrap();
-    let import_section = wasm_module.get_import_section();
-
-    if func_section.is_none() {
-        println!("[Function Section (0 bytes)] None");
-        return;
-    }
-
-    let func_section = func_section.unwrap();
+    let func_section = match wasm_module.get_function_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Function Section (0 bytes)] None");
+            return;
+        }
+    };
     println!("[Function Section ({} bytes)]", func_section.sizeof());
 
-    let base_func_idx = match import_section {
-        Some(sec) => sec.get_num_import_entries(),
-        None => 0 as u32,
-    };
-    let type_indices = func_section.get_indice_list();
-    for (cnt, type_idx) in type_indices.iter().enumerate() {
-        let func_type = type_section.get_type(*type_idx as usize).unwrap();
+    // 関数インデックスはインポートされた関数の後に続く
+    let functions = wasm_module.functions();
+    for function in functions.iter().filter(|x| !x.get_common().is_imported()) {
         println!(
-            "  {}: {}, func_idx={}, func_idx(rel)={}",
-            cnt,
-            func_type,
-            base_func_idx + cnt as u32,
-            cnt
+            "  {}: {}",
+            function.get_common().get_defined_index().unwrap_or(0),
+            describe_function(&functions, function.get_common().get_index())
         );
     }
 }
 
 pub fn print_table_section(wasm_module: &WasmModule) {
-    let table_section = wasm_module.get_table_section();
-
-    if table_section.is_none() {
-        println!("[Table Section (0 bytes)] None");
-        return;
-    }
-
-    let table_section = table_section.unwrap();
+    let table_section = match wasm_module.get_table_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Table Section (0 bytes)] None");
+            return;
+        }
+    };
     println!("[Table Section ({} bytes)]", table_section.sizeof());
 
-    let table_type = table_section.get_table_type(0); // wasm v1ではテーブルは一つのみ
-
-    match table_type {
-        Some(ty) => println!("  table type: {}", ty),
-        None => println!(""),
-    };
+    // reference-types提案により、テーブルは複数定義できる
+    // テーブルインデックスはインポートされたテーブルの後に続く
+    let tables = wasm_module.tables();
+    for table in tables.iter().filter(|x| !x.get_common().is_imported()) {
+        let init_str = match table.get_init_expr() {
+            Some(expr) => format!(", init=[{}]", format_expr(expr)),
+            None => String::new(),
+        };
+        println!(
+            "  {}{}: table type: {}, table_idx={}{}",
+            table.get_common().get_defined_index().unwrap_or(0),
+            format_name(table.get_common().get_name()),
+            table.get_table_type(),
+            table.get_common().get_index(),
+            init_str
+        );
+    }
 }
 
 pub fn print_memory_section(wasm_module: &WasmModule) {
@@ -114,12 +309,52 @@ pub fn print_memory_section(wasm_module: &WasmModule) {
     let memory_section = memory_section.unwrap();
     println!("[Memory Section ({} bytes)]", memory_section.sizeof());
 
-    let memory_info = memory_section.get_memory(0); // wasm v1では、線形メモリは一つのみ
+    // multi-memory提案により、メモリは複数定義できる
+    // メモリインデックスはインポートされたメモリの後に続く
+    let memories = wasm_module.memories();
+    for memory in memories.iter().filter(|x| !x.get_common().is_imported()) {
+        let mem = memory.get_memory_type();
+        println!(
+            "  {}{}: memory limits: {}, index_type={}, mem_idx={}",
+            memory.get_common().get_defined_index().unwrap_or(0),
+            format_name(memory.get_common().get_name()),
+            mem.get_limits(),
+            mem.get_index_type(),
+            memory.get_common().get_index()
+        );
+    }
+}
+
+pub fn print_tag_section(wasm_module: &WasmModule) {
+    let tag_section = wasm_module.get_tag_section();
+    let type_section = wasm_module.get_type_section();
 
-    match memory_info {
-        Some(mem) => println!("  memory limits: {}", mem.get_limits()),
-        None => println!(""),
-    };
+    if tag_section.is_none() {
+        println!("[Tag Section (0 bytes)] None");
+        return;
+    }
+
+    let tag_section = tag_section.unwrap();
+    println!("[Tag Section ({} bytes)]", tag_section.sizeof());
+
+    // タグインデックスはインポートされたタグの後に続く
+    let tags = wasm_module.tags();
+    for tag in tags.iter().filter(|x| !x.get_common().is_imported()) {
+        let tag_type = tag.get_tag_type();
+        let func_type =
+            type_section.and_then(|sec| sec.get_type(tag_type.get_type_index() as usize));
+        let type_str = match func_type {
+            Some(ty) => format!("{} {}", tag_type, ty),
+            None => format!("{}", tag_type),
+        };
+        println!(
+            "  {}{}: {}, tag_idx={}",
+            tag.get_common().get_defined_index().unwrap_or(0),
+            format_name(tag.get_common().get_name()),
+            type_str,
+            tag.get_common().get_index()
+        );
+    }
 }
 
 pub fn print_global_section(wasm_module: &WasmModule) {
@@ -133,83 +368,77 @@ pub fn print_global_section(wasm_module: &WasmModule) {
     let global_section = global_section.unwrap();
     println!("[Global Section ({} bytes)]", global_section.sizeof());
 
-    let global_variables = global_section.get_global_variable_list();
-    for (cnt, global_var) in global_variables.into_iter().enumerate() {
-        println!("  {}: {}", cnt, global_var.get_global_type());
+    // グローバル変数インデックスはインポートされたグローバル変数の後に続く
+    let globals = wasm_module.globals();
+    for global in globals.iter().filter(|x| !x.get_common().is_imported()) {
+        println!(
+            "  {}{}: {}, global_idx={}",
+            global.get_common().get_defined_index().unwrap_or(0),
+            format_name(global.get_common().get_name()),
+            global.get_global_type(),
+            global.get_common().get_index()
+        );
     }
 }
 
-pub fn print_export_section(wasm_module: &WasmModule) {
-    let export_section = wasm_module.get_export_section();
-    let import_section = wasm_module.get_import_section();
-    let func_section = wasm_module.get_function_section();
-    let global_section = wasm_module.get_global_section();
-    let table_section = wasm_module.get_table_section();
-    let type_section = wasm_module.get_type_section();
-
-    if export_section.is_none() {
-        println!("[Export Section (0 bytes)] None");
-        return;
-    }
-
-    let export_section = export_section.unwrap();
+/// エクスポートを表示する
+///
+/// patternを指定した場合は、名前がglobに一致するものだけを表示する
+pub fn print_export_section(wasm_module: &WasmModule, pattern: Option<&str>) {
+    let export_section = match wasm_module.get_export_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Export Section (0 bytes)] None");
+            return;
+        }
+    };
     println!("[Export Section ({} bytes)]", export_section.sizeof());
 
+    let functions = wasm_module.functions();
+    let tables = wasm_module.tables();
+    let globals = wasm_module.globals();
     let export_entries = export_section.get_export_entry_list();
     for export_entry in export_entries.into_iter() {
         let entry_name = export_entry.get_entry_name();
+        if pattern.is_some_and(|x| !glob_match(x, entry_name)) {
+            continue;
+        }
+
+        let idx = export_entry.get_index();
         match export_entry.get_kind() {
             ExternalKind::Function => {
-                let base_func_idx = match import_section {
-                    Some(sec) => sec.get_num_import_entries(),
-                    None => 0 as u32,
-                };
-
-                let func_idx = export_entry.get_index();
-                let func_idx_rel = func_idx - base_func_idx;
-                let idx_of_func_type = func_section
-                    .unwrap()
-                    .get_indice(func_idx_rel as usize)
-                    .unwrap();
-                let func_type = type_section
-                    .unwrap()
-                    .get_type(idx_of_func_type as usize)
-                    .unwrap();
-
                 println!(
-                    "  (Function) {}: {}, func_idx={}, func_idx(rel)={}",
-                    entry_name, func_type, func_idx, func_idx_rel
-                );
-            }
-            ExternalKind::Global => {
-                let idx = export_entry.get_index() as usize;
-                let global_var_type = match global_section.unwrap().get_global_variable_type(idx) {
-                    Some(gvt) => gvt,
-                    None => panic!(
-                        "> Error: unexpected error: export entry (idx: {}) not found",
-                        idx
-                    ),
-                };
-                let mut_flg = global_var_type.get_mutability();
-                let val_type = global_var_type.get_type();
-
-                println!(
-                    "  (Global) {}:{}{}",
+                    "  (Function) {}: {}",
                     entry_name,
-                    if mut_flg { " mut" } else { " " },
-                    val_type
+                    describe_function(&functions, idx)
                 );
             }
+            ExternalKind::Global => match globals.get(idx as usize).map(|x| x.get_global_type()) {
+                Some(global_type) => println!(
+                    "  (Global) {}:{}{}, global_idx={}",
+                    entry_name,
+                    if global_type.get_mutability() {
+                        " mut "
+                    } else {
+                        " "
+                    },
+                    global_type.get_type(),
+                    idx
+                ),
+                None => println!("  (Global) {}: (not found), global_idx={}", entry_name, idx),
+            },
             ExternalKind::Memory => {
-                println!("  (Memory) {}", entry_name);
+                println!("  (Memory) {}: mem_idx={}", entry_name, idx);
             }
-            ExternalKind::Table => {
-                let idx = export_entry.get_index() as usize;
-                println!(
-                    "  (Table) {}: {:?}",
-                    entry_name,
-                    table_section.unwrap().get_table_type(idx).unwrap()
-                );
+            ExternalKind::Table => match tables.get(idx as usize).map(|x| x.get_table_type()) {
+                Some(table_type) => println!(
+                    "  (Table) {}: {}, table_idx={}",
+                    entry_name, table_type, idx
+                ),
+                None => println!("  (Table) {}: (not found), table_idx={}", entry_name, idx),
+            },
+            ExternalKind::Tag => {
+                println!("  (Tag) {}: tag_idx={}", entry_name, idx);
             }
         };
     }
@@ -230,29 +459,107 @@ pub fn print_start_section(wasm_module: &WasmModule) {
 }
 
 pub fn print_element_section(wasm_module: &WasmModule) {
-    let elem_section = wasm_module.get_element_section();
-    let type_section = wasm_module.get_type_section().unwrap();
-    let func_section = wasm_module.get_function_section().unwrap();
+    let elem_section = match wasm_module.get_element_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Element Section (0 bytes)] None");
+            return;
+        }
+    };
+    println!("[Element Section ({} bytes)]", elem_section.sizeof());
 
-    if elem_section.is_none() {
-        println!("[Element Section (0 bytes)] None");
-        return;
+    let functions = wasm_module.functions();
+    let elem_names = wasm_module
+        .get_name_section()
+        .and_then(|x| x.get_elem_names());
+    for (seg_idx, segment) in elem_section.get_element_list().into_iter().enumerate() {
+        let mode = match segment.get_table_index() {
+            Some(table_idx) => {
+                let offset_str = match segment.get_offset() {
+                    Some(offset) => format!("{:#x}", offset),
+                    None => String::from("?"),
+                };
+                format!("active, table_idx={}, offset={}", table_idx, offset_str)
+            }
+            None if segment.is_passive() => String::from("passive"),
+            None => String::from("declarative"),
+        };
+        println!(
+            "  segment {}{}: {}, elem_type={}, num_elements={}",
+            seg_idx,
+            format_name(lookup_name(elem_names, seg_idx as u32)),
+            mode,
+            segment.get_elem_type(),
+            segment.get_num_elements()
+        );
+
+        // 初期化式で表す場合は式をそのまま表示する
+        let elem_exprs = segment.get_element_exprs();
+        if !elem_exprs.is_empty() {
+            for (cnt, expr) in elem_exprs.into_iter().enumerate() {
+                println!("    {}: {}", cnt, format_expr(expr));
+            }
+            continue;
+        }
+
+        for (cnt, func_idx) in segment.get_elements().into_iter().enumerate() {
+            println!("    {}: {}", cnt, describe_function(&functions, func_idx));
+        }
     }
+}
 
-    let elem_section = elem_section.unwrap();
-    println!("[Element Section ({} bytes)]", elem_section.sizeof());
+// nameセクションの名前を" <name>"の形で表す (名前がない場合は空文字列)
+fn format_name(name: Option<&String>) -> String {
+    name.map(|x| format!(" <{}>", x)).unwrap_or_default()
+}
 
-    let elem_entries = elem_section.get_element(0).unwrap().get_elements();
-    let base_func_idx = match wasm_module.get_import_section() {
-        Some(sec) => sec.get_num_import_entries(),
-        None => 0 as u32,
+// nameセクションのサブセクションからインデックスに対応する名前を引く
+fn lookup_name(names: Option<&NameMapSubsection>, index: u32) -> Option<&String> {
+    names.and_then(|x| x.get_name_map().get_name(index))
+}
+
+// 初期化式を末尾のendを除いた命令のリストとして表す
+fn format_expr(expr: &Expr) -> String {
+    match expr.get_instructions() {
+        Ok(instrs) => instrs
+            .iter()
+            .filter(|x| !x.is_end())
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        Err(err) => format!("Error: {}", err),
+    }
+}
+
+// 型インデックスを関数型として表す (見つからない場合もインデックスを表示する)
+fn describe_type_index(wasm_module: &WasmModule, type_idx: u32) -> String {
+    match wasm_module
+        .get_type_section()
+        .and_then(|sec| sec.get_type(type_idx as usize))
+    {
+        Some(func_type) => func_type.to_string(),
+        None => format!("(type not found: type_idx={})", type_idx),
+    }
+}
+
+// 関数インデックス空間の関数を、関数型とインデックスで表す
+fn describe_function(functions: &[FunctionEntity], func_idx: u32) -> String {
+    let function = match functions.get(func_idx as usize) {
+        Some(function) => function,
+        None => return format!("(not found), func_idx={}", func_idx),
     };
-    for (cnt, func_idx) in elem_entries.into_iter().enumerate() {
-        let func_idx_rel = func_section
-            .get_indice(func_idx as usize - base_func_idx as usize)
-            .unwrap();
-        let func_type = type_section.get_type(func_idx_rel as usize).unwrap();
-        println!("  {}: {}, func_idx(rel): {}", cnt, func_type, func_idx_rel);
+
+    let type_str = match (function.get_func_type(), function.get_type_index()) {
+        (Some(func_type), _) => func_type.to_string(),
+        (None, Some(type_idx)) => format!("(type not found: type_idx={})", type_idx),
+        (None, None) => String::from("(not found)"),
+    };
+    match function.get_common().get_defined_index() {
+        Some(func_idx_rel) => format!(
+            "{}, func_idx={}, func_idx(rel)={}",
+            type_str, func_idx, func_idx_rel
+        ),
+        None => format!("{}, func_idx={} (imported)", type_str, func_idx),
     }
 }
 
@@ -267,60 +574,224 @@ pub fn print_data_section(wasm_module: &WasmModule) {
     let data_section = data_section.unwrap();
     println!("[Data Section ({} bytes)]", data_section.sizeof());
 
+    let data_names = wasm_module
+        .get_name_section()
+        .and_then(|x| x.get_data_names());
     let data_segments = data_section.get_data_segment_list();
     for (cnt, data_entry) in data_segments.into_iter().enumerate() {
+        let name_str = format_name(lookup_name(data_names, cnt as u32));
+        let mem_idx = match data_entry.get_memory_index() {
+            Some(mem_idx) => mem_idx,
+            None => {
+                println!(
+                    "  {}{}: passive, data_size={}",
+                    cnt,
+                    name_str,
+                    data_entry.get_data_size()
+                );
+                continue;
+            }
+        };
+        let offset_str = match data_entry.get_offset() {
+            Some(offset) => format!("{:#x}", offset),
+            None => String::from("?"),
+        };
         println!(
-            "  {}: mem_idx={}, data_size={}",
+            "  {}{}: mem_idx={}, offset={}, data_size={}",
             cnt,
-            data_entry.get_memory_index(),
+            name_str,
+            mem_idx,
+            offset_str,
             data_entry.get_data_size()
         );
     }
 }
 
-pub fn print_custom_sections(wasm_module: &WasmModule) {
+pub fn print_data_count_section(wasm_module: &WasmModule) {
+    let data_count_section = wasm_module.get_data_count_section();
+
+    if data_count_section.is_none() {
+        println!("[DataCount Section (0 bytes)] None");
+        return;
+    }
+
+    let data_count_section = data_count_section.unwrap();
+    println!(
+        "[DataCount Section ({} bytes)]",
+        data_count_section.sizeof()
+    );
+
+    println!("  data count: {}", data_count_section.get_data_count());
+}
+
+pub fn print_features(wasm_module: &WasmModule) {
+    match features::detect_features(wasm_module) {
+        Ok(feature_set) => println!("[Features] {}", feature_set),
+        Err(err) => println!("[Features] Error: {}", err),
+    }
+}
+
+pub fn print_feature_report(wasm_module: &WasmModule) {
+    let first_uses = match features::detect_first_feature_uses(wasm_module) {
+        Ok(first_uses) => first_uses,
+        Err(err) => {
+            println!("[Features] Error: {}", err);
+            return;
+        }
+    };
+
+    if first_uses.is_empty() {
+        println!("[Features] mvp");
+    } else {
+        println!("[Features]");
+    }
+    for feature_use in first_uses.iter() {
+        println!(
+            "  {}: first used at {}",
+            feature_use.get_proposal(),
+            feature_use.get_location()
+        );
+    }
+
+    let target_features = match features::get_target_features(wasm_module) {
+        Some(payload) => payload.get_features(),
+        None => {
+            println!("[Target Features] None");
+            return;
+        }
+    };
+
+    println!("[Target Features]");
+    for target_feature in target_features.iter() {
+        let used = match Proposal::from_name(target_feature.get_name()) {
+            Some(proposal) => first_uses.iter().any(|x| x.get_proposal() == proposal),
+            None => {
+                println!("  {} (not detectable)", target_feature);
+                continue;
+            }
+        };
+
+        let status = target_feature_status(target_feature.get_prefix(), used);
+        println!("  {} ({})", target_feature, status);
+    }
+
+    // 利用しているのにtarget_featuresに書かれていない提案
+    for feature_use in first_uses.iter() {
+        let proposal = feature_use.get_proposal();
+        if target_features
+            .iter()
+            .all(|x| Proposal::from_name(x.get_name()) != Some(proposal))
+        {
+            println!("  {} (used, but not listed)", proposal);
+        }
+    }
+}
+
+// '+'/'='なのに使われていない、'-'なのに使われている場合は印をつける
+fn target_feature_status(prefix: TargetFeaturePrefix, used: bool) -> &'static str {
+    match (prefix, used) {
+        (TargetFeaturePrefix::Disallowed, true) => "used, but disallowed",
+        (TargetFeaturePrefix::Disallowed, false) => "not used",
+        (_, true) => "used",
+        (_, false) => "not used, but declared",
+    }
+}
+
+pub fn print_custom_sections(wasm_module: &WasmModule, selection: &SectionSelection) {
     let custom_sections = wasm_module.get_custom_sections();
 
     for custom_section in custom_sections.into_iter() {
+        let section_name = custom_section.get_name().map(|x| x.as_str()).unwrap_or("");
+        if !selection.is_custom_selected(section_name) {
+            continue;
+        }
+
         println!(
             "[Custom Section ({} bytes)] {}",
             custom_section.sizeof(),
-            custom_section.get_name().unwrap()
+            custom_section.get_name().map(|x| x.as_str()).unwrap_or("")
         );
 
-        match custom_section.get_payload() {
-            CustomSectionPayload::Name { payload } => {
-                if let Some(module_name) = payload.get_module_name() {
-                    println!("  {}: (Module) {}", 0, module_name.get_name());
+        if let CustomSectionPayload::Name { payload } = custom_section.get_payload() {
+            if let Some(module_name) = payload.get_module_name() {
+                println!("  {}: (Module) {}", 0, module_name.get_name());
+            }
+
+            for subsection in payload.get_name_map_subsections().into_iter() {
+                let name_map = subsection.get_name_map();
+                for (cnt, naming) in name_map.get_name_list().into_iter().enumerate() {
+                    println!(
+                        "  {}: ({}) {}, index={}",
+                        cnt,
+                        subsection.get_kind_name(),
+                        naming.get_name_str(),
+                        naming.get_indice()
+                    );
                 }
+            }
 
-                if let Some(function_names) = payload.get_function_names() {
-                    let func_map = function_names.get_function_map();
-                    for (cnt, naming) in func_map.get_name_list().into_iter().enumerate() {
+            // local, labelは関数ごと、fieldは型ごとに名前を持つ
+            for subsection in payload.get_indirect_name_map_subsections().into_iter() {
+                let outer_name = if subsection.get_name_type() == NAME_TYPE_FIELD {
+                    "type_idx"
+                } else {
+                    "func_idx"
+                };
+                for entry in subsection.get_entries().into_iter() {
+                    let name_map = entry.get_name_map();
+                    for (cnt, naming) in name_map.get_name_list().into_iter().enumerate() {
                         println!(
-                            "  {}: (function) {}, index={}",
+                            "  {}: ({}) {}, {}={}, index={}",
                             cnt,
+                            subsection.get_kind_name(),
                             naming.get_name_str(),
+                            outer_name,
+                            entry.get_indice(),
                             naming.get_indice()
                         );
                     }
                 }
+            }
+        }
 
-                if let Some(local_names) = payload.get_local_names() {
-                    let locals = local_names.get_locals();
-                    for local_name in locals.into_iter() {
-                        let func_idx = local_name.get_indice();
-                        let local_map = local_name.get_local_map();
-                        for (cnt, naming) in local_map.get_name_list().into_iter().enumerate() {
-                            println!(
-                                "  {}: (local) {}, func_idx={}, index={}",
-                                cnt,
-                                naming.get_name_str(),
-                                func_idx,
-                                naming.get_indice()
-                            );
-                        }
-                    }
+        if let CustomSectionPayload::TargetFeatures { payload } = custom_section.get_payload() {
+            for (cnt, target_feature) in payload.get_features().into_iter().enumerate() {
+                println!("  {}: {}", cnt, target_feature);
+            }
+        }
+
+        if let CustomSectionPayload::Linking { payload } = custom_section.get_payload() {
+            print_linking_payload(wasm_module, payload);
+        }
+
+        if let CustomSectionPayload::Reloc { payload } = custom_section.get_payload() {
+            print_reloc_payload(wasm_module, payload);
+        }
+
+        if let CustomSectionPayload::Dylink { payload } = custom_section.get_payload() {
+            print_dylink_payload(payload);
+        }
+
+        if let CustomSectionPayload::Producers { payload } = custom_section.get_payload() {
+            for field in payload.get_fields().into_iter() {
+                println!("  {}:", field.get_field_name());
+                for (cnt, value) in field.get_values().into_iter().enumerate() {
+                    println!("    {}: {}", cnt, value);
+                }
+            }
+        }
+
+        match custom_section.get_payload() {
+            CustomSectionPayload::SourceMappingUrl { payload }
+            | CustomSectionPayload::ExternalDebugInfo { payload } => {
+                println!("  url: {}", payload.get_url());
+            }
+            CustomSectionPayload::BuildId { payload } => {
+                println!("  id: {}", payload);
+            }
+            CustomSectionPayload::Registered { payload, .. } => {
+                for line in payload.to_string().lines() {
+                    println!("  {}", line);
                 }
             }
             _ => (),
@@ -430,7 +901,7 @@ pub fn print_all_section_for_debug(wasm_module: &WasmModule) {
 
     for custom_section in wasm_module.get_custom_sections().into_iter() {
         // println!("CustomSection:\n{:?}\n", custom_section);
-        let name = custom_section.get_name().unwrap();
+        let name = custom_section.get_name().map(|x| x.as_str()).unwrap_or("");
 
         if name == "name" {
             println!(
@@ -450,3 +921,656 @@ pub fn print_all_section_for_debug(wasm_module: &WasmModule) {
 
     println!("\nSizeof WasmModule: {} bytes", wasm_module.sizeof());
 }
+
+/// オブジェクトファイルの再配置情報を表示する (objdump -r 相当)
+pub fn print_relocations(wasm_module: &WasmModule) {
+    let mut found = false;
+    for custom_section in wasm_module.get_custom_sections().into_iter() {
+        if let CustomSectionPayload::Reloc { payload } = custom_section.get_payload() {
+            println!(
+                "[Relocations] {} ({} entries)",
+                custom_section.get_name().map(|x| x.as_str()).unwrap_or(""),
+                payload.get_num_entries()
+            );
+            print_reloc_payload(wasm_module, payload);
+            found = true;
+        }
+    }
+
+    if !found {
+        println!("[Relocations] None");
+    }
+}
+
+fn print_linking_payload(wasm_module: &WasmModule, payload: &LinkingSectionPayload) {
+    println!("  version: {}", payload.get_version());
+
+    for subsection in payload.get_subsections().into_iter() {
+        match subsection.get_payload() {
+            LinkingSubsectionPayload::SegmentInfo(segments) => {
+                println!("  segment_info:");
+                for (cnt, segment) in segments.iter().enumerate() {
+                    println!(
+                        "    {}: {}, align=2^{}, flags={:#x}",
+                        cnt,
+                        segment.get_name(),
+                        segment.get_alignment(),
+                        segment.get_flags()
+                    );
+                }
+            }
+            LinkingSubsectionPayload::InitFuncs(init_funcs) => {
+                println!("  init_funcs:");
+                for (cnt, init_func) in init_funcs.iter().enumerate() {
+                    println!(
+                        "    {}: priority={}, symbol={} <{}>",
+                        cnt,
+                        init_func.get_priority(),
+                        init_func.get_symbol_index(),
+                        get_symbol_name(wasm_module, payload, init_func.get_symbol_index())
+                    );
+                }
+            }
+            LinkingSubsectionPayload::ComdatInfo(comdats) => {
+                println!("  comdat_info:");
+                for (cnt, comdat) in comdats.iter().enumerate() {
+                    println!(
+                        "    {}: {}, flags={:#x}",
+                        cnt,
+                        comdat.get_name(),
+                        comdat.get_flags()
+                    );
+                    for symbol in comdat.get_symbols().into_iter() {
+                        println!("      {} {}", symbol.get_kind(), symbol.get_index());
+                    }
+                }
+            }
+            LinkingSubsectionPayload::SymbolTable(symbols) => {
+                println!("  symbol_table:");
+                for (cnt, symbol) in symbols.iter().enumerate() {
+                    let location = match (symbol.get_index(), symbol.get_data()) {
+                        (Some(index), _) => format!("index={}", index),
+                        (None, Some(data)) => format!(
+                            "segment={}, offset={:#x}, size={}",
+                            data.get_segment_index(),
+                            data.get_offset(),
+                            data.get_size()
+                        ),
+                        (None, None) => String::from("undefined"),
+                    };
+                    println!(
+                        "    {}: {} <{}>, {}, flags=[{}]",
+                        cnt,
+                        symbol.get_kind(),
+                        get_symbol_name(wasm_module, payload, cnt as u32),
+                        location,
+                        symbol.get_flag_names()
+                    );
+                }
+            }
+            LinkingSubsectionPayload::Unknown(data) => {
+                println!(
+                    "  unknown subsection({}): {} bytes",
+                    subsection.get_subsection_type(),
+                    data.len()
+                );
+            }
+        }
+    }
+}
+
+fn print_dylink_payload(payload: &DylinkSectionPayload) {
+    for subsection in payload.get_subsections().into_iter() {
+        match subsection {
+            DylinkSubsection::MemInfo(mem_info) => {
+                println!(
+                    "  mem_info: memory_size={}, memory_align=2^{}, table_size={}, table_align=2^{}",
+                    mem_info.get_memory_size(),
+                    mem_info.get_memory_alignment(),
+                    mem_info.get_table_size(),
+                    mem_info.get_table_alignment()
+                );
+            }
+            DylinkSubsection::Needed(needed) => {
+                println!("  needed:");
+                for (cnt, name) in needed.iter().enumerate() {
+                    println!("    {}: {}", cnt, name);
+                }
+            }
+            DylinkSubsection::ExportInfo(exports) => {
+                println!("  export_info:");
+                for (cnt, export) in exports.iter().enumerate() {
+                    println!(
+                        "    {}: {}, flags=[{}]",
+                        cnt,
+                        export.get_name(),
+                        export.get_flag_names()
+                    );
+                }
+            }
+            DylinkSubsection::ImportInfo(imports) => {
+                println!("  import_info:");
+                for (cnt, import) in imports.iter().enumerate() {
+                    println!(
+                        "    {}: {}.{}, flags=[{}]",
+                        cnt,
+                        import.get_module_name(),
+                        import.get_field_name(),
+                        import.get_flag_names()
+                    );
+                }
+            }
+            DylinkSubsection::RuntimePath(paths) => {
+                println!("  runtime_path:");
+                for (cnt, path) in paths.iter().enumerate() {
+                    println!("    {}: {}", cnt, path);
+                }
+            }
+            DylinkSubsection::Unknown {
+                subsection_type,
+                data,
+            } => {
+                println!(
+                    "  unknown subsection({}): {} bytes",
+                    subsection_type,
+                    data.len()
+                );
+            }
+        }
+    }
+}
+
+fn print_reloc_payload(wasm_module: &WasmModule, payload: &RelocSectionPayload) {
+    let section_index = payload.get_section_index();
+    println!(
+        "  target: section[{}] {}",
+        section_index,
+        wasm_module
+            .get_section_name_by_index(section_index)
+            .unwrap_or_else(|| String::from("(not found)"))
+    );
+
+    let linking =
+        wasm_module
+            .get_custom_sections()
+            .into_iter()
+            .find_map(|x| match x.get_payload() {
+                CustomSectionPayload::Linking { payload } => Some(payload),
+                _ => None,
+            });
+
+    for (cnt, entry) in payload.get_entries().into_iter().enumerate() {
+        let reloc_type = entry.get_type();
+        let target = match linking {
+            Some(linking) if reloc_type.refers_to_symbol() => format!(
+                "symbol={} <{}>",
+                entry.get_index(),
+                get_symbol_name(wasm_module, linking, entry.get_index())
+            ),
+            Some(_) => format!("type={}", entry.get_index()),
+            None => format!("index={}", entry.get_index()),
+        };
+        let addend = match entry.get_addend() {
+            Some(addend) => format!(", addend={}", addend),
+            None => String::new(),
+        };
+        println!(
+            "  {}: offset={:#010x} {} {}{}",
+            cnt,
+            entry.get_offset(),
+            reloc_type,
+            target,
+            addend
+        );
+    }
+}
+
+// シンボルの名前を返す
+// 名前を持たない未定義シンボルはインポート名、セクションシンボルはセクション名を使う
+fn get_symbol_name(
+    wasm_module: &WasmModule,
+    linking: &LinkingSectionPayload,
+    symbol_index: u32,
+) -> String {
+    let symbol = match linking.get_symbol(symbol_index) {
+        Some(symbol) => symbol,
+        None => return String::from("(not found)"),
+    };
+
+    if let Some(name) = symbol.get_name() {
+        return name.clone();
+    }
+
+    let index = symbol.get_index().unwrap_or(0);
+    if symbol.get_kind() == SymbolKind::Section {
+        return wasm_module
+            .get_section_name_by_index(index)
+            .unwrap_or_else(|| String::from("(not found)"));
+    }
+
+    let imports = match wasm_module.get_import_section() {
+        Some(sec) => sec.get_import_entries(),
+        None => Vec::new(),
+    };
+    imports
+        .into_iter()
+        .filter(|entry| {
+            matches!(
+                (symbol.get_kind(), entry.get_type()),
+                (SymbolKind::Function, TypeEntry::FuncIndex { .. })
+                    | (SymbolKind::Global, TypeEntry::GblType { .. })
+                    | (SymbolKind::Tag, TypeEntry::TagType { .. })
+                    | (SymbolKind::Table, TypeEntry::TblType { .. })
+            )
+        })
+        .nth(index as usize)
+        .map(|entry| entry.get_import_entry_name().clone())
+        .unwrap_or_else(|| String::from("(not found)"))
+}
+
+/// DWARFのコンパイル単位・関数・行番号表を表示する
+///
+/// アドレスはコードセクションのペイロード先頭からのオフセット
+pub fn print_lines(wasm_module: &WasmModule) {
+    let debug_info = match DebugInfo::parse(wasm_module) {
+        Ok(Some(debug_info)) => debug_info,
+        Ok(None) => {
+            println!("[Lines] None (no .debug_info section)");
+            return;
+        }
+        Err(err) => {
+            println!("[Lines] Error: {}", err);
+            return;
+        }
+    };
+
+    for unit in debug_info.get_units().into_iter() {
+        println!(
+            "[Compilation Unit] {} (DWARF v{})",
+            unit.get_name().map(|x| x.as_str()).unwrap_or("(unknown)"),
+            unit.get_version()
+        );
+        if let Some(producer) = unit.get_producer() {
+            println!("  producer: {}", producer);
+        }
+        if let Some(comp_dir) = unit.get_comp_dir() {
+            println!("  comp_dir: {}", comp_dir);
+        }
+
+        println!("  functions:");
+        for (cnt, func) in unit.get_functions().into_iter().enumerate() {
+            let name = match (func.get_name(), func.get_linkage_name()) {
+                (Some(name), Some(linkage_name)) => format!("{} ({})", name, linkage_name),
+                (Some(name), None) | (None, Some(name)) => name.clone(),
+                (None, None) => String::from("(anonymous)"),
+            };
+            let decl = match (func.get_decl_file(), func.get_decl_line()) {
+                (Some(file), Some(line)) => format!(" {}:{}", file, line),
+                (Some(file), None) => format!(" {}", file),
+                _ => String::new(),
+            };
+            println!(
+                "    {}: {} [{:#x}, {:#x}){}",
+                cnt,
+                name,
+                func.get_low_pc(),
+                func.get_high_pc(),
+                decl
+            );
+        }
+
+        let line_table = match unit.get_line_table() {
+            Some(line_table) => line_table,
+            None => continue,
+        };
+        println!("  lines:");
+        for row in line_table.get_rows().into_iter() {
+            if row.is_end_sequence() {
+                println!("    {:#010x} end_sequence", row.get_address());
+                continue;
+            }
+
+            let path = line_table
+                .get_file_path(row.get_file_index())
+                .unwrap_or_else(|| format!("(file {})", row.get_file_index()));
+            println!(
+                "    {:#010x} {}:{}:{}",
+                row.get_address(),
+                path,
+                row.get_line(),
+                row.get_column()
+            );
+        }
+    }
+}
+
+pub fn print_addr2line(
+    wasm_module: &WasmModule,
+    debug_info: Option<&DebugInfo>,
+    source_map: Option<&SourceMap>,
+    offsets: &[u32],
+) {
+    let name_section = wasm_module.get_name_section();
+
+    for offset in offsets.iter() {
+        let func_idx = match wasm_module.find_function_by_code_offset(*offset) {
+            Some(func_idx) => func_idx,
+            None => {
+                println!("{:#010x}: (not in any function body)", offset);
+                continue;
+            }
+        };
+
+        let frames = match debug_info {
+            Some(debug_info) => debug_info.find_frames(*offset as u64),
+            None => Vec::new(),
+        };
+
+        // 関数名はnameセクションを優先し、なければDWARFの名前を使う
+        let func_name = name_section
+            .and_then(|x| x.get_function_name(func_idx))
+            .or_else(|| frames.last().and_then(|x| x.get_linkage_name()))
+            .or_else(|| frames.last().and_then(|x| x.get_name()));
+        match func_name {
+            Some(name) => println!("{:#010x}: func[{}] <{}>", offset, func_idx, name),
+            None => println!("{:#010x}: func[{}]", offset, func_idx),
+        }
+
+        // DWARFがなければソースマップの位置を使う
+        if frames.is_empty() {
+            let mapping = source_map.and_then(|x| {
+                let mapping = x.find_mapping_by_code_offset(wasm_module, *offset)?;
+                Some((x.get_source(mapping)?, x.get_name(mapping), mapping))
+            });
+            match mapping {
+                Some((source, name, mapping)) => println!(
+                    "  {} at {}:{}:{}",
+                    name.or(func_name).map(|x| x.as_str()).unwrap_or("??"),
+                    source,
+                    mapping.get_original_line() + 1,
+                    mapping.get_original_column() + 1
+                ),
+                None => println!("  ??:0"),
+            }
+            continue;
+        }
+
+        for frame in frames.iter() {
+            let name = frame
+                .get_name()
+                .or_else(|| frame.get_linkage_name())
+                .or(func_name.filter(|_| !frame.is_inlined()))
+                .map(|x| x.as_str())
+                .unwrap_or("??");
+            let location = match (frame.get_file(), frame.get_line(), frame.get_column()) {
+                (Some(file), Some(line), Some(column)) if column != 0 => {
+                    format!("{}:{}:{}", file, line, column)
+                }
+                (Some(file), Some(line), _) => format!("{}:{}", file, line),
+                (Some(file), None, _) => format!("{}:0", file),
+                (None, _, _) => String::from("??:0"),
+            };
+            if frame.is_inlined() {
+                println!("  {} at {} (inlined)", name, location);
+            } else {
+                println!("  {} at {}", name, location);
+            }
+        }
+    }
+}
+
+// content_hashの種類 (名前, オプション)
+const CONTENT_HASH_KINDS: [(&str, ContentHashOptions); 3] = [
+    (
+        "all",
+        ContentHashOptions {
+            exclude_custom_sections: false,
+            exclude_debug_sections: false,
+        },
+    ),
+    (
+        "without debug info",
+        ContentHashOptions {
+            exclude_custom_sections: false,
+            exclude_debug_sections: true,
+        },
+    ),
+    (
+        "without custom sections",
+        ContentHashOptions {
+            exclude_custom_sections: true,
+            exclude_debug_sections: true,
+        },
+    ),
+];
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+pub fn print_module_id(wasm_module: &WasmModule) {
+    match wasm_module.get_build_id() {
+        Some(build_id) => println!("[Build ID] {}", build_id),
+        None => println!("[Build ID] None"),
+    }
+
+    println!("[Content Hash] (SHA-256)");
+    for (kind, options) in CONTENT_HASH_KINDS.iter() {
+        let hash_str = match wasm_module.content_hash(*options) {
+            Some(hash) => to_hex(&hash),
+            None => String::from("(section digests not recorded)"),
+        };
+        println!("  {:<24} {}", format!("{}:", kind), hash_str);
+    }
+}
+
+pub fn print_module_id_comparison(wasm_module: &WasmModule, other: &WasmModule, other_path: &str) {
+    // 除く範囲の狭いものから比べ、最初に一致した種類で違いを判定する
+    let matched = CONTENT_HASH_KINDS.iter().position(|(_, options)| {
+        let hash = wasm_module.content_hash(*options);
+        hash.is_some() && hash == other.content_hash(*options)
+    });
+
+    let result = match matched {
+        Some(0) => "identical",
+        Some(1) => "differ only in debug info",
+        Some(_) => "differ only in custom sections",
+        None => "differ in module contents",
+    };
+    println!("[Compare] {}: {}", other_path, result);
+}
+
+// disasmのバイト列の欄の幅 (これより長い命令ははみ出して表示する)
+const DISASM_BYTES_WIDTH: usize = 26;
+
+/// 関数本体を逆アセンブルして表示する
+///
+/// func_idxを指定した場合はその関数のみを表示する(インポートされた関数を含む絶対インデックス)
+/// 関数・グローバル変数・ローカル変数のインデックスはnameセクションの名前を併記する
+pub fn print_disasm(wasm_module: &WasmModule, func_idx: Option<u32>) {
+    let code_section = match wasm_module.get_code_section() {
+        Some(sec) => sec,
+        None => {
+            println!("[Code Section] None");
+            return;
+        }
+    };
+
+    let num_imported_functions = wasm_module.get_num_imported_functions();
+    let code_payload_offset = wasm_module.get_code_payload_offset().unwrap_or(0);
+    let name_section = wasm_module.get_name_section();
+
+    for (body_idx, func_body) in code_section.get_func_body_list().into_iter().enumerate() {
+        let idx = num_imported_functions + body_idx as u32;
+        if func_idx.is_some_and(|x| x != idx) {
+            continue;
+        }
+
+        let func_name = name_section.and_then(|x| x.get_function_name(idx));
+        let func_type = wasm_module
+            .get_function_section()
+            .and_then(|sec| sec.get_indice(body_idx))
+            .and_then(|type_idx| wasm_module.get_type_section()?.get_type(type_idx as usize));
+        println!(
+            "func[{}]{}: {}",
+            idx,
+            func_name.map(|x| format!(" <{}>", x)).unwrap_or_default(),
+            func_type.map(|x| x.to_string()).unwrap_or_default()
+        );
+
+        // ローカル変数のインデックスは引数の後ろから数える
+        let mut local_idx = func_type.map_or(0, |x| x.get_param_types().len() as u32);
+        for local_entry in func_body.get_local_entries().into_iter() {
+            let count = local_entry.get_count();
+            if count > 0 {
+                println!(
+                    "  local[{}..{}] {}",
+                    local_idx,
+                    local_idx + count - 1,
+                    local_entry.get_value_type()
+                );
+            }
+            local_idx += count;
+        }
+
+        let instrs = match func_body.get_instructions() {
+            Ok(instrs) => instrs,
+            Err(err) => {
+                println!("  Error: {}", err);
+                continue;
+            }
+        };
+
+        // 命令の位置はファイル先頭からのオフセット
+        let code_offset =
+            code_payload_offset + code_section.get_code_offset(body_idx).unwrap_or(0) as u64;
+        let code = func_body.get_code();
+        let mut depth: usize = 0;
+        for instr in instrs.iter() {
+            if instr.is_block_end() || instr.get_opcode() == 0x05 {
+                // end, delegate, elseはブロックの外側の深さで表示する
+                depth = depth.saturating_sub(1);
+            }
+
+            let start = instr.get_offset() as usize;
+            let end = start + instr.get_size() as usize;
+            let hex: Vec<String> = code[start..end]
+                .iter()
+                .map(|x| format!("{:02x}", x))
+                .collect();
+            let name = match instr.get_immediate() {
+                Immediate::Function(idx) => name_section.and_then(|x| x.get_function_name(*idx)),
+                Immediate::Global(idx) => name_section.and_then(|x| x.get_global_name(*idx)),
+                Immediate::Local(local) => name_section.and_then(|x| x.get_local_name(idx, *local)),
+                _ => None,
+            };
+            println!(
+                " {:06x}: {:<width$} | {}{}{}",
+                code_offset + start as u64,
+                hex.join(" "),
+                "  ".repeat(depth),
+                instr,
+                name.map(|x| format!(" <{}>", x)).unwrap_or_default(),
+                width = DISASM_BYTES_WIDTH
+            );
+
+            if instr.is_block_start() || instr.get_opcode() == 0x05 {
+                depth += 1;
+            }
+        }
+        // 関数本体の末尾のendはget_code()に含まれない
+        println!(
+            " {:06x}: {:<width$} | end",
+            code_offset + code.len() as u64,
+            "0b",
+            width = DISASM_BYTES_WIDTH
+        );
+        println!();
+    }
+}
+
+// dumpの1行に表示するバイト数
+const DUMP_BYTES_PER_LINE: usize = 8;
+
+/// 注釈付きの16進ダンプを表示する
+///
+/// 1行に収まらないバイト範囲は続きの行に分けて表示する(注釈は最初の行のみ)
+pub fn print_dump(wasm_module: &WasmModule, bytes: &[u8]) {
+    let lines = match dump::dump_module(wasm_module, bytes) {
+        Ok(lines) => lines,
+        Err(err) => {
+            println!("[Dump] Error: {}", err);
+            return;
+        }
+    };
+
+    for line in lines.iter() {
+        if line.is_heading() {
+            if line.get_depth() == 0 {
+                println!();
+            }
+            println!(
+                "{:08x}: {:width$} ; {}{}",
+                line.get_offset(),
+                "",
+                "  ".repeat(line.get_depth() as usize),
+                line.get_label(),
+                width = DUMP_BYTES_PER_LINE * 3 - 1
+            );
+            continue;
+        }
+
+        let start = line.get_offset() as usize;
+        let end = start + line.get_size() as usize;
+        for (cnt, chunk) in bytes[start..end].chunks(DUMP_BYTES_PER_LINE).enumerate() {
+            let hex: Vec<String> = chunk.iter().map(|x| format!("{:02x}", x)).collect();
+            let offset = start + cnt * DUMP_BYTES_PER_LINE;
+            if cnt == 0 {
+                println!(
+                    "{:08x}: {:width$} ; {}{}",
+                    offset,
+                    hex.join(" "),
+                    "  ".repeat(line.get_depth() as usize),
+                    line.get_label(),
+                    width = DUMP_BYTES_PER_LINE * 3 - 1
+                );
+            } else {
+                println!("{:08x}: {}", offset, hex.join(" "));
+            }
+        }
+    }
+}
+
+/// モジュールをJSON(schema::ModuleSchema)で出力する
+#[cfg(feature = "serde")]
+pub fn print_json(wasm_module: &WasmModule) {
+    let schema = ModuleSchema::new(wasm_module);
+    match serde_json::to_string_pretty(&schema) {
+        Ok(json) => println!("{}", json),
+        Err(err) => eprintln!(" > Error: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_feature_status_marks_mismatches() {
+        let status = target_feature_status;
+        assert_eq!(status(TargetFeaturePrefix::Used, true), "used");
+        assert_eq!(
+            status(TargetFeaturePrefix::Used, false),
+            "not used, but declared"
+        );
+        assert_eq!(
+            status(TargetFeaturePrefix::Required, false),
+            "not used, but declared"
+        );
+        assert_eq!(status(TargetFeaturePrefix::Disallowed, false), "not used");
+        assert_eq!(
+            status(TargetFeaturePrefix::Disallowed, true),
+            "used, but disallowed"
+        );
+    }
+}
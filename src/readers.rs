@@ -2,8 +2,7 @@ use std::io::{Read, Seek, SeekFrom};
 
 // Read x byte
 pub fn read_x<R: Read>(reader: &mut R, size: usize) -> Result<Vec<u8>, std::io::Error> {
-    let mut buf: Vec<u8> = Vec::new();
-    buf.resize(size, 0);
+    let mut buf: Vec<u8> = vec![0; size];
     // Stack Overflow : https://stackoverflow.com/questions/30412521/how-to-read-a-specific-number-of-bytes-from-a-stream
     // なぜかVecが渡せる
     if let Err(err) = reader.read_exact(&mut buf) {
@@ -39,13 +38,8 @@ pub fn read_32<R: Read>(reader: &mut R) -> Result<[u8; 4], std::io::Error> {
 pub fn peep_8<R: Read + Seek>(reader: &mut R) -> Result<[u8; 1], std::io::Error> {
     let mut buf: [u8; 1] = [0; 1];
 
-    if let Err(err) = reader.read_exact(&mut buf) {
-        return Err(err);
-    };
-
-    if let Err(err) = reader.seek(SeekFrom::Current(-1)) {
-        return Err(err);
-    };
+    reader.read_exact(&mut buf)?;
+    reader.seek(SeekFrom::Current(-1))?;
 
     Ok(buf)
 }
@@ -75,10 +69,13 @@ pub fn read_signed_leb128<R: Read>(
 }
 
 pub fn usage_bytes_leb128_u(value: u64) -> u8 {
+    // 7bitずつ切り出していき、残りが0になるまでのバイト数を数える
+    // (u64全域で桁あふれしないようにシフトで計算する)
     let mut ord: u8 = 1;
-    let base: i64 = 2;
-    while (value as i64) >= base.pow(7 * (ord as u32)) {
+    let mut rest = value >> 7;
+    while rest != 0 {
         ord += 1;
+        rest >>= 7;
     }
 
     ord
@@ -86,15 +83,52 @@ pub fn usage_bytes_leb128_u(value: u64) -> u8 {
 
 pub fn usage_bytes_leb128_s(value: i64) -> u8 {
     let mut ord: u8 = 1;
-    let base: i64 = 2;
 
     // unsigned intにおいて、例えば、-xのビット数とx-1のビット数は同じ (where x > 0)
     let value = if value >= 0 { value } else { -(value + 1) };
 
-    let sign_bit: u32 = 1;
-    while value >= base.pow(7 * (ord as u32) - sign_bit) {
+    // 最終バイトの符号ビット(bit6)を除いた6bitに収まるまで7bitずつシフトする
+    let mut rest = value >> 6;
+    while rest != 0 {
         ord += 1;
+        rest >>= 7;
     }
 
     ord
 }
+
+// 読み込んだバイト列を記録しながら読み進めるReader
+//
+// 命令列のように、読み終えるまで長さが分からないデータの
+// バイト列とサイズを知りたい場合に使う
+pub struct RecordingReader<'a, R: Read> {
+    reader: &'a mut R,
+    recorded: Vec<u8>,
+}
+
+impl<'a, R: Read> RecordingReader<'a, R> {
+    pub fn new(reader: &'a mut R) -> Self {
+        Self {
+            reader,
+            recorded: Vec::new(),
+        }
+    }
+
+    /// これまでに読み込んだバイト数を返す
+    pub fn get_read_size(&self) -> usize {
+        self.recorded.len()
+    }
+
+    /// これまでに読み込んだバイト列を返す
+    pub fn into_recorded(self) -> Vec<u8> {
+        self.recorded
+    }
+}
+
+impl<'a, R: Read> Read for RecordingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = self.reader.read(buf)?;
+        self.recorded.extend_from_slice(&buf[..size]);
+        Ok(size)
+    }
+}
@@ -0,0 +1,1042 @@
+use serde::Serialize;
+
+use crate::wasm_components::code::LocalEntry;
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::*;
+use crate::wasm_components::types::*;
+
+/*
+ * --format json の出力形式
+ *
+ * セクションを出現順に並べ、各セクションの位置・大きさと内容を出力する
+ * wasm_componentsの型をそのまま出力せず、この形式のための構造体に変換する
+ * (内部の表現を変えても出力が変わらないようにするため)
+ *
+ * - 列挙型は "kind" などのタグを持つオブジェクトとし、タグの値はsnake_caseにする
+ * - 値型はテキスト形式の表記(e.g. "i32", "(ref null 0)")にする
+ * - 初期化式・命令列・バイト列は16進数の文字列にする
+ *
+ * 出力の形式を互換性のない形で変えるときは SCHEMA_VERSION を上げる
+ */
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// モジュール全体
+#[derive(Debug, Serialize)]
+pub struct ModuleSchema {
+    schema_version: u32,
+    version: u32, // バイナリのバージョン
+    size: u64,    // ファイル全体の大きさ
+    sections: Vec<SectionSchema>,
+}
+
+/// セクション
+#[derive(Debug, Serialize)]
+pub struct SectionSchema {
+    index: u32,
+    id: u8,
+    name: String, // カスタムセクションの場合はその名前
+    offset: u64,  // ファイル先頭からの位置
+    size: u64,    // セクションID・サイズを含む大きさ
+    payload: SectionPayloadSchema,
+}
+
+/// セクションの内容
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SectionPayloadSchema {
+    Type { rec_groups: Vec<RecGroupSchema> },
+    Import { imports: Vec<ImportSchema> },
+    Function { type_indices: Vec<u32> },
+    Table { tables: Vec<TableSchema> },
+    Memory { memories: Vec<LimitsSchema> },
+    Global { globals: Vec<GlobalSchema> },
+    Export { exports: Vec<ExportSchema> },
+    Start { function_index: u32 },
+    Element { segments: Vec<ElementSegmentSchema> },
+    Code { bodies: Vec<FunctionBodySchema> },
+    Data { segments: Vec<DataSegmentSchema> },
+    DataCount { count: u32 },
+    Tag { tags: Vec<TagTypeSchema> },
+    Custom { content: CustomContentSchema },
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecGroupSchema {
+    explicit: bool, // recで明示されたグループかどうか
+    types: Vec<SubTypeSchema>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubTypeSchema {
+    type_index: u32,
+    is_final: bool,
+    super_types: Vec<u32>,
+    composite: CompositeTypeSchema,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompositeTypeSchema {
+    Func {
+        params: Vec<String>,
+        results: Vec<String>,
+    },
+    Struct {
+        fields: Vec<FieldTypeSchema>,
+    },
+    Array {
+        field: FieldTypeSchema,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldTypeSchema {
+    storage_type: String,
+    mutable: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSchema {
+    module: String,
+    field: String,
+    desc: ExternalTypeSchema,
+}
+
+/// インポートする要素の型
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExternalTypeSchema {
+    Function { type_index: u32 },
+    Table(TableTypeSchema),
+    Memory(LimitsSchema),
+    Global(GlobalTypeSchema),
+    Tag(TagTypeSchema),
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableTypeSchema {
+    element_type: String,
+    limits: LimitsSchema,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableSchema {
+    element_type: String,
+    limits: LimitsSchema,
+    init: Option<String>, // 初期化式(持たない場合はnull)
+}
+
+#[derive(Debug, Serialize)]
+pub struct LimitsSchema {
+    index_type: String, // "i32" or "i64"
+    initial: u64,
+    maximum: Option<u64>,
+    shared: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GlobalTypeSchema {
+    content_type: String,
+    mutable: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GlobalSchema {
+    content_type: String,
+    mutable: bool,
+    init: String, // 初期化式
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportSchema {
+    name: String,
+    kind: String, // "function", "table", "memory", "global", "tag"
+    index: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ElementSegmentSchema {
+    #[serde(flatten)]
+    mode: ElementModeSchema,
+    element_type: String,
+    items: ElementItemsSchema,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ElementModeSchema {
+    Active { table_index: u32, offset: String },
+    Passive,
+    Declarative,
+}
+
+/// elemセグメントの要素 (関数インデックスのリストか、初期化式のリスト)
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "values", rename_all = "snake_case")]
+pub enum ElementItemsSchema {
+    Functions(Vec<u32>),
+    Exprs(Vec<String>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionBodySchema {
+    function_index: u32, // インポートされた関数を含む関数インデックス空間
+    offset: u64,         // ファイル先頭からの位置(大きさのLEB128を含む)
+    size: u64,
+    locals: Vec<LocalEntrySchema>,
+    code: String, // 末尾のendを除く命令列
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocalEntrySchema {
+    count: u32,
+    value_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataSegmentSchema {
+    #[serde(flatten)]
+    mode: DataModeSchema,
+    bytes: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum DataModeSchema {
+    Active { memory_index: u32, offset: String },
+    Passive,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagTypeSchema {
+    attribute: u8,
+    type_index: u32,
+}
+
+/// カスタムセクションの内容
+///
+/// デコードできたものはその形式で、それ以外はバイト列(raw)で出力する
+#[derive(Debug, Serialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum CustomContentSchema {
+    Name(Box<NameSchema>),
+    Producers {
+        fields: Vec<ProducerFieldSchema>,
+    },
+    TargetFeatures {
+        features: Vec<TargetFeatureSchema>,
+    },
+    Linking {
+        version: u32,
+        subsections: Vec<LinkingSubsectionSchema>,
+    },
+    Reloc {
+        section_index: u32,
+        entries: Vec<RelocationSchema>,
+    },
+    Dylink {
+        subsections: Vec<DylinkSubsectionSchema>,
+    },
+    SourceMappingUrl {
+        url: String,
+    },
+    ExternalDebugInfo {
+        url: String,
+    },
+    BuildId {
+        id: String,
+    },
+    /// CustomSectionRegistryに登録したデコーダで読んだもの
+    Registered {
+        text: String,
+        bytes: String,
+    },
+    Raw {
+        bytes: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct NameSchema {
+    module: Option<String>,
+    functions: Vec<NamingSchema>,
+    locals: Vec<IndirectNamingSchema>,
+    labels: Vec<IndirectNamingSchema>,
+    types: Vec<NamingSchema>,
+    tables: Vec<NamingSchema>,
+    memories: Vec<NamingSchema>,
+    globals: Vec<NamingSchema>,
+    elems: Vec<NamingSchema>,
+    data: Vec<NamingSchema>,
+    fields: Vec<IndirectNamingSchema>,
+    tags: Vec<NamingSchema>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NamingSchema {
+    index: u32,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndirectNamingSchema {
+    index: u32,
+    names: Vec<NamingSchema>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProducerFieldSchema {
+    name: String,
+    values: Vec<VersionedNameSchema>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionedNameSchema {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetFeatureSchema {
+    prefix: String, // "+", "-", "="
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LinkingSubsectionSchema {
+    SegmentInfo { segments: Vec<SegmentInfoSchema> },
+    InitFuncs { functions: Vec<InitFuncSchema> },
+    ComdatInfo { comdats: Vec<ComdatSchema> },
+    SymbolTable { symbols: Vec<SymbolSchema> },
+    Unknown { subsection_type: u8, bytes: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SegmentInfoSchema {
+    name: String,
+    alignment: u32, // log2
+    flags: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InitFuncSchema {
+    priority: u32,
+    symbol_index: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComdatSchema {
+    name: String,
+    flags: u32,
+    symbols: Vec<ComdatSymbolSchema>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComdatSymbolSchema {
+    kind: String,
+    index: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolSchema {
+    kind: String,
+    flags: u32,
+    index: Option<u32>, // Data以外: 対象のインデックス
+    name: Option<String>,
+    data: Option<DataSymbolSchema>, // 定義されたDataのみ
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataSymbolSchema {
+    segment_index: u32,
+    offset: u32,
+    size: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelocationSchema {
+    #[serde(rename = "type")]
+    reloc_type: String, // R_WASM_*
+    offset: u32,
+    index: u32,
+    addend: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DylinkSubsectionSchema {
+    MemInfo {
+        memory_size: u32,
+        memory_alignment: u32, // log2
+        table_size: u32,
+        table_alignment: u32, // log2
+    },
+    Needed {
+        libraries: Vec<String>,
+    },
+    ExportInfo {
+        exports: Vec<DylinkExportSchema>,
+    },
+    ImportInfo {
+        imports: Vec<DylinkImportSchema>,
+    },
+    RuntimePath {
+        paths: Vec<String>,
+    },
+    Unknown {
+        subsection_type: u8,
+        bytes: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct DylinkExportSchema {
+    name: String,
+    flags: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DylinkImportSchema {
+    module: String,
+    field: String,
+    flags: u32,
+}
+
+impl ModuleSchema {
+    pub fn new(module: &WasmModule) -> Self {
+        let custom_sections = module.get_custom_sections();
+
+        let mut sections: Vec<SectionSchema> = Vec::new();
+        let mut custom_idx = 0;
+        for (idx, section) in module.get_sections().iter().enumerate() {
+            let section_id = section.get_id();
+            let payload = match section_id {
+                0 => {
+                    custom_idx += 1;
+                    custom_sections
+                        .get(custom_idx - 1)
+                        .map(|x| SectionPayloadSchema::Custom {
+                            content: CustomContentSchema::new(x),
+                        })
+                }
+                1 => module
+                    .get_type_section()
+                    .map(|x| SectionPayloadSchema::Type {
+                        rec_groups: convert_rec_groups(x),
+                    }),
+                2 => module
+                    .get_import_section()
+                    .map(|x| SectionPayloadSchema::Import {
+                        imports: x
+                            .get_import_entries()
+                            .into_iter()
+                            .map(ImportSchema::new)
+                            .collect(),
+                    }),
+                3 => module
+                    .get_function_section()
+                    .map(|x| SectionPayloadSchema::Function {
+                        type_indices: x.get_indice_list(),
+                    }),
+                4 => module
+                    .get_table_section()
+                    .map(|x| SectionPayloadSchema::Table {
+                        tables: x
+                            .get_table_list()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(cnt, table_type)| {
+                                TableSchema::new(table_type, x.get_table_init_expr(cnt))
+                            })
+                            .collect(),
+                    }),
+                5 => module
+                    .get_memory_section()
+                    .map(|x| SectionPayloadSchema::Memory {
+                        memories: x
+                            .get_memories()
+                            .into_iter()
+                            .map(|x| LimitsSchema::new(x.get_limits(), x.get_index_type()))
+                            .collect(),
+                    }),
+                6 => module
+                    .get_global_section()
+                    .map(|x| SectionPayloadSchema::Global {
+                        globals: x
+                            .get_global_variable_list()
+                            .into_iter()
+                            .map(GlobalSchema::new)
+                            .collect(),
+                    }),
+                7 => module
+                    .get_export_section()
+                    .map(|x| SectionPayloadSchema::Export {
+                        exports: x
+                            .get_export_entry_list()
+                            .into_iter()
+                            .map(ExportSchema::new)
+                            .collect(),
+                    }),
+                8 => module
+                    .get_start_section()
+                    .map(|x| SectionPayloadSchema::Start {
+                        function_index: x.get_start_func_index(),
+                    }),
+                9 => module
+                    .get_element_section()
+                    .map(|x| SectionPayloadSchema::Element {
+                        segments: x
+                            .get_element_list()
+                            .into_iter()
+                            .map(ElementSegmentSchema::new)
+                            .collect(),
+                    }),
+                10 => module
+                    .get_code_section()
+                    .map(|x| SectionPayloadSchema::Code {
+                        bodies: convert_function_bodies(
+                            x,
+                            module.get_num_imported_functions(),
+                            section.get_payload_offset(),
+                        ),
+                    }),
+                11 => module
+                    .get_data_section()
+                    .map(|x| SectionPayloadSchema::Data {
+                        segments: x
+                            .get_data_segment_list()
+                            .into_iter()
+                            .map(DataSegmentSchema::new)
+                            .collect(),
+                    }),
+                12 => module
+                    .get_data_count_section()
+                    .map(|x| SectionPayloadSchema::DataCount {
+                        count: x.get_data_count(),
+                    }),
+                13 => module.get_tag_section().map(|x| SectionPayloadSchema::Tag {
+                    tags: x
+                        .get_tag_list()
+                        .into_iter()
+                        .map(TagTypeSchema::new)
+                        .collect(),
+                }),
+                _ => None,
+            }
+            .unwrap_or(SectionPayloadSchema::Unknown);
+
+            sections.push(SectionSchema {
+                index: idx as u32,
+                id: section_id,
+                name: module
+                    .get_section_name_by_index(idx as u32)
+                    .unwrap_or_default(),
+                offset: section.get_offset(),
+                size: section.get_size(),
+                payload,
+            });
+        }
+
+        // magic・versionの8byteの後ろにセクションが並ぶ
+        let size = match sections.last() {
+            Some(section) => section.offset + section.size,
+            None => 8,
+        };
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            version: module.get_magic_and_version().get_version(),
+            size,
+            sections,
+        }
+    }
+}
+
+fn convert_rec_groups(type_section: &TypeSection) -> Vec<RecGroupSchema> {
+    let mut type_index: u32 = 0;
+    let mut rec_groups: Vec<RecGroupSchema> = Vec::new();
+    for rec_group in type_section.get_rec_groups().into_iter() {
+        let mut types: Vec<SubTypeSchema> = Vec::new();
+        for sub_type in rec_group.get_sub_types().into_iter() {
+            types.push(SubTypeSchema {
+                type_index,
+                is_final: sub_type.is_final(),
+                super_types: sub_type.get_super_types().to_vec(),
+                composite: CompositeTypeSchema::new(sub_type.get_composite_type()),
+            });
+            type_index += 1;
+        }
+        rec_groups.push(RecGroupSchema {
+            explicit: rec_group.is_explicit(),
+            types,
+        });
+    }
+    rec_groups
+}
+
+impl CompositeTypeSchema {
+    fn new(composite_type: &CompositeType) -> Self {
+        match composite_type {
+            CompositeType::Func(func_type) => CompositeTypeSchema::Func {
+                params: func_type
+                    .get_param_types()
+                    .into_iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+                results: func_type
+                    .get_return_types()
+                    .into_iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+            },
+            CompositeType::Struct(struct_type) => CompositeTypeSchema::Struct {
+                fields: struct_type
+                    .get_fields()
+                    .into_iter()
+                    .map(FieldTypeSchema::new)
+                    .collect(),
+            },
+            CompositeType::Array(array_type) => CompositeTypeSchema::Array {
+                field: FieldTypeSchema::new(array_type.get_field()),
+            },
+        }
+    }
+}
+
+impl FieldTypeSchema {
+    fn new(field_type: &FieldType) -> Self {
+        Self {
+            storage_type: field_type.get_storage_type().to_string(),
+            mutable: field_type.get_mutability(),
+        }
+    }
+}
+
+impl ImportSchema {
+    fn new(entry: &ImportEntry) -> Self {
+        let desc = match entry.get_type() {
+            TypeEntry::FuncIndex { type_ } => ExternalTypeSchema::Function { type_index: *type_ },
+            TypeEntry::TblType { type_ } => ExternalTypeSchema::Table(TableTypeSchema::new(type_)),
+            TypeEntry::MemType { type_ } => ExternalTypeSchema::Memory(LimitsSchema::new(
+                type_.get_limits(),
+                type_.get_index_type(),
+            )),
+            TypeEntry::GblType { type_ } => ExternalTypeSchema::Global(GlobalTypeSchema {
+                content_type: type_.get_type().to_string(),
+                mutable: type_.get_mutability(),
+            }),
+            TypeEntry::TagType { type_ } => ExternalTypeSchema::Tag(TagTypeSchema::new(type_)),
+        };
+
+        Self {
+            module: entry.get_import_module_name().clone(),
+            field: entry.get_import_entry_name().clone(),
+            desc,
+        }
+    }
+}
+
+impl TableTypeSchema {
+    fn new(table_type: &TableType) -> Self {
+        Self {
+            element_type: table_type.get_elem_type().to_string(),
+            limits: LimitsSchema::new(table_type.get_limits(), table_type.get_index_type()),
+        }
+    }
+}
+
+impl TableSchema {
+    fn new(table_type: &TableType, init_expr: Option<&InitExpr>) -> Self {
+        Self {
+            element_type: table_type.get_elem_type().to_string(),
+            limits: LimitsSchema::new(table_type.get_limits(), table_type.get_index_type()),
+            init: init_expr.map(|x| to_hex(x.get_instrs())),
+        }
+    }
+}
+
+impl LimitsSchema {
+    fn new(limits: &ResizableLimits, index_type: IndexType) -> Self {
+        Self {
+            index_type: index_type.to_lang_type().to_string(),
+            initial: limits.get_initial_length(),
+            maximum: limits.get_maximum_length(),
+            shared: limits.is_shared(),
+        }
+    }
+}
+
+impl GlobalSchema {
+    fn new(global_var: &GlobalVariable) -> Self {
+        let global_type = global_var.get_global_type();
+        Self {
+            content_type: global_type.get_type().to_string(),
+            mutable: global_type.get_mutability(),
+            init: to_hex(global_var.get_init_expr().get_instrs()),
+        }
+    }
+}
+
+impl ExportSchema {
+    fn new(entry: &ExportEntry) -> Self {
+        Self {
+            name: entry.get_entry_name().clone(),
+            kind: entry.get_kind().to_string().to_lowercase(),
+            index: entry.get_index(),
+        }
+    }
+}
+
+impl ElementSegmentSchema {
+    fn new(segment: &ElementSegment) -> Self {
+        let mode = if segment.is_declarative() {
+            ElementModeSchema::Declarative
+        } else if segment.is_passive() {
+            ElementModeSchema::Passive
+        } else {
+            ElementModeSchema::Active {
+                table_index: segment.get_table_index().unwrap_or(0),
+                offset: match segment.get_offset_expr() {
+                    Some(expr) => to_hex(expr.get_instrs()),
+                    None => String::new(),
+                },
+            }
+        };
+
+        let exprs = segment.get_element_exprs();
+        let items = if exprs.is_empty() && segment.get_elem_type() == LangTypes::ANYFUNC {
+            ElementItemsSchema::Functions(segment.get_elements())
+        } else {
+            ElementItemsSchema::Exprs(exprs.into_iter().map(|x| to_hex(x.get_instrs())).collect())
+        };
+
+        Self {
+            mode,
+            element_type: segment.get_elem_type().to_string(),
+            items,
+        }
+    }
+}
+
+fn convert_function_bodies(
+    code_section: &CodeSection,
+    num_imported_functions: u32,
+    payload_offset: u64,
+) -> Vec<FunctionBodySchema> {
+    let mut bodies: Vec<FunctionBodySchema> = Vec::new();
+    for (idx, body) in code_section.get_func_body_list().into_iter().enumerate() {
+        let (start, end) = code_section.get_func_body_range(idx).unwrap_or((0, 0));
+        bodies.push(FunctionBodySchema {
+            function_index: num_imported_functions + idx as u32,
+            offset: payload_offset + start as u64,
+            size: (end - start) as u64,
+            locals: body
+                .get_local_entries()
+                .into_iter()
+                .map(LocalEntrySchema::new)
+                .collect(),
+            code: to_hex(body.get_code()),
+        });
+    }
+    bodies
+}
+
+impl LocalEntrySchema {
+    fn new(entry: &LocalEntry) -> Self {
+        Self {
+            count: entry.get_count(),
+            value_type: entry.get_value_type().to_string(),
+        }
+    }
+}
+
+impl DataSegmentSchema {
+    fn new(segment: &DataSegment) -> Self {
+        let mode = if segment.is_passive() {
+            DataModeSchema::Passive
+        } else {
+            DataModeSchema::Active {
+                memory_index: segment.get_memory_index().unwrap_or(0),
+                offset: match segment.get_offset_expr() {
+                    Some(expr) => to_hex(expr.get_instrs()),
+                    None => String::new(),
+                },
+            }
+        };
+
+        Self {
+            mode,
+            bytes: to_hex(&segment.get_data()),
+        }
+    }
+}
+
+impl TagTypeSchema {
+    fn new(tag_type: &TagType) -> Self {
+        Self {
+            attribute: tag_type.get_attribute(),
+            type_index: tag_type.get_type_index(),
+        }
+    }
+}
+
+impl CustomContentSchema {
+    fn new(custom_section: &CustomSection) -> Self {
+        match custom_section.get_payload() {
+            CustomSectionPayload::Name { payload } => {
+                CustomContentSchema::Name(Box::new(NameSchema::new(payload)))
+            }
+            CustomSectionPayload::Producers { payload } => CustomContentSchema::Producers {
+                fields: payload
+                    .get_fields()
+                    .into_iter()
+                    .map(|field| ProducerFieldSchema {
+                        name: field.get_field_name().clone(),
+                        values: field
+                            .get_values()
+                            .into_iter()
+                            .map(|x| VersionedNameSchema {
+                                name: x.get_name().clone(),
+                                version: x.get_version().clone(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            },
+            CustomSectionPayload::TargetFeatures { payload } => {
+                CustomContentSchema::TargetFeatures {
+                    features: payload
+                        .get_features()
+                        .into_iter()
+                        .map(|x| TargetFeatureSchema {
+                            prefix: x.get_prefix().get_char().to_string(),
+                            name: x.get_name().clone(),
+                        })
+                        .collect(),
+                }
+            }
+            CustomSectionPayload::Linking { payload } => CustomContentSchema::Linking {
+                version: payload.get_version(),
+                subsections: payload
+                    .get_subsections()
+                    .into_iter()
+                    .map(LinkingSubsectionSchema::new)
+                    .collect(),
+            },
+            CustomSectionPayload::Reloc { payload } => CustomContentSchema::Reloc {
+                section_index: payload.get_section_index(),
+                entries: payload
+                    .get_entries()
+                    .into_iter()
+                    .map(|x| RelocationSchema {
+                        reloc_type: x.get_type().get_name().to_string(),
+                        offset: x.get_offset(),
+                        index: x.get_index(),
+                        addend: x.get_addend(),
+                    })
+                    .collect(),
+            },
+            CustomSectionPayload::Dylink { payload } => CustomContentSchema::Dylink {
+                subsections: payload
+                    .get_subsections()
+                    .into_iter()
+                    .map(DylinkSubsectionSchema::new)
+                    .collect(),
+            },
+            CustomSectionPayload::SourceMappingUrl { payload } => {
+                CustomContentSchema::SourceMappingUrl {
+                    url: payload.get_url().clone(),
+                }
+            }
+            CustomSectionPayload::ExternalDebugInfo { payload } => {
+                CustomContentSchema::ExternalDebugInfo {
+                    url: payload.get_url().clone(),
+                }
+            }
+            CustomSectionPayload::BuildId { payload } => CustomContentSchema::BuildId {
+                id: to_hex(payload.get_id()),
+            },
+            CustomSectionPayload::Registered { payload, bytes } => {
+                CustomContentSchema::Registered {
+                    text: payload.to_string(),
+                    bytes: to_hex(bytes),
+                }
+            }
+            CustomSectionPayload::General { payload } => CustomContentSchema::Raw {
+                bytes: to_hex(payload),
+            },
+        }
+    }
+}
+
+impl NameSchema {
+    fn new(payload: &NameSectionPayload) -> Self {
+        Self {
+            module: payload.get_module_name().map(|x| x.get_name().clone()),
+            functions: convert_name_map(payload.get_function_names()),
+            locals: convert_indirect_name_map(payload.get_local_names()),
+            labels: convert_indirect_name_map(payload.get_label_names()),
+            types: convert_name_map(payload.get_type_names()),
+            tables: convert_name_map(payload.get_table_names()),
+            memories: convert_name_map(payload.get_memory_names()),
+            globals: convert_name_map(payload.get_global_names()),
+            elems: convert_name_map(payload.get_elem_names()),
+            data: convert_name_map(payload.get_data_names()),
+            fields: convert_indirect_name_map(payload.get_field_names()),
+            tags: convert_name_map(payload.get_tag_names()),
+        }
+    }
+}
+
+fn convert_naming_list(name_map: &NameMap) -> Vec<NamingSchema> {
+    name_map
+        .get_name_list()
+        .into_iter()
+        .map(|x| NamingSchema {
+            index: x.get_indice(),
+            name: x.get_name_str().clone(),
+        })
+        .collect()
+}
+
+fn convert_name_map(subsection: Option<&NameMapSubsection>) -> Vec<NamingSchema> {
+    match subsection {
+        Some(subsection) => convert_naming_list(subsection.get_name_map()),
+        None => Vec::new(),
+    }
+}
+
+fn convert_indirect_name_map(
+    subsection: Option<&IndirectNameMapSubsection>,
+) -> Vec<IndirectNamingSchema> {
+    match subsection {
+        Some(subsection) => subsection
+            .get_entries()
+            .into_iter()
+            .map(|x| IndirectNamingSchema {
+                index: x.get_indice(),
+                names: convert_naming_list(x.get_name_map()),
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+impl LinkingSubsectionSchema {
+    fn new(subsection: &LinkingSubsection) -> Self {
+        match subsection.get_payload() {
+            LinkingSubsectionPayload::SegmentInfo(segments) => {
+                LinkingSubsectionSchema::SegmentInfo {
+                    segments: segments
+                        .iter()
+                        .map(|x| SegmentInfoSchema {
+                            name: x.get_name().clone(),
+                            alignment: x.get_alignment(),
+                            flags: x.get_flags(),
+                        })
+                        .collect(),
+                }
+            }
+            LinkingSubsectionPayload::InitFuncs(functions) => LinkingSubsectionSchema::InitFuncs {
+                functions: functions
+                    .iter()
+                    .map(|x| InitFuncSchema {
+                        priority: x.get_priority(),
+                        symbol_index: x.get_symbol_index(),
+                    })
+                    .collect(),
+            },
+            LinkingSubsectionPayload::ComdatInfo(comdats) => LinkingSubsectionSchema::ComdatInfo {
+                comdats: comdats
+                    .iter()
+                    .map(|x| ComdatSchema {
+                        name: x.get_name().clone(),
+                        flags: x.get_flags(),
+                        symbols: x
+                            .get_symbols()
+                            .into_iter()
+                            .map(|x| ComdatSymbolSchema {
+                                kind: x.get_kind().to_string().to_lowercase(),
+                                index: x.get_index(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            },
+            LinkingSubsectionPayload::SymbolTable(symbols) => {
+                LinkingSubsectionSchema::SymbolTable {
+                    symbols: symbols
+                        .iter()
+                        .map(|x| SymbolSchema {
+                            kind: x.get_kind().to_string().to_lowercase(),
+                            flags: x.get_flags(),
+                            index: x.get_index(),
+                            name: x.get_name().cloned(),
+                            data: x.get_data().map(|x| DataSymbolSchema {
+                                segment_index: x.get_segment_index(),
+                                offset: x.get_offset(),
+                                size: x.get_size(),
+                            }),
+                        })
+                        .collect(),
+                }
+            }
+            LinkingSubsectionPayload::Unknown(bytes) => LinkingSubsectionSchema::Unknown {
+                subsection_type: subsection.get_subsection_type(),
+                bytes: to_hex(bytes),
+            },
+        }
+    }
+}
+
+impl DylinkSubsectionSchema {
+    fn new(subsection: &DylinkSubsection) -> Self {
+        match subsection {
+            DylinkSubsection::MemInfo(mem_info) => DylinkSubsectionSchema::MemInfo {
+                memory_size: mem_info.get_memory_size(),
+                memory_alignment: mem_info.get_memory_alignment(),
+                table_size: mem_info.get_table_size(),
+                table_alignment: mem_info.get_table_alignment(),
+            },
+            DylinkSubsection::Needed(libraries) => DylinkSubsectionSchema::Needed {
+                libraries: libraries.clone(),
+            },
+            DylinkSubsection::ExportInfo(exports) => DylinkSubsectionSchema::ExportInfo {
+                exports: exports
+                    .iter()
+                    .map(|x| DylinkExportSchema {
+                        name: x.get_name().clone(),
+                        flags: x.get_flags(),
+                    })
+                    .collect(),
+            },
+            DylinkSubsection::ImportInfo(imports) => DylinkSubsectionSchema::ImportInfo {
+                imports: imports
+                    .iter()
+                    .map(|x| DylinkImportSchema {
+                        module: x.get_module_name().clone(),
+                        field: x.get_field_name().clone(),
+                        flags: x.get_flags(),
+                    })
+                    .collect(),
+            },
+            DylinkSubsection::RuntimePath(paths) => DylinkSubsectionSchema::RuntimePath {
+                paths: paths.clone(),
+            },
+            DylinkSubsection::Unknown {
+                subsection_type,
+                data,
+            } => DylinkSubsectionSchema::Unknown {
+                subsection_type: *subsection_type,
+                bytes: to_hex(data),
+            },
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
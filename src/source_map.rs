@@ -0,0 +1,338 @@
+mod json;
+
+use self::json::JsonValue;
+
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::ParseError;
+
+/*
+ * Source Map (v3)
+ *
+ * sourceMappingURLセクションが参照するJSONファイルを読み、
+ * 生成されたコードの位置を元のソースの位置に対応付ける
+ *
+ * WebAssemblyのソースマップでは、生成側の位置は
+ * 1行目の列(= wasmファイルの先頭からのバイトオフセット)で表す
+ */
+
+/// ソースマップ
+#[derive(Debug)]
+pub struct SourceMap {
+    file: Option<String>,
+    sources: Vec<String>, // sourceRootを付けたパス
+    names: Vec<String>,
+    mappings: Vec<SourceMapping>, // 生成側の位置の順
+}
+
+/// 生成側の位置から元のソースの位置への対応(行・列は0始まり)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMapping {
+    generated_line: u32,
+    generated_column: u32,
+    source: Option<u32>, // sourcesのインデックス(対応する位置がない場合はNone)
+    original_line: u32,
+    original_column: u32,
+    name: Option<u32>, // namesのインデックス
+}
+
+impl SourceMap {
+    /// ソースマップのJSONを読む
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let root = JsonValue::parse(text)?;
+
+        match root.get("version").and_then(|x| x.as_u64()) {
+            Some(3) => (),
+            Some(version) => {
+                return Err(ParseError::FormatError(format!(
+                    "source map: unsupported version: {}",
+                    version
+                )))
+            }
+            None => {
+                return Err(ParseError::FormatError(String::from(
+                    "source map: version not found",
+                )))
+            }
+        }
+        if root.get("sections").is_some() {
+            return Err(ParseError::FormatError(String::from(
+                "source map: index map (sections) is not supported",
+            )));
+        }
+
+        let source_root = root
+            .get("sourceRoot")
+            .and_then(|x| x.as_str())
+            .unwrap_or("");
+        let sources: Vec<String> = read_string_array(&root, "sources")?
+            .into_iter()
+            .map(|source| {
+                if source_root.is_empty() || source_root.ends_with('/') {
+                    format!("{}{}", source_root, source)
+                } else {
+                    format!("{}/{}", source_root, source)
+                }
+            })
+            .collect();
+        let names = read_string_array(&root, "names")?;
+
+        let mappings = match root.get("mappings").and_then(|x| x.as_str()) {
+            Some(mappings) => decode_mappings(mappings, sources.len(), names.len())?,
+            None => {
+                return Err(ParseError::FormatError(String::from(
+                    "source map: mappings not found",
+                )))
+            }
+        };
+
+        Ok(Self {
+            file: root.get("file").and_then(|x| x.as_str()).map(String::from),
+            sources,
+            names,
+            mappings,
+        })
+    }
+
+    pub fn get_file(&self) -> Option<&String> {
+        self.file.as_ref()
+    }
+
+    pub fn get_sources(&self) -> Vec<&String> {
+        self.sources.iter().collect()
+    }
+
+    pub fn get_names(&self) -> Vec<&String> {
+        self.names.iter().collect()
+    }
+
+    pub fn get_mappings(&self) -> Vec<&SourceMapping> {
+        self.mappings.iter().collect()
+    }
+
+    /// マッピングの元のソースのパスを返す
+    pub fn get_source(&self, mapping: &SourceMapping) -> Option<&String> {
+        self.sources.get(mapping.source? as usize)
+    }
+
+    /// マッピングの元の名前を返す
+    pub fn get_name(&self, mapping: &SourceMapping) -> Option<&String> {
+        self.names.get(mapping.name? as usize)
+    }
+
+    /// 生成側の位置(0始まり)を含むマッピングを返す
+    ///
+    /// 同じ行で、その列以前に始まる最後のマッピング
+    pub fn find_mapping(&self, line: u32, column: u32) -> Option<&SourceMapping> {
+        let idx = self
+            .mappings
+            .partition_point(|x| (x.generated_line, x.generated_column) <= (line, column))
+            .checked_sub(1)?;
+        let mapping = &self.mappings[idx];
+
+        if mapping.generated_line == line {
+            Some(mapping)
+        } else {
+            None
+        }
+    }
+
+    /// wasmファイルの先頭からのオフセットに対応するマッピングを返す
+    pub fn find_mapping_by_offset(&self, offset: u64) -> Option<&SourceMapping> {
+        self.find_mapping(0, u32::try_from(offset).ok()?)
+    }
+
+    /// コードセクションのペイロード先頭からのオフセット(DWARFのアドレスと同じ基準)に
+    /// 対応するマッピングを返す
+    pub fn find_mapping_by_code_offset(
+        &self,
+        module: &WasmModule,
+        code_offset: u32,
+    ) -> Option<&SourceMapping> {
+        let code_payload_offset = module.get_code_payload_offset()?;
+        self.find_mapping_by_offset(code_payload_offset + code_offset as u64)
+    }
+}
+
+impl SourceMapping {
+    pub fn get_generated_line(&self) -> u32 {
+        self.generated_line
+    }
+
+    pub fn get_generated_column(&self) -> u32 {
+        self.generated_column
+    }
+
+    pub fn get_source_index(&self) -> Option<u32> {
+        self.source
+    }
+
+    pub fn get_original_line(&self) -> u32 {
+        self.original_line
+    }
+
+    pub fn get_original_column(&self) -> u32 {
+        self.original_column
+    }
+
+    pub fn get_name_index(&self) -> Option<u32> {
+        self.name
+    }
+}
+
+// 文字列の配列(nullの要素は空文字列)を読む
+fn read_string_array(root: &JsonValue, key: &str) -> Result<Vec<String>, ParseError> {
+    let values = match root.get(key) {
+        Some(value) => match value.as_array() {
+            Some(values) => values,
+            None => {
+                return Err(ParseError::FormatError(format!(
+                    "source map: {} is not an array",
+                    key
+                )))
+            }
+        },
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(values
+        .iter()
+        .map(|x| String::from(x.as_str().unwrap_or("")))
+        .collect())
+}
+
+// Base64 VLQの1文字を値に変換する
+fn decode_base64(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+// セグメントのフィールド(Base64 VLQの列)を読む
+fn decode_vlq_fields(segment: &str) -> Result<Vec<i64>, ParseError> {
+    let mut fields: Vec<i64> = Vec::new();
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    for c in segment.bytes() {
+        let digit = match decode_base64(c) {
+            Some(digit) => digit as u64,
+            None => {
+                return Err(ParseError::FormatError(format!(
+                    "source map: invalid base64 character: {:?}",
+                    c as char
+                )))
+            }
+        };
+        if shift > 60 {
+            return Err(ParseError::FormatError(String::from(
+                "source map: VLQ value too large",
+            )));
+        }
+
+        // 下位5ビットが値、6ビット目が継続ビット
+        value |= (digit & 0x1f) << shift;
+        shift += 5;
+        if digit & 0x20 == 0 {
+            // 最下位ビットが符号
+            let magnitude = (value >> 1) as i64;
+            fields.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        }
+    }
+    if shift != 0 {
+        return Err(ParseError::FormatError(String::from(
+            "source map: unterminated VLQ value",
+        )));
+    }
+
+    Ok(fields)
+}
+
+// mappingsを読む
+//
+// 行は';'、セグメントは','で区切られ、各フィールドは直前のセグメントからの差分で表す
+// (生成側の列のみ行ごとにリセットされる)
+fn decode_mappings(
+    mappings: &str,
+    num_sources: usize,
+    num_names: usize,
+) -> Result<Vec<SourceMapping>, ParseError> {
+    let mut result: Vec<SourceMapping> = Vec::new();
+    let mut source: i64 = 0;
+    let mut original_line: i64 = 0;
+    let mut original_column: i64 = 0;
+    let mut name: i64 = 0;
+
+    for (generated_line, line) in mappings.split(';').enumerate() {
+        let mut generated_column: i64 = 0;
+        for segment in line.split(',').filter(|x| !x.is_empty()) {
+            let fields = decode_vlq_fields(segment)?;
+            if !matches!(fields.len(), 1 | 4 | 5) {
+                return Err(ParseError::FormatError(format!(
+                    "source map: invalid segment: {}",
+                    segment
+                )));
+            }
+
+            generated_column += fields[0];
+            let mut mapping = SourceMapping {
+                generated_line: generated_line as u32,
+                generated_column: to_u32(generated_column)?,
+                source: None,
+                original_line: 0,
+                original_column: 0,
+                name: None,
+            };
+            if fields.len() >= 4 {
+                source += fields[1];
+                original_line += fields[2];
+                original_column += fields[3];
+                if to_u32(source)? as usize >= num_sources {
+                    return Err(ParseError::FormatError(format!(
+                        "source map: source index out of range: {}",
+                        source
+                    )));
+                }
+                mapping.source = Some(to_u32(source)?);
+                mapping.original_line = to_u32(original_line)?;
+                mapping.original_column = to_u32(original_column)?;
+            }
+            if fields.len() == 5 {
+                name += fields[4];
+                if to_u32(name)? as usize >= num_names {
+                    return Err(ParseError::FormatError(format!(
+                        "source map: name index out of range: {}",
+                        name
+                    )));
+                }
+                mapping.name = Some(to_u32(name)?);
+            }
+
+            result.push(mapping);
+        }
+    }
+
+    // 通常は生成側の位置の順に並んでいるが、検索のために並べ直す
+    result.sort_by_key(|x| (x.generated_line, x.generated_column));
+
+    Ok(result)
+}
+
+fn to_u32(value: i64) -> Result<u32, ParseError> {
+    match u32::try_from(value) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(ParseError::FormatError(format!(
+            "source map: position out of range: {}",
+            value
+        ))),
+    }
+}
@@ -0,0 +1,760 @@
+use crate::wasm_components::code::Expr;
+use crate::wasm_components::instruction::{Immediate, Instruction, PREFIX_GC};
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{ParseError, TypeEntry};
+use crate::wasm_components::types::ExternalKind;
+use crate::wasm_components::types::{
+    CompositeType, FuncType, LangTypes, MemoryType, TableType, TagType,
+};
+
+/*
+ * Validator
+ *
+ * パース済みのモジュールに対して、バイナリ形式としては正しいが
+ * 意味的に不正な箇所がないかを検査する
+ *
+ * メモリインデックス空間は、インポートされたメモリ、定義されたメモリの順に並ぶ
+ * (multi-memory提案により、どちらも複数存在しうる)
+ * テーブルインデックス空間も同様に、インポートされたテーブル、定義されたテーブルの順に並ぶ
+ */
+
+/// モジュールを検証する
+///
+/// 最初に見つかった不正箇所をValidationErrorとして返す
+pub fn validate(module: &WasmModule) -> Result<(), ParseError> {
+    let memories = collect_memories(module);
+    let tables = collect_tables(module);
+    let global_types = collect_global_types(module);
+
+    validate_types(module)?;
+    validate_type_indices(module)?;
+    validate_exports(module, &tables, &memories, &global_types)?;
+    validate_memories(&memories)?;
+    validate_code(module, &memories)?;
+    validate_const_exprs(module)?;
+    validate_element_segments(module, &tables, &global_types)?;
+    validate_data_segments(module, &memories, &global_types)?;
+    validate_tags(module)?;
+    validate_tail_calls(module)?;
+
+    Ok(())
+}
+
+// インポートされたメモリ、定義されたメモリの順に並べる(メモリインデックス空間)
+fn collect_memories(module: &WasmModule) -> Vec<&MemoryType> {
+    let mut memories: Vec<&MemoryType> = Vec::new();
+
+    if let Some(import_section) = module.get_import_section() {
+        for entry in import_section.get_import_entries().into_iter() {
+            if let TypeEntry::MemType { type_ } = entry.get_type() {
+                memories.push(type_);
+            }
+        }
+    }
+
+    if let Some(memory_section) = module.get_memory_section() {
+        memories.extend(memory_section.get_memories());
+    }
+
+    memories
+}
+
+// インポートされたテーブル、定義されたテーブルの順に並べる(テーブルインデックス空間)
+fn collect_tables(module: &WasmModule) -> Vec<&TableType> {
+    let mut tables: Vec<&TableType> = Vec::new();
+
+    if let Some(import_section) = module.get_import_section() {
+        for entry in import_section.get_import_entries().into_iter() {
+            if let TypeEntry::TblType { type_ } = entry.get_type() {
+                tables.push(type_);
+            }
+        }
+    }
+
+    if let Some(table_section) = module.get_table_section() {
+        tables.extend(table_section.get_table_list());
+    }
+
+    tables
+}
+
+// インポートされたタグ、定義されたタグの順に並べる(タグインデックス空間)
+fn collect_tags(module: &WasmModule) -> Vec<&TagType> {
+    let mut tags: Vec<&TagType> = Vec::new();
+
+    if let Some(import_section) = module.get_import_section() {
+        for entry in import_section.get_import_entries().into_iter() {
+            if let TypeEntry::TagType { type_ } = entry.get_type() {
+                tags.push(type_);
+            }
+        }
+    }
+
+    if let Some(tag_section) = module.get_tag_section() {
+        tags.extend(tag_section.get_tag_list());
+    }
+
+    tags
+}
+
+// インポートされたグローバル変数、定義されたグローバル変数の順に型を並べる
+fn collect_global_types(module: &WasmModule) -> Vec<LangTypes> {
+    let mut global_types: Vec<LangTypes> = Vec::new();
+
+    if let Some(import_section) = module.get_import_section() {
+        for entry in import_section.get_import_entries().into_iter() {
+            if let TypeEntry::GblType { type_ } = entry.get_type() {
+                global_types.push(*type_.get_type());
+            }
+        }
+    }
+
+    if let Some(global_section) = module.get_global_section() {
+        for global_var in global_section.get_global_variable_list().into_iter() {
+            global_types.push(*global_var.get_global_type().get_type());
+        }
+    }
+
+    global_types
+}
+
+// 型セクションのサブタイプ宣言を検査する (GC提案)
+//
+// - supertypeは自身より前に定義された型であること
+// - supertypeがfinalでないこと
+// - supertypeと複合型の種類(func, struct, array)が一致すること
+fn validate_types(module: &WasmModule) -> Result<(), ParseError> {
+    let type_section = match module.get_type_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    let sub_types = type_section.get_sub_type_list();
+    for (type_idx, sub_type) in sub_types.iter().enumerate() {
+        for super_idx in sub_type.get_super_types().iter() {
+            let super_type = match sub_types.get(*super_idx as usize) {
+                Some(ty) if (*super_idx as usize) < type_idx => ty,
+                _ => {
+                    return Err(ParseError::ValidationError(format!(
+                        "type[{}]: supertype {} must be defined before the type",
+                        type_idx, super_idx
+                    )))
+                }
+            };
+
+            if super_type.is_final() {
+                return Err(ParseError::ValidationError(format!(
+                    "type[{}]: supertype {} is final",
+                    type_idx, super_idx
+                )));
+            }
+
+            let kind = sub_type.get_composite_type();
+            let super_kind = super_type.get_composite_type();
+            let matches_kind = matches!(
+                (kind, super_kind),
+                (CompositeType::Func(_), CompositeType::Func(_))
+                    | (CompositeType::Struct(_), CompositeType::Struct(_))
+                    | (CompositeType::Array(_), CompositeType::Array(_))
+            );
+            if !matches_kind {
+                return Err(ParseError::ValidationError(format!(
+                    "type[{}]: {} type cannot be a subtype of {} type {}",
+                    type_idx,
+                    kind.get_kind_name(),
+                    super_kind.get_kind_name(),
+                    super_idx
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 関数の型インデックスを検査する
+//
+// - インポートされた関数・定義された関数の型インデックスが型セクションの範囲内であること
+// - その型が関数型であること
+fn validate_type_indices(module: &WasmModule) -> Result<(), ParseError> {
+    let type_section = module.get_type_section();
+    let num_types = type_section.map_or(0, |sec| sec.get_num_types());
+    let check_type = |name: String, type_idx: u32| -> Result<(), ParseError> {
+        if type_idx >= num_types {
+            return Err(ParseError::ValidationError(format!(
+                "{}: type {} out of range (number of types: {})",
+                name, type_idx, num_types
+            )));
+        }
+        if type_section
+            .and_then(|sec| sec.get_type(type_idx as usize))
+            .is_none()
+        {
+            return Err(ParseError::ValidationError(format!(
+                "{}: type {} is not a function type",
+                name, type_idx
+            )));
+        }
+        Ok(())
+    };
+
+    if let Some(import_section) = module.get_import_section() {
+        for (cnt, entry) in import_section.get_import_entries().into_iter().enumerate() {
+            if let TypeEntry::FuncIndex { type_ } = entry.get_type() {
+                check_type(format!("import[{}]", cnt), *type_)?;
+            }
+        }
+    }
+
+    if let Some(function_section) = module.get_function_section() {
+        let num_imported_functions = module.get_num_imported_functions();
+        for (cnt, type_idx) in function_section.get_indice_list().into_iter().enumerate() {
+            check_type(
+                format!("func[{}]", num_imported_functions + cnt as u32),
+                type_idx,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// エクスポートするインデックスが、種類ごとのインデックス空間の範囲内であるかを検査する
+fn validate_exports(
+    module: &WasmModule,
+    tables: &[&TableType],
+    memories: &[&MemoryType],
+    global_types: &[LangTypes],
+) -> Result<(), ParseError> {
+    let export_section = match module.get_export_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    let num_functions = module.get_num_imported_functions()
+        + module
+            .get_function_section()
+            .map_or(0, |sec| sec.get_num_functions());
+    let num_tags = collect_tags(module).len() as u32;
+
+    for (cnt, entry) in export_section
+        .get_export_entry_list()
+        .into_iter()
+        .enumerate()
+    {
+        let num_entries = match entry.get_kind() {
+            ExternalKind::Function => num_functions,
+            ExternalKind::Table => tables.len() as u32,
+            ExternalKind::Memory => memories.len() as u32,
+            ExternalKind::Global => global_types.len() as u32,
+            ExternalKind::Tag => num_tags,
+        };
+        if entry.get_index() >= num_entries {
+            return Err(ParseError::ValidationError(format!(
+                "export[{}] \"{}\": {} index {} out of range (size of index space: {})",
+                cnt,
+                entry.get_entry_name(),
+                entry.get_kind().to_string().to_lowercase(),
+                entry.get_index(),
+                num_entries
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// インポートされた関数、定義された関数の順に型を並べる(関数インデックス空間)
+//
+// 型が見つからない場合はNone
+fn collect_func_types(module: &WasmModule) -> Vec<Option<&FuncType>> {
+    let type_section = module.get_type_section();
+    let get_type = |type_idx: u32| type_section.and_then(|sec| sec.get_type(type_idx as usize));
+
+    let mut func_types: Vec<Option<&FuncType>> = Vec::new();
+
+    if let Some(import_section) = module.get_import_section() {
+        for entry in import_section.get_import_entries().into_iter() {
+            if let TypeEntry::FuncIndex { type_ } = entry.get_type() {
+                func_types.push(get_type(*type_));
+            }
+        }
+    }
+
+    if let Some(function_section) = module.get_function_section() {
+        for type_idx in function_section.get_indice_list().into_iter() {
+            func_types.push(get_type(type_idx));
+        }
+    }
+
+    func_types
+}
+
+// メモリ関連の命令を検査する
+//
+// - 参照するメモリインデックス・dataセグメントインデックスが存在すること
+// - memargのオフセットがメモリのインデックス型で表現できる範囲であること
+//   (i32メモリではu32, i64メモリではu64)
+fn validate_code(module: &WasmModule, memories: &[&MemoryType]) -> Result<(), ParseError> {
+    let code_section = match module.get_code_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    // DataCountSectionがない場合はDataSectionのセグメント数を使う
+    let data_count = match module.get_data_count_section() {
+        Some(sec) => sec.get_data_count(),
+        None => module
+            .get_data_section()
+            .map(|sec| sec.get_num_data_segments())
+            .unwrap_or(0),
+    };
+
+    for (body_idx, func_body) in code_section.get_func_body_list().into_iter().enumerate() {
+        for instr in func_body.get_instructions()?.iter() {
+            let location = format!(
+                "code[{}] +{:#x}: {}",
+                body_idx,
+                instr.get_offset(),
+                instr.get_name()
+            );
+
+            let (memory_indices, data_index) = match instr.get_immediate() {
+                Immediate::MemArg(memarg) | Immediate::MemArgLane { memarg, .. } => {
+                    (vec![memarg.get_memory_index()], None)
+                }
+                Immediate::Memory(idx) => (vec![*idx], None),
+                Immediate::MemoryCopy {
+                    dst_index,
+                    src_index,
+                } => (vec![*dst_index, *src_index], None),
+                Immediate::MemoryInit {
+                    data_index,
+                    memory_index,
+                } => (vec![*memory_index], Some(*data_index)),
+                Immediate::Data(idx) => (vec![], Some(*idx)),
+                _ => continue,
+            };
+
+            for mem_idx in memory_indices.into_iter() {
+                let memory = match memories.get(mem_idx as usize) {
+                    Some(mem) => mem,
+                    None => {
+                        return Err(ParseError::ValidationError(format!(
+                            "{}: memory {} not found",
+                            location, mem_idx
+                        )))
+                    }
+                };
+
+                if let Immediate::MemArg(memarg) | Immediate::MemArgLane { memarg, .. } =
+                    instr.get_immediate()
+                {
+                    let index_type = memory.get_index_type();
+                    if memarg.get_offset() > index_type.max_address() {
+                        return Err(ParseError::ValidationError(format!(
+                            "{}: offset {} out of range for {} memory {}",
+                            location,
+                            memarg.get_offset(),
+                            index_type,
+                            mem_idx
+                        )));
+                    }
+                }
+            }
+
+            if let Some(data_idx) = data_index {
+                if data_idx >= data_count {
+                    return Err(ParseError::ValidationError(format!(
+                        "{}: data segment {} not found",
+                        location, data_idx
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// メモリのサイズの制限を検査する
+//
+// - 初期サイズ・上限がインデックス型で指定できるページ数(i32: 2^16, i64: 2^48)以下であること
+// - 上限が初期サイズ以上であること
+fn validate_memories(memories: &[&MemoryType]) -> Result<(), ParseError> {
+    for (mem_idx, memory) in memories.iter().enumerate() {
+        let limits = memory.get_limits();
+        let max_pages = memory.get_index_type().max_memory_pages();
+        if limits.get_initial_length() > max_pages
+            || limits.get_maximum_length().is_some_and(|m| m > max_pages)
+        {
+            return Err(ParseError::ValidationError(format!(
+                "memory[{}]: size must be at most {} pages: {}",
+                mem_idx, max_pages, limits
+            )));
+        }
+        if limits
+            .get_maximum_length()
+            .is_some_and(|m| m < limits.get_initial_length())
+        {
+            return Err(ParseError::ValidationError(format!(
+                "memory[{}]: maximum must be larger than or equal to initial: {}",
+                mem_idx, limits
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// elemセグメントのオフセット式の型がテーブルのインデックス型と一致するかを検査する
+fn validate_element_segments(
+    module: &WasmModule,
+    tables: &[&TableType],
+    global_types: &[LangTypes],
+) -> Result<(), ParseError> {
+    let element_section = match module.get_element_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (cnt, segment) in element_section.get_element_list().into_iter().enumerate() {
+        // passive/declarativeセグメントはテーブルに対応しない
+        let (table_idx, offset_expr) = match (segment.get_table_index(), segment.get_offset_expr())
+        {
+            (Some(table_idx), Some(expr)) => (table_idx, expr),
+            _ => continue,
+        };
+
+        let table = match tables.get(table_idx as usize) {
+            Some(table) => table,
+            None => {
+                return Err(ParseError::ValidationError(format!(
+                    "elem[{}]: table {} not found",
+                    cnt, table_idx
+                )))
+            }
+        };
+
+        let expected = table.get_index_type().to_lang_type();
+        let actual = eval_offset_type(offset_expr, global_types)?;
+        if actual != expected {
+            return Err(ParseError::ValidationError(format!(
+                "elem[{}]: offset type mismatch: expected {}, found {}",
+                cnt, expected, actual
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// dataセグメントのオフセット式の型がメモリのインデックス型と一致するかを検査する
+fn validate_data_segments(
+    module: &WasmModule,
+    memories: &[&MemoryType],
+    global_types: &[LangTypes],
+) -> Result<(), ParseError> {
+    let data_section = match module.get_data_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (cnt, data_segment) in data_section.get_data_segment_list().into_iter().enumerate() {
+        // passiveセグメントはメモリに対応しない
+        let (mem_idx, offset_expr) = match (
+            data_segment.get_memory_index(),
+            data_segment.get_offset_expr(),
+        ) {
+            (Some(mem_idx), Some(expr)) => (mem_idx, expr),
+            _ => continue,
+        };
+
+        let memory = match memories.get(mem_idx as usize) {
+            Some(mem) => mem,
+            None => {
+                return Err(ParseError::ValidationError(format!(
+                    "data[{}]: memory {} not found",
+                    cnt, mem_idx
+                )))
+            }
+        };
+
+        let expected = memory.get_index_type().to_lang_type();
+        let actual = eval_offset_type(offset_expr, global_types)?;
+        if actual != expected {
+            return Err(ParseError::ValidationError(format!(
+                "data[{}]: offset type mismatch: expected {}, found {}",
+                cnt, expected, actual
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// 例外のタグを検査する
+//
+// - タグの型は戻り値を持たない関数型であること
+// - throw, catch, try_tableのcatch節が参照するタグが存在すること
+fn validate_tags(module: &WasmModule) -> Result<(), ParseError> {
+    let tags = collect_tags(module);
+
+    for (tag_idx, tag) in tags.iter().enumerate() {
+        let type_idx = tag.get_type_index();
+        let func_type = module
+            .get_type_section()
+            .and_then(|sec| sec.get_type(type_idx as usize));
+        match func_type {
+            Some(ty) if ty.get_num_return() != 0 => {
+                return Err(ParseError::ValidationError(format!(
+                    "tag[{}]: type {} must not have results",
+                    tag_idx, type_idx
+                )))
+            }
+            Some(_) => (),
+            None => {
+                return Err(ParseError::ValidationError(format!(
+                    "tag[{}]: type {} not found or not a function type",
+                    tag_idx, type_idx
+                )))
+            }
+        }
+    }
+
+    let code_section = match module.get_code_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    for (body_idx, func_body) in code_section.get_func_body_list().into_iter().enumerate() {
+        for instr in func_body.get_instructions()?.iter() {
+            let tag_indices: Vec<u32> = match instr.get_immediate() {
+                Immediate::Tag(idx) => vec![*idx],
+                Immediate::TryTable { catches, .. } => {
+                    catches.iter().filter_map(|c| c.get_tag_index()).collect()
+                }
+                _ => continue,
+            };
+
+            for tag_idx in tag_indices.into_iter() {
+                if tag_idx as usize >= tags.len() {
+                    return Err(ParseError::ValidationError(format!(
+                        "code[{}] +{:#x}: {}: tag {} not found",
+                        body_idx,
+                        instr.get_offset(),
+                        instr.get_name(),
+                        tag_idx
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 定数式(グローバル変数の初期値、data/elemのオフセットなど)が
+// 定数命令のみからなるかを検査する
+fn validate_const_exprs(module: &WasmModule) -> Result<(), ParseError> {
+    let mut exprs: Vec<(String, &Expr)> = Vec::new();
+
+    if let Some(table_section) = module.get_table_section() {
+        for cnt in 0..table_section.get_num_tables() as usize {
+            if let Some(expr) = table_section.get_table_init_expr(cnt) {
+                exprs.push((format!("table[{}]", cnt), expr));
+            }
+        }
+    }
+
+    if let Some(global_section) = module.get_global_section() {
+        for (cnt, global_var) in global_section
+            .get_global_variable_list()
+            .into_iter()
+            .enumerate()
+        {
+            exprs.push((format!("global[{}]", cnt), global_var.get_init_expr()));
+        }
+    }
+
+    if let Some(element_section) = module.get_element_section() {
+        for (cnt, elem_segment) in element_section.get_element_list().into_iter().enumerate() {
+            if let Some(expr) = elem_segment.get_offset_expr() {
+                exprs.push((format!("elem[{}]", cnt), expr));
+            }
+            for expr in elem_segment.get_element_exprs().into_iter() {
+                exprs.push((format!("elem[{}]", cnt), expr));
+            }
+        }
+    }
+
+    if let Some(data_section) = module.get_data_section() {
+        for (cnt, data_segment) in data_section.get_data_segment_list().into_iter().enumerate() {
+            if let Some(expr) = data_segment.get_offset_expr() {
+                exprs.push((format!("data[{}]", cnt), expr));
+            }
+        }
+    }
+
+    for (location, expr) in exprs.into_iter() {
+        for instr in expr.get_instructions()?.iter() {
+            if !is_const_instruction(instr) {
+                return Err(ParseError::ValidationError(format!(
+                    "{}: {} is not a constant instruction",
+                    location,
+                    instr.get_name()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 定数式で使える命令かどうか
+//
+// extended-const提案でi32/i64のadd, sub, mulが、
+// GC提案でstruct.newなどのアロケーション命令が追加された
+fn is_const_instruction(instr: &Instruction) -> bool {
+    match instr.get_sub_opcode() {
+        // struct.new(_default), array.new(_default, _fixed), ref.i31,
+        // any.convert_extern, extern.convert_any
+        Some(sub) => instr.get_opcode() == PREFIX_GC && matches!(sub, 0 | 1 | 6..=8 | 26..=28),
+        None => matches!(
+            instr.get_opcode(),
+            0x0B | 0x23 | 0x41..=0x44 | 0x6A..=0x6C | 0x7C..=0x7E | 0xD0 | 0xD2
+        ),
+    }
+}
+
+// 末尾呼び出し(return_call, return_call_indirect)を検査する
+//
+// - 呼び出し先の関数・型が存在すること
+// - 呼び出し先の戻り値の型が、呼び出し元の戻り値の型と一致すること
+fn validate_tail_calls(module: &WasmModule) -> Result<(), ParseError> {
+    let code_section = match module.get_code_section() {
+        Some(sec) => sec,
+        None => return Ok(()),
+    };
+
+    let func_types = collect_func_types(module);
+    let num_imported_funcs = module.get_num_imported_functions() as usize;
+
+    for (body_idx, func_body) in code_section.get_func_body_list().into_iter().enumerate() {
+        let caller_type = match func_types.get(num_imported_funcs + body_idx) {
+            Some(Some(ty)) => ty,
+            _ => continue,
+        };
+
+        for instr in func_body.get_instructions()?.iter() {
+            let location = format!(
+                "code[{}] +{:#x}: {}",
+                body_idx,
+                instr.get_offset(),
+                instr.get_name()
+            );
+
+            let callee_type = match (instr.get_opcode(), instr.get_immediate()) {
+                (0x12, Immediate::Function(func_idx)) => match func_types.get(*func_idx as usize) {
+                    Some(Some(ty)) => ty,
+                    _ => {
+                        return Err(ParseError::ValidationError(format!(
+                            "{}: function {} not found",
+                            location, func_idx
+                        )))
+                    }
+                },
+                (0x13, Immediate::CallIndirect { type_index, .. }) => {
+                    match module
+                        .get_type_section()
+                        .and_then(|sec| sec.get_type(*type_index as usize))
+                    {
+                        Some(ty) => ty,
+                        None => {
+                            return Err(ParseError::ValidationError(format!(
+                                "{}: type {} not found or not a function type",
+                                location, type_index
+                            )))
+                        }
+                    }
+                }
+                _ => continue,
+            };
+
+            let caller_result: Vec<&LangTypes> = caller_type
+                .get_return_types()
+                .into_iter()
+                .map(|x| x.get_value())
+                .collect();
+            let callee_result: Vec<&LangTypes> = callee_type
+                .get_return_types()
+                .into_iter()
+                .map(|x| x.get_value())
+                .collect();
+            if caller_result != callee_result {
+                return Err(ParseError::ValidationError(format!(
+                    "{}: result type mismatch: caller {}, callee {}",
+                    location, caller_type, callee_type
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// オフセット式の結果の型を求める
+//
+// extended-const提案により、i32/i64のadd, sub, mulを含む場合がある
+fn eval_offset_type(expr: &Expr, global_types: &[LangTypes]) -> Result<LangTypes, ParseError> {
+    if let Some((ty, _)) = expr.eval_const_int() {
+        return Ok(ty);
+    }
+
+    let mut stack: Vec<LangTypes> = Vec::new();
+    for instr in expr.get_instructions()?.iter() {
+        let opcode = instr.get_opcode();
+        match (opcode, instr.get_immediate()) {
+            (0x41, _) => stack.push(LangTypes::I32),
+            (0x42, _) => stack.push(LangTypes::I64),
+            (0x23, Immediate::Global(idx)) => match global_types.get(*idx as usize) {
+                Some(ty) => stack.push(*ty),
+                None => {
+                    return Err(ParseError::ValidationError(format!(
+                        "global {} not found",
+                        idx
+                    )))
+                }
+            },
+            (0x6A..=0x6C | 0x7C..=0x7E, _) => {
+                let ty = if opcode <= 0x6C {
+                    LangTypes::I32
+                } else {
+                    LangTypes::I64
+                };
+                let rhs = stack.pop();
+                let lhs = stack.pop();
+                if lhs != Some(ty) || rhs != Some(ty) {
+                    return Err(ParseError::ValidationError(format!(
+                        "{}: operand type mismatch",
+                        instr.get_name()
+                    )));
+                }
+                stack.push(ty);
+            }
+            (0x0B, _) => break,
+            _ => {
+                return Err(ParseError::ValidationError(String::from(
+                    "offset is not a constant expression",
+                )))
+            }
+        }
+    }
+
+    match stack.as_slice() {
+        [ty] => Ok(*ty),
+        _ => Err(ParseError::ValidationError(String::from(
+            "offset is not a constant expression",
+        ))),
+    }
+}
@@ -1,5 +1,7 @@
 pub mod base;
 pub mod code;
+pub mod entity;
+pub mod instruction;
 pub mod module;
 pub mod sections;
 pub mod types;
@@ -1,15 +1,13 @@
 use std::io::Read;
 
 use crate::readers::usage_bytes_leb128_u;
-use crate::readers::{read_8, read_unsigned_leb128, read_x};
+use crate::readers::{read_8, read_unsigned_leb128, read_x, RecordingReader};
 use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::instruction::{decode_instructions, Immediate, Instruction};
 use crate::wasm_components::sections::ParseError;
 
 use super::types::{LangTypes, ValueType, VarUInt32};
 
-#[derive(Debug)]
-pub enum InstrType {}
-
 #[derive(Debug)]
 pub struct Expr {
     bytes: Vec<u8>,
@@ -32,13 +30,87 @@ pub struct LocalEntry {
 
 impl Expr {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let bytes = read_all_instrs(reader)?;
-        Ok(Expr { bytes: bytes })
+        // 対応するendが現れるまで1命令ずつデコードする
+        // (即値に0x0Bが含まれる場合があるため、バイト単位で0x0Bを探してはいけない)
+        let mut reader = RecordingReader::new(reader);
+        let mut depth: u32 = 0;
+        loop {
+            let offset = reader.get_read_size() as u32;
+            let instr = Instruction::parse(&mut reader, offset)?;
+            if instr.is_block_start() {
+                depth += 1;
+            } else if instr.is_block_end() {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+
+        Ok(Expr {
+            bytes: reader.into_recorded(),
+        })
     }
 
     pub fn get_instrs(&self) -> &Vec<u8> {
         &self.bytes
     }
+
+    /// 命令列をデコードして返す(末尾のendを含む)
+    pub fn get_instructions(&self) -> Result<Vec<Instruction>, ParseError> {
+        decode_instructions(&self.bytes)
+    }
+
+    /// 定数式を評価して、整数値とその型を返す
+    ///
+    /// i32.const/i64.constと、extended-const提案で追加された
+    /// i32/i64のadd, sub, mulのみからなる場合に値を返す。
+    /// global.getなど、モジュール単体では評価できない場合はNone
+    pub fn eval_const_int(&self) -> Option<(LangTypes, i64)> {
+        let instrs = self.get_instructions().ok()?;
+
+        let mut stack: Vec<(LangTypes, i64)> = Vec::new();
+        for instr in instrs.iter() {
+            let opcode = instr.get_opcode();
+            match (opcode, instr.get_immediate()) {
+                (0x41, Immediate::I32(v)) => stack.push((LangTypes::I32, *v as i64)),
+                (0x42, Immediate::I64(v)) => stack.push((LangTypes::I64, *v)),
+                // i32.add, i32.sub, i32.mul, i64.add, i64.sub, i64.mul
+                (0x6A..=0x6C | 0x7C..=0x7E, _) => {
+                    let ty = if opcode <= 0x6C {
+                        LangTypes::I32
+                    } else {
+                        LangTypes::I64
+                    };
+                    let (ty_rhs, rhs) = stack.pop()?;
+                    let (ty_lhs, lhs) = stack.pop()?;
+                    if ty_lhs != ty || ty_rhs != ty {
+                        return None;
+                    }
+
+                    let v = match opcode {
+                        0x6A | 0x7C => lhs.wrapping_add(rhs),
+                        0x6B | 0x7D => lhs.wrapping_sub(rhs),
+                        _ => lhs.wrapping_mul(rhs),
+                    };
+                    // i32の演算結果は32bitで切り捨てる
+                    let v = if ty == LangTypes::I32 {
+                        v as i32 as i64
+                    } else {
+                        v
+                    };
+                    stack.push((ty, v));
+                }
+                (0x0B, _) => break,
+                _ => return None,
+            }
+        }
+
+        match stack.as_slice() {
+            [value] => Some(*value),
+            _ => None,
+        }
+    }
 }
 
 impl Sizeof for Expr {
@@ -56,9 +128,9 @@ impl FunctionBody {
         };
 
         let mut local_count: u64 = 0; // VarUInt32
-        let sizeof_local_count: i64;
-        match read_unsigned_leb128(reader, &mut local_count) {
-            Ok(rs) => sizeof_local_count = rs as i64,
+
+        let sizeof_local_count: i64 = match read_unsigned_leb128(reader, &mut local_count) {
+            Ok(rs) => rs as i64,
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
@@ -91,8 +163,8 @@ impl FunctionBody {
         Ok(Self {
             body_size: body_size as VarUInt32,
             local_count: local_count as VarUInt32,
-            locals: locals,
-            code: code,
+            locals,
+            code,
             _end: end,
         })
     }
@@ -100,6 +172,23 @@ impl FunctionBody {
     pub fn get_locals(&self) -> Vec<&LangTypes> {
         self.locals.iter().map(|x| x.get_value_type()).collect()
     }
+
+    /// ローカル変数の宣言(個数と型の組)のリストを返す
+    pub fn get_local_entries(&self) -> Vec<&LocalEntry> {
+        self.locals.iter().collect()
+    }
+
+    /// 関数本体のバイト列(ローカル変数宣言と末尾のendを除く)を返す
+    pub fn get_code(&self) -> &Vec<u8> {
+        &self.code
+    }
+
+    /// 関数本体の命令列をデコードして返す
+    ///
+    /// 各命令のオフセットはget_code()の先頭からの位置
+    pub fn get_instructions(&self) -> Result<Vec<Instruction>, ParseError> {
+        decode_instructions(&self.code)
+    }
 }
 
 impl Sizeof for FunctionBody {
@@ -125,10 +214,14 @@ impl LocalEntry {
 
         Ok(Self {
             count: count as VarUInt32,
-            type_: type_,
+            type_,
         })
     }
 
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
     pub fn get_value_type(&self) -> &LangTypes {
         self.type_.get_value()
     }
@@ -142,19 +235,3 @@ impl Sizeof for LocalEntry {
         sizeof_count + sizeof_type
     }
 }
-
-fn read_all_instrs<R: Read>(reader: &mut R) -> Result<Vec<u8>, ParseError> {
-    let mut instrs: Vec<u8> = Vec::new();
-    loop {
-        let b = match read_8(reader) {
-            Ok(data) => data[0],
-            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
-        };
-
-        instrs.push(b);
-        if b == 0x0B {
-            break;
-        }
-    }
-    Ok(instrs)
-}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::mem;
+
+use crate::wasm_components::code::FunctionBody;
+use crate::wasm_components::module::WasmModule;
+use crate::wasm_components::sections::{ImportEntry, NameMapSubsection, TypeEntry};
+use crate::wasm_components::types::{
+    ExternalKind, FuncType, GlobalType, InitExpr, MemoryType, SubType, TableType, TagType,
+};
+
+/*
+ * Index Space
+ *
+ * 関数・テーブル・メモリ・グローバル変数・タグのインデックス空間を解決したもの
+ *
+ * 各インデックス空間では、インポートされたものがモジュールで定義されたものより前に並ぶ
+ * エンティティは命令などで使われる絶対インデックスの順に並び、
+ * インポート/定義の区別、型、エクスポート名、nameセクションの名前を持つ
+ */
+
+/// エンティティがインポートされたものか、モジュールで定義されたものか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityOrigin<'a> {
+    Imported {
+        module_name: &'a String,
+        field_name: &'a String,
+    },
+    Defined {
+        index: u32, // 定義されたものの中での番号(セクション内のインデックス)
+    },
+}
+
+/// エンティティに共通する情報
+#[derive(Debug, Clone)]
+pub struct EntityCommon<'a> {
+    index: u32, // インデックス空間での絶対インデックス
+    origin: EntityOrigin<'a>,
+    export_names: Vec<&'a String>,
+    name: Option<&'a String>, // nameセクションの名前
+}
+
+/// 関数
+#[derive(Debug, Clone)]
+pub struct FunctionEntity<'a> {
+    common: EntityCommon<'a>,
+    type_index: Option<u32>,
+    func_type: Option<&'a FuncType>, // 型が見つからない場合はNone
+    body: Option<&'a FunctionBody>,  // 定義された関数の場合のみ
+}
+
+/// テーブル
+#[derive(Debug, Clone)]
+pub struct TableEntity<'a> {
+    common: EntityCommon<'a>,
+    table_type: &'a TableType,
+    init_expr: Option<&'a InitExpr>, // 初期化式を持つ定義されたテーブルの場合のみ
+}
+
+/// メモリ
+#[derive(Debug, Clone)]
+pub struct MemoryEntity<'a> {
+    common: EntityCommon<'a>,
+    memory_type: &'a MemoryType,
+}
+
+/// グローバル変数
+#[derive(Debug, Clone)]
+pub struct GlobalEntity<'a> {
+    common: EntityCommon<'a>,
+    global_type: &'a GlobalType,
+    init_expr: Option<&'a InitExpr>, // 定義されたグローバル変数の場合のみ
+}
+
+/// タグ
+#[derive(Debug, Clone)]
+pub struct TagEntity<'a> {
+    common: EntityCommon<'a>,
+    tag_type: &'a TagType,
+}
+
+impl<'a> EntityOrigin<'a> {
+    pub fn is_imported(&self) -> bool {
+        matches!(self, EntityOrigin::Imported { .. })
+    }
+}
+
+// インデックスからエクスポート名・nameセクションの名前を引く表
+//
+// エンティティごとにエクスポートや名前のリストを走査しないよう、一覧を作る際に一度だけ作る
+struct NameTable<'a> {
+    export_names: HashMap<u32, Vec<&'a String>>,
+    names: HashMap<u32, &'a String>,
+}
+
+impl<'a> NameTable<'a> {
+    fn new(
+        module: &'a WasmModule,
+        kind: ExternalKind,
+        names: Option<&'a NameMapSubsection>,
+    ) -> Self {
+        let mut export_names: HashMap<u32, Vec<&String>> = HashMap::new();
+        if let Some(sec) = module.get_export_section() {
+            for entry in sec.get_export_entry_list().into_iter() {
+                if is_same_kind(entry.get_kind(), &kind) {
+                    export_names
+                        .entry(entry.get_index())
+                        .or_default()
+                        .push(entry.get_entry_name());
+                }
+            }
+        }
+
+        // 同じインデックスが複数ある場合は、NameMap::get_nameと同じく先頭のものを使う
+        let mut index_names: HashMap<u32, &String> = HashMap::new();
+        if let Some(names) = names {
+            for naming in names.get_name_map().get_name_list().into_iter() {
+                index_names
+                    .entry(naming.get_indice())
+                    .or_insert(naming.get_name_str());
+            }
+        }
+
+        Self {
+            export_names,
+            names: index_names,
+        }
+    }
+}
+
+impl<'a> EntityCommon<'a> {
+    fn new(index: u32, origin: EntityOrigin<'a>, table: &NameTable<'a>) -> Self {
+        Self {
+            index,
+            origin,
+            export_names: table.export_names.get(&index).cloned().unwrap_or_default(),
+            name: table.names.get(&index).copied(),
+        }
+    }
+
+    /// インデックス空間での絶対インデックスを返す
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn get_origin(&self) -> &EntityOrigin<'a> {
+        &self.origin
+    }
+
+    pub fn is_imported(&self) -> bool {
+        self.origin.is_imported()
+    }
+
+    /// インポート元の(モジュール名, フィールド名)を返す
+    pub fn get_import_name(&self) -> Option<(&'a String, &'a String)> {
+        match self.origin {
+            EntityOrigin::Imported {
+                module_name,
+                field_name,
+            } => Some((module_name, field_name)),
+            EntityOrigin::Defined { .. } => None,
+        }
+    }
+
+    /// 定義されたものの中での番号を返す(インポートされたものはNone)
+    pub fn get_defined_index(&self) -> Option<u32> {
+        match self.origin {
+            EntityOrigin::Imported { .. } => None,
+            EntityOrigin::Defined { index } => Some(index),
+        }
+    }
+
+    /// エクスポートされている名前のリストを返す
+    pub fn get_export_names(&self) -> &[&'a String] {
+        &self.export_names
+    }
+
+    /// nameセクションの名前を返す
+    pub fn get_name(&self) -> Option<&'a String> {
+        self.name
+    }
+}
+
+impl<'a> FunctionEntity<'a> {
+    fn new(
+        index: u32,
+        origin: EntityOrigin<'a>,
+        type_index: Option<u32>,
+        sub_types: &[&'a SubType],
+        table: &NameTable<'a>,
+    ) -> Self {
+        let func_type = type_index
+            .and_then(|idx| sub_types.get(idx as usize))
+            .and_then(|x| x.get_func_type());
+
+        Self {
+            common: EntityCommon::new(index, origin, table),
+            type_index,
+            func_type,
+            body: None,
+        }
+    }
+
+    pub fn get_common(&self) -> &EntityCommon<'a> {
+        &self.common
+    }
+
+    pub fn get_type_index(&self) -> Option<u32> {
+        self.type_index
+    }
+
+    pub fn get_func_type(&self) -> Option<&'a FuncType> {
+        self.func_type
+    }
+
+    pub fn get_body(&self) -> Option<&'a FunctionBody> {
+        self.body
+    }
+}
+
+impl<'a> TableEntity<'a> {
+    pub fn get_common(&self) -> &EntityCommon<'a> {
+        &self.common
+    }
+
+    pub fn get_table_type(&self) -> &'a TableType {
+        self.table_type
+    }
+
+    pub fn get_init_expr(&self) -> Option<&'a InitExpr> {
+        self.init_expr
+    }
+}
+
+impl<'a> MemoryEntity<'a> {
+    pub fn get_common(&self) -> &EntityCommon<'a> {
+        &self.common
+    }
+
+    pub fn get_memory_type(&self) -> &'a MemoryType {
+        self.memory_type
+    }
+}
+
+impl<'a> GlobalEntity<'a> {
+    pub fn get_common(&self) -> &EntityCommon<'a> {
+        &self.common
+    }
+
+    pub fn get_global_type(&self) -> &'a GlobalType {
+        self.global_type
+    }
+
+    pub fn get_init_expr(&self) -> Option<&'a InitExpr> {
+        self.init_expr
+    }
+}
+
+impl<'a> TagEntity<'a> {
+    pub fn get_common(&self) -> &EntityCommon<'a> {
+        &self.common
+    }
+
+    pub fn get_tag_type(&self) -> &'a TagType {
+        self.tag_type
+    }
+}
+
+impl WasmModule {
+    /// 関数インデックス空間の関数のリストを返す
+    pub fn functions(&self) -> Vec<FunctionEntity<'_>> {
+        let names = self.get_name_section().and_then(|x| x.get_function_names());
+        let name_table = NameTable::new(self, ExternalKind::Function, names);
+        let sub_types = match self.get_type_section() {
+            Some(sec) => sec.get_sub_type_list(),
+            None => Vec::new(),
+        };
+
+        let mut functions: Vec<FunctionEntity> = Vec::new();
+        for entry in get_imported_entries(self, ExternalKind::Function).into_iter() {
+            let type_index = match entry.get_type() {
+                TypeEntry::FuncIndex { type_ } => Some(*type_),
+                _ => None,
+            };
+            functions.push(FunctionEntity::new(
+                functions.len() as u32,
+                get_import_origin(entry),
+                type_index,
+                &sub_types,
+                &name_table,
+            ));
+        }
+
+        let type_indices = match self.get_function_section() {
+            Some(sec) => sec.get_indice_list(),
+            None => Vec::new(),
+        };
+        for (cnt, type_index) in type_indices.into_iter().enumerate() {
+            let mut function = FunctionEntity::new(
+                functions.len() as u32,
+                EntityOrigin::Defined { index: cnt as u32 },
+                Some(type_index),
+                &sub_types,
+                &name_table,
+            );
+            function.body = self.get_code_section().and_then(|x| x.get_func_body(cnt));
+            functions.push(function);
+        }
+
+        functions
+    }
+
+    /// テーブルインデックス空間のテーブルのリストを返す
+    pub fn tables(&self) -> Vec<TableEntity<'_>> {
+        let names = self.get_name_section().and_then(|x| x.get_table_names());
+        let name_table = NameTable::new(self, ExternalKind::Table, names);
+
+        let mut table_types: Vec<(EntityOrigin, &TableType, Option<&InitExpr>)> = Vec::new();
+        for entry in get_imported_entries(self, ExternalKind::Table).into_iter() {
+            if let TypeEntry::TblType { type_ } = entry.get_type() {
+                table_types.push((get_import_origin(entry), type_, None));
+            }
+        }
+        if let Some(sec) = self.get_table_section() {
+            for (cnt, table_type) in sec.get_table_list().into_iter().enumerate() {
+                table_types.push((
+                    EntityOrigin::Defined { index: cnt as u32 },
+                    table_type,
+                    sec.get_table_init_expr(cnt),
+                ));
+            }
+        }
+
+        table_types
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (origin, table_type, init_expr))| TableEntity {
+                common: EntityCommon::new(idx as u32, origin, &name_table),
+                table_type,
+                init_expr,
+            })
+            .collect()
+    }
+
+    /// メモリインデックス空間のメモリのリストを返す
+    pub fn memories(&self) -> Vec<MemoryEntity<'_>> {
+        let names = self.get_name_section().and_then(|x| x.get_memory_names());
+        let name_table = NameTable::new(self, ExternalKind::Memory, names);
+
+        let mut memory_types: Vec<(EntityOrigin, &MemoryType)> = Vec::new();
+        for entry in get_imported_entries(self, ExternalKind::Memory).into_iter() {
+            if let TypeEntry::MemType { type_ } = entry.get_type() {
+                memory_types.push((get_import_origin(entry), type_));
+            }
+        }
+        if let Some(sec) = self.get_memory_section() {
+            for (cnt, memory_type) in sec.get_memories().into_iter().enumerate() {
+                memory_types.push((EntityOrigin::Defined { index: cnt as u32 }, memory_type));
+            }
+        }
+
+        memory_types
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (origin, memory_type))| MemoryEntity {
+                common: EntityCommon::new(idx as u32, origin, &name_table),
+                memory_type,
+            })
+            .collect()
+    }
+
+    /// グローバル変数のインデックス空間のグローバル変数のリストを返す
+    pub fn globals(&self) -> Vec<GlobalEntity<'_>> {
+        let names = self.get_name_section().and_then(|x| x.get_global_names());
+        let name_table = NameTable::new(self, ExternalKind::Global, names);
+
+        let mut global_types: Vec<(EntityOrigin, &GlobalType, Option<&InitExpr>)> = Vec::new();
+        for entry in get_imported_entries(self, ExternalKind::Global).into_iter() {
+            if let TypeEntry::GblType { type_ } = entry.get_type() {
+                global_types.push((get_import_origin(entry), type_, None));
+            }
+        }
+        if let Some(sec) = self.get_global_section() {
+            for (cnt, global_var) in sec.get_global_variable_list().into_iter().enumerate() {
+                global_types.push((
+                    EntityOrigin::Defined { index: cnt as u32 },
+                    global_var.get_global_type(),
+                    Some(global_var.get_init_expr()),
+                ));
+            }
+        }
+
+        global_types
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (origin, global_type, init_expr))| GlobalEntity {
+                common: EntityCommon::new(idx as u32, origin, &name_table),
+                global_type,
+                init_expr,
+            })
+            .collect()
+    }
+
+    /// タグインデックス空間のタグのリストを返す
+    pub fn tags(&self) -> Vec<TagEntity<'_>> {
+        let names = self.get_name_section().and_then(|x| x.get_tag_names());
+        let name_table = NameTable::new(self, ExternalKind::Tag, names);
+
+        let mut tag_types: Vec<(EntityOrigin, &TagType)> = Vec::new();
+        for entry in get_imported_entries(self, ExternalKind::Tag).into_iter() {
+            if let TypeEntry::TagType { type_ } = entry.get_type() {
+                tag_types.push((get_import_origin(entry), type_));
+            }
+        }
+        if let Some(sec) = self.get_tag_section() {
+            for (cnt, tag_type) in sec.get_tag_list().into_iter().enumerate() {
+                tag_types.push((EntityOrigin::Defined { index: cnt as u32 }, tag_type));
+            }
+        }
+
+        tag_types
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (origin, tag_type))| TagEntity {
+                common: EntityCommon::new(idx as u32, origin, &name_table),
+                tag_type,
+            })
+            .collect()
+    }
+}
+
+fn is_same_kind(lhs: &ExternalKind, rhs: &ExternalKind) -> bool {
+    mem::discriminant(lhs) == mem::discriminant(rhs)
+}
+
+// 指定した種類のインポートを、インデックス空間の順に返す
+fn get_imported_entries(module: &WasmModule, kind: ExternalKind) -> Vec<&ImportEntry> {
+    match module.get_import_section() {
+        Some(sec) => sec
+            .get_import_entries()
+            .into_iter()
+            .filter(|x| is_same_kind(x.get_kind(), &kind))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn get_import_origin(entry: &ImportEntry) -> EntityOrigin<'_> {
+    EntityOrigin::Imported {
+        module_name: entry.get_import_module_name(),
+        field_name: entry.get_import_entry_name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::test_utils::*;
+
+    fn import(module_name: &str, field_name: &str, desc: &[u8]) -> Vec<u8> {
+        let mut bytes = name(module_name);
+        bytes.extend(name(field_name));
+        bytes.extend_from_slice(desc);
+        bytes
+    }
+
+    fn name_section(name_type: u8, names: &[(u32, &str)]) -> Vec<u8> {
+        let mut payload = vec![name_type];
+        payload.extend(sized(&name_map(names)));
+        custom_section("name", &payload)
+    }
+
+    #[test]
+    fn tags_are_named_by_absolute_index() {
+        let bytes = module(&[
+            section(1, &vec(&[func_type(&[0x7f], &[])])),
+            // (import "env" "e" (tag (type 0)))
+            section(2, &vec(&[import("env", "e", &[0x04, 0x00, 0x00])])),
+            section(13, &vec(&[vec![0x00, 0x00]])),
+            name_section(11, &[(0, "imported_tag"), (1, "defined_tag")]),
+        ]);
+        let wasm_module = parse(&bytes);
+
+        let tags = wasm_module.tags();
+        assert_eq!(tags.len(), 2);
+        assert!(tags[0].get_common().is_imported());
+
+        let defined = tags[1].get_common();
+        assert_eq!(defined.get_index(), 1);
+        assert_eq!(defined.get_defined_index(), Some(0));
+        assert_eq!(defined.get_name().map(|x| x.as_str()), Some("defined_tag"));
+    }
+
+    #[test]
+    fn globals_are_named_by_absolute_index() {
+        let bytes = module(&[
+            // (import "env" "g0" (global i32))
+            section(2, &vec(&[import("env", "g0", &[0x03, 0x7f, 0x00])])),
+            // (global (mut i32) (i32.const 0))
+            section(6, &vec(&[vec![0x7f, 0x01, 0x41, 0x00, 0x0b]])),
+            name_section(7, &[(0, "imp"), (1, "counter")]),
+        ]);
+        let wasm_module = parse(&bytes);
+
+        let globals = wasm_module.globals();
+        let defined = globals[1].get_common();
+        assert_eq!(defined.get_index(), 1);
+        assert_eq!(defined.get_defined_index(), Some(0));
+        assert_eq!(defined.get_name().map(|x| x.as_str()), Some("counter"));
+    }
+}
@@ -0,0 +1,1496 @@
+use byteorder::{ByteOrder, LittleEndian};
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use crate::readers::{read_8, read_signed_leb128, read_unsigned_leb128, read_x, RecordingReader};
+use crate::wasm_components::sections::ParseError;
+use crate::wasm_components::types::{BlockType, HeapType, ValueType};
+
+use super::types::LangTypes;
+
+/*
+ * Instructions
+ *
+ * 関数本体や初期化式(InitExpr)に含まれる命令列をデコードする
+ * オペコードごとに即値(immediate)の形式が異なるため、
+ * オペコード表(lookup_opcode)で即値の種類を引いてから読み込む
+ *
+ * 0xFCなどのプレフィックス付き命令は、プレフィックスに続くLEB128(u32)の
+ * サブオペコードで命令が決まる
+ */
+
+/// GC命令(struct.*, array.*, ref.test, ...)のプレフィックス
+pub const PREFIX_GC: u8 = 0xFB;
+/// bulk memory命令などのプレフィックス
+pub const PREFIX_MISC: u8 = 0xFC;
+/// SIMD命令のプレフィックス
+pub const PREFIX_SIMD: u8 = 0xFD;
+/// アトミック命令(threads提案)のプレフィックス
+pub const PREFIX_THREADS: u8 = 0xFE;
+
+/// 即値の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImmediateKind {
+    None,
+    Block,
+    Label,
+    BrTable,
+    Function,
+    CallIndirect,
+    Local,
+    Global,
+    MemArg,
+    Memory,
+    MemoryInit,
+    MemoryCopy,
+    Data,
+    Table,
+    TableInit,
+    TableCopy,
+    Elem,
+    Tag,
+    TryTable,
+    SelectTypes,
+    Type,
+    Field,
+    ArrayFixed,
+    ArrayData,
+    ArrayElem,
+    ArrayCopy,
+    HeapType,
+    RefType,
+    RefTypeNull,
+    BrOnCast,
+    Reserved,
+    V128,
+    Shuffle,
+    Lane,
+    MemArgLane,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// 命令の即値
+#[derive(Debug, Clone, PartialEq)]
+pub enum Immediate {
+    None,
+    Block(BlockSignature),
+    Label(u32),
+    BrTable {
+        targets: Vec<u32>,
+        default: u32,
+    },
+    Function(u32),
+    CallIndirect {
+        type_index: u32,
+        table_index: u32,
+    },
+    Local(u32),
+    Global(u32),
+    MemArg(MemArg),
+    Memory(u32),
+    MemoryInit {
+        data_index: u32,
+        memory_index: u32,
+    },
+    MemoryCopy {
+        dst_index: u32,
+        src_index: u32,
+    },
+    Data(u32),
+    Table(u32),
+    TableInit {
+        elem_index: u32,
+        table_index: u32,
+    },
+    TableCopy {
+        dst_index: u32,
+        src_index: u32,
+    },
+    Elem(u32),
+    Tag(u32),
+    TryTable {
+        signature: BlockSignature,
+        catches: Vec<CatchClause>,
+    },
+    SelectTypes(Vec<LangTypes>),
+    Type(u32),
+    Field {
+        type_index: u32,
+        field_index: u32,
+    },
+    ArrayFixed {
+        type_index: u32,
+        length: u32,
+    },
+    ArrayData {
+        type_index: u32,
+        data_index: u32,
+    },
+    ArrayElem {
+        type_index: u32,
+        elem_index: u32,
+    },
+    ArrayCopy {
+        dst_index: u32,
+        src_index: u32,
+    },
+    HeapType(HeapType),
+    RefType(LangTypes),
+    BrOnCast {
+        label: u32,
+        from: LangTypes,
+        to: LangTypes,
+    },
+    V128([u8; 16]),
+    Shuffle([u8; 16]),
+    Lane(u8),
+    MemArgLane {
+        memarg: MemArg,
+        lane: u8,
+    },
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+/// block, loop, ifのシグネチャ
+///
+/// MVPでは空か値型1つのみ。multi-value提案で型インデックスが使えるようになった
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSignature {
+    Empty,
+    Value(LangTypes),
+    TypeIndex(u32),
+}
+
+/// try_tableのcatch節 (exception handling提案)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchClause {
+    Catch { tag_index: u32, label: u32 },
+    CatchRef { tag_index: u32, label: u32 },
+    CatchAll { label: u32 },
+    CatchAllRef { label: u32 },
+}
+
+/// load/store命令のメモリ引数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemArg {
+    align: u32,        // log2(alignment)
+    memory_index: u32, // multi-memory提案で追加. alignのbit6が立っている場合のみバイナリに含まれる
+    offset: u64,       // memory64ではu64になる
+}
+
+// alignフィールドのbit6が立っている場合、メモリインデックスが続く
+const MEMARG_FLAG_MEMORY_INDEX: u64 = 0x40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    offset: u32, // 命令列の先頭からのオフセット
+    size: u32,
+    opcode: u8,
+    sub_opcode: Option<u32>, // プレフィックス付き命令の場合のみ
+    name: &'static str,
+    immediate: Immediate,
+}
+
+impl Instruction {
+    /// 1命令分をデコードする
+    ///
+    /// offsetは命令列の先頭からの位置で、デコード結果に記録するだけに使う
+    pub fn parse<R: Read>(reader: &mut R, offset: u32) -> Result<Self, ParseError> {
+        let mut reader = RecordingReader::new(reader);
+
+        let opcode = match read_8(&mut reader) {
+            Ok(data) => data[0],
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let sub_opcode: Option<u32>;
+        let info = if matches!(
+            opcode,
+            PREFIX_GC | PREFIX_MISC | PREFIX_SIMD | PREFIX_THREADS
+        ) {
+            let sub = read_index(&mut reader)?;
+            sub_opcode = Some(sub);
+            lookup_prefixed_opcode(opcode, sub)
+        } else {
+            sub_opcode = None;
+            lookup_opcode(opcode)
+        };
+
+        let (name, kind) = match info {
+            Some(info) => info,
+            None => {
+                let opcode_str = match sub_opcode {
+                    Some(sub) => format!("{:#04x} {}", opcode, sub),
+                    None => format!("{:#04x}", opcode),
+                };
+                return Err(ParseError::FormatError(format!(
+                    "unknown opcode: {} (offset={:#x})",
+                    opcode_str, offset
+                )));
+            }
+        };
+
+        let immediate = Immediate::parse(&mut reader, kind)?;
+
+        Ok(Self {
+            offset,
+            size: reader.get_read_size() as u32,
+            opcode,
+            sub_opcode,
+            name,
+            immediate,
+        })
+    }
+
+    /// 命令列の先頭からのオフセットを返す
+    pub fn get_offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// 命令のサイズ(オペコードと即値を含む)を返す
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    /// オペコードを返す
+    pub fn get_opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    /// プレフィックス付き命令のサブオペコードを返す
+    pub fn get_sub_opcode(&self) -> Option<u32> {
+        self.sub_opcode
+    }
+
+    /// テキスト表現での命令名を返す
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 即値を返す
+    pub fn get_immediate(&self) -> &Immediate {
+        &self.immediate
+    }
+
+    /// ブロックを開始する命令かどうか(block, loop, if, try, try_table)
+    pub fn is_block_start(&self) -> bool {
+        matches!(self.opcode, 0x02..=0x04 | 0x06 | 0x1F)
+    }
+
+    /// ブロックを終了する命令かどうか(end, delegate)
+    ///
+    /// delegateはtryブロックをendの代わりに閉じる
+    pub fn is_block_end(&self) -> bool {
+        matches!(self.opcode, 0x0B | 0x18)
+    }
+
+    /// end命令かどうか
+    pub fn is_end(&self) -> bool {
+        self.opcode == 0x0B
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let imm_str = self.immediate.to_string();
+        if imm_str.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} {}", self.name, imm_str)
+        }
+    }
+}
+
+impl Immediate {
+    pub fn parse<R: Read>(reader: &mut R, kind: ImmediateKind) -> Result<Self, ParseError> {
+        let immediate = match kind {
+            ImmediateKind::None => Immediate::None,
+            ImmediateKind::Block => Immediate::Block(BlockSignature::parse(reader)?),
+            ImmediateKind::Label => Immediate::Label(read_index(reader)?),
+            ImmediateKind::BrTable => {
+                let count = read_index(reader)?;
+                let mut targets: Vec<u32> = Vec::new();
+                for _ in 0..count {
+                    targets.push(read_index(reader)?);
+                }
+                let default = read_index(reader)?;
+                Immediate::BrTable { targets, default }
+            }
+            ImmediateKind::Function => Immediate::Function(read_index(reader)?),
+            ImmediateKind::CallIndirect => {
+                let type_index = read_index(reader)?;
+                // MVPでは予約バイト(0x00)
+                let table_index = read_index(reader)?;
+                Immediate::CallIndirect {
+                    type_index,
+                    table_index,
+                }
+            }
+            ImmediateKind::Local => Immediate::Local(read_index(reader)?),
+            ImmediateKind::Global => Immediate::Global(read_index(reader)?),
+            ImmediateKind::MemArg => Immediate::MemArg(MemArg::parse(reader)?),
+            // MVPでは予約バイト(0x00)だったが、multi-memory提案でメモリインデックスになった
+            ImmediateKind::Memory => Immediate::Memory(read_index(reader)?),
+            ImmediateKind::MemoryInit => {
+                let data_index = read_index(reader)?;
+                let memory_index = read_index(reader)?;
+                Immediate::MemoryInit {
+                    data_index,
+                    memory_index,
+                }
+            }
+            ImmediateKind::MemoryCopy => {
+                let dst_index = read_index(reader)?;
+                let src_index = read_index(reader)?;
+                Immediate::MemoryCopy {
+                    dst_index,
+                    src_index,
+                }
+            }
+            ImmediateKind::Data => Immediate::Data(read_index(reader)?),
+            ImmediateKind::Table => Immediate::Table(read_index(reader)?),
+            ImmediateKind::TableInit => {
+                let elem_index = read_index(reader)?;
+                let table_index = read_index(reader)?;
+                Immediate::TableInit {
+                    elem_index,
+                    table_index,
+                }
+            }
+            ImmediateKind::TableCopy => {
+                let dst_index = read_index(reader)?;
+                let src_index = read_index(reader)?;
+                Immediate::TableCopy {
+                    dst_index,
+                    src_index,
+                }
+            }
+            ImmediateKind::Elem => Immediate::Elem(read_index(reader)?),
+            ImmediateKind::Tag => Immediate::Tag(read_index(reader)?),
+            ImmediateKind::TryTable => {
+                let signature = BlockSignature::parse(reader)?;
+                let count = read_index(reader)?;
+                let mut catches: Vec<CatchClause> = Vec::new();
+                for _ in 0..count {
+                    catches.push(CatchClause::parse(reader)?);
+                }
+                Immediate::TryTable { signature, catches }
+            }
+            ImmediateKind::SelectTypes => {
+                let count = read_index(reader)?;
+                let mut types: Vec<LangTypes> = Vec::new();
+                for _ in 0..count {
+                    types.push(*ValueType::parse(reader)?.get_value());
+                }
+                Immediate::SelectTypes(types)
+            }
+            ImmediateKind::Type => Immediate::Type(read_index(reader)?),
+            ImmediateKind::Field => {
+                let type_index = read_index(reader)?;
+                let field_index = read_index(reader)?;
+                Immediate::Field {
+                    type_index,
+                    field_index,
+                }
+            }
+            ImmediateKind::ArrayFixed => {
+                let type_index = read_index(reader)?;
+                let length = read_index(reader)?;
+                Immediate::ArrayFixed { type_index, length }
+            }
+            ImmediateKind::ArrayData => {
+                let type_index = read_index(reader)?;
+                let data_index = read_index(reader)?;
+                Immediate::ArrayData {
+                    type_index,
+                    data_index,
+                }
+            }
+            ImmediateKind::ArrayElem => {
+                let type_index = read_index(reader)?;
+                let elem_index = read_index(reader)?;
+                Immediate::ArrayElem {
+                    type_index,
+                    elem_index,
+                }
+            }
+            ImmediateKind::ArrayCopy => {
+                let dst_index = read_index(reader)?;
+                let src_index = read_index(reader)?;
+                Immediate::ArrayCopy {
+                    dst_index,
+                    src_index,
+                }
+            }
+            ImmediateKind::HeapType => Immediate::HeapType(HeapType::parse(reader)?.0),
+            // null許容かどうかはオペコードで区別される (e.g. ref.test / ref.test null)
+            ImmediateKind::RefType | ImmediateKind::RefTypeNull => {
+                let (heap_type, _) = HeapType::parse(reader)?;
+                let nullable = kind == ImmediateKind::RefTypeNull;
+                Immediate::RefType(LangTypes::from_heap_type(nullable, heap_type))
+            }
+            ImmediateKind::BrOnCast => {
+                // bit0: 変換元がnull許容, bit1: 変換先がnull許容
+                let flags = match read_8(reader) {
+                    Ok(data) => data[0],
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                let label = read_index(reader)?;
+                let (from, _) = HeapType::parse(reader)?;
+                let (to, _) = HeapType::parse(reader)?;
+                Immediate::BrOnCast {
+                    label,
+                    from: LangTypes::from_heap_type(flags & 0x01 != 0, from),
+                    to: LangTypes::from_heap_type(flags & 0x02 != 0, to),
+                }
+            }
+            // atomic.fenceの予約バイト(0x00)
+            ImmediateKind::Reserved => {
+                let reserved = match read_8(reader) {
+                    Ok(data) => data[0],
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                if reserved != 0x00 {
+                    return Err(ParseError::FormatError(format!(
+                        "reserved byte must be 0x00: {:#04x}",
+                        reserved
+                    )));
+                }
+                Immediate::None
+            }
+            ImmediateKind::V128 => Immediate::V128(read_bytes16(reader)?),
+            ImmediateKind::Shuffle => Immediate::Shuffle(read_bytes16(reader)?),
+            ImmediateKind::Lane => Immediate::Lane(read_lane(reader)?),
+            ImmediateKind::MemArgLane => {
+                let memarg = MemArg::parse(reader)?;
+                let lane = read_lane(reader)?;
+                Immediate::MemArgLane { memarg, lane }
+            }
+            ImmediateKind::I32 => {
+                let mut v: i64 = 0;
+                match read_signed_leb128(reader, &mut v) {
+                    Ok(_rs) => (/* To check read size */),
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                Immediate::I32(v as i32)
+            }
+            ImmediateKind::I64 => {
+                let mut v: i64 = 0;
+                match read_signed_leb128(reader, &mut v) {
+                    Ok(_rs) => (/* To check read size */),
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                Immediate::I64(v)
+            }
+            ImmediateKind::F32 => match read_x(reader, 4) {
+                Ok(data) => Immediate::F32(LittleEndian::read_f32(&data)),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            },
+            ImmediateKind::F64 => match read_x(reader, 8) {
+                Ok(data) => Immediate::F64(LittleEndian::read_f64(&data)),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            },
+        };
+
+        Ok(immediate)
+    }
+}
+
+impl fmt::Display for Immediate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Immediate::None => Ok(()),
+            Immediate::Block(sig) => write!(f, "{}", sig),
+            Immediate::Label(idx) => write!(f, "{}", idx),
+            Immediate::BrTable { targets, default } => {
+                for target in targets.iter() {
+                    write!(f, "{} ", target)?;
+                }
+                write!(f, "{}", default)
+            }
+            Immediate::Function(idx) => write!(f, "{}", idx),
+            Immediate::CallIndirect {
+                type_index,
+                table_index,
+            } => {
+                if *table_index == 0 {
+                    write!(f, "(type {})", type_index)
+                } else {
+                    write!(f, "{} (type {})", table_index, type_index)
+                }
+            }
+            Immediate::Local(idx) => write!(f, "{}", idx),
+            Immediate::Global(idx) => write!(f, "{}", idx),
+            Immediate::MemArg(memarg) => write!(f, "{}", memarg),
+            // テキスト表現では、インデックス0のメモリ・テーブルは省略できる
+            Immediate::Memory(idx) | Immediate::Table(idx) => {
+                if *idx == 0 {
+                    Ok(())
+                } else {
+                    write!(f, "{}", idx)
+                }
+            }
+            Immediate::MemoryInit {
+                data_index,
+                memory_index,
+            } => {
+                if *memory_index == 0 {
+                    write!(f, "{}", data_index)
+                } else {
+                    write!(f, "{} {}", memory_index, data_index)
+                }
+            }
+            Immediate::TableInit {
+                elem_index,
+                table_index,
+            } => {
+                if *table_index == 0 {
+                    write!(f, "{}", elem_index)
+                } else {
+                    write!(f, "{} {}", table_index, elem_index)
+                }
+            }
+            Immediate::MemoryCopy {
+                dst_index,
+                src_index,
+            }
+            | Immediate::TableCopy {
+                dst_index,
+                src_index,
+            } => {
+                if *dst_index == 0 && *src_index == 0 {
+                    Ok(())
+                } else {
+                    write!(f, "{} {}", dst_index, src_index)
+                }
+            }
+            Immediate::Data(idx) | Immediate::Elem(idx) | Immediate::Tag(idx) => {
+                write!(f, "{}", idx)
+            }
+            Immediate::TryTable { signature, catches } => {
+                let mut parts: Vec<String> = Vec::new();
+                if *signature != BlockSignature::Empty {
+                    parts.push(signature.to_string());
+                }
+                parts.extend(catches.iter().map(|c| c.to_string()));
+                write!(f, "{}", parts.join(" "))
+            }
+            Immediate::SelectTypes(types) => {
+                let types_str: Vec<String> = types.iter().map(|ty| ty.to_string()).collect();
+                write!(f, "(result {})", types_str.join(" "))
+            }
+            Immediate::Type(idx) => write!(f, "{}", idx),
+            Immediate::Field {
+                type_index,
+                field_index,
+            } => write!(f, "{} {}", type_index, field_index),
+            Immediate::ArrayFixed { type_index, length } => write!(f, "{} {}", type_index, length),
+            Immediate::ArrayData {
+                type_index,
+                data_index,
+            } => write!(f, "{} {}", type_index, data_index),
+            Immediate::ArrayElem {
+                type_index,
+                elem_index,
+            } => write!(f, "{} {}", type_index, elem_index),
+            Immediate::ArrayCopy {
+                dst_index,
+                src_index,
+            } => write!(f, "{} {}", dst_index, src_index),
+            Immediate::HeapType(heap_type) => write!(f, "{}", heap_type),
+            Immediate::RefType(ty) => write!(f, "{}", ty),
+            Immediate::BrOnCast { label, from, to } => write!(f, "{} {} {}", label, from, to),
+            // テキスト表現に合わせて、i32x4として表示する
+            Immediate::V128(bytes) => {
+                let lanes: Vec<String> = bytes
+                    .chunks(4)
+                    .map(|x| format!("{:#010x}", LittleEndian::read_u32(x)))
+                    .collect();
+                write!(f, "i32x4 {}", lanes.join(" "))
+            }
+            Immediate::Shuffle(lanes) => {
+                let lanes: Vec<String> = lanes.iter().map(|x| x.to_string()).collect();
+                write!(f, "{}", lanes.join(" "))
+            }
+            Immediate::Lane(lane) => write!(f, "{}", lane),
+            Immediate::MemArgLane { memarg, lane } => write!(f, "{} {}", memarg, lane),
+            Immediate::I32(v) => write!(f, "{}", v),
+            Immediate::I64(v) => write!(f, "{}", v),
+            Immediate::F32(v) => write!(f, "{}", v),
+            Immediate::F64(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl BlockSignature {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        // 負の値(1byte)は値型または空(0x40)、非負の値は型インデックス(s33)
+        let mut v: i64 = 0;
+        match read_signed_leb128(reader, &mut v) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        if v >= 0 {
+            return Ok(BlockSignature::TypeIndex(v as u32));
+        }
+
+        // 参照型(0x64/0x63)の場合は続くヒープ型も読み込む
+        let (lang_type, _) = LangTypes::parse_with_head(reader, v, 1)?;
+        let block_type = BlockType::from_lang_type(lang_type)?;
+        match block_type.get_value() {
+            LangTypes::PSEUDO => Ok(BlockSignature::Empty),
+            ty => Ok(BlockSignature::Value(*ty)),
+        }
+    }
+}
+
+impl fmt::Display for BlockSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockSignature::Empty => Ok(()),
+            BlockSignature::Value(ty) => write!(f, "(result {})", ty),
+            BlockSignature::TypeIndex(idx) => write!(f, "(type {})", idx),
+        }
+    }
+}
+
+impl CatchClause {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let kind = match read_8(reader) {
+            Ok(data) => data[0],
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let clause = match kind {
+            0x00 => {
+                let tag_index = read_index(reader)?;
+                let label = read_index(reader)?;
+                CatchClause::Catch { tag_index, label }
+            }
+            0x01 => {
+                let tag_index = read_index(reader)?;
+                let label = read_index(reader)?;
+                CatchClause::CatchRef { tag_index, label }
+            }
+            0x02 => CatchClause::CatchAll {
+                label: read_index(reader)?,
+            },
+            0x03 => CatchClause::CatchAllRef {
+                label: read_index(reader)?,
+            },
+            _ => {
+                return Err(ParseError::FormatError(format!(
+                    "unknown catch clause: kind={:#04x}",
+                    kind
+                )))
+            }
+        };
+
+        Ok(clause)
+    }
+
+    /// catch節が参照するタグのインデックスを返す(catch_all系ではNone)
+    pub fn get_tag_index(&self) -> Option<u32> {
+        match self {
+            CatchClause::Catch { tag_index, .. } | CatchClause::CatchRef { tag_index, .. } => {
+                Some(*tag_index)
+            }
+            CatchClause::CatchAll { .. } | CatchClause::CatchAllRef { .. } => None,
+        }
+    }
+
+    /// 例外を捕捉したときの分岐先ラベルを返す
+    pub fn get_label(&self) -> u32 {
+        match self {
+            CatchClause::Catch { label, .. }
+            | CatchClause::CatchRef { label, .. }
+            | CatchClause::CatchAll { label }
+            | CatchClause::CatchAllRef { label } => *label,
+        }
+    }
+}
+
+impl fmt::Display for CatchClause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatchClause::Catch { tag_index, label } => write!(f, "(catch {} {})", tag_index, label),
+            CatchClause::CatchRef { tag_index, label } => {
+                write!(f, "(catch_ref {} {})", tag_index, label)
+            }
+            CatchClause::CatchAll { label } => write!(f, "(catch_all {})", label),
+            CatchClause::CatchAllRef { label } => write!(f, "(catch_all_ref {})", label),
+        }
+    }
+}
+
+impl MemArg {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut align: u64 = 0; // VarUInt32
+        match read_unsigned_leb128(reader, &mut align) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let memory_index = if align & MEMARG_FLAG_MEMORY_INDEX != 0 {
+            align &= !MEMARG_FLAG_MEMORY_INDEX;
+            read_index(reader)?
+        } else {
+            0
+        };
+
+        // memory64ではオフセットがu64になるため、ここではu64として読み込む
+        // インデックス型に対する範囲チェックはvalidatorで行う
+        let mut offset: u64 = 0;
+        match read_unsigned_leb128(reader, &mut offset) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Ok(Self {
+            align: align as u32,
+            memory_index,
+            offset,
+        })
+    }
+
+    /// アラインメント(log2)を返す
+    pub fn get_align(&self) -> u32 {
+        self.align
+    }
+
+    /// アクセス対象のメモリインデックスを返す
+    ///
+    /// multi-memory提案以前は常に0
+    pub fn get_memory_index(&self) -> u32 {
+        self.memory_index
+    }
+
+    /// オフセットを返す
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl fmt::Display for MemArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.memory_index != 0 {
+            write!(f, "{} ", self.memory_index)?;
+        }
+        if self.offset != 0 {
+            write!(f, "offset={} ", self.offset)?;
+        }
+        write!(f, "align={}", 1u64 << self.align.min(63))
+    }
+}
+
+/// バイト列に含まれる命令をすべてデコードする
+pub fn decode_instructions(bytes: &[u8]) -> Result<Vec<Instruction>, ParseError> {
+    let mut reader = Cursor::new(bytes);
+    let mut instrs: Vec<Instruction> = Vec::new();
+
+    while (reader.position() as usize) < bytes.len() {
+        let offset = reader.position() as u32;
+        instrs.push(Instruction::parse(&mut reader, offset)?);
+    }
+
+    Ok(instrs)
+}
+
+fn read_lane<R: Read>(reader: &mut R) -> Result<u8, ParseError> {
+    match read_8(reader) {
+        Ok(data) => Ok(data[0]),
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
+}
+
+fn read_bytes16<R: Read>(reader: &mut R) -> Result<[u8; 16], ParseError> {
+    let mut bytes: [u8; 16] = [0; 16];
+    match reader.read_exact(&mut bytes) {
+        Ok(_) => Ok(bytes),
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
+}
+
+fn read_index<R: Read>(reader: &mut R) -> Result<u32, ParseError> {
+    let mut index: u64 = 0; // VarUInt32
+    match read_unsigned_leb128(reader, &mut index) {
+        Ok(_rs) => (/* To check read size */),
+        Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+    };
+    // インデックスはu32で表す (それを超える値は切り捨てずにエラーにする)
+    if index > u32::MAX as u64 {
+        return Err(ParseError::FormatError(format!(
+            "index out of range for u32: {}",
+            index
+        )));
+    }
+    Ok(index as u32)
+}
+
+// 0x45 (i32.eqz) - 0xBF (f64.reinterpret_i64)
+const NUMERIC_OPCODE_BASE: u8 = 0x45;
+const NUMERIC_OPCODE_NAMES: [&str; 123] = [
+    "i32.eqz",
+    "i32.eq",
+    "i32.ne",
+    "i32.lt_s",
+    "i32.lt_u",
+    "i32.gt_s",
+    "i32.gt_u",
+    "i32.le_s",
+    "i32.le_u",
+    "i32.ge_s",
+    "i32.ge_u",
+    "i64.eqz",
+    "i64.eq",
+    "i64.ne",
+    "i64.lt_s",
+    "i64.lt_u",
+    "i64.gt_s",
+    "i64.gt_u",
+    "i64.le_s",
+    "i64.le_u",
+    "i64.ge_s",
+    "i64.ge_u",
+    "f32.eq",
+    "f32.ne",
+    "f32.lt",
+    "f32.gt",
+    "f32.le",
+    "f32.ge",
+    "f64.eq",
+    "f64.ne",
+    "f64.lt",
+    "f64.gt",
+    "f64.le",
+    "f64.ge",
+    "i32.clz",
+    "i32.ctz",
+    "i32.popcnt",
+    "i32.add",
+    "i32.sub",
+    "i32.mul",
+    "i32.div_s",
+    "i32.div_u",
+    "i32.rem_s",
+    "i32.rem_u",
+    "i32.and",
+    "i32.or",
+    "i32.xor",
+    "i32.shl",
+    "i32.shr_s",
+    "i32.shr_u",
+    "i32.rotl",
+    "i32.rotr",
+    "i64.clz",
+    "i64.ctz",
+    "i64.popcnt",
+    "i64.add",
+    "i64.sub",
+    "i64.mul",
+    "i64.div_s",
+    "i64.div_u",
+    "i64.rem_s",
+    "i64.rem_u",
+    "i64.and",
+    "i64.or",
+    "i64.xor",
+    "i64.shl",
+    "i64.shr_s",
+    "i64.shr_u",
+    "i64.rotl",
+    "i64.rotr",
+    "f32.abs",
+    "f32.neg",
+    "f32.ceil",
+    "f32.floor",
+    "f32.trunc",
+    "f32.nearest",
+    "f32.sqrt",
+    "f32.add",
+    "f32.sub",
+    "f32.mul",
+    "f32.div",
+    "f32.min",
+    "f32.max",
+    "f32.copysign",
+    "f64.abs",
+    "f64.neg",
+    "f64.ceil",
+    "f64.floor",
+    "f64.trunc",
+    "f64.nearest",
+    "f64.sqrt",
+    "f64.add",
+    "f64.sub",
+    "f64.mul",
+    "f64.div",
+    "f64.min",
+    "f64.max",
+    "f64.copysign",
+    "i32.wrap_i64",
+    "i32.trunc_f32_s",
+    "i32.trunc_f32_u",
+    "i32.trunc_f64_s",
+    "i32.trunc_f64_u",
+    "i64.extend_i32_s",
+    "i64.extend_i32_u",
+    "i64.trunc_f32_s",
+    "i64.trunc_f32_u",
+    "i64.trunc_f64_s",
+    "i64.trunc_f64_u",
+    "f32.convert_i32_s",
+    "f32.convert_i32_u",
+    "f32.convert_i64_s",
+    "f32.convert_i64_u",
+    "f32.demote_f64",
+    "f64.convert_i32_s",
+    "f64.convert_i32_u",
+    "f64.convert_i64_s",
+    "f64.convert_i64_u",
+    "f64.promote_f32",
+    "i32.reinterpret_f32",
+    "i64.reinterpret_f64",
+    "f32.reinterpret_i32",
+    "f64.reinterpret_i64",
+];
+
+// 0xFD 0 (v128.load) - 0xFD 255 (f64x2.convert_low_i32x4_u)
+// 予約されている(未割り当ての)サブオペコードは空文字列
+const SIMD_OPCODE_NAMES: [&str; 256] = [
+    "v128.load",
+    "v128.load8x8_s",
+    "v128.load8x8_u",
+    "v128.load16x4_s",
+    "v128.load16x4_u",
+    "v128.load32x2_s",
+    "v128.load32x2_u",
+    "v128.load8_splat",
+    "v128.load16_splat",
+    "v128.load32_splat",
+    "v128.load64_splat",
+    "v128.store",
+    "v128.const",
+    "i8x16.shuffle",
+    "i8x16.swizzle",
+    "i8x16.splat",
+    "i16x8.splat",
+    "i32x4.splat",
+    "i64x2.splat",
+    "f32x4.splat",
+    "f64x2.splat",
+    "i8x16.extract_lane_s",
+    "i8x16.extract_lane_u",
+    "i8x16.replace_lane",
+    "i16x8.extract_lane_s",
+    "i16x8.extract_lane_u",
+    "i16x8.replace_lane",
+    "i32x4.extract_lane",
+    "i32x4.replace_lane",
+    "i64x2.extract_lane",
+    "i64x2.replace_lane",
+    "f32x4.extract_lane",
+    "f32x4.replace_lane",
+    "f64x2.extract_lane",
+    "f64x2.replace_lane",
+    "i8x16.eq",
+    "i8x16.ne",
+    "i8x16.lt_s",
+    "i8x16.lt_u",
+    "i8x16.gt_s",
+    "i8x16.gt_u",
+    "i8x16.le_s",
+    "i8x16.le_u",
+    "i8x16.ge_s",
+    "i8x16.ge_u",
+    "i16x8.eq",
+    "i16x8.ne",
+    "i16x8.lt_s",
+    "i16x8.lt_u",
+    "i16x8.gt_s",
+    "i16x8.gt_u",
+    "i16x8.le_s",
+    "i16x8.le_u",
+    "i16x8.ge_s",
+    "i16x8.ge_u",
+    "i32x4.eq",
+    "i32x4.ne",
+    "i32x4.lt_s",
+    "i32x4.lt_u",
+    "i32x4.gt_s",
+    "i32x4.gt_u",
+    "i32x4.le_s",
+    "i32x4.le_u",
+    "i32x4.ge_s",
+    "i32x4.ge_u",
+    "f32x4.eq",
+    "f32x4.ne",
+    "f32x4.lt",
+    "f32x4.gt",
+    "f32x4.le",
+    "f32x4.ge",
+    "f64x2.eq",
+    "f64x2.ne",
+    "f64x2.lt",
+    "f64x2.gt",
+    "f64x2.le",
+    "f64x2.ge",
+    "v128.not",
+    "v128.and",
+    "v128.andnot",
+    "v128.or",
+    "v128.xor",
+    "v128.bitselect",
+    "v128.any_true",
+    "v128.load8_lane",
+    "v128.load16_lane",
+    "v128.load32_lane",
+    "v128.load64_lane",
+    "v128.store8_lane",
+    "v128.store16_lane",
+    "v128.store32_lane",
+    "v128.store64_lane",
+    "v128.load32_zero",
+    "v128.load64_zero",
+    "f32x4.demote_f64x2_zero",
+    "f64x2.promote_low_f32x4",
+    "i8x16.abs",
+    "i8x16.neg",
+    "i8x16.popcnt",
+    "i8x16.all_true",
+    "i8x16.bitmask",
+    "i8x16.narrow_i16x8_s",
+    "i8x16.narrow_i16x8_u",
+    "f32x4.ceil",
+    "f32x4.floor",
+    "f32x4.trunc",
+    "f32x4.nearest",
+    "i8x16.shl",
+    "i8x16.shr_s",
+    "i8x16.shr_u",
+    "i8x16.add",
+    "i8x16.add_sat_s",
+    "i8x16.add_sat_u",
+    "i8x16.sub",
+    "i8x16.sub_sat_s",
+    "i8x16.sub_sat_u",
+    "f64x2.ceil",
+    "f64x2.floor",
+    "i8x16.min_s",
+    "i8x16.min_u",
+    "i8x16.max_s",
+    "i8x16.max_u",
+    "f64x2.trunc",
+    "i8x16.avgr_u",
+    "i16x8.extadd_pairwise_i8x16_s",
+    "i16x8.extadd_pairwise_i8x16_u",
+    "i32x4.extadd_pairwise_i16x8_s",
+    "i32x4.extadd_pairwise_i16x8_u",
+    "i16x8.abs",
+    "i16x8.neg",
+    "i16x8.q15mulr_sat_s",
+    "i16x8.all_true",
+    "i16x8.bitmask",
+    "i16x8.narrow_i32x4_s",
+    "i16x8.narrow_i32x4_u",
+    "i16x8.extend_low_i8x16_s",
+    "i16x8.extend_high_i8x16_s",
+    "i16x8.extend_low_i8x16_u",
+    "i16x8.extend_high_i8x16_u",
+    "i16x8.shl",
+    "i16x8.shr_s",
+    "i16x8.shr_u",
+    "i16x8.add",
+    "i16x8.add_sat_s",
+    "i16x8.add_sat_u",
+    "i16x8.sub",
+    "i16x8.sub_sat_s",
+    "i16x8.sub_sat_u",
+    "f64x2.nearest",
+    "i16x8.mul",
+    "i16x8.min_s",
+    "i16x8.min_u",
+    "i16x8.max_s",
+    "i16x8.max_u",
+    "",
+    "i16x8.avgr_u",
+    "i16x8.extmul_low_i8x16_s",
+    "i16x8.extmul_high_i8x16_s",
+    "i16x8.extmul_low_i8x16_u",
+    "i16x8.extmul_high_i8x16_u",
+    "i32x4.abs",
+    "i32x4.neg",
+    "",
+    "i32x4.all_true",
+    "i32x4.bitmask",
+    "",
+    "",
+    "i32x4.extend_low_i16x8_s",
+    "i32x4.extend_high_i16x8_s",
+    "i32x4.extend_low_i16x8_u",
+    "i32x4.extend_high_i16x8_u",
+    "i32x4.shl",
+    "i32x4.shr_s",
+    "i32x4.shr_u",
+    "i32x4.add",
+    "",
+    "",
+    "i32x4.sub",
+    "",
+    "",
+    "",
+    "i32x4.mul",
+    "i32x4.min_s",
+    "i32x4.min_u",
+    "i32x4.max_s",
+    "i32x4.max_u",
+    "i32x4.dot_i16x8_s",
+    "",
+    "i32x4.extmul_low_i16x8_s",
+    "i32x4.extmul_high_i16x8_s",
+    "i32x4.extmul_low_i16x8_u",
+    "i32x4.extmul_high_i16x8_u",
+    "i64x2.abs",
+    "i64x2.neg",
+    "",
+    "i64x2.all_true",
+    "i64x2.bitmask",
+    "",
+    "",
+    "i64x2.extend_low_i32x4_s",
+    "i64x2.extend_high_i32x4_s",
+    "i64x2.extend_low_i32x4_u",
+    "i64x2.extend_high_i32x4_u",
+    "i64x2.shl",
+    "i64x2.shr_s",
+    "i64x2.shr_u",
+    "i64x2.add",
+    "",
+    "",
+    "i64x2.sub",
+    "",
+    "",
+    "",
+    "i64x2.mul",
+    "i64x2.eq",
+    "i64x2.ne",
+    "i64x2.lt_s",
+    "i64x2.gt_s",
+    "i64x2.le_s",
+    "i64x2.ge_s",
+    "i64x2.extmul_low_i32x4_s",
+    "i64x2.extmul_high_i32x4_s",
+    "i64x2.extmul_low_i32x4_u",
+    "i64x2.extmul_high_i32x4_u",
+    "f32x4.abs",
+    "f32x4.neg",
+    "",
+    "f32x4.sqrt",
+    "f32x4.add",
+    "f32x4.sub",
+    "f32x4.mul",
+    "f32x4.div",
+    "f32x4.min",
+    "f32x4.max",
+    "f32x4.pmin",
+    "f32x4.pmax",
+    "f64x2.abs",
+    "f64x2.neg",
+    "",
+    "f64x2.sqrt",
+    "f64x2.add",
+    "f64x2.sub",
+    "f64x2.mul",
+    "f64x2.div",
+    "f64x2.min",
+    "f64x2.max",
+    "f64x2.pmin",
+    "f64x2.pmax",
+    "i32x4.trunc_sat_f32x4_s",
+    "i32x4.trunc_sat_f32x4_u",
+    "f32x4.convert_i32x4_s",
+    "f32x4.convert_i32x4_u",
+    "i32x4.trunc_sat_f64x2_s_zero",
+    "i32x4.trunc_sat_f64x2_u_zero",
+    "f64x2.convert_low_i32x4_s",
+    "f64x2.convert_low_i32x4_u",
+];
+
+// 0xFE 0x10 (i32.atomic.load) - 0xFE 0x4E (i64.atomic.rmw32.cmpxchg_u)
+const ATOMIC_MEMORY_OPCODE_BASE: u32 = 0x10;
+const ATOMIC_MEMORY_OPCODE_NAMES: [&str; 63] = [
+    "i32.atomic.load",
+    "i64.atomic.load",
+    "i32.atomic.load8_u",
+    "i32.atomic.load16_u",
+    "i64.atomic.load8_u",
+    "i64.atomic.load16_u",
+    "i64.atomic.load32_u",
+    "i32.atomic.store",
+    "i64.atomic.store",
+    "i32.atomic.store8",
+    "i32.atomic.store16",
+    "i64.atomic.store8",
+    "i64.atomic.store16",
+    "i64.atomic.store32",
+    "i32.atomic.rmw.add",
+    "i64.atomic.rmw.add",
+    "i32.atomic.rmw8.add_u",
+    "i32.atomic.rmw16.add_u",
+    "i64.atomic.rmw8.add_u",
+    "i64.atomic.rmw16.add_u",
+    "i64.atomic.rmw32.add_u",
+    "i32.atomic.rmw.sub",
+    "i64.atomic.rmw.sub",
+    "i32.atomic.rmw8.sub_u",
+    "i32.atomic.rmw16.sub_u",
+    "i64.atomic.rmw8.sub_u",
+    "i64.atomic.rmw16.sub_u",
+    "i64.atomic.rmw32.sub_u",
+    "i32.atomic.rmw.and",
+    "i64.atomic.rmw.and",
+    "i32.atomic.rmw8.and_u",
+    "i32.atomic.rmw16.and_u",
+    "i64.atomic.rmw8.and_u",
+    "i64.atomic.rmw16.and_u",
+    "i64.atomic.rmw32.and_u",
+    "i32.atomic.rmw.or",
+    "i64.atomic.rmw.or",
+    "i32.atomic.rmw8.or_u",
+    "i32.atomic.rmw16.or_u",
+    "i64.atomic.rmw8.or_u",
+    "i64.atomic.rmw16.or_u",
+    "i64.atomic.rmw32.or_u",
+    "i32.atomic.rmw.xor",
+    "i64.atomic.rmw.xor",
+    "i32.atomic.rmw8.xor_u",
+    "i32.atomic.rmw16.xor_u",
+    "i64.atomic.rmw8.xor_u",
+    "i64.atomic.rmw16.xor_u",
+    "i64.atomic.rmw32.xor_u",
+    "i32.atomic.rmw.xchg",
+    "i64.atomic.rmw.xchg",
+    "i32.atomic.rmw8.xchg_u",
+    "i32.atomic.rmw16.xchg_u",
+    "i64.atomic.rmw8.xchg_u",
+    "i64.atomic.rmw16.xchg_u",
+    "i64.atomic.rmw32.xchg_u",
+    "i32.atomic.rmw.cmpxchg",
+    "i64.atomic.rmw.cmpxchg",
+    "i32.atomic.rmw8.cmpxchg_u",
+    "i32.atomic.rmw16.cmpxchg_u",
+    "i64.atomic.rmw8.cmpxchg_u",
+    "i64.atomic.rmw16.cmpxchg_u",
+    "i64.atomic.rmw32.cmpxchg_u",
+];
+
+/// オペコードから命令名と即値の種類を引く
+fn lookup_opcode(opcode: u8) -> Option<(&'static str, ImmediateKind)> {
+    let info = match opcode {
+        // Control instructions
+        0x00 => ("unreachable", ImmediateKind::None),
+        0x01 => ("nop", ImmediateKind::None),
+        0x02 => ("block", ImmediateKind::Block),
+        0x03 => ("loop", ImmediateKind::Block),
+        0x04 => ("if", ImmediateKind::Block),
+        0x05 => ("else", ImmediateKind::None),
+        0x0B => ("end", ImmediateKind::None),
+        0x0C => ("br", ImmediateKind::Label),
+        0x0D => ("br_if", ImmediateKind::Label),
+        0x0E => ("br_table", ImmediateKind::BrTable),
+        0x0F => ("return", ImmediateKind::None),
+        0x10 => ("call", ImmediateKind::Function),
+        0x11 => ("call_indirect", ImmediateKind::CallIndirect),
+
+        // Tail call instructions
+        0x12 => ("return_call", ImmediateKind::Function),
+        0x13 => ("return_call_indirect", ImmediateKind::CallIndirect),
+
+        // Typed function references instructions
+        0x14 => ("call_ref", ImmediateKind::Type),
+        0x15 => ("return_call_ref", ImmediateKind::Type),
+        0xD3 => ("ref.as_non_null", ImmediateKind::None),
+        0xD4 => ("br_on_null", ImmediateKind::Label),
+        0xD6 => ("br_on_non_null", ImmediateKind::Label),
+
+        // Exception handling instructions
+        0x08 => ("throw", ImmediateKind::Tag),
+        0x0A => ("throw_ref", ImmediateKind::None),
+        0x1F => ("try_table", ImmediateKind::TryTable),
+
+        // Legacy exception handling instructions
+        0x06 => ("try", ImmediateKind::Block),
+        0x07 => ("catch", ImmediateKind::Tag),
+        0x09 => ("rethrow", ImmediateKind::Label),
+        0x18 => ("delegate", ImmediateKind::Label),
+        0x19 => ("catch_all", ImmediateKind::None),
+
+        // Parametric instructions
+        0x1A => ("drop", ImmediateKind::None),
+        0x1B => ("select", ImmediateKind::None),
+        0x1C => ("select", ImmediateKind::SelectTypes),
+
+        // Variable instructions
+        0x20 => ("local.get", ImmediateKind::Local),
+        0x21 => ("local.set", ImmediateKind::Local),
+        0x22 => ("local.tee", ImmediateKind::Local),
+        0x23 => ("global.get", ImmediateKind::Global),
+        0x24 => ("global.set", ImmediateKind::Global),
+
+        // Table instructions
+        0x25 => ("table.get", ImmediateKind::Table),
+        0x26 => ("table.set", ImmediateKind::Table),
+
+        // Memory instructions
+        0x28 => ("i32.load", ImmediateKind::MemArg),
+        0x29 => ("i64.load", ImmediateKind::MemArg),
+        0x2A => ("f32.load", ImmediateKind::MemArg),
+        0x2B => ("f64.load", ImmediateKind::MemArg),
+        0x2C => ("i32.load8_s", ImmediateKind::MemArg),
+        0x2D => ("i32.load8_u", ImmediateKind::MemArg),
+        0x2E => ("i32.load16_s", ImmediateKind::MemArg),
+        0x2F => ("i32.load16_u", ImmediateKind::MemArg),
+        0x30 => ("i64.load8_s", ImmediateKind::MemArg),
+        0x31 => ("i64.load8_u", ImmediateKind::MemArg),
+        0x32 => ("i64.load16_s", ImmediateKind::MemArg),
+        0x33 => ("i64.load16_u", ImmediateKind::MemArg),
+        0x34 => ("i64.load32_s", ImmediateKind::MemArg),
+        0x35 => ("i64.load32_u", ImmediateKind::MemArg),
+        0x36 => ("i32.store", ImmediateKind::MemArg),
+        0x37 => ("i64.store", ImmediateKind::MemArg),
+        0x38 => ("f32.store", ImmediateKind::MemArg),
+        0x39 => ("f64.store", ImmediateKind::MemArg),
+        0x3A => ("i32.store8", ImmediateKind::MemArg),
+        0x3B => ("i32.store16", ImmediateKind::MemArg),
+        0x3C => ("i64.store8", ImmediateKind::MemArg),
+        0x3D => ("i64.store16", ImmediateKind::MemArg),
+        0x3E => ("i64.store32", ImmediateKind::MemArg),
+        0x3F => ("memory.size", ImmediateKind::Memory),
+        0x40 => ("memory.grow", ImmediateKind::Memory),
+
+        // Numeric instructions
+        0x41 => ("i32.const", ImmediateKind::I32),
+        0x42 => ("i64.const", ImmediateKind::I64),
+        0x43 => ("f32.const", ImmediateKind::F32),
+        0x44 => ("f64.const", ImmediateKind::F64),
+        0x45..=0xBF => (
+            NUMERIC_OPCODE_NAMES[(opcode - NUMERIC_OPCODE_BASE) as usize],
+            ImmediateKind::None,
+        ),
+
+        // Sign-extension instructions
+        0xC0 => ("i32.extend8_s", ImmediateKind::None),
+        0xC1 => ("i32.extend16_s", ImmediateKind::None),
+        0xC2 => ("i64.extend8_s", ImmediateKind::None),
+        0xC3 => ("i64.extend16_s", ImmediateKind::None),
+        0xC4 => ("i64.extend32_s", ImmediateKind::None),
+
+        // Reference instructions
+        0xD0 => ("ref.null", ImmediateKind::HeapType),
+        0xD1 => ("ref.is_null", ImmediateKind::None),
+        0xD2 => ("ref.func", ImmediateKind::Function),
+        0xD5 => ("ref.eq", ImmediateKind::None),
+
+        _ => return None,
+    };
+
+    Some(info)
+}
+
+/// プレフィックス付きのオペコードから命令名と即値の種類を引く
+fn lookup_prefixed_opcode(prefix: u8, sub_opcode: u32) -> Option<(&'static str, ImmediateKind)> {
+    let info = match (prefix, sub_opcode) {
+        // GC instructions
+        (PREFIX_GC, 0) => ("struct.new", ImmediateKind::Type),
+        (PREFIX_GC, 1) => ("struct.new_default", ImmediateKind::Type),
+        (PREFIX_GC, 2) => ("struct.get", ImmediateKind::Field),
+        (PREFIX_GC, 3) => ("struct.get_s", ImmediateKind::Field),
+        (PREFIX_GC, 4) => ("struct.get_u", ImmediateKind::Field),
+        (PREFIX_GC, 5) => ("struct.set", ImmediateKind::Field),
+        (PREFIX_GC, 6) => ("array.new", ImmediateKind::Type),
+        (PREFIX_GC, 7) => ("array.new_default", ImmediateKind::Type),
+        (PREFIX_GC, 8) => ("array.new_fixed", ImmediateKind::ArrayFixed),
+        (PREFIX_GC, 9) => ("array.new_data", ImmediateKind::ArrayData),
+        (PREFIX_GC, 10) => ("array.new_elem", ImmediateKind::ArrayElem),
+        (PREFIX_GC, 11) => ("array.get", ImmediateKind::Type),
+        (PREFIX_GC, 12) => ("array.get_s", ImmediateKind::Type),
+        (PREFIX_GC, 13) => ("array.get_u", ImmediateKind::Type),
+        (PREFIX_GC, 14) => ("array.set", ImmediateKind::Type),
+        (PREFIX_GC, 15) => ("array.len", ImmediateKind::None),
+        (PREFIX_GC, 16) => ("array.fill", ImmediateKind::Type),
+        (PREFIX_GC, 17) => ("array.copy", ImmediateKind::ArrayCopy),
+        (PREFIX_GC, 18) => ("array.init_data", ImmediateKind::ArrayData),
+        (PREFIX_GC, 19) => ("array.init_elem", ImmediateKind::ArrayElem),
+        (PREFIX_GC, 20) => ("ref.test", ImmediateKind::RefType),
+        (PREFIX_GC, 21) => ("ref.test", ImmediateKind::RefTypeNull),
+        (PREFIX_GC, 22) => ("ref.cast", ImmediateKind::RefType),
+        (PREFIX_GC, 23) => ("ref.cast", ImmediateKind::RefTypeNull),
+        (PREFIX_GC, 24) => ("br_on_cast", ImmediateKind::BrOnCast),
+        (PREFIX_GC, 25) => ("br_on_cast_fail", ImmediateKind::BrOnCast),
+        (PREFIX_GC, 26) => ("any.convert_extern", ImmediateKind::None),
+        (PREFIX_GC, 27) => ("extern.convert_any", ImmediateKind::None),
+        (PREFIX_GC, 28) => ("ref.i31", ImmediateKind::None),
+        (PREFIX_GC, 29) => ("i31.get_s", ImmediateKind::None),
+        (PREFIX_GC, 30) => ("i31.get_u", ImmediateKind::None),
+
+        // Saturating float-to-int instructions
+        (PREFIX_MISC, 0) => ("i32.trunc_sat_f32_s", ImmediateKind::None),
+        (PREFIX_MISC, 1) => ("i32.trunc_sat_f32_u", ImmediateKind::None),
+        (PREFIX_MISC, 2) => ("i32.trunc_sat_f64_s", ImmediateKind::None),
+        (PREFIX_MISC, 3) => ("i32.trunc_sat_f64_u", ImmediateKind::None),
+        (PREFIX_MISC, 4) => ("i64.trunc_sat_f32_s", ImmediateKind::None),
+        (PREFIX_MISC, 5) => ("i64.trunc_sat_f32_u", ImmediateKind::None),
+        (PREFIX_MISC, 6) => ("i64.trunc_sat_f64_s", ImmediateKind::None),
+        (PREFIX_MISC, 7) => ("i64.trunc_sat_f64_u", ImmediateKind::None),
+
+        // Bulk memory instructions
+        (PREFIX_MISC, 8) => ("memory.init", ImmediateKind::MemoryInit),
+        (PREFIX_MISC, 9) => ("data.drop", ImmediateKind::Data),
+        (PREFIX_MISC, 10) => ("memory.copy", ImmediateKind::MemoryCopy),
+        (PREFIX_MISC, 11) => ("memory.fill", ImmediateKind::Memory),
+        (PREFIX_MISC, 12) => ("table.init", ImmediateKind::TableInit),
+        (PREFIX_MISC, 13) => ("elem.drop", ImmediateKind::Elem),
+        (PREFIX_MISC, 14) => ("table.copy", ImmediateKind::TableCopy),
+        (PREFIX_MISC, 15) => ("table.grow", ImmediateKind::Table),
+        (PREFIX_MISC, 16) => ("table.size", ImmediateKind::Table),
+        (PREFIX_MISC, 17) => ("table.fill", ImmediateKind::Table),
+
+        // SIMD instructions
+        (PREFIX_SIMD, 0..=255) => {
+            let name = SIMD_OPCODE_NAMES[sub_opcode as usize];
+            if name.is_empty() {
+                return None;
+            }
+            let kind = match sub_opcode {
+                12 => ImmediateKind::V128,
+                13 => ImmediateKind::Shuffle,
+                21..=34 => ImmediateKind::Lane,
+                84..=91 => ImmediateKind::MemArgLane,
+                0..=11 | 92 | 93 => ImmediateKind::MemArg,
+                _ => ImmediateKind::None,
+            };
+            (name, kind)
+        }
+
+        // Atomic memory instructions
+        (PREFIX_THREADS, 0) => ("memory.atomic.notify", ImmediateKind::MemArg),
+        (PREFIX_THREADS, 1) => ("memory.atomic.wait32", ImmediateKind::MemArg),
+        (PREFIX_THREADS, 2) => ("memory.atomic.wait64", ImmediateKind::MemArg),
+        (PREFIX_THREADS, 3) => ("atomic.fence", ImmediateKind::Reserved),
+        (PREFIX_THREADS, 0x10..=0x4E) => (
+            ATOMIC_MEMORY_OPCODE_NAMES[(sub_opcode - ATOMIC_MEMORY_OPCODE_BASE) as usize],
+            ImmediateKind::MemArg,
+        ),
+
+        _ => return None,
+    };
+
+    Some(info)
+}
@@ -1,5 +1,8 @@
 use std::io::{Read, Seek};
 
+use sha2::{Digest, Sha256};
+
+use crate::features::WasmFeatures;
 use crate::parser::Parser;
 use crate::wasm_components::base::Sizeof;
 use crate::wasm_components::sections::*;
@@ -18,13 +21,104 @@ pub struct WasmModule {
     pub element_section: Option<ElementSection>,
     pub code_section: Option<CodeSection>,
     pub data_section: Option<DataSection>,
+    pub data_count_section: Option<DataCountSection>,
+    pub tag_section: Option<TagSection>,
     pub custom_sections: Vec<CustomSection>,
+    sections: Vec<SectionInfo>, // 出現順のセクションの位置・大きさ(未知のセクションも含む)
+}
+
+/// セクションの位置と大きさ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionInfo {
+    id: u8,
+    offset: u64,              // ファイル先頭からのセクションの位置
+    payload_offset: u64,      // ファイル先頭からのペイロードの位置
+    size: u64,                // セクション全体(ID・大きさを含む)の大きさ
+    digest: Option<[u8; 32]>, // セクション全体のバイト列のハッシュ(SHA-256)。記録を有効にした場合のみ
+}
+
+impl SectionInfo {
+    pub fn new(id: u8, offset: u64, payload_offset: u64, size: u64) -> Self {
+        Self {
+            id,
+            offset,
+            payload_offset,
+            size,
+            digest: None,
+        }
+    }
+
+    /// セクション全体のバイト列のハッシュを設定する
+    pub fn with_digest(self, digest: [u8; 32]) -> Self {
+        Self {
+            digest: Some(digest),
+            ..self
+        }
+    }
+
+    /// セクションIDを返す
+    pub fn get_id(&self) -> u8 {
+        self.id
+    }
+
+    /// セクションの位置(ファイル先頭からのオフセット)を返す
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// ペイロードの位置(ファイル先頭からのオフセット)を返す
+    ///
+    /// セクションの大きさがパディングされたLEB128で書かれている場合(オブジェクトファイル)も正しい位置になる
+    pub fn get_payload_offset(&self) -> u64 {
+        self.payload_offset
+    }
+
+    /// セクション全体(ID・大きさを含む)の大きさを返す
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// セクション全体のバイト列のハッシュを返す(記録していない場合はNone)
+    pub fn get_digest(&self) -> Option<&[u8; 32]> {
+        self.digest.as_ref()
+    }
+}
+
+/// content_hashの対象から除くセクション
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentHashOptions {
+    /// すべてのカスタムセクションを除く
+    pub exclude_custom_sections: bool,
+    /// デバッグ情報のカスタムセクション(is_debug_section_name)とbuild_idを除く
+    ///
+    /// build_idは通常デバッグ情報を含む内容から求められるため、あわせて除く
+    pub exclude_debug_sections: bool,
 }
 
 impl WasmModule {
+    /// すべての提案を有効にしてパースする
     pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut parser = Parser::new(reader);
-        Ok(parser.parse_all()?)
+        Self::parse_with_features(reader, WasmFeatures::default())
+    }
+
+    /// 有効にする提案を指定してパースする
+    pub fn parse_with_features<R: Read + Seek>(
+        reader: &mut R,
+        features: WasmFeatures,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(reader, features);
+        parser.parse_all()
+    }
+
+    /// 有効にする提案と、独自のカスタムセクションのデコーダを指定してパースする
+    pub fn parse_with_registry<R: Read + Seek>(
+        reader: &mut R,
+        features: WasmFeatures,
+        registry: &CustomSectionRegistry,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(reader, features);
+        parser.set_custom_section_registry(registry);
+        parser.parse_all()
     }
 
     pub fn empty(mv: &MagicAndVersion) -> Self {
@@ -41,7 +135,10 @@ impl WasmModule {
             element_section: None,
             code_section: None,
             data_section: None,
+            data_count_section: None,
+            tag_section: None,
             custom_sections: Vec::new(),
+            sections: Vec::new(),
         }
     }
 
@@ -93,9 +190,161 @@ impl WasmModule {
         self.data_section.as_ref()
     }
 
+    pub fn get_data_count_section(&self) -> Option<&DataCountSection> {
+        self.data_count_section.as_ref()
+    }
+
+    pub fn get_tag_section(&self) -> Option<&TagSection> {
+        self.tag_section.as_ref()
+    }
+
     pub fn get_custom_sections(&self) -> Vec<&CustomSection> {
         self.custom_sections.iter().collect()
     }
+
+    /// 出現順のセクションの位置・大きさのリストを返す(未知のセクションも含む)
+    pub fn get_sections(&self) -> &[SectionInfo] {
+        &self.sections
+    }
+
+    /// 読み込んだセクションの位置・大きさを記録する
+    pub(crate) fn push_section(&mut self, section: SectionInfo) {
+        self.sections.push(section);
+    }
+
+    /// コードセクションのペイロードの位置(ファイル先頭からのオフセット)を返す
+    ///
+    /// DWARFのアドレスやaddr2lineのオフセットは、この位置を基準とする
+    pub fn get_code_payload_offset(&self) -> Option<u64> {
+        self.code_section.as_ref()?;
+        self.sections
+            .iter()
+            .find(|x| x.id == 10)
+            .map(|x| x.payload_offset)
+    }
+
+    /// セクションの通し番号(カスタムセクションを含む)に対応する名前を返す
+    ///
+    /// カスタムセクションの場合はその名前を返す
+    pub fn get_section_name_by_index(&self, section_index: u32) -> Option<String> {
+        let section_id = self.sections.get(section_index as usize)?.id;
+        if section_id != 0 {
+            return Some(String::from(get_section_name(section_id)));
+        }
+
+        let custom_idx = self.sections[..section_index as usize]
+            .iter()
+            .filter(|x| x.id == 0)
+            .count();
+        self.custom_sections
+            .get(custom_idx)
+            .and_then(|x| x.get_name())
+            .cloned()
+    }
+
+    /// build_idセクションのペイロードを返す
+    pub fn get_build_id(&self) -> Option<&BuildIdSectionPayload> {
+        self.custom_sections
+            .iter()
+            .find_map(|x| match x.get_payload() {
+                CustomSectionPayload::BuildId { payload } => Some(payload),
+                _ => None,
+            })
+    }
+
+    /// モジュールの内容のハッシュ(SHA-256)を返す
+    ///
+    /// バージョンと、対象とするセクションのID・バイト列のハッシュを出現順に連結してハッシュする
+    /// デバッグ情報を除いたハッシュが等しければ、2つのモジュールの違いはデバッグ情報のみ
+    ///
+    /// セクションのハッシュはパース時に求めたもの(SectionInfo::get_digest)を使う
+    /// パース時に記録していない場合(Parser::set_record_section_digests)はNoneを返す
+    pub fn content_hash(&self, options: ContentHashOptions) -> Option<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.magic_and_version.get_version().to_le_bytes());
+
+        for (idx, section) in self.sections.iter().enumerate() {
+            if section.id == 0 {
+                let name = self
+                    .get_section_name_by_index(idx as u32)
+                    .unwrap_or_default();
+                if options.exclude_custom_sections
+                    || (options.exclude_debug_sections
+                        && (is_debug_section_name(&name) || name == "build_id"))
+                {
+                    continue;
+                }
+            }
+
+            hasher.update([section.id]);
+            hasher.update(section.get_digest()?);
+        }
+
+        Some(hasher.finalize().into())
+    }
+
+    /// nameセクションのペイロードを返す
+    pub fn get_name_section(&self) -> Option<&NameSectionPayload> {
+        self.custom_sections
+            .iter()
+            .find_map(|x| match x.get_payload() {
+                CustomSectionPayload::Name { payload } => Some(payload.as_ref()),
+                _ => None,
+            })
+    }
+
+    /// sourceMappingURLセクションのペイロードを返す
+    pub fn get_source_mapping_url(&self) -> Option<&UrlSectionPayload> {
+        self.custom_sections
+            .iter()
+            .find_map(|x| match x.get_payload() {
+                CustomSectionPayload::SourceMappingUrl { payload } => Some(payload),
+                _ => None,
+            })
+    }
+
+    /// external_debug_infoセクションのペイロードを返す
+    pub fn get_external_debug_info(&self) -> Option<&UrlSectionPayload> {
+        self.custom_sections
+            .iter()
+            .find_map(|x| match x.get_payload() {
+                CustomSectionPayload::ExternalDebugInfo { payload } => Some(payload),
+                _ => None,
+            })
+    }
+
+    /// コードセクションのペイロード先頭からのオフセットを含む関数のインデックスを返す
+    ///
+    /// インポートされた関数を含む、関数インデックス空間でのインデックス
+    pub fn find_function_by_code_offset(&self, offset: u32) -> Option<u32> {
+        let body_idx = self.code_section.as_ref()?.find_func_body(offset)?;
+        Some(self.get_num_imported_functions() + body_idx as u32)
+    }
+
+    /// nameセクションの名前に一致する関数のインデックスを返す
+    pub fn find_function_by_name(&self, name: &str) -> Option<u32> {
+        self.get_name_section()?
+            .get_function_names()?
+            .get_name_map()
+            .get_name_list()
+            .into_iter()
+            .find(|x| x.get_name_str() == name)
+            .map(|x| x.get_indice())
+    }
+
+    /// インポートされた関数の数を返す
+    ///
+    /// 関数インデックス空間では、インポートされた関数が定義された関数より前に並ぶ
+    pub fn get_num_imported_functions(&self) -> u32 {
+        match &self.import_section {
+            Some(sec) => sec
+                .get_import_entries()
+                .into_iter()
+                .filter(|entry| matches!(entry.get_type(), TypeEntry::FuncIndex { .. }))
+                .count() as u32,
+            None => 0,
+        }
+    }
 }
 
 impl Sizeof for WasmModule {
@@ -118,6 +367,8 @@ impl Sizeof for WasmModule {
             + sizeof_option_section(&self.element_section)
             + sizeof_option_section(&self.code_section)
             + sizeof_option_section(&self.data_section)
+            + sizeof_option_section(&self.data_count_section)
+            + sizeof_option_section(&self.tag_section)
             + sizeof_custom_sections
     }
 }
@@ -129,3 +380,64 @@ fn sizeof_option_section<T: Sizeof>(section: &Option<T>) -> u32 {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::ir::test_utils::*;
+
+    fn parse_with_digests(bytes: &[u8]) -> WasmModule {
+        let mut reader = Cursor::new(bytes.to_vec());
+        let mut parser = Parser::new(&mut reader, WasmFeatures::default());
+        parser.set_record_section_digests(true);
+        parser.parse_all().unwrap()
+    }
+
+    const ALL: ContentHashOptions = ContentHashOptions {
+        exclude_custom_sections: false,
+        exclude_debug_sections: false,
+    };
+    const WITHOUT_DEBUG: ContentHashOptions = ContentHashOptions {
+        exclude_custom_sections: false,
+        exclude_debug_sections: true,
+    };
+
+    #[test]
+    fn content_hash_requires_recorded_digests() {
+        let bytes = module(&[section(1, &vec(&[func_type(&[], &[])]))]);
+
+        assert_eq!(parse(&bytes).content_hash(ALL), None);
+        assert!(parse_with_digests(&bytes).content_hash(ALL).is_some());
+    }
+
+    #[test]
+    fn content_hash_ignores_debug_sections() {
+        let types = section(1, &vec(&[func_type(&[], &[])]));
+        let with_debug = parse_with_digests(&module(&[
+            types.clone(),
+            custom_section(".debug_info", &[0x00, 0x01]),
+        ]));
+        let stripped = parse_with_digests(&module(&[types]));
+
+        assert_ne!(stripped.content_hash(ALL), with_debug.content_hash(ALL));
+        assert_eq!(
+            stripped.content_hash(WITHOUT_DEBUG),
+            with_debug.content_hash(WITHOUT_DEBUG)
+        );
+    }
+
+    #[test]
+    fn truncated_section_is_rejected_when_recording_digests() {
+        // 大きさが実際のバイト列より大きいセクション
+        let mut bytes = module(&[]);
+        bytes.extend([0x01, 0x7f, 0x00]);
+
+        let mut reader = Cursor::new(bytes);
+        let mut parser = Parser::new(&mut reader, WasmFeatures::default());
+        parser.set_record_section_digests(true);
+        assert!(parser.parse_all().is_err());
+    }
+}
@@ -1,33 +1,53 @@
 mod base;
 
+mod build_id_section;
 mod code_section;
 mod custom_section;
+mod custom_section_registry;
+mod data_count_section;
 mod data_section;
+mod dylink_section;
 mod element_section;
 mod export_section;
 mod function_section;
 mod global_section;
 mod import_section;
+mod linking_section;
 mod magic_and_version;
 mod memory_section;
 mod name_section;
+mod producers_section;
+mod reloc_section;
 mod start_section;
 mod table_section;
+mod tag_section;
+mod target_features_section;
 mod type_section;
+mod url_section;
 
 pub use self::base::Section;
-pub use self::base::{ParseError, SectionCommonInterface};
+pub use self::base::{get_section_name, ParseError, SectionCommon, SectionCommonInterface};
+pub use self::build_id_section::*;
 pub use self::code_section::*;
 pub use self::custom_section::*;
+pub use self::custom_section_registry::*;
+pub use self::data_count_section::*;
 pub use self::data_section::*;
+pub use self::dylink_section::*;
 pub use self::element_section::*;
 pub use self::export_section::*;
 pub use self::function_section::*;
 pub use self::global_section::*;
 pub use self::import_section::*;
+pub use self::linking_section::*;
 pub use self::magic_and_version::*;
 pub use self::memory_section::*;
 pub use self::name_section::*;
+pub use self::producers_section::*;
+pub use self::reloc_section::*;
 pub use self::start_section::*;
 pub use self::table_section::*;
+pub use self::tag_section::*;
+pub use self::target_features_section::*;
 pub use self::type_section::*;
+pub use self::url_section::*;
@@ -1,8 +1,10 @@
-use std::io::Read;
+use std::fmt;
+use std::io::{Read, Seek};
 
 use super::{
-    CodeSection, CustomSection, DataSection, ElementSection, ExportSection, FunctionSection,
-    GlobalSection, ImportSection, MemorySection, StartSection, TableSection, TypeSection,
+    CodeSection, CustomSection, DataCountSection, DataSection, ElementSection, ExportSection,
+    FunctionSection, GlobalSection, ImportSection, MemorySection, StartSection, TableSection,
+    TagSection, TypeSection,
 };
 
 use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
@@ -22,13 +24,61 @@ pub enum Section {
     ElementSection(ElementSection),
     CodeSection(CodeSection),
     DataSection(DataSection),
+    DataCountSection(DataCountSection),
+    TagSection(TagSection),
     CustomSections(Vec<CustomSection>),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseError {
     ReaderError(String),
     FormatError(String),
     UnexpectedError(String),
+    ValidationError(String),
+    FeatureError(String), // 無効にした提案を利用している
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::ReaderError(msg) => write!(f, "ReaderError: {}", msg),
+            ParseError::FormatError(msg) => write!(f, "FormatError: {}", msg),
+            ParseError::UnexpectedError(msg) => write!(f, "UnexpectedError: {}", msg),
+            ParseError::ValidationError(msg) => write!(f, "ValidationError: {}", msg),
+            ParseError::FeatureError(msg) => write!(f, "FeatureError: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// セクションIDに対応する名前を返す
+pub fn get_section_name(section_id: u8) -> &'static str {
+    match section_id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "elem",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+// readerの現在の位置を返す
+pub(crate) fn get_position<R: Seek>(reader: &mut R) -> Result<u64, ParseError> {
+    match reader.stream_position() {
+        Ok(pos) => Ok(pos),
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
 }
 
 pub trait SectionCommonInterface {
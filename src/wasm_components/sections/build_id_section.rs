@@ -0,0 +1,62 @@
+use std::fmt;
+use std::io::Read;
+
+use super::base::ParseError;
+use super::linking_section::read_u32;
+
+use crate::readers::{read_x, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+
+/*
+ * build_id Section
+ *
+ * ビルドを一意に識別するIDを持つカスタムセクション
+ * ペイロードはIDのバイト列(vec(byte))のみ
+ */
+
+#[derive(Debug)]
+pub struct BuildIdSectionPayload {
+    id: Vec<u8>,
+}
+
+impl BuildIdSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let id_len = read_u32(reader)?;
+        let id = match read_x(reader, id_len as usize) {
+            Ok(data) => data,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let payload = Self { id };
+
+        if payload.sizeof() != payload_size {
+            return Err(ParseError::FormatError(format!(
+                "build_id section size mismatch: expected {} bytes, but read {} bytes",
+                payload_size,
+                payload.sizeof()
+            )));
+        }
+
+        Ok(payload)
+    }
+
+    pub fn get_id(&self) -> &Vec<u8> {
+        &self.id
+    }
+}
+
+impl Sizeof for BuildIdSectionPayload {
+    fn sizeof(&self) -> u32 {
+        usage_bytes_leb128_u(self.id.len() as u64) as u32 + self.id.len() as u32
+    }
+}
+
+/// IDを16進数の文字列で表示する
+impl fmt::Display for BuildIdSectionPayload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.id.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
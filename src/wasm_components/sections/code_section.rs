@@ -1,6 +1,6 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
 use crate::wasm_components::code::FunctionBody;
@@ -16,10 +16,14 @@ pub struct CodeSection {
 pub struct CodeSectionPayload {
     count: VarUInt32,
     bodies: Vec<FunctionBody>,
+    // ペイロード先頭からの各FuncBodyの位置と、その命令列の位置
+    // パディングされたLEB128を含む場合に備えて、実際に読み込んだ位置を記録する
+    body_offsets: Vec<u32>,
+    code_offsets: Vec<u32>,
 }
 
 impl CodeSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections
         let common = SectionCommon::parse(reader)?;
         if common.id != 10 {
@@ -31,10 +35,7 @@ impl CodeSection {
 
         let payload = CodeSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// FuncBodyの数を返す
@@ -53,6 +54,47 @@ impl CodeSection {
     pub fn get_func_body(&self, idx: usize) -> Option<&FunctionBody> {
         self.payload.bodies.get(idx)
     }
+
+    /// idx番目のFuncBodyの命令列(get_code())の位置を返す
+    ///
+    /// ペイロードの先頭(count)からのオフセット
+    pub fn get_code_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.code_offsets.get(idx).copied()
+    }
+
+    /// idx番目のFuncBodyの位置を返す
+    ///
+    /// ペイロードの先頭(count)からのオフセット (DWARFのアドレスと同じ基準)
+    pub fn get_func_body_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.body_offsets.get(idx).copied()
+    }
+
+    /// idx番目のFuncBodyが占める範囲 [start, end) を返す
+    ///
+    /// ペイロードの先頭(count)からのオフセット
+    pub fn get_func_body_range(&self, idx: usize) -> Option<(u32, u32)> {
+        let start = self.get_func_body_offset(idx)?;
+        // 命令列の後ろには末尾のendが1byteある
+        let end = self.get_code_offset(idx)? + self.payload.bodies[idx].get_code().len() as u32 + 1;
+        Some((start, end))
+    }
+
+    /// ペイロードの先頭からのオフセットを含むFuncBodyのインデックスを返す
+    pub fn find_func_body(&self, offset: u32) -> Option<usize> {
+        // offset以下で最後に始まるFuncBody
+        let idx = self
+            .payload
+            .body_offsets
+            .partition_point(|x| *x <= offset)
+            .checked_sub(1)?;
+        let (_, end) = self.get_func_body_range(idx)?;
+
+        if offset < end {
+            Some(idx)
+        } else {
+            None
+        }
+    }
 }
 
 impl Sizeof for CodeSection {
@@ -71,7 +113,9 @@ impl SectionCommonInterface for CodeSection {
 }
 
 impl CodeSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
@@ -79,12 +123,22 @@ impl CodeSectionPayload {
         };
 
         let mut func_bodies: Vec<FunctionBody> = Vec::new();
+        let mut body_offsets: Vec<u32> = Vec::new();
+        let mut code_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
-            func_bodies.push(FunctionBody::parse(reader)?);
+            body_offsets.push((get_position(reader)? - payload_offset) as u32);
+            let func_body = FunctionBody::parse(reader)?;
+
+            // 命令列は末尾のendの直前にある
+            let body_end = get_position(reader)? - payload_offset;
+            code_offsets.push((body_end - 1 - func_body.get_code().len() as u64) as u32);
+            func_bodies.push(func_body);
         }
         Ok(Self {
             count: count as VarUInt32,
             bodies: func_bodies,
+            body_offsets,
+            code_offsets,
         })
     }
 }
@@ -2,26 +2,88 @@ use std::io::{Read, Seek};
 use std::str;
 
 use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::build_id_section::*;
+use super::custom_section_registry::*;
+use super::dylink_section::*;
+use super::linking_section::*;
 use super::name_section::*;
+use super::producers_section::*;
+use super::reloc_section::*;
+use super::target_features_section::*;
+use super::url_section::*;
 
 use crate::readers::{read_unsigned_leb128, read_x};
 use crate::wasm_components::base::Sizeof;
 
+/// デバッグ情報を持つカスタムセクションの名前かどうかを返す
+///
+/// DWARF(.debug_*とその再配置)、name、ソースマップ・分割DWARFへの参照が該当する
+pub fn is_debug_section_name(name: &str) -> bool {
+    name.starts_with(".debug_")
+        || name.starts_with("reloc..debug_")
+        || matches!(name, "name" | "sourceMappingURL" | "external_debug_info")
+}
+
 #[derive(Debug)]
 pub struct CustomSection {
     common: SectionCommon,
     real_payload_size: u32,
+    raw_payload: Vec<u8>, // ペイロードのバイト列(General・Registeredの場合はpayloadが持つため空)
     payload: CustomSectionPayload,
 }
 
 #[derive(Debug)]
 pub enum CustomSectionPayload {
-    Name { payload: NameSectionPayload },
-    General { payload: Vec<u8> },
+    Name {
+        payload: Box<NameSectionPayload>,
+    },
+    Producers {
+        payload: ProducersSectionPayload,
+    },
+    TargetFeatures {
+        payload: TargetFeaturesSectionPayload,
+    },
+    Linking {
+        payload: LinkingSectionPayload,
+    },
+    Reloc {
+        payload: RelocSectionPayload,
+    },
+    Dylink {
+        payload: DylinkSectionPayload,
+    },
+    SourceMappingUrl {
+        payload: UrlSectionPayload,
+    },
+    ExternalDebugInfo {
+        payload: UrlSectionPayload,
+    },
+    BuildId {
+        payload: BuildIdSectionPayload,
+    },
+    /// CustomSectionRegistryに登録したデコーダで読んだペイロード
+    Registered {
+        payload: Box<dyn CustomPayload>,
+        bytes: Vec<u8>,
+    },
+    General {
+        payload: Vec<u8>,
+    },
 }
 
 impl CustomSection {
+    /// 組み込みのデコーダを使ってパースする
     pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        Self::parse_with_registry(reader, &CustomSectionRegistry::new())
+    }
+
+    /// 登録したデコーダを使ってパースする
+    ///
+    /// デコーダが登録されていない名前のセクションと、デコードに失敗したセクションはGeneralとして読む
+    pub fn parse_with_registry<R: Read + Seek>(
+        reader: &mut R,
+        registry: &CustomSectionRegistry,
+    ) -> Result<Self, ParseError> {
         let mut common = SectionCommon::parse(reader)?;
         if common.id != 0 {
             return Err(ParseError::FormatError(String::from(
@@ -30,11 +92,11 @@ impl CustomSection {
         }
 
         let mut nl = 0;
-        let sizeof_name_len: i64;
-        match read_unsigned_leb128(reader, &mut nl) {
-            Ok(rs) => sizeof_name_len = rs as i64,
+
+        let sizeof_name_len: i64 = match read_unsigned_leb128(reader, &mut nl) {
+            Ok(rs) => rs as i64,
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
-        }
+        };
 
         let name_len = nl as u32;
 
@@ -49,26 +111,43 @@ impl CustomSection {
         let sizeof_name = name_len as i64;
         let payload_size = common.payload_len as i64 - sizeof_name - sizeof_name_len;
 
-        let payload: CustomSectionPayload;
-        if name.eq("name") {
-            println!(" > Debug: custom section name: {}", name);
-            payload = CustomSectionPayload::Name {
-                payload: NameSectionPayload::parse(reader, payload_size as u32)?,
-            };
-        } else {
-            payload = match read_x(reader, payload_size as usize) {
-                Ok(data) => CustomSectionPayload::General { payload: data },
-                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
-            };
-        }
+        // デコードしたペイロードを再シリアライズせずに書き出せるよう、バイト列も保持する
+        let raw_payload = match read_x(reader, payload_size as usize) {
+            Ok(data) => data,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        // カスタムセクションはモジュールの意味に関わらないため、
+        // デコードに失敗した場合は警告してGeneralとして読む
+        let decoded = match registry.get_decoder(&name) {
+            Some(decoder) => match decoder(&raw_payload) {
+                Ok(payload) => Some(payload),
+                Err(err) => {
+                    eprintln!(" > Warn: custom section \"{}\": {}", name, err);
+                    None
+                }
+            },
+            None => None,
+        };
+        let (payload, raw_payload) = match decoded {
+            Some(payload @ CustomSectionPayload::Registered { .. }) => (payload, Vec::new()),
+            Some(payload) => (payload, raw_payload),
+            None => (
+                CustomSectionPayload::General {
+                    payload: raw_payload,
+                },
+                Vec::new(),
+            ),
+        };
 
         common.name = Some(name);
         common.name_len = Some(name_len);
 
         Ok(Self {
-            common: common,
+            common,
             real_payload_size: payload_size as u32,
-            payload: payload,
+            raw_payload,
+            payload,
         })
     }
 
@@ -79,6 +158,15 @@ impl CustomSection {
     pub fn get_payload(&self) -> &CustomSectionPayload {
         &self.payload
     }
+
+    /// ペイロードのバイト列(セクション名の後ろの部分)を返す
+    pub fn get_raw_payload(&self) -> &[u8] {
+        match &self.payload {
+            CustomSectionPayload::General { payload } => payload,
+            CustomSectionPayload::Registered { bytes, .. } => bytes,
+            _ => &self.raw_payload,
+        }
+    }
 }
 
 impl SectionCommonInterface for CustomSection {
@@ -92,6 +180,15 @@ impl Sizeof for CustomSection {
         let sizeof_common = self.common.sizeof();
         let sizeof_payload = match &self.payload {
             CustomSectionPayload::Name { payload } => payload.sizeof(),
+            CustomSectionPayload::Producers { payload } => payload.sizeof(),
+            CustomSectionPayload::TargetFeatures { payload } => payload.sizeof(),
+            CustomSectionPayload::Linking { payload } => payload.sizeof(),
+            CustomSectionPayload::Reloc { payload } => payload.sizeof(),
+            CustomSectionPayload::Dylink { payload } => payload.sizeof(),
+            CustomSectionPayload::SourceMappingUrl { payload } => payload.sizeof(),
+            CustomSectionPayload::ExternalDebugInfo { payload } => payload.sizeof(),
+            CustomSectionPayload::BuildId { payload } => payload.sizeof(),
+            CustomSectionPayload::Registered { .. } => self.real_payload_size,
             CustomSectionPayload::General { payload } => payload.len() as u32,
         };
 
@@ -104,6 +201,15 @@ impl Sizeof for CustomSectionPayload {
         match self {
             CustomSectionPayload::General { payload } => payload.len() as u32,
             CustomSectionPayload::Name { payload } => payload.sizeof(),
+            CustomSectionPayload::Producers { payload } => payload.sizeof(),
+            CustomSectionPayload::TargetFeatures { payload } => payload.sizeof(),
+            CustomSectionPayload::Linking { payload } => payload.sizeof(),
+            CustomSectionPayload::Reloc { payload } => payload.sizeof(),
+            CustomSectionPayload::Dylink { payload } => payload.sizeof(),
+            CustomSectionPayload::SourceMappingUrl { payload } => payload.sizeof(),
+            CustomSectionPayload::ExternalDebugInfo { payload } => payload.sizeof(),
+            CustomSectionPayload::BuildId { payload } => payload.sizeof(),
+            CustomSectionPayload::Registered { bytes, .. } => bytes.len() as u32,
         }
     }
 }
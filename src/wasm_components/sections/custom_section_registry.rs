@@ -0,0 +1,333 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+
+use super::base::ParseError;
+use super::build_id_section::*;
+use super::custom_section::CustomSectionPayload;
+use super::dylink_section::*;
+use super::linking_section::*;
+use super::name_section::*;
+use super::producers_section::*;
+use super::reloc_section::*;
+use super::target_features_section::*;
+use super::url_section::*;
+
+/*
+ * Custom Section Registry
+ *
+ * カスタムセクションのデコーダをセクション名ごとに登録する
+ * 組み込みのデコーダ(name, producersなど)もnew()で登録しておき、
+ * ライブラリの利用者は同じ名前で登録して置き換えたり、独自のセクションのデコーダを追加したりする
+ * 登録されていない名前のセクションはGeneral(バイト列のまま)として読む
+ */
+
+/// 登録したデコーダが返すカスタムセクションのペイロード
+///
+/// Displayはprinterでの表示に、encodeはペイロードの再シリアライズ(ModuleIr::encode)に使う
+pub trait CustomPayload: fmt::Debug + fmt::Display {
+    /// ペイロードのバイト列(セクション名の後ろの部分)を返す
+    fn encode(&self) -> Vec<u8>;
+
+    /// 具体的な型へのダウンキャストに使う
+    fn as_any(&self) -> &dyn Any;
+
+    /// 具体的な型へのダウンキャストに使う(変更する場合)
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// ペイロードを複製する(IRの複製に使う)
+    fn clone_payload(&self) -> Box<dyn CustomPayload>;
+}
+
+impl dyn CustomPayload {
+    /// 具体的な型のペイロードとして返す
+    pub fn downcast_ref<T: CustomPayload + 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+
+    /// 具体的な型のペイロードとして、変更できる参照を返す
+    pub fn downcast_mut<T: CustomPayload + 'static>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut::<T>()
+    }
+}
+
+impl Clone for Box<dyn CustomPayload> {
+    fn clone(&self) -> Self {
+        self.clone_payload()
+    }
+}
+
+/// ペイロードのバイト列(セクション名の後ろの部分)を読むデコーダ
+pub type CustomSectionDecoder = Box<dyn Fn(&[u8]) -> Result<CustomSectionPayload, ParseError>>;
+
+/// セクション名 -> デコーダ の対応
+pub struct CustomSectionRegistry {
+    decoders: HashMap<String, CustomSectionDecoder>,
+    prefix_decoders: Vec<(String, CustomSectionDecoder)>, // 名前の先頭で対応させるデコーダ(reloc.*)
+}
+
+impl CustomSectionRegistry {
+    /// 組み込みのデコーダを登録した状態で作る
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+
+        registry.insert("name", |data| {
+            Ok(CustomSectionPayload::Name {
+                payload: Box::new(NameSectionPayload::parse(
+                    &mut Cursor::new(data),
+                    data.len() as u32,
+                )?),
+            })
+        });
+        registry.insert("producers", |data| {
+            Ok(CustomSectionPayload::Producers {
+                payload: ProducersSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.insert("target_features", |data| {
+            Ok(CustomSectionPayload::TargetFeatures {
+                payload: TargetFeaturesSectionPayload::parse(
+                    &mut Cursor::new(data),
+                    data.len() as u32,
+                )?,
+            })
+        });
+        registry.insert("linking", |data| {
+            Ok(CustomSectionPayload::Linking {
+                payload: LinkingSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.insert("dylink.0", |data| {
+            Ok(CustomSectionPayload::Dylink {
+                payload: DylinkSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.insert("sourceMappingURL", |data| {
+            Ok(CustomSectionPayload::SourceMappingUrl {
+                payload: UrlSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.insert("external_debug_info", |data| {
+            Ok(CustomSectionPayload::ExternalDebugInfo {
+                payload: UrlSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.insert("build_id", |data| {
+            Ok(CustomSectionPayload::BuildId {
+                payload: BuildIdSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+            })
+        });
+        registry.prefix_decoders.push((
+            String::from("reloc."),
+            Box::new(|data| {
+                Ok(CustomSectionPayload::Reloc {
+                    payload: RelocSectionPayload::parse(&mut Cursor::new(data), data.len() as u32)?,
+                })
+            }),
+        ));
+
+        registry
+    }
+
+    /// デコーダを1つも登録していない状態で作る(すべてのカスタムセクションをGeneralとして読む)
+    pub fn empty() -> Self {
+        Self {
+            decoders: HashMap::new(),
+            prefix_decoders: Vec::new(),
+        }
+    }
+
+    fn insert<F>(&mut self, section_name: &str, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<CustomSectionPayload, ParseError> + 'static,
+    {
+        self.decoders
+            .insert(String::from(section_name), Box::new(decoder));
+    }
+
+    /// セクション名に対するデコーダを登録する(組み込みのものを含め、登録済みの場合は置き換える)
+    ///
+    /// デコーダが返したペイロードはCustomSectionPayload::Registeredになる
+    pub fn register<F>(&mut self, section_name: &str, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<Box<dyn CustomPayload>, ParseError> + 'static,
+    {
+        self.insert(section_name, move |data| {
+            Ok(CustomSectionPayload::Registered {
+                payload: decoder(data)?,
+                bytes: data.to_vec(),
+            })
+        });
+    }
+
+    /// セクション名に対するデコーダの登録を解除する
+    pub fn unregister(&mut self, section_name: &str) -> bool {
+        let num_prefix_decoders = self.prefix_decoders.len();
+        self.prefix_decoders.retain(|(x, _)| x != section_name);
+        self.decoders.remove(section_name).is_some()
+            || self.prefix_decoders.len() != num_prefix_decoders
+    }
+
+    /// セクション名に対するデコーダを返す(名前が完全に一致するものを優先する)
+    pub fn get_decoder(&self, section_name: &str) -> Option<&CustomSectionDecoder> {
+        self.decoders.get(section_name).or_else(|| {
+            self.prefix_decoders
+                .iter()
+                .find(|(prefix, _)| section_name.starts_with(prefix.as_str()))
+                .map(|(_, decoder)| decoder)
+        })
+    }
+
+    /// 登録されたセクション名のリストを返す(先頭で対応させるものは末尾に*を付ける)
+    pub fn get_section_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .decoders
+            .keys()
+            .cloned()
+            .chain(self.prefix_decoders.iter().map(|(x, _)| format!("{}*", x)))
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for CustomSectionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CustomSectionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomSectionRegistry")
+            .field("decoders", &self.get_section_names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::features::WasmFeatures;
+    use crate::ir::test_utils::*;
+    use crate::ir::{IrCustomPayload, ModuleIr};
+    use crate::wasm_components::module::WasmModule;
+
+    // 登録したデコーダのテスト用のペイロード(1バイトのカウンタ)
+    #[derive(Debug, Clone)]
+    struct Counter(u8);
+
+    impl std::fmt::Display for Counter {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "counter={}", self.0)
+        }
+    }
+
+    impl CustomPayload for Counter {
+        fn encode(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+
+        fn clone_payload(&self) -> Box<dyn CustomPayload> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn registered_custom_payload_is_reencoded() {
+        let bytes = module(&[custom_section("counter", &[0x01])]);
+        let mut registry = CustomSectionRegistry::new();
+        registry.register("counter", |data| Ok(Box::new(Counter(data[0]))));
+        let wasm_module = WasmModule::parse_with_registry(
+            &mut Cursor::new(&bytes),
+            WasmFeatures::default(),
+            &registry,
+        )
+        .unwrap();
+        let mut ir = ModuleIr::from_module(&wasm_module).unwrap();
+        assert_eq!(ir.encode().unwrap(), bytes);
+
+        match &mut ir.custom_sections[0].payload {
+            IrCustomPayload::Registered(payload) => {
+                payload.downcast_mut::<Counter>().unwrap().0 = 2;
+            }
+            payload => panic!("unexpected payload: {:?}", payload),
+        }
+        assert_eq!(
+            ir.encode().unwrap(),
+            module(&[custom_section("counter", &[0x02])])
+        );
+    }
+
+    #[test]
+    fn registered_decoder_is_used() {
+        let bytes = module(&[custom_section("counter", &[0x01])]);
+        let mut registry = CustomSectionRegistry::new();
+        registry.register("counter", |data| Ok(Box::new(Counter(data[0]))));
+        let wasm_module = WasmModule::parse_with_registry(
+            &mut Cursor::new(&bytes),
+            WasmFeatures::default(),
+            &registry,
+        )
+        .unwrap();
+
+        match wasm_module.get_custom_sections()[0].get_payload() {
+            CustomSectionPayload::Registered { payload, .. } => {
+                assert_eq!(payload.downcast_ref::<Counter>().unwrap().0, 1);
+                assert_eq!(payload.encode(), vec![0x01]);
+            }
+            payload => panic!("unexpected payload: {:?}", payload),
+        }
+    }
+
+    #[test]
+    fn failed_decoder_falls_back_to_general() {
+        let bytes = module(&[custom_section("counter", &[])]);
+        let mut registry = CustomSectionRegistry::new();
+        registry.register("counter", |data| match data.first() {
+            Some(x) => Ok(Box::new(Counter(*x))),
+            None => Err(ParseError::FormatError(String::from("empty counter"))),
+        });
+        let wasm_module = WasmModule::parse_with_registry(
+            &mut Cursor::new(&bytes),
+            WasmFeatures::default(),
+            &registry,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            wasm_module.get_custom_sections()[0].get_payload(),
+            CustomSectionPayload::General { .. }
+        ));
+    }
+
+    #[test]
+    fn malformed_custom_section_is_read_as_general() {
+        // producersのフィールド数だけで中身がない
+        let bytes = module(&[custom_section("producers", &[0x01])]);
+        let wasm_module = parse(&bytes);
+        let custom_section = wasm_module.get_custom_sections()[0];
+
+        assert!(matches!(
+            custom_section.get_payload(),
+            CustomSectionPayload::General { .. }
+        ));
+        assert_eq!(
+            ModuleIr::from_module(&wasm_module)
+                .unwrap()
+                .encode()
+                .unwrap(),
+            bytes
+        );
+    }
+}
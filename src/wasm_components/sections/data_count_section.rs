@@ -0,0 +1,82 @@
+use std::io::Read;
+
+use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+
+use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::VarUInt32;
+
+/*
+ * DataCountSection (bulk memory提案で追加)
+ *
+ * memory.init, data.dropがCodeSectionより前でdataセグメントを参照できるように、
+ * dataセグメントの数を先に宣言する
+ */
+
+#[derive(Debug)]
+pub struct DataCountSection {
+    common: SectionCommon,
+    payload: DataCountSectionPayload,
+}
+
+#[derive(Debug)]
+pub struct DataCountSectionPayload {
+    count: VarUInt32,
+}
+
+impl DataCountSection {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        // Common reading in all sections
+        let common = SectionCommon::parse(reader)?;
+        if common.id != 12 {
+            return Err(ParseError::FormatError(String::from(
+                "This Section is not DataCountSection",
+            )));
+        }
+        // ここまで共通 //
+
+        let payload = DataCountSectionPayload::parse(reader)?;
+
+        Ok(Self { common, payload })
+    }
+
+    /// dataセグメントの数を返す
+    pub fn get_data_count(&self) -> u32 {
+        self.payload.count
+    }
+}
+
+impl SectionCommonInterface for DataCountSection {
+    fn get_base(&self) -> &SectionCommon {
+        &self.common
+    }
+}
+
+impl Sizeof for DataCountSection {
+    fn sizeof(&self) -> u32 {
+        let sizeof_common = self.common.sizeof();
+        let sizeof_payload = self.payload.sizeof();
+
+        sizeof_common + sizeof_payload
+    }
+}
+
+impl DataCountSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut count = 0;
+        match read_unsigned_leb128(reader, &mut count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Ok(Self {
+            count: count as VarUInt32,
+        })
+    }
+}
+
+impl Sizeof for DataCountSectionPayload {
+    fn sizeof(&self) -> u32 {
+        usage_bytes_leb128_u(self.count as u64) as u32
+    }
+}
@@ -1,6 +1,6 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
 use crate::readers::{read_8, read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
@@ -16,18 +16,27 @@ pub struct DataSection {
 pub struct DataSectionPayload {
     count: VarUInt32,
     entries: Vec<DataSegment>,
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各エントリまでの位置
 }
 
+/*
+ * DataSegmentのflags (bulk memory提案で追加)
+ *
+ * 0: active, メモリインデックス0 (MVPと同じ形式)
+ * 1: passive (メモリインデックスとオフセットなし)
+ * 2: active, メモリインデックスを明示
+ */
 #[derive(Debug)]
 pub struct DataSegment {
-    index: VarUInt32,
-    offset: InitExpr,
-    size: VarUInt32, // size of data (bytes)
+    flags: VarUInt32,
+    index: VarUInt32,         // flags=2の場合のみバイナリに含まれる
+    offset: Option<InitExpr>, // passiveの場合はNone
+    size: VarUInt32,          // size of data (bytes)
     data: Vec<u8>,
 }
 
 impl DataSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections //
         let common = SectionCommon::parse(reader)?;
         if common.id != 11 {
@@ -39,10 +48,7 @@ impl DataSection {
 
         let payload = DataSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// dataの個数を返す
@@ -57,6 +63,11 @@ impl DataSection {
 
     // Utilities
 
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
+
     /// idx番目のdataセグメント
     pub fn get_data_segment(&self, idx: usize) -> Option<&DataSegment> {
         self.payload.entries.get(idx)
@@ -79,20 +90,25 @@ impl SectionCommonInterface for DataSection {
 }
 
 impl DataSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
         let mut entries: Vec<DataSegment> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
             entries.push(DataSegment::parse(reader)?);
         }
 
         Ok(Self {
             count: count as VarUInt32,
-            entries: entries,
+            entries,
+            entry_offsets,
         })
     }
 }
@@ -108,12 +124,32 @@ impl Sizeof for DataSectionPayload {
 
 impl DataSegment {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut index: u64 = 0;
-        match read_unsigned_leb128(reader, &mut index) {
+        let mut flags: u64 = 0;
+        match read_unsigned_leb128(reader, &mut flags) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
-        let offset = InitExpr::parse(reader)?;
+
+        let mut index: u64 = 0;
+        let offset: Option<InitExpr>;
+        match flags {
+            0 => offset = Some(InitExpr::parse(reader)?),
+            1 => offset = None,
+            2 => {
+                match read_unsigned_leb128(reader, &mut index) {
+                    Ok(_rs) => (/* To check read size */),
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                offset = Some(InitExpr::parse(reader)?);
+            }
+            _ => {
+                return Err(ParseError::FormatError(format!(
+                    "unknown data segment flags: {}",
+                    flags
+                )))
+            }
+        };
+
         let mut size: u64 = 0;
         match read_unsigned_leb128(reader, &mut size) {
             Ok(_rs) => (/* To check read size */),
@@ -128,23 +164,41 @@ impl DataSegment {
         }
 
         Ok(Self {
+            flags: flags as VarUInt32,
             index: index as VarUInt32,
-            offset: offset,
+            offset,
             size: size as VarUInt32,
-            data: data,
+            data,
         })
     }
 
+    /// passiveセグメント(memory.initで明示的にコピーされる)かどうかを返す
+    pub fn is_passive(&self) -> bool {
+        self.offset.is_none()
+    }
+
     /// 対応する線形メモリのインデックスを返す
     ///
-    /// Wasm v1のMVPモデルでは、1つで固定
-    pub fn get_memory_index(&self) -> u32 {
-        self.index
+    /// passiveセグメントの場合はNone
+    pub fn get_memory_index(&self) -> Option<u32> {
+        if self.is_passive() {
+            None
+        } else {
+            Some(self.index)
+        }
     }
 
-    /// (not implemented) オフセットを返す(予定)
-    pub fn get_offset(&self) -> i32 {
-        panic!("not implemented")
+    /// 線形メモリ上のオフセットを返す
+    ///
+    /// memory64ではi64.constで指定されるため、i64に拡張して返す。
+    /// passiveセグメントの場合や、global.getなど定数でない場合はNone
+    pub fn get_offset(&self) -> Option<i64> {
+        self.offset.as_ref()?.eval_const_int().map(|(_, v)| v)
+    }
+
+    /// オフセットを表す初期化式を返す(passiveの場合はNone)
+    pub fn get_offset_expr(&self) -> Option<&InitExpr> {
+        self.offset.as_ref()
     }
 
     /// dataのサイズをを返す
@@ -160,11 +214,16 @@ impl DataSegment {
 
 impl Sizeof for DataSegment {
     fn sizeof(&self) -> u32 {
-        let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
-        let sizeof_offset = self.offset.sizeof();
+        let sizeof_flags = usage_bytes_leb128_u(self.flags as u64) as u32;
+        let sizeof_index = if self.flags == 2 {
+            usage_bytes_leb128_u(self.index as u64) as u32
+        } else {
+            0
+        };
+        let sizeof_offset = self.offset.as_ref().map(|x| x.sizeof()).unwrap_or(0);
         let sizeof_size = usage_bytes_leb128_u(self.size as u64) as u32;
         let sizeof_data = self.data.len() as u32;
 
-        sizeof_index + sizeof_offset + sizeof_size + sizeof_data
+        sizeof_flags + sizeof_index + sizeof_offset + sizeof_size + sizeof_data
     }
 }
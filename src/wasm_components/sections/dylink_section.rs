@@ -0,0 +1,229 @@
+use std::io::{Cursor, Read};
+
+use super::base::ParseError;
+use super::linking_section::{get_symbol_flag_names, read_byte, read_name, read_u32};
+
+use crate::readers::read_x;
+use crate::wasm_components::base::Sizeof;
+
+/*
+ * Dylink Section ("dylink.0")
+ *
+ * Emscriptenのサイドモジュール(共有ライブラリ)が持つ、動的リンク用の情報
+ *
+ * https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md
+ */
+
+pub const WASM_DYLINK_MEM_INFO: u8 = 1;
+pub const WASM_DYLINK_NEEDED: u8 = 2;
+pub const WASM_DYLINK_EXPORT_INFO: u8 = 3;
+pub const WASM_DYLINK_IMPORT_INFO: u8 = 4;
+pub const WASM_DYLINK_RUNTIME_PATH: u8 = 5;
+
+#[derive(Debug)]
+pub struct DylinkSectionPayload {
+    payload_size: u32,
+    subsections: Vec<DylinkSubsection>,
+}
+
+#[derive(Debug)]
+pub enum DylinkSubsection {
+    MemInfo(DylinkMemInfo),
+    Needed(Vec<String>),
+    ExportInfo(Vec<DylinkExportInfo>),
+    ImportInfo(Vec<DylinkImportInfo>),
+    RuntimePath(Vec<String>),
+    Unknown { subsection_type: u8, data: Vec<u8> },
+}
+
+/// モジュールが必要とするメモリ・テーブルのサイズとアライメント
+#[derive(Debug)]
+pub struct DylinkMemInfo {
+    memory_size: u32,
+    memory_alignment: u32, // log2
+    table_size: u32,
+    table_alignment: u32, // log2
+}
+
+#[derive(Debug)]
+pub struct DylinkExportInfo {
+    name: String,
+    flags: u32, // WASM_SYM_*
+}
+
+#[derive(Debug)]
+pub struct DylinkImportInfo {
+    module_name: String,
+    field_name: String,
+    flags: u32, // WASM_SYM_*
+}
+
+impl DylinkSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let data = match read_x(reader, payload_size as usize) {
+            Ok(data) => data,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        let mut cursor = Cursor::new(data);
+
+        let mut subsections: Vec<DylinkSubsection> = Vec::new();
+        while cursor.position() < payload_size as u64 {
+            subsections.push(DylinkSubsection::parse(&mut cursor)?);
+        }
+
+        Ok(Self {
+            payload_size,
+            subsections,
+        })
+    }
+
+    pub fn get_subsections(&self) -> Vec<&DylinkSubsection> {
+        self.subsections.iter().collect()
+    }
+
+    pub fn get_mem_info(&self) -> Option<&DylinkMemInfo> {
+        self.subsections.iter().find_map(|x| match x {
+            DylinkSubsection::MemInfo(mem_info) => Some(mem_info),
+            _ => None,
+        })
+    }
+
+    /// 依存する共有ライブラリの名前を返す
+    pub fn get_needed(&self) -> Vec<&String> {
+        self.subsections
+            .iter()
+            .filter_map(|x| match x {
+                DylinkSubsection::Needed(needed) => Some(needed),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+impl Sizeof for DylinkSectionPayload {
+    fn sizeof(&self) -> u32 {
+        self.payload_size
+    }
+}
+
+impl DylinkSubsection {
+    pub fn parse(cursor: &mut Cursor<Vec<u8>>) -> Result<Self, ParseError> {
+        let subsection_type = read_byte(cursor)?;
+        let payload_len = read_u32(cursor)?;
+
+        let start = cursor.position();
+        let subsection = match subsection_type {
+            WASM_DYLINK_MEM_INFO => DylinkSubsection::MemInfo(DylinkMemInfo {
+                memory_size: read_u32(cursor)?,
+                memory_alignment: read_u32(cursor)?,
+                table_size: read_u32(cursor)?,
+                table_alignment: read_u32(cursor)?,
+            }),
+            WASM_DYLINK_NEEDED => DylinkSubsection::Needed(read_names(cursor)?),
+            WASM_DYLINK_EXPORT_INFO => {
+                let count = read_u32(cursor)?;
+                let mut exports: Vec<DylinkExportInfo> = Vec::new();
+                for _ in 0..count {
+                    exports.push(DylinkExportInfo {
+                        name: read_name(cursor)?,
+                        flags: read_u32(cursor)?,
+                    });
+                }
+                DylinkSubsection::ExportInfo(exports)
+            }
+            WASM_DYLINK_IMPORT_INFO => {
+                let count = read_u32(cursor)?;
+                let mut imports: Vec<DylinkImportInfo> = Vec::new();
+                for _ in 0..count {
+                    imports.push(DylinkImportInfo {
+                        module_name: read_name(cursor)?,
+                        field_name: read_name(cursor)?,
+                        flags: read_u32(cursor)?,
+                    });
+                }
+                DylinkSubsection::ImportInfo(imports)
+            }
+            WASM_DYLINK_RUNTIME_PATH => DylinkSubsection::RuntimePath(read_names(cursor)?),
+            _ => match read_x(cursor, payload_len as usize) {
+                Ok(data) => DylinkSubsection::Unknown {
+                    subsection_type,
+                    data,
+                },
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            },
+        };
+
+        let read_size = cursor.position() - start;
+        if read_size != payload_len as u64 {
+            return Err(ParseError::FormatError(format!(
+                "dylink subsection({}) size mismatch: payload_len={}, read_size={}",
+                subsection_type, payload_len, read_size
+            )));
+        }
+
+        Ok(subsection)
+    }
+}
+
+fn read_names<R: Read>(reader: &mut R) -> Result<Vec<String>, ParseError> {
+    let count = read_u32(reader)?;
+    let mut names: Vec<String> = Vec::new();
+    for _ in 0..count {
+        names.push(read_name(reader)?);
+    }
+
+    Ok(names)
+}
+
+impl DylinkMemInfo {
+    pub fn get_memory_size(&self) -> u32 {
+        self.memory_size
+    }
+
+    /// メモリのアライメント(log2)を返す
+    pub fn get_memory_alignment(&self) -> u32 {
+        self.memory_alignment
+    }
+
+    pub fn get_table_size(&self) -> u32 {
+        self.table_size
+    }
+
+    /// テーブルのアライメント(log2)を返す
+    pub fn get_table_alignment(&self) -> u32 {
+        self.table_alignment
+    }
+}
+
+impl DylinkExportInfo {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn get_flag_names(&self) -> String {
+        get_symbol_flag_names(self.flags)
+    }
+}
+
+impl DylinkImportInfo {
+    pub fn get_module_name(&self) -> &String {
+        &self.module_name
+    }
+
+    pub fn get_field_name(&self) -> &String {
+        &self.field_name
+    }
+
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn get_flag_names(&self) -> String {
+        get_symbol_flag_names(self.flags)
+    }
+}
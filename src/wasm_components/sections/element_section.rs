@@ -1,11 +1,11 @@
-use core::panic;
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
-use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
+use crate::readers::{read_8, read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
-use crate::wasm_components::types::{InitExpr, VarUInt32};
+use crate::wasm_components::instruction::Immediate;
+use crate::wasm_components::types::{ElemType, InitExpr, LangTypes, VarUInt32};
 
 #[derive(Debug)]
 pub struct ElementSection {
@@ -17,18 +17,36 @@ pub struct ElementSection {
 pub struct ElementSectionPayload {
     count: VarUInt32,
     entries: Vec<ElementSegment>,
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各エントリまでの位置
 }
 
+/*
+ * elemセグメントのflags (bulk memory/reference types提案)
+ *
+ * bit0: passiveまたはdeclarative (0ならactive)
+ * bit1: activeならテーブルインデックスを明示、そうでなければdeclarative
+ * bit2: 要素を関数インデックスではなく初期化式で表す
+ *
+ * MVPの形式はflags=0(テーブルインデックス0, 関数インデックスのリスト)
+ */
+const ELEM_FLAG_PASSIVE_OR_DECLARATIVE: u32 = 0x01;
+const ELEM_FLAG_EXPLICIT_INDEX_OR_DECLARATIVE: u32 = 0x02;
+const ELEM_FLAG_EXPRS: u32 = 0x04;
+
 #[derive(Debug)]
 pub struct ElementSegment {
+    flags: VarUInt32,
     index: VarUInt32,
-    offset: InitExpr,
+    offset: Option<InitExpr>,
+    elem_kind: Option<u8>,       // flags=1,2,3の場合のみ(0x00 = funcref)
+    elem_type: Option<ElemType>, // flags=5,6,7の場合のみ
     num_elem: VarUInt32,
-    elems: Vec<VarUInt32>,
+    elems: Vec<VarUInt32>,     // 関数インデックスで表す場合
+    elem_exprs: Vec<InitExpr>, // 初期化式で表す場合
 }
 
 impl ElementSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections
         let common = SectionCommon::parse(reader)?;
         if common.id != 9 {
@@ -40,10 +58,7 @@ impl ElementSection {
 
         let payload = ElementSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// elemの個数を返す
@@ -60,6 +75,11 @@ impl ElementSection {
 
     // Utilities
 
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
+
     /// idx番目のelemセグメント
     pub fn get_element(&self, idx: usize) -> Option<&ElementSegment> {
         self.payload.entries.get(idx)
@@ -82,7 +102,9 @@ impl SectionCommonInterface for ElementSection {
 }
 
 impl ElementSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
@@ -90,13 +112,16 @@ impl ElementSectionPayload {
         };
 
         let mut element_entries: Vec<ElementSegment> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
             element_entries.push(ElementSegment::parse(reader)?);
         }
 
         Ok(Self {
             count: count as VarUInt32,
             entries: element_entries,
+            entry_offsets,
         })
     }
 }
@@ -112,13 +137,57 @@ impl Sizeof for ElementSectionPayload {
 
 impl ElementSegment {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut index: u64 = 0;
-        match read_unsigned_leb128(reader, &mut index) {
+        let mut flags: u64 = 0;
+        match read_unsigned_leb128(reader, &mut flags) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
+        if flags > 7 {
+            return Err(ParseError::FormatError(format!(
+                "unknown element segment flags: {}",
+                flags
+            )));
+        }
+        let flags = flags as u32;
 
-        let offset = InitExpr::parse(reader)?;
+        let is_active = flags & ELEM_FLAG_PASSIVE_OR_DECLARATIVE == 0;
+        let has_index = is_active && flags & ELEM_FLAG_EXPLICIT_INDEX_OR_DECLARATIVE != 0;
+        let uses_exprs = flags & ELEM_FLAG_EXPRS != 0;
+
+        let mut index: u64 = 0;
+        if has_index {
+            match read_unsigned_leb128(reader, &mut index) {
+                Ok(_rs) => (/* To check read size */),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            };
+        }
+
+        let offset = if is_active {
+            Some(InitExpr::parse(reader)?)
+        } else {
+            None
+        };
+
+        // flags=0,4ではelemkind/reftypeは省略される(funcref)
+        let mut elem_kind: Option<u8> = None;
+        let mut elem_type: Option<ElemType> = None;
+        if flags & 0x03 != 0 {
+            if uses_exprs {
+                elem_type = Some(ElemType::parse(reader)?);
+            } else {
+                let kind = match read_8(reader) {
+                    Ok(v) => v[0],
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                if kind != 0x00 {
+                    return Err(ParseError::FormatError(format!(
+                        "unknown element kind: {:#x}",
+                        kind
+                    )));
+                }
+                elem_kind = Some(kind);
+            }
+        }
 
         let mut num_elem: u64 = 0;
         match read_unsigned_leb128(reader, &mut num_elem) {
@@ -127,7 +196,13 @@ impl ElementSegment {
         };
 
         let mut elems: Vec<VarUInt32> = Vec::new();
+        let mut elem_exprs: Vec<InitExpr> = Vec::new();
         for _ in 0..num_elem {
+            if uses_exprs {
+                elem_exprs.push(InitExpr::parse(reader)?);
+                continue;
+            }
+
             let mut e = 0;
             match read_unsigned_leb128(reader, &mut e) {
                 Ok(_rs) => (/* To check read size */),
@@ -137,23 +212,64 @@ impl ElementSegment {
         }
 
         Ok(Self {
+            flags,
             index: index as VarUInt32,
-            offset: offset,
+            offset,
+            elem_kind,
+            elem_type,
             num_elem: num_elem as VarUInt32,
-            elems: elems,
+            elems,
+            elem_exprs,
         })
     }
 
+    /// activeセグメント(インスタンス化時にテーブルへコピーされる)かどうかを返す
+    pub fn is_active(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// passiveセグメント(table.initで明示的にコピーされる)かどうかを返す
+    pub fn is_passive(&self) -> bool {
+        self.flags & 0x03 == ELEM_FLAG_PASSIVE_OR_DECLARATIVE
+    }
+
+    /// declarativeセグメント(ref.funcで参照する関数の宣言のみ)かどうかを返す
+    pub fn is_declarative(&self) -> bool {
+        self.flags & 0x03 == 0x03
+    }
+
     /// 対応するテーブルインデックスを返す
     ///
-    /// Wasm v1のMVPモデルでは、1つで固定
-    pub fn get_table_index(&self) -> u32 {
-        self.index
+    /// passive/declarativeセグメントの場合はNone
+    pub fn get_table_index(&self) -> Option<u32> {
+        if self.is_active() {
+            Some(self.index)
+        } else {
+            None
+        }
+    }
+
+    /// テーブル上のオフセットを返す
+    ///
+    /// table64ではi64.constで指定されるため、i64に拡張して返す。
+    /// passive/declarativeの場合や、global.getなど定数でない場合はNone
+    pub fn get_offset(&self) -> Option<i64> {
+        self.offset.as_ref()?.eval_const_int().map(|(_, v)| v)
+    }
+
+    /// オフセットを表す初期化式を返す(activeでない場合はNone)
+    pub fn get_offset_expr(&self) -> Option<&InitExpr> {
+        self.offset.as_ref()
     }
 
-    /// (not implemented) オフセットを返す(予定)
-    pub fn get_offset(&self) -> i32 {
-        panic!("not implemented")
+    /// 要素の型を返す
+    ///
+    /// 関数インデックスで表す場合はfuncref(ANYFUNC)
+    pub fn get_elem_type(&self) -> LangTypes {
+        match &self.elem_type {
+            Some(ty) => *ty.get_value(),
+            None => LangTypes::ANYFUNC,
+        }
     }
 
     /// elemの個数を返す
@@ -161,23 +277,59 @@ impl ElementSegment {
         self.num_elem
     }
 
-    /// elemのリストを返す
+    /// elemのリスト(関数インデックス)を返す
+    ///
+    /// 初期化式で表されている場合は、ref.funcで参照される関数のみを返す
     pub fn get_elements(&self) -> Vec<u32> {
-        self.elems.clone()
+        if self.flags & ELEM_FLAG_EXPRS == 0 {
+            return self.elems.clone();
+        }
+
+        let mut elems: Vec<u32> = Vec::new();
+        for expr in self.elem_exprs.iter() {
+            if let Ok(instrs) = expr.get_instructions() {
+                for instr in instrs.iter() {
+                    if let Immediate::Function(func_idx) = instr.get_immediate() {
+                        elems.push(*func_idx);
+                    }
+                }
+            }
+        }
+        elems
+    }
+
+    /// elemの初期化式のリストを返す(関数インデックスで表す場合は空)
+    pub fn get_element_exprs(&self) -> Vec<&InitExpr> {
+        self.elem_exprs.iter().collect()
     }
 }
 
 impl Sizeof for ElementSegment {
     fn sizeof(&self) -> u32 {
-        let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
-        let sizeof_offset = self.offset.sizeof();
+        let sizeof_flags = usage_bytes_leb128_u(self.flags as u64) as u32;
+        let sizeof_index = if self.flags & 0x03 == ELEM_FLAG_EXPLICIT_INDEX_OR_DECLARATIVE {
+            usage_bytes_leb128_u(self.index as u64) as u32
+        } else {
+            0
+        };
+        let sizeof_offset = self.offset.as_ref().map(|x| x.sizeof()).unwrap_or(0);
+        let sizeof_elem_kind: u32 = if self.elem_kind.is_some() { 1 } else { 0 };
+        let sizeof_elem_type = self.elem_type.as_ref().map(|x| x.sizeof()).unwrap_or(0);
         let sizeof_num_elem = usage_bytes_leb128_u(self.num_elem as u64) as u32;
         let sizeof_elems: u32 = self
             .elems
             .iter()
             .map(|x| usage_bytes_leb128_u(*x as u64) as u32)
             .sum();
+        let sizeof_elem_exprs: u32 = self.elem_exprs.iter().map(|x| x.sizeof()).sum();
 
-        sizeof_index + sizeof_offset + sizeof_num_elem + sizeof_elems
+        sizeof_flags
+            + sizeof_index
+            + sizeof_offset
+            + sizeof_elem_kind
+            + sizeof_elem_type
+            + sizeof_num_elem
+            + sizeof_elems
+            + sizeof_elem_exprs
     }
 }
@@ -39,10 +39,7 @@ impl ExportSection {
 
         let payload = ExportSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// エクスポートエントリの数
@@ -133,8 +130,8 @@ impl ExportEntry {
 
         Ok(Self {
             field_len: field_len as VarUInt32,
-            field_str: field_str,
-            kind: kind,
+            field_str,
+            kind,
             index: index as VarUInt32,
         })
     }
@@ -146,7 +143,7 @@ impl ExportEntry {
 
     /// エクスポートされるデータの種類
     ///
-    /// Function, Table, Memory, Global
+    /// Function, Table, Memory, Global, Tag
     pub fn get_kind(&self) -> &ExternalKind {
         &self.kind
     }
@@ -160,7 +157,7 @@ impl ExportEntry {
 impl Sizeof for ExportEntry {
     fn sizeof(&self) -> u32 {
         let sizeof_field_len = usage_bytes_leb128_u(self.field_len as u64) as u32;
-        let sizeof_field_str = self.field_len as u32;
+        let sizeof_field_str = self.field_len;
         let sizeof_kind = self.kind.sizeof();
         let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
 
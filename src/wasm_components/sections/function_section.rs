@@ -31,10 +31,7 @@ impl FunctionSection {
 
         let payload = FunctionSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// 関数の数を返す
@@ -51,7 +48,7 @@ impl FunctionSection {
 
     /// idx番目の関数の関数型リストへのインデックスを返す
     pub fn get_indice(&self, idx: usize) -> Option<u32> {
-        self.get_indice_list().get(idx).map(|v| *v)
+        self.get_indice_list().get(idx).copied()
     }
 }
 
@@ -90,7 +87,7 @@ impl FunctionSectionPayload {
 
         Ok(Self {
             count: count as VarUInt32,
-            types: types,
+            types,
         })
     }
 }
@@ -1,6 +1,6 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
 use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
@@ -16,6 +16,7 @@ pub struct GlobalSection {
 pub struct GlobalSectionPayload {
     count: VarUInt32,
     globals: Vec<GlobalVariable>,
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各エントリまでの位置
 }
 
 #[derive(Debug)]
@@ -25,7 +26,7 @@ pub struct GlobalVariable {
 }
 
 impl GlobalSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections //
         let common = SectionCommon::parse(reader)?;
         if common.id != 6 {
@@ -37,10 +38,7 @@ impl GlobalSection {
 
         let payload = GlobalSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     // GlobalVariableのリストのサイズ
@@ -55,6 +53,11 @@ impl GlobalSection {
 
     // Utilities
 
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
+
     /// idx番目のGlobalVariableを返す
     pub fn get_global_variable(&self, idx: usize) -> Option<&GlobalVariable> {
         self.payload.globals.get(idx)
@@ -83,20 +86,25 @@ impl SectionCommonInterface for GlobalSection {
 }
 
 impl GlobalSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
         let mut globals: Vec<GlobalVariable> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
             globals.push(GlobalVariable::parse(reader)?);
         }
 
         Ok(Self {
             count: count as VarUInt32,
-            globals: globals,
+            globals,
+            entry_offsets,
         })
     }
 }
@@ -125,7 +133,10 @@ impl GlobalVariable {
         &self.type_
     }
 
-    // pub fn get_initial(&self);
+    /// 初期値を表す初期化式を返す
+    pub fn get_init_expr(&self) -> &InitExpr {
+        &self.init
+    }
 }
 
 impl Sizeof for GlobalVariable {
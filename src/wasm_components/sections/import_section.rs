@@ -1,11 +1,13 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 use std::str;
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
 use crate::readers::{read_unsigned_leb128, read_x, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
-use crate::wasm_components::types::{ExternalKind, GlobalType, MemoryType, TableType, VarUInt32};
+use crate::wasm_components::types::{
+    ExternalKind, GlobalType, MemoryType, TableType, TagType, VarUInt32,
+};
 
 #[derive(Debug)]
 pub struct ImportSection {
@@ -17,6 +19,7 @@ pub struct ImportSection {
 pub struct ImportSectionPayload {
     count: VarUInt32,
     entries: Vec<ImportEntry>,
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各エントリまでの位置
 }
 
 #[derive(Debug)]
@@ -35,10 +38,11 @@ pub enum TypeEntry {
     TblType { type_: TableType },
     MemType { type_: MemoryType },
     GblType { type_: GlobalType },
+    TagType { type_: TagType },
 }
 
 impl ImportSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections
         let common = SectionCommon::parse(reader)?;
         if common.id != 2 {
@@ -50,10 +54,7 @@ impl ImportSection {
 
         let payload = ImportSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// インポートエントリの数
@@ -68,6 +69,11 @@ impl ImportSection {
 
     // Utilities
 
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
+
     /// idx番目のインポートエントリを返す
     pub fn get_import_entry(&self, idx: usize) -> Option<&ImportEntry> {
         self.payload.entries.get(idx)
@@ -90,7 +96,9 @@ impl SectionCommonInterface for ImportSection {
 }
 
 impl ImportSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
@@ -98,13 +106,16 @@ impl ImportSectionPayload {
         };
 
         let mut import_entries: Vec<ImportEntry> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
             import_entries.push(ImportEntry::parse(reader)?);
         }
 
         Ok(Self {
             count: count as VarUInt32,
             entries: import_entries,
+            entry_offsets,
         })
     }
 }
@@ -153,11 +164,11 @@ impl ImportEntry {
 
         Ok(Self {
             module_len: module_len as VarUInt32,
-            module_str: module_str,
+            module_str,
             field_len: field_len as VarUInt32,
-            field_str: field_str,
-            kind: kind,
-            type_: type_,
+            field_str,
+            kind,
+            type_,
         })
     }
 
@@ -185,9 +196,9 @@ impl ImportEntry {
 impl Sizeof for ImportEntry {
     fn sizeof(&self) -> u32 {
         let sizeof_module_len = usage_bytes_leb128_u(self.module_len as u64) as u32;
-        let sizeof_module_str = self.module_len as u32;
+        let sizeof_module_str = self.module_len;
         let sizeof_field_len = usage_bytes_leb128_u(self.field_len as u64) as u32;
-        let sizeof_str = self.field_len as u32;
+        let sizeof_str = self.field_len;
         let sizeof_kind = self.kind.sizeof();
         let sizeof_type = self.type_.sizeof();
 
@@ -225,6 +236,10 @@ impl TypeEntry {
                 let global_type = GlobalType::parse(reader)?;
                 Ok(TypeEntry::GblType { type_: global_type })
             }
+            ExternalKind::Tag => {
+                let tag_type = TagType::parse(reader)?;
+                Ok(TypeEntry::TagType { type_: tag_type })
+            }
         }
     }
 }
@@ -236,6 +251,7 @@ impl Sizeof for TypeEntry {
             TypeEntry::TblType { type_ } => type_.sizeof(),
             TypeEntry::MemType { type_ } => type_.sizeof(),
             TypeEntry::GblType { type_ } => type_.sizeof(),
+            TypeEntry::TagType { type_ } => type_.sizeof(),
         }
     }
 }
@@ -0,0 +1,552 @@
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::str;
+
+use super::base::ParseError;
+
+use crate::readers::{read_8, read_unsigned_leb128, read_x};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::VarUInt32;
+
+/*
+ * Linking Section
+ *
+ * clangなどが出力するオブジェクトファイル(.o)に含まれる、
+ * リンカ向けのメタデータ(シンボルテーブル、データセグメントの情報など)
+ *
+ * https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md
+ */
+
+pub const LINKING_VERSION: u32 = 2;
+
+pub const WASM_SEGMENT_INFO: u8 = 5;
+pub const WASM_INIT_FUNCS: u8 = 6;
+pub const WASM_COMDAT_INFO: u8 = 7;
+pub const WASM_SYMBOL_TABLE: u8 = 8;
+
+pub const WASM_SYM_BINDING_WEAK: u32 = 0x1;
+pub const WASM_SYM_BINDING_LOCAL: u32 = 0x2;
+pub const WASM_SYM_VISIBILITY_HIDDEN: u32 = 0x4;
+pub const WASM_SYM_UNDEFINED: u32 = 0x10;
+pub const WASM_SYM_EXPORTED: u32 = 0x20;
+pub const WASM_SYM_EXPLICIT_NAME: u32 = 0x40;
+pub const WASM_SYM_NO_STRIP: u32 = 0x80;
+pub const WASM_SYM_TLS: u32 = 0x100;
+pub const WASM_SYM_ABSOLUTE: u32 = 0x200;
+
+#[derive(Debug)]
+pub struct LinkingSectionPayload {
+    payload_size: u32,
+    version: VarUInt32,
+    subsections: Vec<LinkingSubsection>,
+}
+
+#[derive(Debug)]
+pub struct LinkingSubsection {
+    subsection_type: u8,
+    payload_len: VarUInt32,
+    payload: LinkingSubsectionPayload,
+}
+
+#[derive(Debug)]
+pub enum LinkingSubsectionPayload {
+    SegmentInfo(Vec<SegmentInfo>),
+    InitFuncs(Vec<InitFunc>),
+    ComdatInfo(Vec<Comdat>),
+    SymbolTable(Vec<SymbolInfo>),
+    Unknown(Vec<u8>),
+}
+
+/// データセグメントの情報
+#[derive(Debug)]
+pub struct SegmentInfo {
+    name: String,
+    alignment: u32, // log2
+    flags: u32,
+}
+
+/// 初期化関数
+#[derive(Debug)]
+pub struct InitFunc {
+    priority: u32,
+    symbol_index: u32,
+}
+
+#[derive(Debug)]
+pub struct Comdat {
+    name: String,
+    flags: u32,
+    symbols: Vec<ComdatSymbol>,
+}
+
+#[derive(Debug)]
+pub struct ComdatSymbol {
+    kind: ComdatSymbolKind,
+    index: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComdatSymbolKind {
+    Data,
+    Function,
+    Global,
+    Event,
+    Table,
+    Section,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Data,
+    Global,
+    Section,
+    Tag,
+    Table,
+}
+
+#[derive(Debug)]
+pub struct SymbolInfo {
+    kind: SymbolKind,
+    flags: u32,
+    index: Option<u32>, // Data以外: 対象のインデックス
+    name: Option<String>,
+    data: Option<DataSymbolRef>, // 定義されたDataのみ
+}
+
+/// データシンボルが指す位置
+#[derive(Debug, Clone, Copy)]
+pub struct DataSymbolRef {
+    segment_index: u32,
+    offset: u32,
+    size: u32,
+}
+
+pub(super) fn read_u32<R: Read>(reader: &mut R) -> Result<u32, ParseError> {
+    let mut value: u64 = 0;
+    match read_unsigned_leb128(reader, &mut value) {
+        Ok(_rs) => (/* To check read size */),
+        Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+    };
+
+    Ok(value as u32)
+}
+
+pub(super) fn read_name<R: Read>(reader: &mut R) -> Result<String, ParseError> {
+    let name_len = read_u32(reader)?;
+
+    match read_x(reader, name_len as usize) {
+        Ok(data) => match str::from_utf8(&data) {
+            Ok(s) => Ok(String::from(s)),
+            Err(err) => Err(ParseError::FormatError(format!("{:?}", err))),
+        },
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
+}
+
+pub(super) fn read_byte<R: Read>(reader: &mut R) -> Result<u8, ParseError> {
+    match read_8(reader) {
+        Ok(v) => Ok(v[0]),
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
+}
+
+/// シンボルのフラグ(WASM_SYM_*)を"weak|hidden"のような文字列で返す
+pub fn get_symbol_flag_names(flags: u32) -> String {
+    let flag_names = [
+        (WASM_SYM_BINDING_WEAK, "weak"),
+        (WASM_SYM_BINDING_LOCAL, "local"),
+        (WASM_SYM_VISIBILITY_HIDDEN, "hidden"),
+        (WASM_SYM_UNDEFINED, "undefined"),
+        (WASM_SYM_EXPORTED, "exported"),
+        (WASM_SYM_EXPLICIT_NAME, "explicit_name"),
+        (WASM_SYM_NO_STRIP, "no_strip"),
+        (WASM_SYM_TLS, "tls"),
+        (WASM_SYM_ABSOLUTE, "absolute"),
+    ];
+
+    let names: Vec<&str> = flag_names
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    names.join("|")
+}
+
+impl LinkingSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        // サブセクションの長さを検査できるように、ペイロード全体を読み込んでから解析する
+        let data = match read_x(reader, payload_size as usize) {
+            Ok(data) => data,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        let mut cursor = Cursor::new(data);
+
+        let version = read_u32(&mut cursor)?;
+        if version != LINKING_VERSION {
+            return Err(ParseError::FormatError(format!(
+                "Unsupported linking section version: {}",
+                version
+            )));
+        }
+
+        let mut subsections: Vec<LinkingSubsection> = Vec::new();
+        while cursor.position() < payload_size as u64 {
+            subsections.push(LinkingSubsection::parse(&mut cursor)?);
+        }
+
+        Ok(Self {
+            payload_size,
+            version: version as VarUInt32,
+            subsections,
+        })
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get_subsections(&self) -> Vec<&LinkingSubsection> {
+        self.subsections.iter().collect()
+    }
+
+    /// シンボルテーブルを返す(サブセクションがない場合は空)
+    pub fn get_symbol_table(&self) -> Vec<&SymbolInfo> {
+        self.subsections
+            .iter()
+            .filter_map(|x| match &x.payload {
+                LinkingSubsectionPayload::SymbolTable(symbols) => Some(symbols),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// シンボルインデックスに対応するシンボルを返す
+    pub fn get_symbol(&self, symbol_index: u32) -> Option<&SymbolInfo> {
+        self.get_symbol_table()
+            .into_iter()
+            .nth(symbol_index as usize)
+    }
+}
+
+impl Sizeof for LinkingSectionPayload {
+    fn sizeof(&self) -> u32 {
+        self.payload_size
+    }
+}
+
+impl LinkingSubsection {
+    pub fn parse(cursor: &mut Cursor<Vec<u8>>) -> Result<Self, ParseError> {
+        let subsection_type = read_byte(cursor)?;
+        let payload_len = read_u32(cursor)?;
+
+        let start = cursor.position();
+        let payload = match subsection_type {
+            WASM_SEGMENT_INFO => {
+                let count = read_u32(cursor)?;
+                let mut segments: Vec<SegmentInfo> = Vec::new();
+                for _ in 0..count {
+                    segments.push(SegmentInfo::parse(cursor)?);
+                }
+                LinkingSubsectionPayload::SegmentInfo(segments)
+            }
+            WASM_INIT_FUNCS => {
+                let count = read_u32(cursor)?;
+                let mut init_funcs: Vec<InitFunc> = Vec::new();
+                for _ in 0..count {
+                    init_funcs.push(InitFunc::parse(cursor)?);
+                }
+                LinkingSubsectionPayload::InitFuncs(init_funcs)
+            }
+            WASM_COMDAT_INFO => {
+                let count = read_u32(cursor)?;
+                let mut comdats: Vec<Comdat> = Vec::new();
+                for _ in 0..count {
+                    comdats.push(Comdat::parse(cursor)?);
+                }
+                LinkingSubsectionPayload::ComdatInfo(comdats)
+            }
+            WASM_SYMBOL_TABLE => {
+                let count = read_u32(cursor)?;
+                let mut symbols: Vec<SymbolInfo> = Vec::new();
+                for _ in 0..count {
+                    symbols.push(SymbolInfo::parse(cursor)?);
+                }
+                LinkingSubsectionPayload::SymbolTable(symbols)
+            }
+            _ => match read_x(cursor, payload_len as usize) {
+                Ok(data) => LinkingSubsectionPayload::Unknown(data),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            },
+        };
+
+        let read_size = cursor.position() - start;
+        if read_size != payload_len as u64 {
+            return Err(ParseError::FormatError(format!(
+                "linking subsection({}) size mismatch: payload_len={}, read_size={}",
+                subsection_type, payload_len, read_size
+            )));
+        }
+
+        Ok(Self {
+            subsection_type,
+            payload_len: payload_len as VarUInt32,
+            payload,
+        })
+    }
+
+    pub fn get_subsection_type(&self) -> u8 {
+        self.subsection_type
+    }
+
+    pub fn get_payload_len(&self) -> u32 {
+        self.payload_len
+    }
+
+    pub fn get_payload(&self) -> &LinkingSubsectionPayload {
+        &self.payload
+    }
+}
+
+impl SegmentInfo {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let name = read_name(reader)?;
+        let alignment = read_u32(reader)?;
+        let flags = read_u32(reader)?;
+
+        Ok(Self {
+            name,
+            alignment,
+            flags,
+        })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    /// アライメント(log2)を返す
+    pub fn get_alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+}
+
+impl InitFunc {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let priority = read_u32(reader)?;
+        let symbol_index = read_u32(reader)?;
+
+        Ok(Self {
+            priority,
+            symbol_index,
+        })
+    }
+
+    pub fn get_priority(&self) -> u32 {
+        self.priority
+    }
+
+    pub fn get_symbol_index(&self) -> u32 {
+        self.symbol_index
+    }
+}
+
+impl Comdat {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let name = read_name(reader)?;
+        let flags = read_u32(reader)?;
+
+        let count = read_u32(reader)?;
+        let mut symbols: Vec<ComdatSymbol> = Vec::new();
+        for _ in 0..count {
+            let kind = ComdatSymbolKind::convert_from_u8(read_byte(reader)?)?;
+            let index = read_u32(reader)?;
+            symbols.push(ComdatSymbol { kind, index });
+        }
+
+        Ok(Self {
+            name,
+            flags,
+            symbols,
+        })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn get_symbols(&self) -> Vec<&ComdatSymbol> {
+        self.symbols.iter().collect()
+    }
+}
+
+impl ComdatSymbol {
+    pub fn get_kind(&self) -> ComdatSymbolKind {
+        self.kind
+    }
+
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl ComdatSymbolKind {
+    pub fn convert_from_u8(kind: u8) -> Result<Self, ParseError> {
+        match kind {
+            0 => Ok(ComdatSymbolKind::Data),
+            1 => Ok(ComdatSymbolKind::Function),
+            2 => Ok(ComdatSymbolKind::Global),
+            3 => Ok(ComdatSymbolKind::Event),
+            4 => Ok(ComdatSymbolKind::Table),
+            5 => Ok(ComdatSymbolKind::Section),
+            _ => Err(ParseError::FormatError(format!(
+                "Invalid comdat symbol kind: {}",
+                kind
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ComdatSymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ComdatSymbolKind::Data => "data",
+            ComdatSymbolKind::Function => "func",
+            ComdatSymbolKind::Global => "global",
+            ComdatSymbolKind::Event => "event",
+            ComdatSymbolKind::Table => "table",
+            ComdatSymbolKind::Section => "section",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl SymbolKind {
+    pub fn convert_from_u8(kind: u8) -> Result<Self, ParseError> {
+        match kind {
+            0 => Ok(SymbolKind::Function),
+            1 => Ok(SymbolKind::Data),
+            2 => Ok(SymbolKind::Global),
+            3 => Ok(SymbolKind::Section),
+            4 => Ok(SymbolKind::Tag),
+            5 => Ok(SymbolKind::Table),
+            _ => Err(ParseError::FormatError(format!(
+                "Invalid symbol kind: {}",
+                kind
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SymbolKind::Function => "func",
+            SymbolKind::Data => "data",
+            SymbolKind::Global => "global",
+            SymbolKind::Section => "section",
+            SymbolKind::Tag => "tag",
+            SymbolKind::Table => "table",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl SymbolInfo {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let kind = SymbolKind::convert_from_u8(read_byte(reader)?)?;
+        let flags = read_u32(reader)?;
+
+        let is_undefined = flags & WASM_SYM_UNDEFINED != 0;
+        let mut index: Option<u32> = None;
+        let mut name: Option<String> = None;
+        let mut data: Option<DataSymbolRef> = None;
+
+        match kind {
+            SymbolKind::Function | SymbolKind::Global | SymbolKind::Tag | SymbolKind::Table => {
+                index = Some(read_u32(reader)?);
+                // 未定義シンボルの名前は、明示されない限りインポート名を使う
+                if !is_undefined || flags & WASM_SYM_EXPLICIT_NAME != 0 {
+                    name = Some(read_name(reader)?);
+                }
+            }
+            SymbolKind::Data => {
+                name = Some(read_name(reader)?);
+                if !is_undefined {
+                    let segment_index = read_u32(reader)?;
+                    let offset = read_u32(reader)?;
+                    let size = read_u32(reader)?;
+                    data = Some(DataSymbolRef {
+                        segment_index,
+                        offset,
+                        size,
+                    });
+                }
+            }
+            SymbolKind::Section => {
+                index = Some(read_u32(reader)?);
+            }
+        }
+
+        Ok(Self {
+            kind,
+            flags,
+            index,
+            name,
+            data,
+        })
+    }
+
+    pub fn get_kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// 対象のインデックス(関数・グローバル・タグ・テーブル・セクション)を返す
+    pub fn get_index(&self) -> Option<u32> {
+        self.index
+    }
+
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn get_data(&self) -> Option<&DataSymbolRef> {
+        self.data.as_ref()
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        self.flags & WASM_SYM_UNDEFINED != 0
+    }
+
+    /// フラグを"weak|hidden"のような文字列で返す
+    pub fn get_flag_names(&self) -> String {
+        get_symbol_flag_names(self.flags)
+    }
+}
+
+impl DataSymbolRef {
+    pub fn get_segment_index(&self) -> u32 {
+        self.segment_index
+    }
+
+    pub fn get_offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+}
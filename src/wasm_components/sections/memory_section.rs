@@ -1,6 +1,6 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
 use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
@@ -16,10 +16,11 @@ pub struct MemorySection {
 pub struct MemorySectionPayload {
     count: VarUInt32,
     entries: Vec<MemoryType>,
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各エントリまでの位置
 }
 
 impl MemorySection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections //
         let common = SectionCommon::parse(reader)?;
         if common.id != 5 {
@@ -30,10 +31,7 @@ impl MemorySection {
         // ここまで共通 //
         let payload = MemorySectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// 線形メモリの数を返す
@@ -50,7 +48,12 @@ impl MemorySection {
         self.payload.entries.iter().collect()
     }
 
-    /// Utilities
+    // Utilities
+
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
 
     /// idx番目の線形メモリを返す
     pub fn get_memory(&self, idx: usize) -> Option<&MemoryType> {
@@ -74,20 +77,25 @@ impl SectionCommonInterface for MemorySection {
 }
 
 impl MemorySectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
         let mut entries: Vec<MemoryType> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
             entries.push(MemoryType::parse(reader)?);
         }
 
         Ok(Self {
             count: count as VarUInt32,
-            entries: entries,
+            entries,
+            entry_offsets,
         })
     }
 }
@@ -7,12 +7,62 @@ use crate::readers::{read_unsigned_leb128, read_x, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
 use crate::wasm_components::types::{VarUInt32, VarUInt7};
 
+/*
+ * Name Section
+ *
+ * 0: module, 1: function, 2: local
+ * 以下はextended-name-section提案で追加されたサブセクション
+ * 3: label, 4: type, 5: table, 6: memory, 7: global,
+ * 8: elem segment, 9: data segment, 10: field, 11: tag
+ */
+
+pub const NAME_TYPE_MODULE: u8 = 0;
+pub const NAME_TYPE_FUNCTION: u8 = 1;
+pub const NAME_TYPE_LOCAL: u8 = 2;
+pub const NAME_TYPE_LABEL: u8 = 3;
+pub const NAME_TYPE_TYPE: u8 = 4;
+pub const NAME_TYPE_TABLE: u8 = 5;
+pub const NAME_TYPE_MEMORY: u8 = 6;
+pub const NAME_TYPE_GLOBAL: u8 = 7;
+pub const NAME_TYPE_ELEM: u8 = 8;
+pub const NAME_TYPE_DATA: u8 = 9;
+pub const NAME_TYPE_FIELD: u8 = 10;
+pub const NAME_TYPE_TAG: u8 = 11;
+
+// サブセクションIDに対応する名前
+fn get_name_type_name(name_type: u8) -> &'static str {
+    match name_type {
+        NAME_TYPE_MODULE => "module",
+        NAME_TYPE_FUNCTION => "function",
+        NAME_TYPE_LOCAL => "local",
+        NAME_TYPE_LABEL => "label",
+        NAME_TYPE_TYPE => "type",
+        NAME_TYPE_TABLE => "table",
+        NAME_TYPE_MEMORY => "memory",
+        NAME_TYPE_GLOBAL => "global",
+        NAME_TYPE_ELEM => "elem",
+        NAME_TYPE_DATA => "data",
+        NAME_TYPE_FIELD => "field",
+        NAME_TYPE_TAG => "tag",
+        _ => "unknown",
+    }
+}
+
 #[derive(Debug)]
 pub struct NameSectionPayload {
     // Name Subsections
     module_name: Option<ModuleName>,
-    function_names: Option<FunctionNames>,
-    local_names: Option<LocalNames>,
+    function_names: Option<NameMapSubsection>,
+    local_names: Option<IndirectNameMapSubsection>,
+    label_names: Option<IndirectNameMapSubsection>,
+    type_names: Option<NameMapSubsection>,
+    table_names: Option<NameMapSubsection>,
+    memory_names: Option<NameMapSubsection>,
+    global_names: Option<NameMapSubsection>,
+    elem_names: Option<NameMapSubsection>,
+    data_names: Option<NameMapSubsection>,
+    field_names: Option<IndirectNameMapSubsection>,
+    tag_names: Option<NameMapSubsection>,
 }
 
 #[derive(Debug)]
@@ -23,25 +73,29 @@ pub struct ModuleName {
     name_str: String,
 }
 
+/// index -> 名前 の対応を持つサブセクション (function, type, table, ...etc)
 #[derive(Debug)]
-pub struct FunctionNames {
+pub struct NameMapSubsection {
     name_type: VarUInt7,
     name_payload_len: VarUInt32,
-    func_map: NameMap,
+    name_map: NameMap,
 }
 
+/// 外側のindexごとにNameMapを持つサブセクション (local, label, field)
+///
+/// localとlabelは関数インデックス、fieldは型インデックスごとに並ぶ
 #[derive(Debug)]
-pub struct LocalNames {
+pub struct IndirectNameMapSubsection {
     name_type: VarUInt7,
     name_payload_len: VarUInt32,
     count: VarUInt32,
-    funcs: Vec<LocalName>,
+    entries: Vec<IndirectNaming>,
 }
 
 #[derive(Debug)]
-pub struct LocalName {
+pub struct IndirectNaming {
     index: VarUInt32,
-    local_map: NameMap,
+    name_map: NameMap,
 }
 
 #[derive(Debug)]
@@ -61,88 +115,193 @@ impl NameSectionPayload {
     pub fn parse<R: Read + Seek>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
         let mut read_size: u32 = 0;
 
-        let mut module_name: Option<ModuleName> = None;
-        let mut function_names: Option<FunctionNames> = None;
-        let mut local_names: Option<LocalNames> = None;
+        let mut payload = Self {
+            module_name: None,
+            function_names: None,
+            local_names: None,
+            label_names: None,
+            type_names: None,
+            table_names: None,
+            memory_names: None,
+            global_names: None,
+            elem_names: None,
+            data_names: None,
+            field_names: None,
+            tag_names: None,
+        };
 
         while read_size < payload_size {
             let mut name_type: u64 = 0;
-            let sizeof_name_type: u32;
-            match read_unsigned_leb128(reader, &mut name_type) {
-                Ok(rs) => (sizeof_name_type = rs as u32),
+
+            let sizeof_name_type: u32 = match read_unsigned_leb128(reader, &mut name_type) {
+                Ok(rs) => rs as u32,
                 Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
             };
 
             let mut name_payload_len: u64 = 0;
-            let sizeof_name_payload_len: u32;
-            match read_unsigned_leb128(reader, &mut name_payload_len) {
-                Ok(rs) => (sizeof_name_payload_len = rs as u32),
-                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
-            };
 
-            match name_type {
-                0 => {
-                    module_name = Some(ModuleName::parse(
-                        reader,
-                        name_type as VarUInt7,
-                        name_payload_len as VarUInt32,
-                    )?);
-                    read_size += module_name.as_ref().unwrap().sizeof()
-                }
-                1 => {
-                    function_names = Some(FunctionNames::parse(
-                        reader,
-                        name_type as VarUInt7,
-                        name_payload_len as VarUInt32,
-                    )?);
-                    read_size += function_names.as_ref().unwrap().sizeof();
-                }
-                2 => {
-                    local_names = Some(LocalNames::parse(
-                        reader,
-                        name_type as VarUInt7,
-                        name_payload_len as VarUInt32,
-                    )?);
-                    read_size += local_names.as_ref().unwrap().sizeof();
-                }
-                _ => {
-                    println!(" > Warn: unexpected: {}", name_type);
-                    let sizeof_unknown_subsection =
-                        sizeof_name_type + sizeof_name_payload_len + name_payload_len as u32;
-                    read_size += sizeof_unknown_subsection;
-                    match reader.seek(SeekFrom::Current(name_payload_len as i64)) {
-                        Ok(_rs) => (/* To check read size */),
-                        Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
-                    };
-                }
+            let sizeof_name_payload_len: u32 =
+                match read_unsigned_leb128(reader, &mut name_payload_len) {
+                    Ok(rs) => rs as u32,
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+
+            let name_type = name_type as VarUInt7;
+            let name_payload_len = name_payload_len as VarUInt32;
+
+            // 名前マップ型のサブセクションの格納先
+            let name_map_slot = match name_type as u8 {
+                NAME_TYPE_FUNCTION => Some(&mut payload.function_names),
+                NAME_TYPE_TYPE => Some(&mut payload.type_names),
+                NAME_TYPE_TABLE => Some(&mut payload.table_names),
+                NAME_TYPE_MEMORY => Some(&mut payload.memory_names),
+                NAME_TYPE_GLOBAL => Some(&mut payload.global_names),
+                NAME_TYPE_ELEM => Some(&mut payload.elem_names),
+                NAME_TYPE_DATA => Some(&mut payload.data_names),
+                NAME_TYPE_TAG => Some(&mut payload.tag_names),
+                _ => None,
             };
+            if let Some(slot) = name_map_slot {
+                let subsection = NameMapSubsection::parse(reader, name_type, name_payload_len)?;
+                read_size += subsection.sizeof();
+                *slot = Some(subsection);
+                continue;
+            }
+
+            // 間接名前マップ型のサブセクションの格納先
+            let indirect_name_map_slot = match name_type as u8 {
+                NAME_TYPE_LOCAL => Some(&mut payload.local_names),
+                NAME_TYPE_LABEL => Some(&mut payload.label_names),
+                NAME_TYPE_FIELD => Some(&mut payload.field_names),
+                _ => None,
+            };
+            if let Some(slot) = indirect_name_map_slot {
+                let subsection =
+                    IndirectNameMapSubsection::parse(reader, name_type, name_payload_len)?;
+                read_size += subsection.sizeof();
+                *slot = Some(subsection);
+                continue;
+            }
+
+            if name_type as u8 == NAME_TYPE_MODULE {
+                let module_name = ModuleName::parse(reader, name_type, name_payload_len)?;
+                read_size += module_name.sizeof();
+                payload.module_name = Some(module_name);
+            } else {
+                eprintln!(" > Warn: unexpected: {}", name_type);
+                let sizeof_unknown_subsection =
+                    sizeof_name_type + sizeof_name_payload_len + name_payload_len as u32;
+                read_size += sizeof_unknown_subsection;
+                match reader.seek(SeekFrom::Current(name_payload_len as i64)) {
+                    Ok(_rs) => (/* To check read size */),
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+            }
         }
 
         if read_size != payload_size {
-            return Err(ParseError::FormatError(String::from(format!(
+            return Err(ParseError::FormatError(format!(
                 "payload_size and read_size are not same: payload_size={}, read_size={}",
                 payload_size, read_size
-            ))));
+            )));
         }
 
-        Ok(Self {
-            module_name: module_name,
-            function_names: function_names,
-            local_names: local_names,
-        })
+        Ok(payload)
     }
 
     pub fn get_module_name(&self) -> Option<&ModuleName> {
         self.module_name.as_ref()
     }
 
-    pub fn get_function_names(&self) -> Option<&FunctionNames> {
+    pub fn get_function_names(&self) -> Option<&NameMapSubsection> {
         self.function_names.as_ref()
     }
 
-    pub fn get_local_names(&self) -> Option<&LocalNames> {
+    pub fn get_local_names(&self) -> Option<&IndirectNameMapSubsection> {
         self.local_names.as_ref()
     }
+
+    pub fn get_label_names(&self) -> Option<&IndirectNameMapSubsection> {
+        self.label_names.as_ref()
+    }
+
+    pub fn get_type_names(&self) -> Option<&NameMapSubsection> {
+        self.type_names.as_ref()
+    }
+
+    pub fn get_table_names(&self) -> Option<&NameMapSubsection> {
+        self.table_names.as_ref()
+    }
+
+    pub fn get_memory_names(&self) -> Option<&NameMapSubsection> {
+        self.memory_names.as_ref()
+    }
+
+    pub fn get_global_names(&self) -> Option<&NameMapSubsection> {
+        self.global_names.as_ref()
+    }
+
+    pub fn get_elem_names(&self) -> Option<&NameMapSubsection> {
+        self.elem_names.as_ref()
+    }
+
+    pub fn get_data_names(&self) -> Option<&NameMapSubsection> {
+        self.data_names.as_ref()
+    }
+
+    pub fn get_field_names(&self) -> Option<&IndirectNameMapSubsection> {
+        self.field_names.as_ref()
+    }
+
+    pub fn get_tag_names(&self) -> Option<&NameMapSubsection> {
+        self.tag_names.as_ref()
+    }
+
+    /// 名前マップ型のサブセクションをサブセクションIDの順に返す
+    pub fn get_name_map_subsections(&self) -> Vec<&NameMapSubsection> {
+        [
+            &self.function_names,
+            &self.type_names,
+            &self.table_names,
+            &self.memory_names,
+            &self.global_names,
+            &self.elem_names,
+            &self.data_names,
+            &self.tag_names,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// 間接名前マップ型のサブセクションをサブセクションIDの順に返す
+    pub fn get_indirect_name_map_subsections(&self) -> Vec<&IndirectNameMapSubsection> {
+        [&self.local_names, &self.label_names, &self.field_names]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// 関数インデックスに対応する名前を返す
+    pub fn get_function_name(&self, func_idx: u32) -> Option<&String> {
+        self.function_names
+            .as_ref()
+            .and_then(|x| x.get_name_map().get_name(func_idx))
+    }
+
+    /// グローバル変数のインデックスに対応する名前を返す
+    pub fn get_global_name(&self, global_idx: u32) -> Option<&String> {
+        self.global_names
+            .as_ref()
+            .and_then(|x| x.get_name_map().get_name(global_idx))
+    }
+
+    /// 関数インデックスとローカル変数のインデックスに対応する名前を返す
+    pub fn get_local_name(&self, func_idx: u32, local_idx: u32) -> Option<&String> {
+        self.local_names
+            .as_ref()
+            .and_then(|x| x.get_name(func_idx, local_idx))
+    }
 }
 
 impl Sizeof for NameSectionPayload {
@@ -152,18 +311,18 @@ impl Sizeof for NameSectionPayload {
             .as_ref()
             .map(|mod_name| mod_name.sizeof())
             .unwrap_or(0);
-        let sizeof_func_name = self
-            .function_names
-            .as_ref()
-            .map(|mod_name| mod_name.sizeof())
-            .unwrap_or(0);
-        let sizeof_local_name = self
-            .local_names
-            .as_ref()
-            .map(|mod_name| mod_name.sizeof())
-            .unwrap_or(0);
-
-        sizeof_module_name + sizeof_func_name + sizeof_local_name
+        let sizeof_name_maps: u32 = self
+            .get_name_map_subsections()
+            .into_iter()
+            .map(|x| x.sizeof())
+            .sum();
+        let sizeof_indirect_name_maps: u32 = self
+            .get_indirect_name_map_subsections()
+            .into_iter()
+            .map(|x| x.sizeof())
+            .sum();
+
+        sizeof_module_name + sizeof_name_maps + sizeof_indirect_name_maps
     }
 }
 
@@ -192,20 +351,20 @@ impl ModuleName {
             name_type: name_type as VarUInt7,
             name_payload_len: name_payload_len as VarUInt32,
             name_len: name_len as VarUInt32,
-            name_str: name_str,
+            name_str,
         })
     }
 
     pub fn get_name_type(&self) -> u8 {
-        self.name_type as u8
+        self.name_type
     }
 
     pub fn get_payload_size(&self) -> u32 {
-        self.name_payload_len as u32
+        self.name_payload_len
     }
 
     pub fn get_name_length(&self) -> u32 {
-        self.name_len as u32
+        self.name_len
     }
 
     pub fn get_name(&self) -> &String {
@@ -218,51 +377,56 @@ impl Sizeof for ModuleName {
         let sizeof_name_type = usage_bytes_leb128_u(self.name_type as u64) as u32;
         let sizeof_name_payload_len = usage_bytes_leb128_u(self.name_payload_len as u64) as u32;
         let sizeof_name_len = usage_bytes_leb128_u(self.name_len as u64) as u32;
-        let sizeof_name_str = self.name_len as u32;
+        let sizeof_name_str = self.name_len;
 
         sizeof_name_type + sizeof_name_payload_len + sizeof_name_len + sizeof_name_str
     }
 }
 
-impl FunctionNames {
+impl NameMapSubsection {
     pub fn parse<R: Read>(
         reader: &mut R,
         name_type: VarUInt7,
         name_payload_len: VarUInt32,
     ) -> Result<Self, ParseError> {
-        let func_map = NameMap::parse(reader)?;
+        let name_map = NameMap::parse(reader)?;
 
         Ok(Self {
             name_type: name_type as VarUInt7,
             name_payload_len: name_payload_len as VarUInt32,
-            func_map: func_map,
+            name_map,
         })
     }
 
     pub fn get_name_type(&self) -> u8 {
-        self.name_type as u8
+        self.name_type
+    }
+
+    /// サブセクションの種類の名前を返す (e.g. "function")
+    pub fn get_kind_name(&self) -> &'static str {
+        get_name_type_name(self.name_type)
     }
 
     pub fn get_payload_size(&self) -> u32 {
-        self.name_payload_len as u32
+        self.name_payload_len
     }
 
-    pub fn get_function_map(&self) -> &NameMap {
-        &self.func_map
+    pub fn get_name_map(&self) -> &NameMap {
+        &self.name_map
     }
 }
 
-impl Sizeof for FunctionNames {
+impl Sizeof for NameMapSubsection {
     fn sizeof(&self) -> u32 {
         let sizeof_name_type = usage_bytes_leb128_u(self.name_type as u64) as u32;
         let sizeof_name_payload_len = usage_bytes_leb128_u(self.name_payload_len as u64) as u32;
-        let sizeof_func_map = self.func_map.sizeof();
+        let sizeof_name_map = self.name_map.sizeof();
 
-        sizeof_name_type + sizeof_name_payload_len + sizeof_func_map
+        sizeof_name_type + sizeof_name_payload_len + sizeof_name_map
     }
 }
 
-impl LocalNames {
+impl IndirectNameMapSubsection {
     pub fn parse<R: Read>(
         reader: &mut R,
         name_type: VarUInt7,
@@ -274,48 +438,61 @@ impl LocalNames {
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let mut funcs: Vec<LocalName> = Vec::new();
+        let mut entries: Vec<IndirectNaming> = Vec::new();
         for _ in 0..count {
-            funcs.push(LocalName::parse(reader)?);
+            entries.push(IndirectNaming::parse(reader)?);
         }
 
         Ok(Self {
             name_type: name_type as VarUInt7,
             name_payload_len: name_payload_len as VarUInt32,
             count: count as VarUInt32,
-            funcs: funcs,
+            entries,
         })
     }
 
     pub fn get_name_type(&self) -> u8 {
-        self.name_type as u8
+        self.name_type
+    }
+
+    /// サブセクションの種類の名前を返す (e.g. "local")
+    pub fn get_kind_name(&self) -> &'static str {
+        get_name_type_name(self.name_type)
     }
 
     pub fn get_payload_size(&self) -> u32 {
-        self.name_payload_len as u32
+        self.name_payload_len
     }
 
     pub fn get_count(&self) -> u32 {
-        self.count as u32
+        self.count
     }
 
-    pub fn get_locals(&self) -> Vec<&LocalName> {
-        self.funcs.iter().collect()
+    pub fn get_entries(&self) -> Vec<&IndirectNaming> {
+        self.entries.iter().collect()
+    }
+
+    /// 外側のindex(関数・型インデックス)と内側のindexに対応する名前を返す
+    pub fn get_name(&self, outer_index: u32, inner_index: u32) -> Option<&String> {
+        self.entries
+            .iter()
+            .find(|x| x.get_indice() == outer_index)
+            .and_then(|x| x.get_name_map().get_name(inner_index))
     }
 }
 
-impl Sizeof for LocalNames {
+impl Sizeof for IndirectNameMapSubsection {
     fn sizeof(&self) -> u32 {
         let sizeof_name_type = usage_bytes_leb128_u(self.name_type as u64) as u32;
         let sizeof_name_payload_len = usage_bytes_leb128_u(self.name_payload_len as u64) as u32;
         let sizeof_count: u32 = usage_bytes_leb128_u(self.count as u64) as u32;
-        let sizeof_funcs: u32 = self.funcs.iter().map(|x| x.sizeof()).sum();
+        let sizeof_entries: u32 = self.entries.iter().map(|x| x.sizeof()).sum();
 
-        sizeof_name_type + sizeof_name_payload_len + sizeof_count + sizeof_funcs
+        sizeof_name_type + sizeof_name_payload_len + sizeof_count + sizeof_entries
     }
 }
 
-impl LocalName {
+impl IndirectNaming {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
         let mut index: u64 = 0;
         match read_unsigned_leb128(reader, &mut index) {
@@ -323,31 +500,31 @@ impl LocalName {
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let local_map = NameMap::parse(reader)?;
+        let name_map = NameMap::parse(reader)?;
 
         Ok(Self {
             index: index as VarUInt32,
-            local_map: local_map,
+            name_map,
         })
     }
 
-    /// func_idxを返す
+    /// 外側のindex(func_idxまたはtype_idx)を返す
     pub fn get_indice(&self) -> u32 {
-        self.index as u32
+        self.index
     }
 
-    /// 対応するローカルスコープのすべてのローカル変数情報を返す
-    pub fn get_local_map(&self) -> &NameMap {
-        &self.local_map
+    /// 外側のindexに属するすべての名前情報を返す
+    pub fn get_name_map(&self) -> &NameMap {
+        &self.name_map
     }
 }
 
-impl Sizeof for LocalName {
+impl Sizeof for IndirectNaming {
     fn sizeof(&self) -> u32 {
         let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
-        let sizeof_local_map = self.local_map.sizeof();
+        let sizeof_name_map = self.name_map.sizeof();
 
-        sizeof_index + sizeof_local_map
+        sizeof_index + sizeof_name_map
     }
 }
 
@@ -366,17 +543,25 @@ impl NameMap {
 
         Ok(Self {
             count: count as VarUInt32,
-            names: names,
+            names,
         })
     }
 
     pub fn get_num_names(&self) -> u32 {
-        self.count as u32
+        self.count
     }
 
     pub fn get_name_list(&self) -> Vec<&Naming> {
         self.names.iter().collect()
     }
+
+    /// indexに対応する名前を返す
+    pub fn get_name(&self, index: u32) -> Option<&String> {
+        self.names
+            .iter()
+            .find(|x| x.get_indice() == index)
+            .map(|x| x.get_name_str())
+    }
 }
 
 impl Sizeof for NameMap {
@@ -414,16 +599,16 @@ impl Naming {
         Ok(Self {
             index: index as VarUInt32,
             name_len: name_len as VarUInt32,
-            name_str: name_str,
+            name_str,
         })
     }
 
     pub fn get_indice(&self) -> u32 {
-        self.index as u32
+        self.index
     }
 
     pub fn get_name_len(&self) -> u32 {
-        self.name_len as u32
+        self.name_len
     }
 
     pub fn get_name_str(&self) -> &String {
@@ -435,7 +620,7 @@ impl Sizeof for Naming {
     fn sizeof(&self) -> u32 {
         let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
         let sizeof_name_len = usage_bytes_leb128_u(self.name_len as u64) as u32;
-        let sizeof_name_str = self.name_len as u32;
+        let sizeof_name_str = self.name_len;
 
         sizeof_index + sizeof_name_len + sizeof_name_str
     }
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::io::Read;
+use std::str;
+
+use super::base::ParseError;
+
+use crate::readers::{read_unsigned_leb128, read_x, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::VarUInt32;
+
+/*
+ * Producers Section
+ *
+ * モジュールを生成したツールの情報を持つカスタムセクション
+ * フィールド名は"language", "processed-by", "sdk"のいずれか
+ */
+
+#[derive(Debug)]
+pub struct ProducersSectionPayload {
+    field_count: VarUInt32,
+    fields: Vec<ProducerField>,
+}
+
+#[derive(Debug)]
+pub struct ProducerField {
+    field_name: String,
+    value_count: VarUInt32,
+    values: Vec<VersionedName>,
+}
+
+#[derive(Debug)]
+pub struct VersionedName {
+    name: String,
+    version: String,
+}
+
+fn read_name<R: Read>(reader: &mut R) -> Result<String, ParseError> {
+    let mut name_len: u64 = 0;
+    match read_unsigned_leb128(reader, &mut name_len) {
+        Ok(_rs) => (/* To check read size */),
+        Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+    };
+
+    match read_x(reader, name_len as usize) {
+        Ok(data) => match str::from_utf8(&data) {
+            Ok(s) => Ok(String::from(s)),
+            Err(err) => Err(ParseError::FormatError(format!("{:?}", err))),
+        },
+        Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
+    }
+}
+
+fn sizeof_name(name: &str) -> u32 {
+    usage_bytes_leb128_u(name.len() as u64) as u32 + name.len() as u32
+}
+
+impl ProducersSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let mut field_count: u64 = 0;
+        match read_unsigned_leb128(reader, &mut field_count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut fields: Vec<ProducerField> = Vec::new();
+        for _ in 0..field_count {
+            fields.push(ProducerField::parse(reader)?);
+        }
+
+        let payload = Self {
+            field_count: field_count as VarUInt32,
+            fields,
+        };
+
+        if payload.sizeof() != payload_size {
+            return Err(ParseError::FormatError(format!(
+                "Producers section size mismatch: expected {} bytes, but read {} bytes",
+                payload_size,
+                payload.sizeof()
+            )));
+        }
+
+        Ok(payload)
+    }
+
+    pub fn get_num_fields(&self) -> u32 {
+        self.field_count
+    }
+
+    pub fn get_fields(&self) -> Vec<&ProducerField> {
+        self.fields.iter().collect()
+    }
+
+    /// 指定した名前のフィールドを返す
+    pub fn get_field(&self, field_name: &str) -> Option<&ProducerField> {
+        self.fields.iter().find(|x| x.field_name == field_name)
+    }
+}
+
+impl Sizeof for ProducersSectionPayload {
+    fn sizeof(&self) -> u32 {
+        let sizeof_field_count = usage_bytes_leb128_u(self.field_count as u64) as u32;
+        let sizeof_fields: u32 = self.fields.iter().map(|x| x.sizeof()).sum();
+
+        sizeof_field_count + sizeof_fields
+    }
+}
+
+impl ProducerField {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let field_name = read_name(reader)?;
+
+        let mut value_count: u64 = 0;
+        match read_unsigned_leb128(reader, &mut value_count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut values: Vec<VersionedName> = Vec::new();
+        for _ in 0..value_count {
+            values.push(VersionedName::parse(reader)?);
+        }
+
+        Ok(Self {
+            field_name,
+            value_count: value_count as VarUInt32,
+            values,
+        })
+    }
+
+    pub fn get_field_name(&self) -> &String {
+        &self.field_name
+    }
+
+    pub fn get_num_values(&self) -> u32 {
+        self.value_count
+    }
+
+    pub fn get_values(&self) -> Vec<&VersionedName> {
+        self.values.iter().collect()
+    }
+}
+
+impl Sizeof for ProducerField {
+    fn sizeof(&self) -> u32 {
+        let sizeof_field_name = sizeof_name(&self.field_name);
+        let sizeof_value_count = usage_bytes_leb128_u(self.value_count as u64) as u32;
+        let sizeof_values: u32 = self.values.iter().map(|x| x.sizeof()).sum();
+
+        sizeof_field_name + sizeof_value_count + sizeof_values
+    }
+}
+
+impl VersionedName {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let name = read_name(reader)?;
+        let version = read_name(reader)?;
+
+        Ok(Self { name, version })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_version(&self) -> &String {
+        &self.version
+    }
+}
+
+impl Sizeof for VersionedName {
+    fn sizeof(&self) -> u32 {
+        sizeof_name(&self.name) + sizeof_name(&self.version)
+    }
+}
+
+impl fmt::Display for VersionedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.version.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} {}", self.name, self.version)
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use super::base::ParseError;
+
+use crate::readers::{read_8, read_signed_leb128, read_unsigned_leb128, read_x};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::VarUInt32;
+
+/*
+ * Relocation Section ("reloc.*")
+ *
+ * オブジェクトファイル内で、リンク時に書き換える必要がある箇所の一覧
+ * section_indexはモジュール内のセクションの通し番号(カスタムセクションを含む)
+ *
+ * https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md
+ */
+
+#[derive(Debug)]
+pub struct RelocSectionPayload {
+    payload_size: u32,
+    section_index: VarUInt32,
+    count: VarUInt32,
+    entries: Vec<RelocationEntry>,
+}
+
+#[derive(Debug)]
+pub struct RelocationEntry {
+    reloc_type: RelocType,
+    offset: u32, // 対象セクションのペイロード先頭からのオフセット
+    index: u32,  // シンボルインデックス(R_WASM_TYPE_INDEX_LEBの場合は型インデックス)
+    addend: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelocType(u8);
+
+const RELOC_TYPE_NAMES: [&str; 27] = [
+    "R_WASM_FUNCTION_INDEX_LEB",
+    "R_WASM_TABLE_INDEX_SLEB",
+    "R_WASM_TABLE_INDEX_I32",
+    "R_WASM_MEMORY_ADDR_LEB",
+    "R_WASM_MEMORY_ADDR_SLEB",
+    "R_WASM_MEMORY_ADDR_I32",
+    "R_WASM_TYPE_INDEX_LEB",
+    "R_WASM_GLOBAL_INDEX_LEB",
+    "R_WASM_FUNCTION_OFFSET_I32",
+    "R_WASM_SECTION_OFFSET_I32",
+    "R_WASM_TAG_INDEX_LEB",
+    "R_WASM_MEMORY_ADDR_REL_SLEB",
+    "R_WASM_TABLE_INDEX_REL_SLEB",
+    "R_WASM_GLOBAL_INDEX_I32",
+    "R_WASM_MEMORY_ADDR_LEB64",
+    "R_WASM_MEMORY_ADDR_SLEB64",
+    "R_WASM_MEMORY_ADDR_I64",
+    "R_WASM_MEMORY_ADDR_REL_SLEB64",
+    "R_WASM_TABLE_INDEX_SLEB64",
+    "R_WASM_TABLE_INDEX_I64",
+    "R_WASM_TABLE_NUMBER_LEB",
+    "R_WASM_MEMORY_ADDR_TLS_SLEB",
+    "R_WASM_FUNCTION_OFFSET_I64",
+    "R_WASM_MEMORY_ADDR_LOCREL_I32",
+    "R_WASM_TABLE_INDEX_REL_SLEB64",
+    "R_WASM_MEMORY_ADDR_TLS_SLEB64",
+    "R_WASM_FUNCTION_INDEX_I32",
+];
+
+pub const R_WASM_TYPE_INDEX_LEB: u8 = 6;
+
+impl RelocType {
+    pub fn convert_from_u8(value: u8) -> Result<Self, ParseError> {
+        if (value as usize) < RELOC_TYPE_NAMES.len() {
+            Ok(RelocType(value))
+        } else {
+            Err(ParseError::FormatError(format!(
+                "Unknown relocation type: {}",
+                value
+            )))
+        }
+    }
+
+    pub fn get_value(&self) -> u8 {
+        self.0
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        RELOC_TYPE_NAMES[self.0 as usize]
+    }
+
+    /// addendを持つ(メモリアドレス・オフセットの)再配置かどうかを返す
+    pub fn has_addend(&self) -> bool {
+        matches!(
+            self.0,
+            3 | 4 | 5 | 8 | 9 | 11 | 14 | 15 | 16 | 17 | 21 | 22 | 23 | 25
+        )
+    }
+
+    /// indexがシンボルインデックスかどうかを返す(falseの場合は型インデックス)
+    pub fn refers_to_symbol(&self) -> bool {
+        self.0 != R_WASM_TYPE_INDEX_LEB
+    }
+}
+
+impl fmt::Display for RelocType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, ParseError> {
+    let mut value: u64 = 0;
+    match read_unsigned_leb128(reader, &mut value) {
+        Ok(_rs) => (/* To check read size */),
+        Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+    };
+
+    Ok(value as u32)
+}
+
+impl RelocSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let data = match read_x(reader, payload_size as usize) {
+            Ok(data) => data,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        let mut cursor = Cursor::new(data);
+
+        let section_index = read_u32(&mut cursor)?;
+        let count = read_u32(&mut cursor)?;
+
+        let mut entries: Vec<RelocationEntry> = Vec::new();
+        for _ in 0..count {
+            entries.push(RelocationEntry::parse(&mut cursor)?);
+        }
+
+        if cursor.position() != payload_size as u64 {
+            return Err(ParseError::FormatError(format!(
+                "reloc section size mismatch: payload_size={}, read_size={}",
+                payload_size,
+                cursor.position()
+            )));
+        }
+
+        Ok(Self {
+            payload_size,
+            section_index: section_index as VarUInt32,
+            count: count as VarUInt32,
+            entries,
+        })
+    }
+
+    /// 再配置の対象となるセクションの通し番号を返す
+    pub fn get_section_index(&self) -> u32 {
+        self.section_index
+    }
+
+    pub fn get_num_entries(&self) -> u32 {
+        self.count
+    }
+
+    pub fn get_entries(&self) -> Vec<&RelocationEntry> {
+        self.entries.iter().collect()
+    }
+}
+
+impl Sizeof for RelocSectionPayload {
+    fn sizeof(&self) -> u32 {
+        self.payload_size
+    }
+}
+
+impl RelocationEntry {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let reloc_type = match read_8(reader) {
+            Ok(v) => RelocType::convert_from_u8(v[0])?,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        let offset = read_u32(reader)?;
+        let index = read_u32(reader)?;
+
+        let mut addend: Option<i64> = None;
+        if reloc_type.has_addend() {
+            let mut value: i64 = 0;
+            match read_signed_leb128(reader, &mut value) {
+                Ok(_rs) => (/* To check read size */),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            };
+            addend = Some(value);
+        }
+
+        Ok(Self {
+            reloc_type,
+            offset,
+            index,
+            addend,
+        })
+    }
+
+    pub fn get_type(&self) -> RelocType {
+        self.reloc_type
+    }
+
+    pub fn get_offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn get_addend(&self) -> Option<i64> {
+        self.addend
+    }
+}
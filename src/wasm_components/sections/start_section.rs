@@ -30,10 +30,7 @@ impl StartSection {
 
         let payload = StartSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     //// start関数のインデックスを返す
@@ -73,8 +70,6 @@ impl StartSectionPayload {
 
 impl Sizeof for StartSectionPayload {
     fn sizeof(&self) -> u32 {
-        let sizeof_index = usage_bytes_leb128_u(self.index as u64) as u32;
-
-        sizeof_index
+        usage_bytes_leb128_u(self.index as u64) as u32
     }
 }
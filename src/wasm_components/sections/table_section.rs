@@ -1,10 +1,10 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
-use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
+use crate::readers::{read_8, read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
-use crate::wasm_components::types::{TableType, VarUInt32};
+use crate::wasm_components::types::{InitExpr, TableType, VarUInt32};
 
 #[derive(Debug)]
 pub struct TableSection {
@@ -16,10 +16,12 @@ pub struct TableSection {
 pub struct TableSectionPayload {
     count: VarUInt32,
     entries: Vec<TableType>,
+    init_exprs: Vec<Option<InitExpr>>, // 0x40 0x00で始まるテーブルの初期化式(typed function references)
+    entry_offsets: Vec<u32>,           // ペイロードの先頭から各エントリまでの位置
 }
 
 impl TableSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections //
         let common = SectionCommon::parse(reader)?;
         if common.id != 4 {
@@ -31,10 +33,7 @@ impl TableSection {
 
         let payload = TableSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
     /// テーブルの数の数を返す
@@ -49,12 +48,24 @@ impl TableSection {
         self.payload.entries.iter().collect()
     }
 
-    /// Utilities
+    // Utilities
+
+    /// idx番目のエントリの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        self.payload.entry_offsets.get(idx).copied()
+    }
 
     /// idx番目のテーブル情報(タイプ)を返す
     pub fn get_table_type(&self, idx: usize) -> Option<&TableType> {
         self.payload.entries.get(idx)
     }
+
+    /// idx番目のテーブルの要素の初期値を表す初期化式を返す
+    ///
+    /// 初期化式を持たない(要素をnullで初期化する)場合はNone
+    pub fn get_table_init_expr(&self, idx: usize) -> Option<&InitExpr> {
+        self.payload.init_exprs.get(idx)?.as_ref()
+    }
 }
 
 impl Sizeof for TableSection {
@@ -73,7 +84,9 @@ impl SectionCommonInterface for TableSection {
 }
 
 impl TableSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
@@ -81,13 +94,41 @@ impl TableSectionPayload {
         };
 
         let mut entries: Vec<TableType> = Vec::new();
+        let mut init_exprs: Vec<Option<InitExpr>> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
         for _ in 0..count {
-            entries.push(TableType::parse(reader)?);
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
+            let head = match read_8(reader) {
+                Ok(head) => head,
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            };
+
+            // 0x40 0x00 tabletype expr: 初期化式を持つテーブル
+            // それ以外は先頭のバイトが要素の型の一部になる
+            if head[0] == 0x40 {
+                match read_8(reader) {
+                    Ok([0x00]) => (),
+                    Ok(v) => {
+                        return Err(ParseError::FormatError(format!(
+                            "unexpected table prefix: 0x40 {:#04x}",
+                            v[0]
+                        )))
+                    }
+                    Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+                };
+                entries.push(TableType::parse(reader)?);
+                init_exprs.push(Some(InitExpr::parse(reader)?));
+            } else {
+                entries.push(TableType::parse(&mut (&head[..]).chain(&mut *reader))?);
+                init_exprs.push(None);
+            }
         }
 
         Ok(Self {
             count: count as VarUInt32,
-            entries: entries,
+            entries,
+            init_exprs,
+            entry_offsets,
         })
     }
 }
@@ -96,7 +137,13 @@ impl Sizeof for TableSectionPayload {
     fn sizeof(&self) -> u32 {
         let sizeof_count: u32 = usage_bytes_leb128_u(self.count as u64) as u32;
         let sizeof_entries: u32 = self.entries.iter().map(|x| x.sizeof()).sum();
-
-        sizeof_count + sizeof_entries
+        let sizeof_init_exprs: u32 = self
+            .init_exprs
+            .iter()
+            .flatten()
+            .map(|x| 2 + x.sizeof()) // 0x40 0x00
+            .sum();
+
+        sizeof_count + sizeof_entries + sizeof_init_exprs
     }
 }
@@ -0,0 +1,103 @@
+use std::io::Read;
+
+use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+
+use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::{TagType, VarUInt32};
+
+/*
+ * TagSection (exception handling提案で追加)
+ *
+ * throwやcatchで使われる例外のタグを定義する
+ */
+
+#[derive(Debug)]
+pub struct TagSection {
+    common: SectionCommon,
+    payload: TagSectionPayload,
+}
+
+#[derive(Debug)]
+pub struct TagSectionPayload {
+    count: VarUInt32,
+    entries: Vec<TagType>,
+}
+
+impl TagSection {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        // Common reading in all sections //
+        let common = SectionCommon::parse(reader)?;
+        if common.id != 13 {
+            return Err(ParseError::FormatError(String::from(
+                "This Section is not TagSection",
+            )));
+        }
+        // ここまで共通 //
+
+        let payload = TagSectionPayload::parse(reader)?;
+
+        Ok(Self { common, payload })
+    }
+
+    /// タグの数を返す
+    pub fn get_num_tags(&self) -> u32 {
+        self.payload.count
+    }
+
+    /// タグのリストを返す
+    pub fn get_tag_list(&self) -> Vec<&TagType> {
+        self.payload.entries.iter().collect()
+    }
+
+    // Utilities
+
+    /// idx番目のタグを返す
+    pub fn get_tag(&self, idx: usize) -> Option<&TagType> {
+        self.payload.entries.get(idx)
+    }
+}
+
+impl Sizeof for TagSection {
+    fn sizeof(&self) -> u32 {
+        let sizeof_common = self.common.sizeof();
+        let sizeof_payload = self.payload.sizeof();
+
+        sizeof_common + sizeof_payload
+    }
+}
+
+impl SectionCommonInterface for TagSection {
+    fn get_base(&self) -> &SectionCommon {
+        &self.common
+    }
+}
+
+impl TagSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut count: u64 = 0;
+        match read_unsigned_leb128(reader, &mut count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut entries: Vec<TagType> = Vec::new();
+        for _ in 0..count {
+            entries.push(TagType::parse(reader)?);
+        }
+
+        Ok(Self {
+            count: count as VarUInt32,
+            entries,
+        })
+    }
+}
+
+impl Sizeof for TagSectionPayload {
+    fn sizeof(&self) -> u32 {
+        let sizeof_count = usage_bytes_leb128_u(self.count as u64) as u32;
+        let sizeof_entries: u32 = self.entries.iter().map(|x| x.sizeof()).sum();
+
+        sizeof_count + sizeof_entries
+    }
+}
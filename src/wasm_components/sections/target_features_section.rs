@@ -0,0 +1,163 @@
+use std::fmt;
+use std::io::Read;
+use std::str;
+
+use super::base::ParseError;
+
+use crate::readers::{read_8, read_unsigned_leb128, read_x, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::types::VarUInt32;
+
+/*
+ * Target Features Section
+ *
+ * LLVMが出力する、モジュールのビルド時に指定されたfeatureの一覧
+ */
+
+#[derive(Debug)]
+pub struct TargetFeaturesSectionPayload {
+    count: VarUInt32,
+    features: Vec<TargetFeature>,
+}
+
+/// featureのprefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetFeaturePrefix {
+    /// '+': モジュールが利用している
+    Used,
+    /// '-': モジュールは利用していない(リンク先でも利用してはいけない)
+    Disallowed,
+    /// '=': リンクするモジュールすべてが利用している必要がある
+    Required,
+}
+
+#[derive(Debug)]
+pub struct TargetFeature {
+    prefix: TargetFeaturePrefix,
+    name: String,
+}
+
+impl TargetFeaturesSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let mut count: u64 = 0;
+        match read_unsigned_leb128(reader, &mut count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut features: Vec<TargetFeature> = Vec::new();
+        for _ in 0..count {
+            features.push(TargetFeature::parse(reader)?);
+        }
+
+        let payload = Self {
+            count: count as VarUInt32,
+            features,
+        };
+
+        if payload.sizeof() != payload_size {
+            return Err(ParseError::FormatError(format!(
+                "Target features section size mismatch: expected {} bytes, but read {} bytes",
+                payload_size,
+                payload.sizeof()
+            )));
+        }
+
+        Ok(payload)
+    }
+
+    pub fn get_num_features(&self) -> u32 {
+        self.count
+    }
+
+    pub fn get_features(&self) -> Vec<&TargetFeature> {
+        self.features.iter().collect()
+    }
+}
+
+impl Sizeof for TargetFeaturesSectionPayload {
+    fn sizeof(&self) -> u32 {
+        let sizeof_count = usage_bytes_leb128_u(self.count as u64) as u32;
+        let sizeof_features: u32 = self.features.iter().map(|x| x.sizeof()).sum();
+
+        sizeof_count + sizeof_features
+    }
+}
+
+impl TargetFeaturePrefix {
+    pub fn convert_from_u8(prefix: u8) -> Result<Self, ParseError> {
+        match prefix {
+            b'+' => Ok(TargetFeaturePrefix::Used),
+            b'-' => Ok(TargetFeaturePrefix::Disallowed),
+            b'=' => Ok(TargetFeaturePrefix::Required),
+            _ => Err(ParseError::FormatError(format!(
+                "Invalid target feature prefix: {:#x}",
+                prefix
+            ))),
+        }
+    }
+
+    pub fn get_char(&self) -> char {
+        match self {
+            TargetFeaturePrefix::Used => '+',
+            TargetFeaturePrefix::Disallowed => '-',
+            TargetFeaturePrefix::Required => '=',
+        }
+    }
+}
+
+impl fmt::Display for TargetFeaturePrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_char())
+    }
+}
+
+impl TargetFeature {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let prefix = match read_8(reader) {
+            Ok(v) => TargetFeaturePrefix::convert_from_u8(v[0])?,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut name_len: u64 = 0;
+        match read_unsigned_leb128(reader, &mut name_len) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let name: String;
+        match read_x(reader, name_len as usize) {
+            Ok(data) => match str::from_utf8(&data) {
+                Ok(s) => name = String::from(s),
+                Err(err) => return Err(ParseError::FormatError(format!("{:?}", err))),
+            },
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Ok(Self { prefix, name })
+    }
+
+    pub fn get_prefix(&self) -> TargetFeaturePrefix {
+        self.prefix
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+}
+
+impl Sizeof for TargetFeature {
+    fn sizeof(&self) -> u32 {
+        let sizeof_prefix = 1;
+        let sizeof_name_len = usage_bytes_leb128_u(self.name.len() as u64) as u32;
+        let sizeof_name = self.name.len() as u32;
+
+        sizeof_prefix + sizeof_name_len + sizeof_name
+    }
+}
+
+impl fmt::Display for TargetFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, self.name)
+    }
+}
@@ -1,10 +1,10 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
-use super::base::{ParseError, SectionCommon, SectionCommonInterface};
+use super::base::{get_position, ParseError, SectionCommon, SectionCommonInterface};
 
 use crate::readers::{read_unsigned_leb128, usage_bytes_leb128_u};
 use crate::wasm_components::base::Sizeof;
-use crate::wasm_components::types::{FuncType, VarUInt32};
+use crate::wasm_components::types::{FuncType, RecGroup, SubType, VarUInt32};
 
 #[derive(Debug)]
 pub struct TypeSection {
@@ -15,11 +15,13 @@ pub struct TypeSection {
 #[derive(Debug)]
 pub struct TypeSectionPayload {
     count: VarUInt32,
-    entries: Vec<FuncType>,
+    entries: Vec<RecGroup>, // GC提案以降、エントリは再帰グループ(rec group)
+    type_indices: Vec<(usize, usize)>, // 型インデックス -> (rec groupの位置, グループ内の位置)
+    entry_offsets: Vec<u32>, // ペイロードの先頭から各rec groupまでの位置
 }
 
 impl TypeSection {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
         // Common reading in all sections //
         let common = SectionCommon::parse(reader)?;
         if common.id != 1 {
@@ -31,27 +33,58 @@ impl TypeSection {
 
         let payload = TypeSectionPayload::parse(reader)?;
 
-        Ok(Self {
-            common: common,
-            payload: payload,
-        })
+        Ok(Self { common, payload })
     }
 
-    /// 関数の型の数を返す
+    /// 型の数を返す
+    ///
+    /// rec groupは展開して数える(型インデックス空間の大きさ)
     pub fn get_num_types(&self) -> u32 {
+        self.payload
+            .entries
+            .iter()
+            .map(|x| x.get_num_sub_types())
+            .sum()
+    }
+
+    /// rec groupの数を返す
+    pub fn get_num_rec_groups(&self) -> u32 {
         self.payload.count
     }
 
-    /// 関数の型のリストを返す
-    pub fn get_type_list(&self) -> Vec<&FuncType> {
+    /// rec groupのリストを返す
+    pub fn get_rec_groups(&self) -> Vec<&RecGroup> {
         self.payload.entries.iter().collect()
     }
 
+    /// 型のリストを返す(rec groupは展開する)
+    ///
+    /// リスト内の位置が型インデックスに対応する
+    pub fn get_sub_type_list(&self) -> Vec<&SubType> {
+        self.payload
+            .entries
+            .iter()
+            .flat_map(|x| x.get_sub_types())
+            .collect()
+    }
+
     // Utilities
 
-    /// idx番目の関数型を返す
+    /// idx番目の型を含むrec groupの位置(ペイロードの先頭から)を返す
+    pub fn get_entry_offset(&self, idx: usize) -> Option<u32> {
+        let (group_idx, _) = *self.payload.type_indices.get(idx)?;
+        self.payload.entry_offsets.get(group_idx).copied()
+    }
+
+    /// idx番目の型を返す
+    pub fn get_sub_type(&self, idx: usize) -> Option<&SubType> {
+        let (group_idx, sub_idx) = *self.payload.type_indices.get(idx)?;
+        self.payload.entries[group_idx].get_sub_type(sub_idx)
+    }
+
+    /// idx番目の型が関数型であれば返す
     pub fn get_type(&self, idx: usize) -> Option<&FuncType> {
-        self.get_type_list().get(idx).map(|v| *v)
+        self.get_sub_type(idx).and_then(|x| x.get_func_type())
     }
 }
 
@@ -71,21 +104,33 @@ impl SectionCommonInterface for TypeSection {
 }
 
 impl TypeSectionPayload {
-    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        let payload_offset = get_position(reader)?;
+
         let mut count: u64 = 0;
         match read_unsigned_leb128(reader, &mut count) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let mut func_types: Vec<FuncType> = Vec::new();
-        for _ in 0..count {
-            func_types.push(FuncType::parse(reader)?);
+        // 型インデックスで引けるように、rec groupを展開した対応表をここで作っておく
+        let mut rec_groups: Vec<RecGroup> = Vec::new();
+        let mut type_indices: Vec<(usize, usize)> = Vec::new();
+        let mut entry_offsets: Vec<u32> = Vec::new();
+        for group_idx in 0..count as usize {
+            entry_offsets.push((get_position(reader)? - payload_offset) as u32);
+            let rec_group = RecGroup::parse(reader)?;
+            for sub_idx in 0..rec_group.get_num_sub_types() as usize {
+                type_indices.push((group_idx, sub_idx));
+            }
+            rec_groups.push(rec_group);
         }
 
         Ok(Self {
             count: count as VarUInt32,
-            entries: func_types,
+            entries: rec_groups,
+            type_indices,
+            entry_offsets,
         })
     }
 }
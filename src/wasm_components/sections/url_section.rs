@@ -0,0 +1,55 @@
+use std::io::Read;
+
+use super::base::ParseError;
+use super::linking_section::read_name;
+
+use crate::readers::usage_bytes_leb128_u;
+use crate::wasm_components::base::Sizeof;
+
+/*
+ * sourceMappingURL / external_debug_info Section
+ *
+ * 外部のデバッグ情報を参照するカスタムセクション
+ * ペイロードはURL(またはパス)を表す名前1つのみ
+ *
+ * - sourceMappingURL: ソースマップ(v3 JSON)
+ * - external_debug_info: 分割されたDWARFを持つwasmファイル
+ */
+
+#[derive(Debug)]
+pub struct UrlSectionPayload {
+    url: String,
+}
+
+impl UrlSectionPayload {
+    pub fn parse<R: Read>(reader: &mut R, payload_size: u32) -> Result<Self, ParseError> {
+        let url = read_name(reader)?;
+
+        let payload = Self { url };
+
+        if payload.sizeof() != payload_size {
+            return Err(ParseError::FormatError(format!(
+                "URL section size mismatch: expected {} bytes, but read {} bytes",
+                payload_size,
+                payload.sizeof()
+            )));
+        }
+
+        Ok(payload)
+    }
+
+    pub fn get_url(&self) -> &String {
+        &self.url
+    }
+
+    /// URLがローカルのファイルを指すかどうかを返す (スキームを持たない、またはfile://)
+    pub fn is_local_path(&self) -> bool {
+        self.url.starts_with("file://") || !self.url.contains("://")
+    }
+}
+
+impl Sizeof for UrlSectionPayload {
+    fn sizeof(&self) -> u32 {
+        usage_bytes_leb128_u(self.url.len() as u64) as u32 + self.url.len() as u32
+    }
+}
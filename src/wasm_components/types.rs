@@ -1,5 +1,7 @@
+mod composite_types;
 mod lang_types;
 mod number_types;
 
+pub use self::composite_types::*;
 pub use self::lang_types::*;
 pub use self::number_types::*;
@@ -0,0 +1,493 @@
+use std::fmt;
+use std::io::Read;
+
+use crate::readers::{read_8, read_signed_leb128, read_unsigned_leb128, usage_bytes_leb128_u};
+use crate::wasm_components::base::Sizeof;
+use crate::wasm_components::sections::ParseError;
+use crate::wasm_components::types::lang_types::{FuncType, LangTypes, ValueType};
+use crate::wasm_components::types::number_types::*;
+
+/*
+ * Composite types (GC提案)
+ *
+ * 型セクションのエントリは再帰グループ(rec group)の列になっている
+ *
+ * rectype  ::= 0x4e vec(subtype) | subtype
+ * subtype  ::= 0x50 vec(typeidx) comptype   (拡張可能)
+ *            | 0x4f vec(typeidx) comptype   (final)
+ *            | comptype                     (final, supertypeなし)
+ * comptype ::= 0x5e fieldtype | 0x5f vec(fieldtype) | 0x60 functype
+ *
+ * MVPの関数型だけのモジュールは、要素1つのrec groupの列として読める
+ */
+
+// 各形式の先頭バイト(符号付きLEB128で読んだ値)
+const FORM_REC: VarInt7 = -0x32; // 0x4e
+const FORM_SUB: VarInt7 = -0x30; // 0x50
+const FORM_SUB_FINAL: VarInt7 = -0x31; // 0x4f
+const FORM_ARRAY: VarInt7 = -0x22; // 0x5e
+const FORM_STRUCT: VarInt7 = -0x21; // 0x5f
+const FORM_FUNC: VarInt7 = -0x20; // 0x60
+
+// パック型
+const PACKED_I8: i64 = -0x08; // 0x78
+const PACKED_I16: i64 = -0x09; // 0x77
+
+#[derive(Debug)]
+pub struct RecGroup {
+    explicit: bool, // 0x4eで明示的にグループ化されているか
+    count: VarUInt32,
+    sub_types: Vec<SubType>,
+}
+
+impl RecGroup {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut form = 0;
+        match read_signed_leb128(reader, &mut form) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        if form as VarInt7 != FORM_REC {
+            let sub_type = SubType::parse_with_form(reader, form as VarInt7)?;
+            return Ok(Self {
+                explicit: false,
+                count: 1,
+                sub_types: vec![sub_type],
+            });
+        }
+
+        let mut count = 0; // VarUInt32
+        match read_unsigned_leb128(reader, &mut count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut sub_types: Vec<SubType> = Vec::new();
+        for _ in 0..count {
+            sub_types.push(SubType::parse(reader)?);
+        }
+
+        Ok(Self {
+            explicit: true,
+            count: count as VarUInt32,
+            sub_types,
+        })
+    }
+
+    /// 0x4eで明示的にグループ化されているかを返す
+    pub fn is_explicit(&self) -> bool {
+        self.explicit
+    }
+
+    /// グループ内の型の数を返す
+    pub fn get_num_sub_types(&self) -> u32 {
+        self.count
+    }
+
+    /// グループ内の型のリストを返す
+    pub fn get_sub_types(&self) -> Vec<&SubType> {
+        self.sub_types.iter().collect()
+    }
+
+    /// グループ内のidx番目の型を返す
+    pub fn get_sub_type(&self, idx: usize) -> Option<&SubType> {
+        self.sub_types.get(idx)
+    }
+}
+
+impl Sizeof for RecGroup {
+    fn sizeof(&self) -> u32 {
+        let sizeof_sub_types: u32 = self.sub_types.iter().map(|x| x.sizeof()).sum();
+        if !self.explicit {
+            return sizeof_sub_types;
+        }
+
+        let sizeof_form: u32 = 1;
+        let sizeof_count: u32 = usage_bytes_leb128_u(self.count as u64) as u32;
+
+        sizeof_form + sizeof_count + sizeof_sub_types
+    }
+}
+
+#[derive(Debug)]
+pub struct SubType {
+    form: Option<VarInt7>, // 0x50 or 0x4f. 省略形の場合はNone
+    is_final: bool,
+    super_count: VarUInt32,
+    super_types: Vec<VarUInt32>,
+    composite_type: CompositeType,
+}
+
+impl SubType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut form = 0;
+        match read_signed_leb128(reader, &mut form) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Self::parse_with_form(reader, form as VarInt7)
+    }
+
+    /// 先頭のバイトを読み込み済みの場合に、残りを読み込む
+    pub fn parse_with_form<R: Read>(reader: &mut R, form: VarInt7) -> Result<Self, ParseError> {
+        if form != FORM_SUB && form != FORM_SUB_FINAL {
+            // 省略形: finalでsupertypeを持たない
+            let composite_type = CompositeType::parse_with_form(reader, form)?;
+            return Ok(Self {
+                form: None,
+                is_final: true,
+                super_count: 0,
+                super_types: Vec::new(),
+                composite_type,
+            });
+        }
+
+        let mut super_count = 0; // VarUInt32
+        match read_unsigned_leb128(reader, &mut super_count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut super_types: Vec<VarUInt32> = Vec::new();
+        for _ in 0..super_count {
+            let mut type_idx = 0;
+            match read_unsigned_leb128(reader, &mut type_idx) {
+                Ok(_rs) => (/* To check read size */),
+                Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+            };
+            super_types.push(type_idx as VarUInt32);
+        }
+
+        let composite_type = CompositeType::parse(reader)?;
+
+        Ok(Self {
+            form: Some(form),
+            is_final: form == FORM_SUB_FINAL,
+            super_count: super_count as VarUInt32,
+            super_types,
+            composite_type,
+        })
+    }
+
+    /// finalかどうか(これ以上サブタイプを宣言できないか)を返す
+    pub fn is_final(&self) -> bool {
+        self.is_final
+    }
+
+    /// supertypeの型インデックスのリストを返す
+    pub fn get_super_types(&self) -> &[u32] {
+        &self.super_types
+    }
+
+    /// 複合型を返す
+    pub fn get_composite_type(&self) -> &CompositeType {
+        &self.composite_type
+    }
+
+    /// 関数型であれば返す
+    pub fn get_func_type(&self) -> Option<&FuncType> {
+        match &self.composite_type {
+            CompositeType::Func(func_type) => Some(func_type),
+            _ => None,
+        }
+    }
+}
+
+impl Sizeof for SubType {
+    fn sizeof(&self) -> u32 {
+        let sizeof_composite_type = self.composite_type.sizeof();
+        if self.form.is_none() {
+            return sizeof_composite_type;
+        }
+
+        let sizeof_form: u32 = 1;
+        let sizeof_super_count: u32 = usage_bytes_leb128_u(self.super_count as u64) as u32;
+        let sizeof_super_types: u32 = self
+            .super_types
+            .iter()
+            .map(|x| usage_bytes_leb128_u(*x as u64) as u32)
+            .sum();
+
+        sizeof_form + sizeof_super_count + sizeof_super_types + sizeof_composite_type
+    }
+}
+
+impl fmt::Display for SubType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.form.is_none() {
+            return write!(f, "{}", self.composite_type);
+        }
+
+        let final_str = if self.is_final { "final " } else { "" };
+        let super_str: String = self
+            .super_types
+            .iter()
+            .map(|idx| format!("{} ", idx))
+            .collect();
+        write!(f, "sub {}{}({})", final_str, super_str, self.composite_type)
+    }
+}
+
+#[derive(Debug)]
+pub enum CompositeType {
+    Func(FuncType),
+    Struct(StructType),
+    Array(ArrayType),
+}
+
+impl CompositeType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut form = 0;
+        match read_signed_leb128(reader, &mut form) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Self::parse_with_form(reader, form as VarInt7)
+    }
+
+    /// form(0x60, 0x5f, 0x5e)を読み込み済みの場合に、残りを読み込む
+    pub fn parse_with_form<R: Read>(reader: &mut R, form: VarInt7) -> Result<Self, ParseError> {
+        match form {
+            FORM_FUNC => Ok(CompositeType::Func(FuncType::parse_with_form(
+                reader, form,
+            )?)),
+            FORM_STRUCT => Ok(CompositeType::Struct(StructType::parse(reader)?)),
+            FORM_ARRAY => Ok(CompositeType::Array(ArrayType::parse(reader)?)),
+            _ => Err(ParseError::FormatError(format!(
+                "unknown composite type form: form={}",
+                form
+            ))),
+        }
+    }
+
+    /// 複合型の種類名を返す(func, struct, array)
+    pub fn get_kind_name(&self) -> &'static str {
+        match self {
+            CompositeType::Func(_) => "func",
+            CompositeType::Struct(_) => "struct",
+            CompositeType::Array(_) => "array",
+        }
+    }
+}
+
+impl Sizeof for CompositeType {
+    fn sizeof(&self) -> u32 {
+        match self {
+            CompositeType::Func(func_type) => func_type.sizeof(),
+            CompositeType::Struct(struct_type) => 1 + struct_type.sizeof(),
+            CompositeType::Array(array_type) => 1 + array_type.sizeof(),
+        }
+    }
+}
+
+impl fmt::Display for CompositeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompositeType::Func(func_type) => write!(f, "func {}", func_type),
+            CompositeType::Struct(struct_type) => write!(f, "struct {}", struct_type),
+            CompositeType::Array(array_type) => write!(f, "array {}", array_type),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StructType {
+    field_count: VarUInt32,
+    fields: Vec<FieldType>,
+}
+
+impl StructType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut field_count = 0; // VarUInt32
+        match read_unsigned_leb128(reader, &mut field_count) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        let mut fields: Vec<FieldType> = Vec::new();
+        for _ in 0..field_count {
+            fields.push(FieldType::parse(reader)?);
+        }
+
+        Ok(Self {
+            field_count: field_count as VarUInt32,
+            fields,
+        })
+    }
+
+    /// フィールドの数を返す
+    pub fn get_num_fields(&self) -> u32 {
+        self.field_count
+    }
+
+    /// フィールドの型のリストを返す
+    pub fn get_fields(&self) -> Vec<&FieldType> {
+        self.fields.iter().collect()
+    }
+}
+
+impl Sizeof for StructType {
+    fn sizeof(&self) -> u32 {
+        let sizeof_field_count: u32 = usage_bytes_leb128_u(self.field_count as u64) as u32;
+        let sizeof_fields: u32 = self.fields.iter().map(|x| x.sizeof()).sum();
+
+        sizeof_field_count + sizeof_fields
+    }
+}
+
+impl fmt::Display for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.fields
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ArrayType {
+    field: FieldType,
+}
+
+impl ArrayType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let field = FieldType::parse(reader)?;
+        Ok(Self { field })
+    }
+
+    /// 要素の型を返す
+    pub fn get_field(&self) -> &FieldType {
+        &self.field
+    }
+}
+
+impl Sizeof for ArrayType {
+    fn sizeof(&self) -> u32 {
+        self.field.sizeof()
+    }
+}
+
+impl fmt::Display for ArrayType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.field)
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldType {
+    storage_type: StorageType,
+    mutability: VarUInt1,
+}
+
+impl FieldType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let storage_type = StorageType::parse(reader)?;
+
+        // 0 if immutable, 1 if mutable
+        let mutability = match read_8(reader) {
+            Ok(v) => v[0],
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        if mutability > 1 {
+            return Err(ParseError::FormatError(format!(
+                "invalid mutability: {}",
+                mutability
+            )));
+        }
+
+        Ok(Self {
+            storage_type,
+            mutability: mutability as VarUInt1,
+        })
+    }
+
+    /// フィールドの格納型を返す
+    pub fn get_storage_type(&self) -> &StorageType {
+        &self.storage_type
+    }
+
+    /// フィールドの可変性を返す(mutable or immutable)
+    pub fn get_mutability(&self) -> bool {
+        self.mutability == 1
+    }
+}
+
+impl Sizeof for FieldType {
+    fn sizeof(&self) -> u32 {
+        let sizeof_storage_type = self.storage_type.sizeof();
+        let sizeof_mutability: u32 = 1;
+
+        sizeof_storage_type + sizeof_mutability
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut_str = if self.mutability == 1 { "mut " } else { "" };
+        write!(f, "{}{}", mut_str, self.storage_type)
+    }
+}
+
+/// フィールドの格納型
+///
+/// 値型に加えて、struct/arrayのフィールドではパック型(i8, i16)が使える
+#[derive(Debug)]
+pub enum StorageType {
+    Val(ValueType),
+    I8,
+    I16,
+}
+
+impl StorageType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut v = 0;
+        let sizeof_head: u32 = match read_signed_leb128(reader, &mut v) {
+            Ok(rs) => rs as u32,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        match v {
+            PACKED_I8 => Ok(StorageType::I8),
+            PACKED_I16 => Ok(StorageType::I16),
+            _ => {
+                let (vt, size) = LangTypes::parse_with_head(reader, v, sizeof_head)?;
+                Ok(StorageType::Val(ValueType::from_lang_type(vt, size)?))
+            }
+        }
+    }
+
+    /// 値型であれば返す(パック型の場合はNone)
+    pub fn get_value_type(&self) -> Option<&LangTypes> {
+        match self {
+            StorageType::Val(vt) => Some(vt.get_value()),
+            _ => None,
+        }
+    }
+}
+
+impl Sizeof for StorageType {
+    fn sizeof(&self) -> u32 {
+        match self {
+            StorageType::Val(vt) => vt.sizeof(),
+            StorageType::I8 | StorageType::I16 => 1,
+        }
+    }
+}
+
+impl fmt::Display for StorageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageType::Val(vt) => write!(f, "{}", vt),
+            StorageType::I8 => write!(f, "i8"),
+            StorageType::I16 => write!(f, "i16"),
+        }
+    }
+}
@@ -15,15 +15,20 @@ use crate::wasm_components::types::number_types::*;
  * number_typesとは異なることに注意
  */
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LangTypes {
     I32,     // 0x7f
     I64,     // 0x7e
     F32,     // 0x7d
     F64,     // 0x7c
-    ANYFUNC, // 0x70
+    V128,    // 0x7b (simd提案)
+    ANYFUNC, // 0x70 (funcref)
     FUNC,    // 0x60
     PSEUDO,  // 0x40
+    // 参照型 (typed function references / GC提案)
+    // 0x64 ht = (ref ht), 0x63 ht = (ref null ht)
+    // externrefなどの省略形も、ヒープ型とnull許容性で表す
+    REF { nullable: bool, heap_type: HeapType },
 }
 
 impl LangTypes {
@@ -40,12 +45,71 @@ impl LangTypes {
             -0x02 => Ok(LangTypes::I64),
             -0x03 => Ok(LangTypes::F32),
             -0x04 => Ok(LangTypes::F64),
+            -0x05 => Ok(LangTypes::V128),
             -0x10 => Ok(LangTypes::ANYFUNC),
             -0x20 => Ok(LangTypes::FUNC),
             -0x40 => Ok(LangTypes::PSEUDO),
-            _ => Err(ParseError::FormatError(format!("unknown type: v={}", v))), // panic!("unknown type: v={}", v),
+            // 参照型の省略形 (0x6f externref, 0x6e anyref, ...)
+            _ => match HeapType::convert_from_vint7(v) {
+                Some(heap_type) => Ok(LangTypes::REF {
+                    nullable: true,
+                    heap_type,
+                }),
+                None => Err(ParseError::FormatError(format!("unknown type: v={}", v))), // panic!("unknown type: v={}", v),
+            },
         }
     }
+
+    /// 型を1つ読み込み、型と読み込んだバイト数を返す
+    ///
+    /// 0x64/0x63の場合は、続くヒープ型も読み込む
+    pub fn parse<R: Read>(reader: &mut R) -> Result<(Self, u32), ParseError> {
+        let mut v: i64 = 0;
+        let sizeof_head: u32 = match read_signed_leb128(reader, &mut v) {
+            Ok(rs) => rs as u32,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        Self::parse_with_head(reader, v, sizeof_head)
+    }
+
+    /// 先頭の値(s33)を読み込み済みの場合に、残りを読み込んで型を返す
+    ///
+    /// blocktypeのように、先頭の値によって型か型インデックスかが変わる場合に使う
+    pub fn parse_with_head<R: Read>(
+        reader: &mut R,
+        head: i64,
+        sizeof_head: u32,
+    ) -> Result<(Self, u32), ParseError> {
+        match head {
+            // 0x64 = (ref ht), 0x63 = (ref null ht)
+            -0x1C | -0x1D => {
+                let (heap_type, sizeof_heap_type) = HeapType::parse(reader)?;
+                let lang_type = Self::from_heap_type(head == -0x1D, heap_type);
+                Ok((lang_type, sizeof_head + sizeof_heap_type))
+            }
+            _ => Ok((Self::convert_from_vint7(head as VarInt7)?, sizeof_head)),
+        }
+    }
+
+    /// ヒープ型から参照型を作る
+    ///
+    /// (ref null func)はfuncref(ANYFUNC)と同じものとして扱う
+    pub fn from_heap_type(nullable: bool, heap_type: HeapType) -> Self {
+        if nullable && heap_type == HeapType::Func {
+            LangTypes::ANYFUNC
+        } else {
+            LangTypes::REF {
+                nullable,
+                heap_type,
+            }
+        }
+    }
+
+    /// 参照型かどうかを返す
+    pub fn is_ref(&self) -> bool {
+        matches!(self, LangTypes::ANYFUNC | LangTypes::REF { .. })
+    }
 }
 
 impl fmt::Display for LangTypes {
@@ -55,42 +119,160 @@ impl fmt::Display for LangTypes {
             LangTypes::I64 => "i64",
             LangTypes::F32 => "f32",
             LangTypes::F64 => "f64",
+            LangTypes::V128 => "v128",
             LangTypes::ANYFUNC => "anyfunc",
             LangTypes::FUNC => "func",
             LangTypes::PSEUDO => "pseudo",
+            LangTypes::REF {
+                nullable,
+                heap_type,
+            } => {
+                // 省略形があればそれを使う (e.g. (ref null extern) -> externref)
+                return match (nullable, heap_type.get_shorthand_name()) {
+                    (true, Some(name)) => write!(f, "{}", name),
+                    (true, None) => write!(f, "(ref null {})", heap_type),
+                    (false, _) => write!(f, "(ref {})", heap_type),
+                };
+            }
         };
         write!(f, "{}", type_str)
     }
 }
 
+/// ヒープ型 (参照型が指す先の型)
+///
+/// 抽象ヒープ型(func, extern, any, ...)か、型セクションの型インデックス
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapType {
+    Func,     // 0x70
+    Extern,   // 0x6f
+    Any,      // 0x6e
+    Eq,       // 0x6d
+    I31,      // 0x6c
+    Struct,   // 0x6b
+    Array,    // 0x6a
+    Exn,      // 0x69 (exception handling提案)
+    NoExn,    // 0x74
+    NoFunc,   // 0x73
+    NoExtern, // 0x72
+    None,     // 0x71
+    Concrete(u32),
+}
+
+impl HeapType {
+    /// 抽象ヒープ型のバイト(vint7)から変換する
+    pub fn convert_from_vint7(v: VarInt7) -> Option<Self> {
+        match v {
+            -0x10 => Some(HeapType::Func),
+            -0x11 => Some(HeapType::Extern),
+            -0x12 => Some(HeapType::Any),
+            -0x13 => Some(HeapType::Eq),
+            -0x14 => Some(HeapType::I31),
+            -0x15 => Some(HeapType::Struct),
+            -0x16 => Some(HeapType::Array),
+            -0x17 => Some(HeapType::Exn),
+            -0x0C => Some(HeapType::NoExn),
+            -0x0D => Some(HeapType::NoFunc),
+            -0x0E => Some(HeapType::NoExtern),
+            -0x0F => Some(HeapType::None),
+            _ => None,
+        }
+    }
+
+    /// ヒープ型(s33)を読み込み、ヒープ型と読み込んだバイト数を返す
+    ///
+    /// 負の値は抽象ヒープ型、非負の値は型インデックス
+    pub fn parse<R: Read>(reader: &mut R) -> Result<(Self, u32), ParseError> {
+        let mut v: i64 = 0;
+        let sizeof_v: u32 = match read_signed_leb128(reader, &mut v) {
+            Ok(rs) => rs as u32,
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+
+        if v >= 0 {
+            return Ok((HeapType::Concrete(v as u32), sizeof_v));
+        }
+
+        match Self::convert_from_vint7(v as VarInt7) {
+            Some(heap_type) => Ok((heap_type, sizeof_v)),
+            None => Err(ParseError::FormatError(format!(
+                "unknown heap type: v={}",
+                v
+            ))),
+        }
+    }
+
+    /// null許容の参照型の省略形の名前を返す (e.g. extern -> externref)
+    pub fn get_shorthand_name(&self) -> Option<&'static str> {
+        match self {
+            HeapType::Func => Some("funcref"),
+            HeapType::Extern => Some("externref"),
+            HeapType::Any => Some("anyref"),
+            HeapType::Eq => Some("eqref"),
+            HeapType::I31 => Some("i31ref"),
+            HeapType::Struct => Some("structref"),
+            HeapType::Array => Some("arrayref"),
+            HeapType::Exn => Some("exnref"),
+            HeapType::NoExn => Some("nullexnref"),
+            HeapType::NoFunc => Some("nullfuncref"),
+            HeapType::NoExtern => Some("nullexternref"),
+            HeapType::None => Some("nullref"),
+            HeapType::Concrete(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for HeapType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeapType::Func => write!(f, "func"),
+            HeapType::Extern => write!(f, "extern"),
+            HeapType::Any => write!(f, "any"),
+            HeapType::Eq => write!(f, "eq"),
+            HeapType::I31 => write!(f, "i31"),
+            HeapType::Struct => write!(f, "struct"),
+            HeapType::Array => write!(f, "array"),
+            HeapType::Exn => write!(f, "exn"),
+            HeapType::NoExn => write!(f, "noexn"),
+            HeapType::NoFunc => write!(f, "nofunc"),
+            HeapType::NoExtern => write!(f, "noextern"),
+            HeapType::None => write!(f, "none"),
+            HeapType::Concrete(idx) => write!(f, "{}", idx),
+        }
+    }
+}
+
 // pub type ValueType = LangTypes;
 #[derive(Debug)]
 pub struct ValueType {
     value: LangTypes,
+    size: u32, // 参照型はヒープ型を含むため1byteとは限らない
 }
 impl ValueType {
     pub fn new(v: VarInt7) -> Result<Self, ParseError> {
         let vt = LangTypes::convert_from_vint7(v)?;
+        Self::from_lang_type(vt, 1)
+    }
+
+    /// 読み込み済みの型から作る
+    pub fn from_lang_type(vt: LangTypes, size: u32) -> Result<Self, ParseError> {
         match vt {
-            LangTypes::ANYFUNC | LangTypes::FUNC | LangTypes::PSEUDO => {
+            LangTypes::FUNC | LangTypes::PSEUDO => {
                 // panic!("{:?} is not value type", vt)
                 Err(ParseError::FormatError(format!(
                     "{:?} is not value type",
                     vt
                 )))
             }
-            _ => Ok(Self { value: vt }),
+            _ => Ok(Self { value: vt, size }),
         }
     }
 
     // BlockTypeやElemTypeと全く同じ処理になっている。
     // なんとかまとめられないか？
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut v: i64 = 0;
-        match read_signed_leb128(reader, &mut v) {
-            Ok(_) => Ok(Self::new(v as VarInt7)?),
-            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
-        }
+        let (vt, size) = LangTypes::parse(reader)?;
+        Self::from_lang_type(vt, size)
     }
 
     /// 値の型を返す
@@ -101,7 +283,7 @@ impl ValueType {
 
 impl Sizeof for ValueType {
     fn sizeof(&self) -> u32 {
-        1
+        self.size
     }
 }
 
@@ -119,8 +301,13 @@ pub struct BlockType {
 impl BlockType {
     pub fn new(v: VarInt7) -> Result<Self, ParseError> {
         let vt = LangTypes::convert_from_vint7(v)?;
+        Self::from_lang_type(vt)
+    }
+
+    /// 型から作る(参照型など、1byteで表現できない型の場合)
+    pub fn from_lang_type(vt: LangTypes) -> Result<Self, ParseError> {
         match vt {
-            LangTypes::ANYFUNC | LangTypes::FUNC => Err(ParseError::FormatError(format!(
+            LangTypes::FUNC => Err(ParseError::FormatError(format!(
                 "{:?} is not block type",
                 vt
             ))),
@@ -129,11 +316,8 @@ impl BlockType {
     }
 
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut v: i64 = 0;
-        match read_signed_leb128(reader, &mut v) {
-            Ok(_) => Ok(Self::new(v as VarInt7)?),
-            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
-        }
+        let (vt, _) = LangTypes::parse(reader)?;
+        Self::from_lang_type(vt)
     }
 
     /// blockの評価値の型を返す
@@ -158,30 +342,34 @@ impl fmt::Display for BlockType {
 #[derive(Debug)]
 pub struct ElemType {
     value: LangTypes,
+    size: u32,
 }
 impl ElemType {
     pub fn new(v: VarInt7) -> Result<Self, ParseError> {
         let vt = LangTypes::convert_from_vint7(v)?;
-        match vt {
-            LangTypes::ANYFUNC => Ok(Self { value: vt }),
-            _ => Err(ParseError::FormatError(format!(
+        Self::from_lang_type(vt, 1)
+    }
+
+    fn from_lang_type(vt: LangTypes, size: u32) -> Result<Self, ParseError> {
+        if vt.is_ref() {
+            Ok(Self { value: vt, size })
+        } else {
+            Err(ParseError::FormatError(format!(
                 "{:?} is not elem type",
                 vt
-            ))),
+            )))
         }
     }
 
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut v: i64 = 0;
-        match read_signed_leb128(reader, &mut v) {
-            Ok(_) => Ok(Self::new(v as VarInt7)?),
-            Err(err) => Err(ParseError::ReaderError(format!("{:?}", err))),
-        }
+        let (vt, size) = LangTypes::parse(reader)?;
+        Self::from_lang_type(vt, size)
     }
 
     /// elemのタイプを返す
     ///
-    /// Wasm v1のMVPモデルでは、ANYFUNCのみ
+    /// Wasm v1のMVPモデルでは、ANYFUNCのみ。
+    /// reference types/GC提案以降は任意の参照型
     pub fn get_value(&self) -> &LangTypes {
         &self.value
     }
@@ -189,7 +377,7 @@ impl ElemType {
 
 impl Sizeof for ElemType {
     fn sizeof(&self) -> u32 {
-        1
+        self.size
     }
 }
 
@@ -204,8 +392,8 @@ pub struct FuncType {
     _form: VarInt7,
     param_count: VarUInt32,
     param_types: Vec<ValueType>,
-    return_count: VarUInt1,
-    return_type: Option<ValueType>, // return_type: Vec<ValueType>
+    return_count: VarUInt32,
+    return_types: Vec<ValueType>, // MVPでは高々1つ. multi-value提案で複数になった
 }
 
 impl FuncType {
@@ -216,6 +404,20 @@ impl FuncType {
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
+        Self::parse_with_form(reader, form as VarInt7)
+    }
+
+    /// form(0x60)を読み込み済みの場合に、残りを読み込む
+    ///
+    /// GC提案のsubtype/rec groupの中では、formの前に他の情報が入るため
+    pub fn parse_with_form<R: Read>(reader: &mut R, form: VarInt7) -> Result<Self, ParseError> {
+        if form != -0x20 {
+            return Err(ParseError::FormatError(format!(
+                "unknown func type form: form={}",
+                form
+            )));
+        }
+
         let mut param_count = 0; // VarUInt32
         match read_unsigned_leb128(reader, &mut param_count) {
             Ok(_rs) => (/* To check read size */),
@@ -227,25 +429,23 @@ impl FuncType {
             param_types.push(ValueType::parse(reader)?);
         }
 
-        let mut return_count = 0; // VarUInt1
+        let mut return_count = 0; // VarUInt32
         match read_unsigned_leb128(reader, &mut return_count) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let return_type: Option<ValueType>;
-        if return_count == 1 {
-            return_type = Some(ValueType::parse(reader)?);
-        } else {
-            return_type = None;
+        let mut return_types: Vec<ValueType> = Vec::new();
+        for _ in 0..return_count {
+            return_types.push(ValueType::parse(reader)?);
         }
 
         Ok(Self {
-            _form: form as VarInt7,
+            _form: form,
             param_count: param_count as VarUInt32,
-            param_types: param_types,
-            return_count: return_count as VarUInt1,
-            return_type: return_type,
+            param_types,
+            return_count: return_count as VarUInt32,
+            return_types,
         })
     }
 
@@ -259,14 +459,21 @@ impl FuncType {
         self.param_types.iter().collect()
     }
 
-    /// 戻り値の数を返す(0 or 1)
-    pub fn get_num_return(&self) -> u8 {
+    /// 戻り値の数を返す
+    ///
+    /// MVPでは0 or 1. multi-value提案以降は2以上もありうる
+    pub fn get_num_return(&self) -> u32 {
         self.return_count
     }
 
-    /// 戻り値の型を返す
+    /// 最初の戻り値の型を返す
     pub fn get_return_type(&self) -> Option<&ValueType> {
-        self.return_type.as_ref()
+        self.return_types.first()
+    }
+
+    /// 戻り値の型リストを返す
+    pub fn get_return_types(&self) -> Vec<&ValueType> {
+        self.return_types.iter().collect()
     }
 }
 
@@ -275,41 +482,34 @@ impl Sizeof for FuncType {
         let sizeof_form: u32 = 1;
         let sizeof_param_count: u32 = usage_bytes_leb128_u(self.param_count as u64) as u32;
         let sizeof_param_types: u32 = self.param_types.iter().map(|x| x.sizeof()).sum();
-        let sizeof_return_count: u32 = 1;
-        let sizeof_return_type: u32 = if self.return_type.is_some() {
-            self.return_type.as_ref().unwrap().sizeof()
-        } else {
-            0
-        };
+        let sizeof_return_count: u32 = usage_bytes_leb128_u(self.return_count as u64) as u32;
+        let sizeof_return_types: u32 = self.return_types.iter().map(|x| x.sizeof()).sum();
 
         sizeof_form
             + sizeof_param_count
             + sizeof_param_types
             + sizeof_return_count
-            + sizeof_return_type
+            + sizeof_return_types
     }
 }
 
 impl fmt::Display for FuncType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let param_types = self.get_param_types();
-        let return_type = self.get_return_type();
-
-        match return_type {
-            Some(rty) => write!(
+        let param_str = self
+            .param_types
+            .iter()
+            .map(|v| v.get_value().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // 戻り値が1つの場合は括弧を省略する
+        match self.return_types.as_slice() {
+            [rty] => write!(f, "({}) -> {}", param_str, rty.get_value()),
+            return_types => write!(
                 f,
-                "({}) -> {}",
-                param_types
-                    .iter()
-                    .map(|v| v.get_value().to_string())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                rty.get_value().to_string()
-            ),
-            None => write!(
-                f,
-                "({}) -> ()",
-                param_types
+                "({}) -> ({})",
+                param_str,
+                return_types
                     .iter()
                     .map(|v| v.get_value().to_string())
                     .collect::<Vec<_>>()
@@ -335,7 +535,7 @@ impl GlobalType {
         };
 
         Ok(Self {
-            content_type: content_type,
+            content_type,
             mutability: mutability as VarUInt1,
         })
     }
@@ -379,7 +579,7 @@ impl TableType {
 
         Ok(Self {
             element_type: elem_type,
-            limits: limits,
+            limits,
         })
     }
 
@@ -396,6 +596,11 @@ impl TableType {
     pub fn get_limits(&self) -> &ResizableLimits {
         &self.limits
     }
+
+    /// テーブルのインデックス型を返す(table64ではI64)
+    pub fn get_index_type(&self) -> IndexType {
+        self.limits.get_index_type()
+    }
 }
 
 impl Sizeof for TableType {
@@ -409,7 +614,10 @@ impl Sizeof for TableType {
 
 impl fmt::Display for TableType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ({})", self.element_type, self.limits)
+        match self.get_index_type() {
+            IndexType::I32 => write!(f, "{} ({})", self.element_type, self.limits),
+            IndexType::I64 => write!(f, "{} i64 ({})", self.element_type, self.limits),
+        }
     }
 }
 
@@ -420,13 +628,20 @@ pub struct MemoryType {
 impl MemoryType {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
         let limits = ResizableLimits::parse(reader)?;
-        Ok(Self { limits: limits })
+        Ok(Self { limits })
     }
 
     /// メモリサイズの制限情報を返す
     pub fn get_limits(&self) -> &ResizableLimits {
         &self.limits
     }
+
+    /// メモリのインデックス型を返す(memory64ではI64)
+    ///
+    /// load/storeのアドレスオペランドやdataセグメントのオフセットはこの型になる
+    pub fn get_index_type(&self) -> IndexType {
+        self.limits.get_index_type()
+    }
 }
 
 impl Sizeof for MemoryType {
@@ -437,68 +652,160 @@ impl Sizeof for MemoryType {
 
 impl fmt::Display for MemoryType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "memory ({})", self.limits)
+        match self.get_index_type() {
+            IndexType::I32 => write!(f, "memory ({})", self.limits),
+            IndexType::I64 => write!(f, "memory i64 ({})", self.limits),
+        }
     }
 }
 
+/// メモリ・テーブルのインデックス型
+///
+/// MVPではI32のみ。memory64/table64提案でI64が追加された
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexType {
+    I32,
+    I64,
+}
+
+impl IndexType {
+    /// このインデックス型で表現できるアドレス(オフセット)の最大値を返す
+    pub fn max_address(&self) -> u64 {
+        match self {
+            IndexType::I32 => u32::MAX as u64,
+            IndexType::I64 => u64::MAX,
+        }
+    }
+
+    /// このインデックス型のメモリで指定できるページ数の最大値を返す
+    ///
+    /// アドレス空間全体を64KiBのページで割った数 (i32: 2^16, i64: 2^48)
+    pub fn max_memory_pages(&self) -> u64 {
+        match self {
+            IndexType::I32 => 1 << 16,
+            IndexType::I64 => 1 << 48,
+        }
+    }
+
+    /// アドレスオペランドの型を返す
+    pub fn to_lang_type(&self) -> LangTypes {
+        match self {
+            IndexType::I32 => LangTypes::I32,
+            IndexType::I64 => LangTypes::I64,
+        }
+    }
+}
+
+impl fmt::Display for IndexType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexType::I32 => write!(f, "i32"),
+            IndexType::I64 => write!(f, "i64"),
+        }
+    }
+}
+
+/*
+ * limitsのflags
+ *
+ * bit0: maximumの有無
+ * bit1: 共有メモリ(threads提案)
+ * bit2: インデックス型がi64(memory64/table64提案)
+ */
+const LIMITS_FLAG_HAS_MAX: u8 = 0x01;
+const LIMITS_FLAG_SHARED: u8 = 0x02;
+const LIMITS_FLAG_INDEX64: u8 = 0x04;
+
 #[derive(Debug)]
 pub struct ResizableLimits {
-    flags: VarUInt1,
-    initial: VarUInt32,
-    maximum: Option<VarUInt32>,
+    flags: VarUInt7,
+    initial: VarUInt64,
+    maximum: Option<VarUInt64>,
 }
 impl ResizableLimits {
     pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
-        let mut flags = 0; // VarUInt1
+        let mut flags = 0; // VarUInt7
         match read_unsigned_leb128(reader, &mut flags) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let mut initial = 0; // VarUInt32
+        let known_flags = LIMITS_FLAG_HAS_MAX | LIMITS_FLAG_SHARED | LIMITS_FLAG_INDEX64;
+        if flags & !(known_flags as u64) != 0 {
+            return Err(ParseError::FormatError(format!(
+                "unknown limits flags: flags={:#x}",
+                flags
+            )));
+        }
+
+        // i32インデックスの場合はVarUInt32, i64インデックスの場合はVarUInt64
+        let mut initial = 0;
         match read_unsigned_leb128(reader, &mut initial) {
             Ok(_rs) => (/* To check read size */),
             Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        let maximum: Option<VarUInt32>;
-        if flags == 1 {
-            let mut m = 0; // Option<VarUInt32>
+        let maximum: Option<VarUInt64> = if flags & LIMITS_FLAG_HAS_MAX as u64 != 0 {
+            let mut m = 0;
             match read_unsigned_leb128(reader, &mut m) {
                 Ok(_rs) => (/* To check read size */),
                 Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
             }
-            maximum = Some(m as VarUInt32);
+            Some(m as VarUInt64)
         } else {
-            maximum = None;
+            None
+        };
+
+        let limits = Self {
+            flags: flags as VarUInt7,
+            initial: initial as VarUInt64,
+            maximum,
+        };
+
+        let max_value = limits.get_index_type().max_address();
+        if limits.initial > max_value || limits.maximum.is_some_and(|m| m > max_value) {
+            return Err(ParseError::FormatError(format!(
+                "limits out of range for {} index: {}",
+                limits.get_index_type(),
+                limits
+            )));
         }
 
-        Ok(Self {
-            flags: flags as VarUInt1,
-            initial: initial as VarUInt32,
-            maximum: maximum,
-        })
+        Ok(limits)
     }
 
     /// 初期サイズを返す
-    pub fn get_initial_length(&self) -> u32 {
+    pub fn get_initial_length(&self) -> u64 {
         self.initial
     }
 
     /// サイズの上限を返す
-    pub fn get_maximum_length(&self) -> Option<u32> {
-        self.maximum.clone()
+    pub fn get_maximum_length(&self) -> Option<u64> {
+        self.maximum
+    }
+
+    /// インデックス型を返す
+    pub fn get_index_type(&self) -> IndexType {
+        if self.flags & LIMITS_FLAG_INDEX64 != 0 {
+            IndexType::I64
+        } else {
+            IndexType::I32
+        }
+    }
+
+    /// 共有メモリかどうかを返す(threads提案)
+    pub fn is_shared(&self) -> bool {
+        self.flags & LIMITS_FLAG_SHARED != 0
     }
 }
 
 impl Sizeof for ResizableLimits {
     fn sizeof(&self) -> u32 {
         let sizeof_flags: u32 = 1;
-        let sizeof_initial: u32 = usage_bytes_leb128_u(self.initial as u64) as u32;
-        let sizeof_maximum: u32 = if self.flags == 1 {
-            usage_bytes_leb128_u(self.maximum.unwrap() as u64) as u32
-        } else {
-            0
+        let sizeof_initial: u32 = usage_bytes_leb128_u(self.initial) as u32;
+        let sizeof_maximum: u32 = match self.maximum {
+            Some(m) => usage_bytes_leb128_u(m) as u32,
+            None => 0,
         };
 
         sizeof_flags + sizeof_initial + sizeof_maximum
@@ -507,13 +814,72 @@ impl Sizeof for ResizableLimits {
 
 impl fmt::Display for ResizableLimits {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let max_str = if self.flags == 1 {
-            self.maximum.unwrap().to_string()
-        } else {
-            String::from("...")
+        let max_str = match self.maximum {
+            Some(m) => m.to_string(),
+            None => String::from("..."),
+        };
+        let shared_str = if self.is_shared() { " shared" } else { "" };
+
+        write!(f, "{} - {}{}", self.initial, max_str, shared_str)
+    }
+}
+
+/// 例外のタグの型 (exception handling提案)
+///
+/// タグは例外の種類を表し、例外が運ぶ値の型を関数型で指定する(戻り値は空)
+#[derive(Debug)]
+pub struct TagType {
+    attribute: VarUInt7, // 0 = exception
+    type_index: VarUInt32,
+}
+impl TagType {
+    pub fn parse<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let attribute = match read_8(reader) {
+            Ok(data) => data[0],
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
+        };
+        if attribute != 0 {
+            return Err(ParseError::FormatError(format!(
+                "unknown tag attribute: attribute={}",
+                attribute
+            )));
+        }
+
+        let mut type_index = 0; // VarUInt32
+        match read_unsigned_leb128(reader, &mut type_index) {
+            Ok(_rs) => (/* To check read size */),
+            Err(err) => return Err(ParseError::ReaderError(format!("{:?}", err))),
         };
 
-        write!(f, "{} - {}", self.initial, max_str)
+        Ok(Self {
+            attribute: attribute as VarUInt7,
+            type_index: type_index as VarUInt32,
+        })
+    }
+
+    /// タグの属性を返す(0 = exception のみ)
+    pub fn get_attribute(&self) -> u8 {
+        self.attribute
+    }
+
+    /// 例外が運ぶ値の型(関数型)へのインデックスを返す
+    pub fn get_type_index(&self) -> u32 {
+        self.type_index
+    }
+}
+
+impl Sizeof for TagType {
+    fn sizeof(&self) -> u32 {
+        let sizeof_attribute: u32 = 1;
+        let sizeof_type_index = usage_bytes_leb128_u(self.type_index as u64) as u32;
+
+        sizeof_attribute + sizeof_type_index
+    }
+}
+
+impl fmt::Display for TagType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tag (type {})", self.type_index)
     }
 }
 
@@ -524,6 +890,7 @@ pub enum ExternalKind {
     Table,
     Memory,
     Global,
+    Tag, // exception handling提案
 }
 
 impl ExternalKind {
@@ -537,7 +904,13 @@ impl ExternalKind {
             1 => ExternalKind::Table,
             2 => ExternalKind::Memory,
             3 => ExternalKind::Global,
-            _ => panic!("unknown external kind: kind_head={}", kind_head),
+            4 => ExternalKind::Tag,
+            _ => {
+                return Err(ParseError::FormatError(format!(
+                    "unknown external kind: kind_head={}",
+                    kind_head
+                )))
+            }
         })
     }
 }
@@ -551,10 +924,11 @@ impl Sizeof for ExternalKind {
 impl fmt::Display for ExternalKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ExternalKind::Function => write!(f, "{}", "Function"),
-            ExternalKind::Table => write!(f, "{}", "Table"),
-            ExternalKind::Memory => write!(f, "{}", "Memory"),
-            ExternalKind::Global => write!(f, "{}", "Global"),
+            ExternalKind::Function => write!(f, "Function"),
+            ExternalKind::Table => write!(f, "Table"),
+            ExternalKind::Memory => write!(f, "Memory"),
+            ExternalKind::Global => write!(f, "Global"),
+            ExternalKind::Tag => write!(f, "Tag"),
         }
     }
 }
@@ -15,6 +15,7 @@ pub type UInt32 = u32;
 pub type VarUInt1 = u8;
 pub type VarUInt7 = u8;
 pub type VarUInt32 = u32;
+pub type VarUInt64 = u64;
 
 pub type VarInt7 = i8;
 pub type VarInt32 = i32;